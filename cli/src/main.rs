@@ -0,0 +1,321 @@
+mod output;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use clap::{Parser, ValueEnum};
+use core_chasam::csam::{
+    self,
+    repository::{InMemoryRepository, Repository, SqliteRepository},
+    CarvingSource, DirectorySource, EvidenceSource, FileListSource, StateMedia,
+};
+use tokio::sync::mpsc;
+
+use output::{OutputFormat, Printer};
+
+/// No media matched the CSAM database.
+const EXIT_NO_HITS: u8 = 0;
+/// At least one media matched the CSAM database.
+const EXIT_HITS: u8 = 1;
+/// The scan could not be performed.
+const EXIT_ERROR: u8 = 2;
+/// No media matched, but some files could not be processed.
+const EXIT_PARTIAL: u8 = 3;
+/// The scan was interrupted by the user (SIGINT) before any media matched.
+const EXIT_INTERRUPTED: u8 = 130;
+
+/// Searches CSAM content in images and videos without a graphical interface.
+#[derive(Debug, Parser)]
+#[command(
+    name = "chasam-cli",
+    version,
+    about,
+    after_help = "Exit codes: 0 no hits, 1 hits found, 2 error, \
+    3 no hits but some files could not be processed, \
+    130 interrupted without hits; an interrupted scan with hits exits with 1."
+)]
+struct Args {
+    /// Directory containing the `hash.txt`, `phash.txt`, `pdq.txt`, `video.txt` and `keyword.txt` databases.
+    #[arg(short, long, value_name = "DIR")]
+    database: PathBuf,
+
+    /// Where the loaded database is kept during the scan.
+    #[arg(short, long, value_enum, default_value_t = Storage::Memory)]
+    storage: Storage,
+
+    /// Output format written to stdout.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Only print media that matched the database.
+    #[arg(long)]
+    hits_only: bool,
+
+    /// Levels of nested archives opened in the evidence, 0 to not open archives.
+    #[arg(long, value_name = "LEVELS", default_value_t = csam::Service::ARCHIVE_DEPTH)]
+    archive_depth: usize,
+
+    /// Also match the rotated, mirrored and cropped copies of the images in the
+    /// perceptual hash database, at the cost of nine times more lookups.
+    #[arg(long)]
+    transforms: bool,
+
+    /// Highest Hamming distance, out of 256 bits, of a match in the PDQ database.
+    #[arg(long, value_name = "BITS", default_value_t = csam::Media::PDQ_THRESHOLD)]
+    pdq_threshold: u32,
+
+    /// How the frames of the videos are sampled.
+    #[arg(long, value_enum, default_value_t = Sampling::Interval)]
+    video_sampling: Sampling,
+
+    /// Most frames sampled from a video, or shown in the contact sheet of the
+    /// interval sampling.
+    #[arg(long, value_name = "FRAMES", default_value_t = csam::VideoSampling::MAX_FRAMES)]
+    max_video_frames: usize,
+
+    /// Carve the images and videos of the ROOT files, such as raw images,
+    /// pagefiles or dumps of unallocated space, instead of searching directories.
+    #[arg(long)]
+    carve: bool,
+
+    /// SQLite file caching the hashes, perceptual hashes and thumbnails of the
+    /// evidence; a rescan with the same file only matches the unchanged media
+    /// against the database.
+    #[arg(long, value_name = "FILE")]
+    cache: Option<PathBuf>,
+
+    /// Do not print the scan progress to stderr.
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Text file with the paths of the files and directories to scan, one per line.
+    #[arg(long, value_name = "FILE")]
+    paths_from: Option<PathBuf>,
+
+    /// Evidence directories to scan, or files to carve with `--carve`.
+    #[arg(required_unless_present = "paths_from", value_name = "ROOT")]
+    roots: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Storage {
+    /// Parse the text databases into memory on every run.
+    Memory,
+    /// Index the text databases once into `csam.sqlite` inside the database directory.
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Sampling {
+    /// One frame a second; decodes the whole video and aligns best with the
+    /// video database.
+    Interval,
+    /// Frames evenly spaced along the video, seeking to each of them.
+    Evenly,
+    /// Only the keyframes, the fastest on long videos.
+    Keyframes,
+    /// The first frame of every scene; decodes the whole video.
+    SceneChange,
+}
+
+impl From<Sampling> for csam::SamplingMode {
+    fn from(sampling: Sampling) -> Self {
+        match sampling {
+            Sampling::Interval => Self::Interval,
+            Sampling::Evenly => Self::Evenly,
+            Sampling::Keyframes => Self::Keyframes,
+            Sampling::SceneChange => Self::SceneChange,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Summary {
+    processed: usize,
+    hits: usize,
+    errors: usize,
+    cancelled: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::ERROR)
+        .init();
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("chasam-cli: could not start the runtime: {err}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    match runtime.block_on(run(args)) {
+        Ok(summary) => ExitCode::from(exit_code(&summary)),
+        Err(err) => {
+            eprintln!("chasam-cli: {err:#}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+// A hit outweighs an interruption and the files that failed, which a clean
+// triage must not hide.
+fn exit_code(summary: &Summary) -> u8 {
+    if summary.hits > 0 {
+        EXIT_HITS
+    } else if summary.cancelled {
+        EXIT_INTERRUPTED
+    } else if summary.errors > 0 {
+        EXIT_PARTIAL
+    } else {
+        EXIT_NO_HITS
+    }
+}
+
+async fn run(args: Args) -> anyhow::Result<Summary> {
+    if !args.database.is_dir() {
+        anyhow::bail!("database directory not found: {}", args.database.display());
+    }
+    if args.carve {
+        if let Some(root) = args.roots.iter().find(|root| !root.is_file()) {
+            anyhow::bail!("evidence file not found: {}", root.display());
+        }
+    } else if let Some(root) = args.roots.iter().find(|root| !root.is_dir()) {
+        anyhow::bail!("evidence directory not found: {}", root.display());
+    }
+
+    let repo: Arc<dyn Repository> = match args.storage {
+        Storage::Memory => Arc::new(InMemoryRepository::new()),
+        Storage::Sqlite => Arc::new(SqliteRepository::new()?),
+    };
+    let service = Arc::new(csam::Service::new(repo));
+    service.set_archive_depth(args.archive_depth);
+    service.set_phash_transforms(args.transforms);
+    service.set_pdq_threshold(args.pdq_threshold);
+    service.set_video_sampling(csam::VideoSampling {
+        mode: args.video_sampling.into(),
+        max_frames: args.max_video_frames.max(1),
+    });
+    if let Some(cache) = args.cache.as_ref() {
+        service.set_scan_cache(Some(csam::ScanCache::open(cache)?));
+    }
+    service.load_database(args.database.clone()).await?;
+
+    // SIGINT stops the running scan, the results found so far are still reported.
+    let cancelled = Arc::new(AtomicBool::new(false));
+    ctrlc::set_handler({
+        let service = service.clone();
+        let cancelled = cancelled.clone();
+        move || {
+            cancelled.store(true, Ordering::SeqCst);
+            service.cancel_task();
+        }
+    })?;
+
+    let mut paths = args.roots.clone();
+    let mut listed = Vec::new();
+    if let Some(path_list) = args.paths_from.as_ref() {
+        listed = csam::read_path_list(path_list)?;
+    }
+    // every listed file is carved on its own
+    if args.carve {
+        paths.append(&mut listed);
+    }
+
+    // roots inside another one are only scanned once
+    let mut roots: Vec<Box<dyn EvidenceSource>> = csam::dedup_roots(paths)
+        .into_iter()
+        .map(|root| -> Box<dyn EvidenceSource> {
+            if args.carve {
+                Box::new(CarvingSource::new(root))
+            } else {
+                Box::new(DirectorySource::new(root))
+            }
+        })
+        .collect();
+    if !listed.is_empty() {
+        roots.push(Box::new(FileListSource::new(csam::dedup_roots(listed))));
+    }
+
+    let mut printer = Printer::new(args.format, args.hits_only, args.quiet);
+    let mut summary = Summary::default();
+
+    let (tx, mut rx) = mpsc::channel(100);
+    service.search_media(roots, tx);
+
+    // The channel is closed once the search threads have sent every result.
+    while let Some(state) = rx.recv().await {
+        match state {
+            StateMedia::Root(index, count, name) => printer.root(index, count, &name)?,
+            StateMedia::Found(count) => printer.found(count)?,
+            StateMedia::Ok(medias) => {
+                for media in medias.iter() {
+                    summary.processed += 1;
+                    if media.is_csam() {
+                        summary.hits += 1;
+                    }
+                    printer.media(media)?;
+                }
+                printer.progress(summary.processed)?;
+            }
+            StateMedia::Err(path, err) => {
+                summary.errors += 1;
+                printer.error(&path, &err)?;
+            }
+            StateMedia::Completed => (),
+        }
+    }
+
+    summary.cancelled = cancelled.load(Ordering::SeqCst);
+    printer.summary(&summary)?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_args() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn test_exit_code_should_report_failed_files_without_hits() {
+        let summary = |hits, errors| Summary {
+            processed: 10,
+            hits,
+            errors,
+            cancelled: false,
+        };
+
+        // Assert
+        assert_eq!(exit_code(&summary(0, 0)), EXIT_NO_HITS);
+        assert_eq!(exit_code(&summary(0, 2)), EXIT_PARTIAL);
+        assert_eq!(exit_code(&summary(1, 2)), EXIT_HITS);
+    }
+
+    #[test]
+    fn test_exit_code_should_report_hits_of_interrupted_scan() {
+        let summary = |hits| Summary {
+            processed: 10,
+            hits,
+            errors: 0,
+            cancelled: true,
+        };
+
+        // Assert
+        assert_eq!(exit_code(&summary(0)), EXIT_INTERRUPTED);
+        assert_eq!(exit_code(&summary(1)), EXIT_HITS);
+    }
+}
@@ -0,0 +1,245 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use core_chasam::csam::{HashAlgorithm, Match, MatchType, Media, MediaType, PHashAlgorithm};
+use serde::Serialize;
+
+use crate::Summary;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable lines, one per media.
+    Text,
+    /// One JSON object per line (JSON Lines).
+    Json,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    Media {
+        path: &'a str,
+        name: &'a str,
+        media_type: &'a str,
+        file_type: Option<String>,
+        extension_mismatch: bool,
+        size: usize,
+        last_modified: i64,
+        md5: &'a str,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        sha1: &'a str,
+        #[serde(skip_serializing_if = "str::is_empty")]
+        sha256: &'a str,
+        phash: String,
+        #[serde(rename = "match")]
+        matched: Option<MatchRecord<'a>>,
+    },
+    Error {
+        path: &'a str,
+        message: String,
+    },
+    Summary {
+        processed: usize,
+        hits: usize,
+        errors: usize,
+        cancelled: bool,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct MatchRecord<'a> {
+    #[serde(rename = "type")]
+    match_type: &'a str,
+    reference: &'a str,
+    distance: u32,
+    source: &'a str,
+    category: Option<&'a str>,
+    filename: Option<&'a str>,
+    series: Option<&'a str>,
+    /// Alteration of the media undone to match the perceptual hash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transform: Option<String>,
+    /// Where a known video plays inside the video.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alignment: Option<AlignmentRecord>,
+}
+
+/// Time ranges in seconds of a video match.
+#[derive(Debug, Serialize)]
+struct AlignmentRecord {
+    start: f64,
+    end: f64,
+    reference_start: f64,
+    reference_end: f64,
+    frames: usize,
+}
+
+impl<'a> From<&'a Match> for MatchRecord<'a> {
+    fn from(matched: &'a Match) -> Self {
+        Self {
+            match_type: match matched.match_type {
+                MatchType::Hash(HashAlgorithm::MD5) => "md5",
+                MatchType::Hash(HashAlgorithm::SHA1) => "sha1",
+                MatchType::Hash(HashAlgorithm::SHA256) => "sha256",
+                MatchType::Keyword => "keyword",
+                MatchType::PHash(PHashAlgorithm::PHash) => "phash",
+                MatchType::PHash(PHashAlgorithm::PHashGray) => "phash-gray",
+                MatchType::PHash(PHashAlgorithm::DHash) => "dhash",
+                MatchType::PHash(PHashAlgorithm::AHash) => "ahash",
+                MatchType::Pdq => "pdq",
+                MatchType::Video => "video",
+            },
+            reference: &matched.reference,
+            distance: matched.distance,
+            source: &matched.source,
+            category: matched.category.as_deref(),
+            filename: matched.filename.as_deref(),
+            series: matched.series.as_deref(),
+            transform: matched.transform.map(|transform| transform.to_string()),
+            alignment: matched.alignment.map(|alignment| AlignmentRecord {
+                start: alignment.start,
+                end: alignment.end,
+                reference_start: alignment.reference_start,
+                reference_end: alignment.reference_end,
+                frames: alignment.frames,
+            }),
+        }
+    }
+}
+
+/// Writes the scan results to stdout and the progress to stderr.
+pub struct Printer {
+    format: OutputFormat,
+    hits_only: bool,
+    quiet: bool,
+    /// Root being scanned.
+    root: String,
+    total: usize,
+    stdout: io::StdoutLock<'static>,
+}
+
+impl Printer {
+    pub fn new(format: OutputFormat, hits_only: bool, quiet: bool) -> Self {
+        Self {
+            format,
+            hits_only,
+            quiet,
+            root: String::new(),
+            total: 0,
+            stdout: io::stdout().lock(),
+        }
+    }
+
+    pub fn root(&mut self, index: usize, count: usize, name: &str) -> io::Result<()> {
+        self.root = name.to_owned();
+        if !self.quiet && count > 1 {
+            eprintln!("scanning {} ({} of {})", name, index + 1, count);
+        }
+        Ok(())
+    }
+
+    /// `total` counts the media found in every root scanned so far.
+    pub fn found(&mut self, total: usize) -> io::Result<()> {
+        let count = total - self.total;
+        self.total = total;
+        if !self.quiet {
+            eprintln!("{}: {} media found", self.root, count);
+        }
+        Ok(())
+    }
+
+    pub fn progress(&mut self, processed: usize) -> io::Result<()> {
+        if !self.quiet {
+            if self.total > 0 {
+                eprintln!("processed {} of {}", processed, self.total);
+            } else {
+                eprintln!("processed {}", processed);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn media(&mut self, media: &Media) -> io::Result<()> {
+        if self.hits_only && !media.is_csam() {
+            return Ok(());
+        }
+
+        match self.format {
+            OutputFormat::Text => {
+                let (match_type, reference) = match media.matched {
+                    Some(ref matched) => (matched.to_string(), matched.reference.as_str()),
+                    None => (String::from("-"), "-"),
+                };
+                writeln!(
+                    self.stdout,
+                    "{}\t{}\t{:016X}\t{}\t{}",
+                    match_type, media.hash, media.phash, reference, media.path
+                )
+            }
+            OutputFormat::Json => self.write_json(&Record::Media {
+                path: &media.path,
+                name: &media.name,
+                media_type: match media.media_type {
+                    MediaType::Image => "image",
+                    MediaType::Video => "video",
+                },
+                file_type: media.file_type.map(|file_type| file_type.to_string()),
+                extension_mismatch: media.extension_mismatch,
+                size: media.size,
+                last_modified: media.last_modified,
+                md5: &media.hash,
+                sha1: &media.sha1,
+                sha256: &media.sha256,
+                phash: format!("{:016X}", media.phash),
+                matched: media.matched.as_ref().map(MatchRecord::from),
+            }),
+        }
+    }
+
+    pub fn error(&mut self, path: &str, err: &anyhow::Error) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Text => {
+                eprintln!("error: {path}: {err:#}");
+                Ok(())
+            }
+            OutputFormat::Json => self.write_json(&Record::Error {
+                path,
+                message: format!("{err:#}"),
+            }),
+        }
+    }
+
+    pub fn summary(&mut self, summary: &Summary) -> io::Result<()> {
+        match self.format {
+            OutputFormat::Text => {
+                if !self.quiet {
+                    eprintln!(
+                        "{} processed, {} hits, {} errors{}",
+                        summary.processed,
+                        summary.hits,
+                        summary.errors,
+                        if summary.cancelled {
+                            " (cancelled)"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+                self.stdout.flush()
+            }
+            OutputFormat::Json => self.write_json(&Record::Summary {
+                processed: summary.processed,
+                hits: summary.hits,
+                errors: summary.errors,
+                cancelled: summary.cancelled,
+            }),
+        }
+    }
+
+    fn write_json(&mut self, record: &Record) -> io::Result<()> {
+        serde_json::to_writer(&mut self.stdout, record)?;
+        writeln!(self.stdout)?;
+        self.stdout.flush()
+    }
+}
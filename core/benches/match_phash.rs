@@ -0,0 +1,80 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use core_chasam::csam::repository::{Entry, InMemoryRepository, Repository};
+use core_chasam::csam::PHashAlgorithm;
+
+const DATABASE_SIZE: usize = 500_000;
+
+// xorshift64, deterministic random hashes for the benchmark.
+fn random_hashes(seed: u64, n: usize) -> Vec<u64> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect()
+}
+
+// The lookup used before the perceptual hashes were indexed.
+fn linear_scan(store: &[u64], phash: u64, max_distance: u32) -> Option<u32> {
+    store
+        .iter()
+        .map(|l_phash| (l_phash ^ phash).count_ones())
+        .filter(|&distance| distance <= max_distance)
+        .min()
+}
+
+fn bench_match_phash(c: &mut Criterion) {
+    let store = random_hashes(0x9E3779B97F4A7C15, DATABASE_SIZE);
+    let repo = InMemoryRepository::new();
+    let entries: Vec<Entry<u64>> = store
+        .iter()
+        .map(|phash| Entry::new(*phash, "phash.txt"))
+        .collect();
+    repo.add_phashes(PHashAlgorithm::PHash, &entries).unwrap();
+
+    // Half of the queries are near duplicates of stored hashes, the other half
+    // are unknown media, which is the common case during a scan.
+    let mut queries: Vec<u64> = store
+        .iter()
+        .step_by(DATABASE_SIZE / 50)
+        .map(|phash| phash ^ 0b1011)
+        .collect();
+    queries.extend(random_hashes(42, 50));
+
+    let mut group = c.benchmark_group("match_phash");
+    group.sample_size(10);
+
+    for max_distance in [4, 10, 20] {
+        group.bench_with_input(
+            BenchmarkId::new("linear_scan", max_distance),
+            &max_distance,
+            |b, &max_distance| {
+                b.iter(|| {
+                    for query in queries.iter() {
+                        black_box(linear_scan(&store, *query, max_distance));
+                    }
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("multi_index", max_distance),
+            &max_distance,
+            |b, &max_distance| {
+                b.iter(|| {
+                    for query in queries.iter() {
+                        black_box(repo.match_phash(PHashAlgorithm::PHash, *query, max_distance));
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_match_phash);
+criterion_main!(benches);
@@ -0,0 +1,220 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Context;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use super::{PHashes, SamplingMode, VideoSampling};
+use crate::utils::hasher::Hashes;
+
+// Incremented whenever the table changes; older caches are emptied, since
+// their content can always be computed again.
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    PRAGMA synchronous = NORMAL;
+    CREATE TABLE IF NOT EXISTS media (
+        path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        last_modified INTEGER NOT NULL,
+        md5 TEXT NOT NULL,
+        sha1 TEXT NOT NULL,
+        sha256 TEXT NOT NULL,
+        phash BLOB NOT NULL,
+        thumbnail BLOB,
+        sampling TEXT
+    ) WITHOUT ROWID;
+";
+
+const DROP_SCHEMA: &str = "
+    DROP TABLE IF EXISTS media;
+";
+
+/// Work done on a media that does not depend on the CSAM database.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Analysis {
+    pub hashes: Hashes,
+    /// Perceptual hashes of every frame, `[0]` when there is none.
+    pub phashes: PHashes,
+    pub thumbnail: Option<Vec<u8>>,
+    /// How the frames of a video were sampled, `None` for an image.
+    pub sampling: Option<VideoSampling>,
+}
+
+/// Hashes, perceptual hashes and thumbnails of the media of a case, kept in a
+/// SQLite file so that a rescan of the same evidence only matches them
+/// against the database.
+///
+/// A media is reused while its path, size and modification time are the same.
+#[derive(Debug)]
+pub struct ScanCache {
+    conn: Mutex<Connection>,
+}
+
+impl ScanCache {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("could not open the scan cache {}", path.display()))?;
+        Self::migrate(&conn)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// File of the cache of the case made of the `roots`, inside `dir`.
+    pub fn path_for(dir: &Path, roots: &[PathBuf]) -> PathBuf {
+        let mut roots: Vec<String> = roots
+            .iter()
+            .map(|root| root.to_string_lossy().into_owned())
+            .collect();
+        roots.sort();
+        let digest = Sha256::digest(roots.join("\n").as_bytes());
+        let name: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+        dir.join(format!("{name}.sqlite"))
+    }
+
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(DROP_SCHEMA)?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        conn.execute_batch(SCHEMA)
+    }
+
+    pub(crate) fn get(&self, path: &str, size: u64, last_modified: i64) -> Option<Analysis> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .prepare_cached(
+                "SELECT md5, sha1, sha256, phash, thumbnail, sampling FROM media
+                WHERE path = ?1 AND size = ?2 AND last_modified = ?3",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_row(params![path, size as i64, last_modified], |row| {
+                    let phash: Vec<u8> = row.get(3)?;
+                    let sampling: Option<String> = row.get(5)?;
+                    Ok(Analysis {
+                        hashes: Hashes {
+                            md5: row.get(0)?,
+                            sha1: row.get(1)?,
+                            sha256: row.get(2)?,
+                        },
+                        phashes: PHashes::from_bytes(&phash),
+                        thumbnail: row.get(4)?,
+                        sampling: sampling.as_deref().and_then(parse_sampling),
+                    })
+                })
+                .optional()
+            });
+
+        match result {
+            Ok(analysis) => analysis,
+            Err(err) => {
+                tracing::error!("Could not read the scan cache.\nError: {:?}", err);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&self, path: &str, size: u64, last_modified: i64, analysis: &Analysis) {
+        let phash = analysis.phashes.to_bytes();
+
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .prepare_cached(
+                "INSERT OR REPLACE INTO media
+                (path, size, last_modified, md5, sha1, sha256, phash, thumbnail, sampling)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
+                    path,
+                    size as i64,
+                    last_modified,
+                    analysis.hashes.md5,
+                    analysis.hashes.sha1,
+                    analysis.hashes.sha256,
+                    phash,
+                    analysis.thumbnail,
+                    analysis.sampling.map(format_sampling),
+                ])
+            });
+
+        if let Err(err) = result {
+            tracing::error!("Could not write in the scan cache.\nError: {:?}", err);
+        }
+    }
+}
+
+// Sampling of a video as `mode:max_frames`.
+fn format_sampling(sampling: VideoSampling) -> String {
+    format!("{}:{}", sampling.mode, sampling.max_frames)
+}
+
+fn parse_sampling(value: &str) -> Option<VideoSampling> {
+    let (mode, max_frames) = value.rsplit_once(':')?;
+    Some(VideoSampling {
+        mode: SamplingMode::from_name(mode)?,
+        max_frames: max_frames.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csam::PHashAlgorithm;
+
+    #[test]
+    fn test_scan_cache_should_match_size_and_last_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = ScanCache::path_for(dir.path(), &[PathBuf::from("/evidence")]);
+        let analysis = Analysis {
+            hashes: Hashes {
+                md5: String::from("506bf7f41ca0c6f9e7612c04e93ab235"),
+                ..Default::default()
+            },
+            phashes: PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568, 1]),
+            thumbnail: Some(vec![0xFF, 0xD8]),
+            sampling: None,
+        };
+        ScanCache::open(&path)
+            .unwrap()
+            .insert("/evidence/horse.jpg", 10, 20, &analysis);
+
+        // reopened, as in a later scan
+        let cache = ScanCache::open(&path).unwrap();
+
+        // Assert
+        assert_eq!(cache.get("/evidence/horse.jpg", 10, 20), Some(analysis));
+        assert_eq!(cache.get("/evidence/horse.jpg", 11, 20), None);
+        assert_eq!(cache.get("/evidence/horse.jpg", 10, 21), None);
+    }
+
+    #[test]
+    fn test_scan_cache_should_keep_video_sampling() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ScanCache::open(&dir.path().join("cache.sqlite")).unwrap();
+        let sampling = VideoSampling {
+            mode: SamplingMode::SceneChange,
+            max_frames: 40,
+        };
+        let analysis = Analysis {
+            sampling: Some(sampling),
+            ..Default::default()
+        };
+        cache.insert("/evidence/vid.mp4", 10, 20, &analysis);
+
+        // Assert
+        assert_eq!(cache.get("/evidence/vid.mp4", 10, 20), Some(analysis));
+        assert_eq!(format_sampling(sampling), "Scene change:40");
+        assert_eq!(parse_sampling("Scene change:40"), Some(sampling));
+        assert_eq!(parse_sampling("Scene change"), None);
+        assert_eq!(parse_sampling("Sometimes:40"), None);
+    }
+}
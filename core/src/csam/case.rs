@@ -0,0 +1,572 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use bytes::Bytes;
+use rusqlite::{params, Connection, Row};
+
+use super::report::DatabaseInfo;
+use super::{
+    FileType, HashAlgorithm, ImageTransform, Match, MatchType, Media, MediaType, PHashAlgorithm,
+    PHashes, Tag,
+};
+use crate::utils::video_fingerprint::Alignment;
+
+/// File of the case inside its folder.
+pub const CASE_FILE: &str = "case.sqlite";
+/// Scan cache of the evidence of the case, inside its folder.
+pub const CACHE_FILE: &str = "cache.sqlite";
+
+// Incremented whenever the tables change. Unlike the caches, a case can not be
+// rebuilt, so newer versions must read the older ones.
+const CASE_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS info (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS root (
+        position INTEGER PRIMARY KEY,
+        path TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS database (
+        name TEXT NOT NULL,
+        version TEXT NOT NULL,
+        count INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS error (
+        path TEXT NOT NULL,
+        message TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS media (
+        position INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        path TEXT NOT NULL,
+        media_type TEXT NOT NULL,
+        file_type TEXT,
+        extension_mismatch INTEGER NOT NULL,
+        size INTEGER NOT NULL,
+        file_size INTEGER NOT NULL,
+        last_modified INTEGER NOT NULL,
+        md5 TEXT NOT NULL,
+        sha1 TEXT NOT NULL,
+        sha256 TEXT NOT NULL,
+        phash INTEGER NOT NULL,
+        phashes BLOB NOT NULL,
+        match_type TEXT,
+        reference TEXT,
+        distance INTEGER,
+        source TEXT,
+        category TEXT,
+        filename TEXT,
+        series TEXT,
+        thumbnail BLOB,
+        selected INTEGER NOT NULL,
+        note TEXT NOT NULL,
+        tag TEXT,
+        transform TEXT,
+        alignment TEXT
+    );
+";
+
+const CLEAR: &str = "
+    DELETE FROM info;
+    DELETE FROM root;
+    DELETE FROM database;
+    DELETE FROM error;
+    DELETE FROM media;
+";
+
+/// Scan session saved to a folder, to be reopened in another day.
+#[derive(Debug, Clone, Default)]
+pub struct Case {
+    pub case_number: String,
+    pub examiner: String,
+    /// Creation of the case as a Unix timestamp.
+    pub created: i64,
+    pub evidence_roots: Vec<PathBuf>,
+    /// Start and end of the scan as Unix timestamps.
+    pub scan_started: Option<i64>,
+    pub scan_finished: Option<i64>,
+    /// Databases used in the scan.
+    pub databases: Vec<DatabaseInfo>,
+    /// Path of the files that could not be processed and the error.
+    pub errors: Vec<(String, String)>,
+    pub medias: Vec<CaseMedia>,
+}
+
+/// Media of a case with the work of the examiner on it.
+#[derive(Debug, Clone)]
+pub struct CaseMedia {
+    pub media: Media,
+    pub selected: bool,
+    pub note: String,
+}
+
+/// Whether the folder holds a case.
+pub fn is_case(dir: &Path) -> bool {
+    dir.join(CASE_FILE).is_file()
+}
+
+/// Saves the case to the folder, replacing the case saved there before.
+pub fn save(dir: &Path, case: &Case) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut conn = Connection::open(dir.join(CASE_FILE))
+        .with_context(|| format!("could not create the case in {}", dir.display()))?;
+    migrate(&conn)?;
+
+    let tx = conn.transaction()?;
+    tx.execute_batch(CLEAR)?;
+    {
+        let mut stmt = tx.prepare("INSERT INTO info (key, value) VALUES (?1, ?2)")?;
+        let info = [
+            ("case_number", case.case_number.clone()),
+            ("examiner", case.examiner.clone()),
+            ("created", case.created.to_string()),
+            ("scan_started", format_time(case.scan_started)),
+            ("scan_finished", format_time(case.scan_finished)),
+        ];
+        for (key, value) in info {
+            stmt.execute(params![key, value])?;
+        }
+
+        let mut stmt = tx.prepare("INSERT INTO root (position, path) VALUES (?1, ?2)")?;
+        for (position, root) in case.evidence_roots.iter().enumerate() {
+            stmt.execute(params![position as i64, root.to_string_lossy()])?;
+        }
+
+        let mut stmt =
+            tx.prepare("INSERT INTO database (name, version, count) VALUES (?1, ?2, ?3)")?;
+        for database in case.databases.iter() {
+            stmt.execute(params![
+                database.name,
+                database.version,
+                database.count as i64
+            ])?;
+        }
+
+        let mut stmt = tx.prepare("INSERT INTO error (path, message) VALUES (?1, ?2)")?;
+        for (path, message) in case.errors.iter() {
+            stmt.execute(params![path, message])?;
+        }
+
+        let mut stmt = tx.prepare(
+            "INSERT INTO media (position, name, path, media_type, file_type,
+            extension_mismatch, size, file_size, last_modified, md5, sha1, sha256,
+            phash, phashes, match_type, reference, distance, source, category,
+            filename, series, thumbnail, selected, note, tag, transform, alignment)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+            ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27)",
+        )?;
+        for (position, item) in case.medias.iter().enumerate() {
+            let media = &item.media;
+            let matched = media.matched.as_ref();
+            stmt.execute(params![
+                position as i64,
+                media.name,
+                media.path,
+                format_media_type(media.media_type),
+                media.file_type.map(|file_type| file_type.to_string()),
+                media.extension_mismatch,
+                media.size as i64,
+                media.file_size as i64,
+                media.last_modified,
+                media.hash,
+                media.sha1,
+                media.sha256,
+                media.phash as i64,
+                media.phashes.to_bytes(),
+                matched.map(|m| format_match_type(m.match_type)),
+                matched.map(|m| m.reference.as_str()),
+                matched.map(|m| m.distance),
+                matched.map(|m| m.source.as_str()),
+                matched.and_then(|m| m.category.as_deref()),
+                matched.and_then(|m| m.filename.as_deref()),
+                matched.and_then(|m| m.series.as_deref()),
+                media.img_buf.as_deref(),
+                item.selected,
+                item.note,
+                media.tag.map(|tag| tag.to_string()),
+                matched
+                    .and_then(|m| m.transform)
+                    .map(|transform| transform.to_string()),
+                matched.and_then(|m| m.alignment).map(format_alignment),
+            ])?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Opens the case saved in the folder.
+pub fn open(dir: &Path) -> anyhow::Result<Case> {
+    if !is_case(dir) {
+        anyhow::bail!("case not found: {}", dir.display());
+    }
+    let conn = Connection::open(dir.join(CASE_FILE))
+        .with_context(|| format!("could not open the case {}", dir.display()))?;
+    migrate(&conn)?;
+
+    let mut case = Case::default();
+    let mut stmt = conn.prepare("SELECT key, value FROM info")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let value: String = row.get(1)?;
+        match row.get::<_, String>(0)?.as_str() {
+            "case_number" => case.case_number = value,
+            "examiner" => case.examiner = value,
+            "created" => case.created = value.parse().unwrap_or_default(),
+            "scan_started" => case.scan_started = value.parse().ok(),
+            "scan_finished" => case.scan_finished = value.parse().ok(),
+            _ => (),
+        }
+    }
+
+    let mut stmt = conn.prepare("SELECT path FROM root ORDER BY position")?;
+    case.evidence_roots = stmt
+        .query_map([], |row| Ok(PathBuf::from(row.get::<_, String>(0)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut stmt = conn.prepare("SELECT name, version, count FROM database ORDER BY rowid")?;
+    case.databases = stmt
+        .query_map([], |row| {
+            Ok(DatabaseInfo {
+                name: row.get(0)?,
+                version: row.get(1)?,
+                count: row.get::<_, i64>(2)? as usize,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut stmt = conn.prepare("SELECT path, message FROM error ORDER BY rowid")?;
+    case.errors = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT name, path, media_type, file_type, extension_mismatch, size, file_size,
+        last_modified, md5, sha1, sha256, phash, phashes, match_type, reference,
+        distance, source, category, filename, series, thumbnail, selected, note, tag,
+        transform, alignment
+        FROM media ORDER BY position",
+    )?;
+    case.medias = stmt
+        .query_map([], media_from_row)?
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(case)
+}
+
+fn migrate(conn: &Connection) -> anyhow::Result<()> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    match version {
+        0 => conn.pragma_update(None, "user_version", CASE_VERSION)?,
+        CASE_VERSION => (),
+        _ => anyhow::bail!("case made by a newer version of ChaSAM"),
+    }
+    conn.execute_batch(SCHEMA)?;
+    Ok(())
+}
+
+fn media_from_row(row: &Row) -> rusqlite::Result<CaseMedia> {
+    let file_type: Option<String> = row.get(3)?;
+    let phashes: Vec<u8> = row.get(12)?;
+    let match_type: Option<String> = row.get(13)?;
+    let tag: Option<String> = row.get(23)?;
+    let transform: Option<String> = row.get(24)?;
+    let alignment: Option<String> = row.get(25)?;
+    let matched = match match_type.as_deref().and_then(parse_match_type) {
+        Some(match_type) => Some(Match {
+            match_type,
+            reference: row.get::<_, Option<String>>(14)?.unwrap_or_default(),
+            distance: row.get::<_, Option<u32>>(15)?.unwrap_or_default(),
+            source: row.get::<_, Option<String>>(16)?.unwrap_or_default(),
+            category: row.get(17)?,
+            filename: row.get(18)?,
+            series: row.get(19)?,
+            transform: transform.as_deref().and_then(ImageTransform::from_name),
+            alignment: alignment.as_deref().and_then(parse_alignment),
+        }),
+        None => None,
+    };
+
+    let media = Media {
+        name: row.get(0)?,
+        path: row.get(1)?,
+        media_type: match row.get::<_, String>(2)?.as_str() {
+            "video" => MediaType::Video,
+            _ => MediaType::Image,
+        },
+        file_type: file_type.as_deref().and_then(|name| {
+            FileType::ALL
+                .into_iter()
+                .find(|file_type| file_type.to_string() == name)
+        }),
+        extension_mismatch: row.get(4)?,
+        size: row.get::<_, i64>(5)? as usize,
+        file_size: row.get::<_, i64>(6)? as u64,
+        last_modified: row.get(7)?,
+        hash: row.get(8)?,
+        sha1: row.get(9)?,
+        sha256: row.get(10)?,
+        phash: row.get::<_, i64>(11)? as u64,
+        phashes: PHashes::from_bytes(&phashes),
+        matched,
+        img_buf: row.get::<_, Option<Vec<u8>>>(20)?.map(Bytes::from),
+        tag: tag.as_deref().and_then(Tag::from_name),
+    };
+
+    Ok(CaseMedia {
+        media,
+        selected: row.get(21)?,
+        note: row.get(22)?,
+    })
+}
+
+fn format_time(time: Option<i64>) -> String {
+    time.map(|time| time.to_string()).unwrap_or_default()
+}
+
+fn format_media_type(media_type: MediaType) -> &'static str {
+    match media_type {
+        MediaType::Image => "image",
+        MediaType::Video => "video",
+    }
+}
+
+fn format_match_type(match_type: MatchType) -> String {
+    match match_type {
+        MatchType::Hash(algorithm) => algorithm.to_string(),
+        MatchType::PHash(algorithm) => algorithm.to_string(),
+        MatchType::Pdq => String::from("PDQ"),
+        MatchType::Video => String::from("Video"),
+        MatchType::Keyword => String::from("Keyword"),
+    }
+}
+
+fn parse_match_type(value: &str) -> Option<MatchType> {
+    match value {
+        "Keyword" => Some(MatchType::Keyword),
+        "PDQ" => Some(MatchType::Pdq),
+        "Video" => Some(MatchType::Video),
+        _ => HashAlgorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.to_string() == value)
+            .map(MatchType::Hash)
+            .or_else(|| PHashAlgorithm::from_name(value).map(MatchType::PHash)),
+    }
+}
+
+// The time ranges in seconds, the number of frames and their mean distance,
+// separated by commas.
+fn format_alignment(alignment: Alignment) -> String {
+    format!(
+        "{},{},{},{},{},{}",
+        alignment.start,
+        alignment.end,
+        alignment.reference_start,
+        alignment.reference_end,
+        alignment.frames,
+        alignment.distance
+    )
+}
+
+fn parse_alignment(value: &str) -> Option<Alignment> {
+    let values: Vec<&str> = value.split(',').collect();
+    let [start, end, reference_start, reference_end, frames, distance] = values[..] else {
+        return None;
+    };
+    Some(Alignment {
+        start: start.parse().ok()?,
+        end: end.parse().ok()?,
+        reference_start: reference_start.parse().ok()?,
+        reference_end: reference_end.parse().ok()?,
+        frames: frames.parse().ok()?,
+        distance: distance.parse().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media(name: &str, matched: Option<Match>) -> Media {
+        Media {
+            name: String::from(name),
+            path: format!("/evidence/{name}"),
+            media_type: MediaType::Image,
+            file_type: Some(FileType::Jpeg),
+            size: 1,
+            file_size: 1024,
+            last_modified: 1704164646,
+            hash: String::from("506bf7f41ca0c6f9e7612c04e93ab235"),
+            sha1: String::new(),
+            sha256: String::new(),
+            phash: 15634510955120228568,
+            phashes: {
+                let mut phashes =
+                    PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568, 1]);
+                phashes.insert(PHashAlgorithm::DHash, vec![2]);
+                phashes
+            },
+            matched,
+            img_buf: Some(Bytes::from_static(&[0xFF, 0xD8, 0xFF])),
+            extension_mismatch: true,
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn test_save_and_open_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let matched = Match {
+            match_type: MatchType::Hash(HashAlgorithm::SHA256),
+            reference: String::from("9b7bc4bef1e301d3b6d4be5a9af84cce"),
+            distance: 0,
+            source: String::from("hash.txt"),
+            category: Some(String::from("A1")),
+            filename: None,
+            series: Some(String::from("Series A")),
+            transform: None,
+            alignment: None,
+        };
+        let flipped = Match {
+            match_type: MatchType::PHash(PHashAlgorithm::DHash),
+            reference: String::from("D8F8F0E0E4E4F8D8"),
+            distance: 2,
+            source: String::from("phash.txt"),
+            category: None,
+            filename: None,
+            series: None,
+            transform: Some(ImageTransform::FlipHorizontal),
+            alignment: None,
+        };
+        let video = Match {
+            match_type: MatchType::Video,
+            reference: String::from("D8F8F0E0E4E4F8D8"),
+            distance: 3,
+            source: String::from("video.txt"),
+            category: None,
+            filename: Some(String::from("known.mp4")),
+            series: None,
+            transform: None,
+            alignment: Some(Alignment {
+                start: 10.4,
+                end: 15.4,
+                reference_start: 2.0,
+                reference_end: 7.0,
+                frames: 6,
+                distance: 3,
+            }),
+        };
+        let case = Case {
+            case_number: String::from("2024-001"),
+            examiner: String::from("Examiner"),
+            created: 1704164000,
+            evidence_roots: vec![PathBuf::from("/evidence"), PathBuf::from("/mnt/usb")],
+            scan_started: Some(1704164646),
+            scan_finished: None,
+            databases: vec![DatabaseInfo {
+                name: String::from("hash.txt"),
+                version: String::from("2024-01-02 03:04:05 +00:00"),
+                count: 10,
+            }],
+            errors: vec![(
+                String::from("/evidence/broken.jpg"),
+                String::from("invalid"),
+            )],
+            medias: vec![
+                CaseMedia {
+                    media: Media {
+                        tag: Some(Tag::Category2),
+                        ..media("horse.jpg", Some(matched.clone()))
+                    },
+                    selected: true,
+                    note: String::from("seen in chat"),
+                },
+                CaseMedia {
+                    media: media("other.jpg", None),
+                    selected: false,
+                    note: String::new(),
+                },
+                CaseMedia {
+                    media: media("flipped.jpg", Some(flipped.clone())),
+                    selected: false,
+                    note: String::new(),
+                },
+                CaseMedia {
+                    media: media("video.mp4", Some(video.clone())),
+                    selected: false,
+                    note: String::new(),
+                },
+            ],
+        };
+        save(dir.path(), &case).unwrap();
+        // saving again replaces the case
+        save(dir.path(), &case).unwrap();
+
+        let opened = open(dir.path()).unwrap();
+
+        // Assert
+        assert_eq!(opened.case_number, "2024-001");
+        assert_eq!(opened.created, 1704164000);
+        assert_eq!(opened.evidence_roots, case.evidence_roots);
+        assert_eq!(opened.scan_started, Some(1704164646));
+        assert_eq!(opened.scan_finished, None);
+        assert_eq!(opened.databases, case.databases);
+        assert_eq!(opened.errors, case.errors);
+        assert_eq!(opened.medias.len(), 4);
+        let first = &opened.medias[0];
+        assert!(first.selected);
+        assert_eq!(first.note, "seen in chat");
+        assert_eq!(first.media.matched, Some(matched));
+        assert_eq!(first.media.file_type, Some(FileType::Jpeg));
+        assert_eq!(
+            first.media.phashes.get(PHashAlgorithm::PHash),
+            &[15634510955120228568, 1]
+        );
+        assert_eq!(first.media.phashes.get(PHashAlgorithm::DHash), &[2]);
+        assert_eq!(first.media.phash, 15634510955120228568);
+        assert!(first.media.extension_mismatch);
+        assert_eq!(first.media.tag, Some(Tag::Category2));
+        assert_eq!(
+            first.media.img_buf.as_deref(),
+            Some(&[0xFF, 0xD8, 0xFF][..])
+        );
+        assert_eq!(opened.medias[1].media.matched, None);
+        assert_eq!(opened.medias[1].media.tag, None);
+        assert_eq!(opened.medias[2].media.matched, Some(flipped));
+        assert_eq!(opened.medias[3].media.matched, Some(video));
+    }
+
+    #[test]
+    fn test_match_type_should_roundtrip() {
+        // Assert
+        assert_eq!(
+            parse_match_type("PHash"),
+            Some(MatchType::PHash(PHashAlgorithm::PHash))
+        );
+        assert_eq!(
+            parse_match_type(&format_match_type(MatchType::PHash(PHashAlgorithm::DHash))),
+            Some(MatchType::PHash(PHashAlgorithm::DHash))
+        );
+        assert_eq!(
+            parse_match_type(&format_match_type(MatchType::Pdq)),
+            Some(MatchType::Pdq)
+        );
+        assert_eq!(
+            parse_match_type(&format_match_type(MatchType::Video)),
+            Some(MatchType::Video)
+        );
+    }
+
+    #[test]
+    fn test_open_should_fail_without_case() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Assert
+        assert!(open(dir.path()).is_err());
+        assert!(!is_case(dir.path()));
+    }
+}
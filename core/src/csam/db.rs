@@ -1,24 +1,34 @@
 use anyhow::Context;
-use std::fs::{self, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     mpsc::{self, Receiver},
     Arc,
 };
 use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 use threadpool::ThreadPool;
-use walkdir::WalkDir;
 
-use crate::utils;
+use crate::utils::{self, pdq::PdqHash, video_fingerprint::VideoFingerprint};
 
-use super::repository::Repository;
+use super::repository::{Entry, Repository};
+use super::{
+    vics, Content, EvidenceItem, EvidenceSource, HashAlgorithm, Media, MediaType, PHashAlgorithm,
+    Tag,
+};
+
+pub(crate) const FILE_HASH: &str = "hash.txt";
+pub(crate) const FILE_KEYWORD: &str = "keyword.txt";
+pub(crate) const FILE_PHASH: &str = "phash.txt";
+pub(crate) const FILE_PDQ: &str = "pdq.txt";
+pub(crate) const FILE_VIDEO: &str = "video.txt";
 
-const FILE_HASH: &str = "hash.txt";
-const FILE_KEYWORD: &str = "keyword.txt";
-const FILE_PHASH: &str = "phash.txt";
+// Number of entries sent to the repository at once when loading a database.
+const BATCH_SIZE: usize = 10_000;
 
 pub fn create_keyword_database(db_path: PathBuf, content: &str) -> anyhow::Result<()> {
     if !db_path.exists() {
@@ -41,122 +51,260 @@ pub fn create_keyword_database(db_path: PathBuf, content: &str) -> anyhow::Resul
     Ok(())
 }
 
-pub fn create_hash_database<P>(
+pub fn create_hash_database(
     db_path: PathBuf,
-    root: P,
+    source: &mut dyn EvidenceSource,
+    archive_depth: usize,
     cancel_flag: Arc<AtomicBool>,
-) -> anyhow::Result<usize>
-where
-    P: AsRef<Path>,
-{
+) -> anyhow::Result<usize> {
     let (hash_sender, hash_receiver) = mpsc::channel::<String>();
 
     write_in_database(db_path, FILE_HASH, hash_receiver)
         .with_context(|| "Could not create hash database.")?;
 
+    self::process_images(source, archive_depth, cancel_flag, move |item| {
+        let hashes = item
+            .reader()
+            .and_then(|reader| utils::hasher::hash_reader(reader, &[HashAlgorithm::MD5]));
+        match hashes {
+            Ok(hashes) => {
+                hash_sender
+                    .send(self::format_entry(&hashes.md5, &item.name))
+                    .expect("could not send hash");
+            }
+            Err(err) => tracing::error!("Could not generate hash. {}\nError: {}", item.path, err),
+        }
+    })
+}
+
+/// Appends the perceptual hashes of the images to the database, one line for
+/// each of the `algorithms`, the default one when there is none.
+pub fn create_phash_database(
+    db_path: PathBuf,
+    source: &mut dyn EvidenceSource,
+    archive_depth: usize,
+    cancel_flag: Arc<AtomicBool>,
+    algorithms: &[PHashAlgorithm],
+) -> anyhow::Result<usize> {
+    let (phash_sender, phash_receiver) = mpsc::channel::<String>();
+
+    write_in_database(db_path, FILE_PHASH, phash_receiver)
+        .with_context(|| "Could not create perceptual hash database.")?;
+
+    let algorithms = match algorithms {
+        [] => vec![PHashAlgorithm::default()],
+        algorithms => algorithms.to_vec(),
+    };
+    self::process_images(source, archive_depth, cancel_flag, move |item| {
+        let hashes = match &item.content {
+            Content::File(path) => utils::media::get_perceptual_hashes_of_file(path, &algorithms),
+            Content::Memory(data) => {
+                utils::media::get_perceptual_hashes_of_memory(data, &algorithms)
+            }
+        };
+        match hashes {
+            Ok(hashes) => {
+                for (algorithm, hash) in algorithms.iter().zip(hashes) {
+                    phash_sender
+                        .send(self::format_phash_entry(hash, &item.name, *algorithm))
+                        .expect("could not send phash");
+                }
+            }
+            Err(err) => tracing::error!(
+                "Could not generate perceptual hash. {}\nError: {}",
+                item.path,
+                err
+            ),
+        }
+    })
+}
+
+// Runs `job` in a thread pool for every image of the source, returning the
+// number of images found.
+fn process_images<F>(
+    source: &mut dyn EvidenceSource,
+    archive_depth: usize,
+    cancel_flag: Arc<AtomicBool>,
+    job: F,
+) -> anyhow::Result<usize>
+where
+    F: Fn(EvidenceItem) + Send + Sync + 'static,
+{
     cancel_flag.store(false, Ordering::SeqCst);
     let mut count_files: usize = 0;
 
     let cpus = num_cpus::get();
     let thread_pool = ThreadPool::new(cpus);
+    let job = Arc::new(job);
 
-    for entry in WalkDir::new(root)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| !e.file_type().is_dir() && self::is_image(e.path()))
-    {
+    source.read_media(archive_depth, &mut |path, item| {
         if cancel_flag.load(Ordering::SeqCst) {
-            break;
+            return false;
         }
 
+        let item = match item {
+            Ok(item) if self::is_image(&item) => item,
+            Ok(_) => return true,
+            Err(err) => {
+                tracing::error!("Could not read file. {}\nError: {}", path, err);
+                return true;
+            }
+        };
+
         count_files += 1;
+
+        // the files read from archives and disk images wait in memory
+        while thread_pool.queued_count() > 2 * cpus {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+
         let cancel_flag = cancel_flag.clone();
-        let c_hash_sender = hash_sender.clone();
+        let job = job.clone();
 
         thread_pool.execute(move || {
             if cancel_flag.load(Ordering::SeqCst) {
                 return;
             }
-
-            match utils::media::get_md5_hash_of_file(entry.path()) {
-                Ok(hash) => {
-                    c_hash_sender.send(hash).expect("could not send hash");
-                }
-                Err(err) => tracing::error!(
-                    "Could not generate hash. {}\nError: {}",
-                    entry.path().display(),
-                    err
-                ),
-            }
+            job(item);
         });
-    }
+        true
+    })?;
 
     // wait for thread pool to process all jobs
     thread_pool.join();
-    drop(hash_sender);
 
     Ok(count_files)
 }
 
-pub fn create_phash_database<P>(
-    db_path: PathBuf,
-    root: P,
-    cancel_flag: Arc<AtomicBool>,
-) -> anyhow::Result<usize>
-where
-    P: AsRef<Path>,
-{
-    let (phash_sender, phash_receiver) = mpsc::channel::<String>();
+/// Appends the hashes of a Project VIC (VICS) or CAID JSON hash set to the hash
+/// database, keeping their category and series. Returns the number of hashes read.
+pub fn import_hash_set(db_path: PathBuf, file_path: &Path) -> anyhow::Result<usize> {
+    let reader = File::open(file_path)
+        .with_context(|| format!("Could not open file: {}", file_path.display()))?;
+    let source = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
 
-    write_in_database(db_path, FILE_PHASH, phash_receiver)
-        .with_context(|| "Could not create perceptual hash database.")?;
+    if !db_path.exists() {
+        fs::create_dir_all(&db_path)
+            .with_context(|| format!("Could not create `{}` path", db_path.display()))?;
+    }
 
-    cancel_flag.store(false, Ordering::SeqCst);
-    let mut count_files: usize = 0;
+    let db_file_path = db_path.join(FILE_HASH);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&db_file_path)
+        .with_context(|| format!("Could not open file: {}", db_file_path.display()))?;
 
-    let cpus = num_cpus::get();
-    let thread_pool = ThreadPool::new(cpus);
+    let mut writer = io::BufWriter::new(file);
+    let mut count: usize = 0;
+    let mut result: io::Result<()> = Ok(());
 
-    for entry in WalkDir::new(root)
-        .follow_links(false)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| !e.file_type().is_dir() && self::is_image(e.path()))
-    {
-        if cancel_flag.load(Ordering::SeqCst) {
-            break;
+    vics::read_hash_set(reader, &source, |entry| {
+        if result.is_ok() {
+            result = writeln!(writer, "{}", self::format_columns(&entry));
+            count += 1;
         }
+    })
+    .with_context(|| format!("Invalid hash set: {}", file_path.display()))?;
 
-        count_files += 1;
-        let cancel_flag = cancel_flag.clone();
-        let c_phash_sender = phash_sender.clone();
+    result?;
+    writer.flush()?;
 
-        thread_pool.execute(move || {
-            if cancel_flag.load(Ordering::SeqCst) {
-                return;
-            }
+    Ok(count)
+}
 
-            match utils::media::get_perceptual_hash_of_file(entry.path()) {
-                Ok(hash) => {
-                    c_phash_sender
-                        .send(hash.to_string())
-                        .expect("could not send phash");
-                }
-                Err(err) => tracing::error!(
-                    "Could not generate perceptual hash. {}\nError: {}",
-                    entry.path().display(),
-                    err
-                ),
+/// Appends the MD5, the perceptual hashes, the PDQ hashes and the fingerprint of
+/// the videos tagged as CSAM by the examiner to the hash databases, with the tag
+/// as category and `source` as the hash list. Returns the number of media added.
+pub fn add_tagged_media(db_path: PathBuf, source: &str, medias: &[Media]) -> anyhow::Result<usize> {
+    if !db_path.exists() {
+        fs::create_dir_all(&db_path)
+            .with_context(|| format!("Could not create `{}` path", db_path.display()))?;
+    }
+
+    let open = |db_name: &str| {
+        let file_path = db_path.join(db_name);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file_path)
+            .map(io::BufWriter::new)
+            .with_context(|| format!("Could not open file: {}", file_path.display()))
+    };
+    let mut hash_writer = open(FILE_HASH)?;
+    let mut phash_writer = open(FILE_PHASH)?;
+    let mut pdq_writer = open(FILE_PDQ)?;
+    let mut video_writer = open(FILE_VIDEO)?;
+    let mut count: usize = 0;
+
+    for media in medias.iter() {
+        let Some(tag) = media.tag.filter(Tag::is_csam) else {
+            continue;
+        };
+        let entry = |value: String| Entry {
+            value,
+            source: source.to_owned(),
+            category: Some(tag.to_string()),
+            filename: Some(media.name.clone()),
+            series: None,
+        };
+
+        if !media.hash.is_empty() {
+            writeln!(
+                hash_writer,
+                "{}",
+                self::format_columns(&entry(media.hash.clone()))
+            )?;
+        }
+        // every frame of a video, without the frames that could not be hashed
+        for (algorithm, frames) in media.phashes.iter() {
+            let mut frames: Vec<u64> = frames.iter().copied().filter(|phash| *phash != 0).collect();
+            frames.dedup();
+            for phash in frames {
+                writeln!(
+                    phash_writer,
+                    "{}\t{}",
+                    self::format_columns(&entry(phash.to_string())),
+                    algorithm
+                )?;
             }
-        });
+        }
+        let mut frames: Vec<PdqHash> = media
+            .phashes
+            .pdq()
+            .iter()
+            .filter(|pdq| pdq.quality > 0)
+            .map(|pdq| pdq.hash)
+            .collect();
+        frames.dedup();
+        for hash in frames {
+            writeln!(
+                pdq_writer,
+                "{}",
+                self::format_columns(&entry(hash.to_string()))
+            )?;
+        }
+        // the frames of a video in order, to find it inside other videos
+        let video = media.phashes.video_fingerprint();
+        if video.len() > 1 {
+            writeln!(
+                video_writer,
+                "{}",
+                self::format_columns(&entry(video.to_string()))
+            )?;
+        }
+        count += 1;
     }
 
-    // wait for thread pool to process all jobs
-    thread_pool.join();
-    drop(phash_sender);
+    hash_writer.flush()?;
+    phash_writer.flush()?;
+    pdq_writer.flush()?;
+    video_writer.flush()?;
 
-    Ok(count_files)
+    Ok(count)
 }
 
 // Worker writing the content to the file.
@@ -199,8 +347,121 @@ fn write_in_database(
     Ok(())
 }
 
-fn is_image(entry: &Path) -> bool {
-    matches!(entry.extension(), Some(e) if utils::media::is_image(&e.to_string_lossy().to_lowercase()))
+// Detects images by their signature as the scan does, so renamed images are
+// hashed too; the extension is only used when the signature is unknown.
+fn is_image(item: &EvidenceItem) -> bool {
+    let (media_type, _) = match &item.content {
+        Content::File(path) => {
+            let header = utils::media::read_header(path).unwrap_or_default();
+            Media::detect_type(&item.name, &header)
+        }
+        Content::Memory(data) => Media::detect_type(&item.name, data),
+    };
+    media_type == MediaType::Image
+}
+
+// Line of a hash database: the value followed by the tab separated source list,
+// category, original file name and series. Empty columns are left to the defaults.
+fn format_entry(value: &str, name: &str) -> String {
+    format!("{}\t\t\t{}", value, name)
+}
+
+// Line of the perceptual hash database: the columns of a hash database followed
+// by the algorithm of the perceptual hash.
+fn format_phash_entry(value: u64, name: &str, algorithm: PHashAlgorithm) -> String {
+    format!("{}\t\t\t{}\t\t{}", value, name, algorithm)
+}
+
+fn format_columns(entry: &Entry<String>) -> String {
+    let column = |value: Option<&str>| value.unwrap_or_default().replace(['\t', '\r', '\n'], " ");
+
+    [
+        column(Some(&entry.value)),
+        column(Some(&entry.source)),
+        column(entry.category.as_deref()),
+        column(entry.filename.as_deref()),
+        column(entry.series.as_deref()),
+    ]
+    .join("\t")
+}
+
+fn parse_entry<T: FromStr>(line: &str, source: &str) -> Option<Entry<T>> {
+    let mut columns = line.split('\t').map(str::trim);
+    let value = columns.next()?.parse::<T>().ok()?;
+    let mut column = || {
+        columns
+            .next()
+            .filter(|column| !column.is_empty())
+            .map(str::to_owned)
+    };
+
+    Some(Entry {
+        value,
+        source: column().unwrap_or_else(|| source.to_owned()),
+        category: column(),
+        filename: column(),
+        series: column(),
+    })
+}
+
+// Entries without the algorithm column were produced by the default one; entries
+// of an unknown algorithm are skipped, since they can not be matched.
+fn parse_phash_entry(line: &str) -> Option<(PHashAlgorithm, Entry<u64>)> {
+    let entry = self::parse_entry::<u64>(line, FILE_PHASH)?;
+    let algorithm = match line.split('\t').nth(5).map(str::trim) {
+        Some(name) if !name.is_empty() => PHashAlgorithm::from_name(name)?,
+        _ => PHashAlgorithm::default(),
+    };
+    Some((algorithm, entry))
+}
+
+// Lines of the PDQ database are in the columns of a hash database, or as written
+// by the reference hasher: the hash, its quality and the file name, separated by
+// commas.
+fn parse_pdq_entry(line: &str) -> Option<Entry<PdqHash>> {
+    if line.contains('\t') || !line.contains(',') {
+        return self::parse_entry::<PdqHash>(line, FILE_PDQ);
+    }
+
+    let mut columns = line.split(',').map(str::trim);
+    let value = columns.next()?.parse::<PdqHash>().ok()?;
+    Some(Entry {
+        filename: columns
+            .nth(1)
+            .filter(|column| !column.is_empty())
+            .map(str::to_owned),
+        ..Entry::new(value, FILE_PDQ)
+    })
+}
+
+// Identifies the content of a database file by its size and modification time.
+fn fingerprint(file: &File) -> String {
+    match file.metadata() {
+        Ok(metadata) => {
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|modified| modified.as_nanos())
+                .unwrap_or_default();
+            format!("{}:{}", metadata.len(), modified)
+        }
+        Err(_) => String::new(),
+    }
+}
+
+/// Returns the name and last modification of the database files, which
+/// identifies the version of the database used in a scan.
+pub fn database_versions(db_path: &Path) -> Vec<(&'static str, Option<SystemTime>)> {
+    [FILE_HASH, FILE_PHASH, FILE_PDQ, FILE_VIDEO, FILE_KEYWORD]
+        .into_iter()
+        .map(|name| {
+            let modified = fs::metadata(db_path.join(name))
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (name, modified)
+        })
+        .collect()
 }
 
 pub fn load_keyword_database(db_path: PathBuf, repo: Arc<dyn Repository>) -> anyhow::Result<()> {
@@ -213,14 +474,26 @@ pub fn load_keyword_database(db_path: PathBuf, repo: Arc<dyn Repository>) -> any
         .open(&path)
     {
         Ok(file) => {
-            repo.remove_all_keywords();
+            let fingerprint = self::fingerprint(&file);
+            if repo.is_synced(FILE_KEYWORD, &fingerprint) {
+                return Ok(());
+            }
+
+            repo.remove_all_keywords()?;
 
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
             let mut lines = utils::file_reader::Lines::new(file);
             while let Some(Ok(line)) = lines.next() {
                 if !line.trim().is_empty() {
-                    repo.add_keyword(line);
+                    batch.push(Entry::new(line.to_owned(), FILE_KEYWORD));
+                }
+                if batch.len() == BATCH_SIZE {
+                    repo.add_keywords(&batch)?;
+                    batch.clear();
                 }
             }
+            repo.add_keywords(&batch)?;
+            repo.set_synced(FILE_KEYWORD, &fingerprint)?;
         }
         Err(err) => anyhow::bail!("Could not open {} : {}", path.display(), err),
     }
@@ -238,12 +511,27 @@ pub fn load_hash_database(db_path: PathBuf, repo: Arc<dyn Repository>) -> anyhow
         .open(&path)
     {
         Ok(file) => {
-            repo.remove_all_hash();
+            let fingerprint = self::fingerprint(&file);
+            if repo.is_synced(FILE_HASH, &fingerprint) {
+                return Ok(());
+            }
+
+            repo.remove_all_hash()?;
 
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
             let mut lines = utils::file_reader::Lines::new(file);
             while let Some(Ok(line)) = lines.next() {
-                repo.add_hash(line);
+                match self::parse_entry::<String>(line, FILE_HASH) {
+                    Some(entry) if !entry.value.is_empty() => batch.push(entry),
+                    _ => (),
+                }
+                if batch.len() == BATCH_SIZE {
+                    repo.add_hashes(&batch)?;
+                    batch.clear();
+                }
             }
+            repo.add_hashes(&batch)?;
+            repo.set_synced(FILE_HASH, &fingerprint)?;
         }
         Err(err) => anyhow::bail!("Could not open {} : {}", path.display(), err),
     }
@@ -261,14 +549,109 @@ pub fn load_phash_database(db_path: PathBuf, repo: Arc<dyn Repository>) -> anyho
         .open(&path)
     {
         Ok(file) => {
-            repo.remove_all_phash();
+            let fingerprint = self::fingerprint(&file);
+            if repo.is_synced(FILE_PHASH, &fingerprint) {
+                return Ok(());
+            }
+
+            repo.remove_all_phash()?;
+
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            let mut batch_algorithm = PHashAlgorithm::default();
+            let mut lines = utils::file_reader::Lines::new(file);
+            while let Some(Ok(line)) = lines.next() {
+                if let Some((algorithm, entry)) = self::parse_phash_entry(line) {
+                    // a batch only has entries of the same algorithm
+                    if algorithm != batch_algorithm {
+                        repo.add_phashes(batch_algorithm, &batch)?;
+                        batch.clear();
+                        batch_algorithm = algorithm;
+                    }
+                    batch.push(entry);
+                }
+                if batch.len() == BATCH_SIZE {
+                    repo.add_phashes(batch_algorithm, &batch)?;
+                    batch.clear();
+                }
+            }
+            repo.add_phashes(batch_algorithm, &batch)?;
+            repo.set_synced(FILE_PHASH, &fingerprint)?;
+        }
+        Err(err) => anyhow::bail!("Could not open {} : {}", path.display(), err),
+    }
+
+    Ok(())
+}
+
+pub fn load_pdq_database(db_path: PathBuf, repo: Arc<dyn Repository>) -> anyhow::Result<()> {
+    let path = db_path.join(FILE_PDQ);
+
+    match OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(&path)
+    {
+        Ok(file) => {
+            let fingerprint = self::fingerprint(&file);
+            if repo.is_synced(FILE_PDQ, &fingerprint) {
+                return Ok(());
+            }
+
+            repo.remove_all_pdq()?;
+
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            let mut lines = utils::file_reader::Lines::new(file);
+            while let Some(Ok(line)) = lines.next() {
+                if let Some(entry) = self::parse_pdq_entry(line) {
+                    batch.push(entry);
+                }
+                if batch.len() == BATCH_SIZE {
+                    repo.add_pdqs(&batch)?;
+                    batch.clear();
+                }
+            }
+            repo.add_pdqs(&batch)?;
+            repo.set_synced(FILE_PDQ, &fingerprint)?;
+        }
+        Err(err) => anyhow::bail!("Could not open {} : {}", path.display(), err),
+    }
+
+    Ok(())
+}
+
+/// Loads the video database: one known video a line, its fingerprint followed
+/// by the columns of a hash database.
+pub fn load_video_database(db_path: PathBuf, repo: Arc<dyn Repository>) -> anyhow::Result<()> {
+    let path = db_path.join(FILE_VIDEO);
+
+    match OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(&path)
+    {
+        Ok(file) => {
+            let fingerprint = self::fingerprint(&file);
+            if repo.is_synced(FILE_VIDEO, &fingerprint) {
+                return Ok(());
+            }
 
+            repo.remove_all_video()?;
+
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
             let mut lines = utils::file_reader::Lines::new(file);
             while let Some(Ok(line)) = lines.next() {
-                if let Ok(hash) = line.parse::<u64>() {
-                    repo.add_phash(hash);
+                if let Some(entry) = self::parse_entry::<VideoFingerprint>(line, FILE_VIDEO) {
+                    batch.push(entry);
+                }
+                if batch.len() == BATCH_SIZE {
+                    repo.add_videos(&batch)?;
+                    batch.clear();
                 }
             }
+            repo.add_videos(&batch)?;
+            repo.set_synced(FILE_VIDEO, &fingerprint)?;
         }
         Err(err) => anyhow::bail!("Could not open {} : {}", path.display(), err),
     }
@@ -279,7 +662,9 @@ pub fn load_phash_database(db_path: PathBuf, repo: Arc<dyn Repository>) -> anyho
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::csam::repository::InMemoryRepository;
+    use crate::csam::{
+        repository::InMemoryRepository, DirectorySource, MediaOptions, MemorySource, PHashes,
+    };
 
     #[test]
     fn test_should_create_keyword_database() {
@@ -294,9 +679,9 @@ mod tests {
     #[test]
     fn test_should_create_hash_database() {
         let db_path = PathBuf::from("../data/tmp/");
-        let root = "../data/img/";
+        let mut source = DirectorySource::new("../data/img/");
         let cancel_flag = Arc::new(AtomicBool::new(false));
-        let total = create_hash_database(db_path, root, cancel_flag.clone())
+        let total = create_hash_database(db_path, &mut source, 0, cancel_flag.clone())
             .expect("Failed to create hash database.");
 
         // Assert
@@ -306,15 +691,33 @@ mod tests {
     #[test]
     fn test_should_create_phash_database() {
         let db_path = PathBuf::from("../data/tmp/");
-        let root = "../data/img/";
+        let mut source = DirectorySource::new("../data/img/");
         let cancel_flag = Arc::new(AtomicBool::new(false));
-        let total = create_phash_database(db_path, root, cancel_flag.clone())
+        let total = create_phash_database(db_path, &mut source, 0, cancel_flag.clone(), &[])
             .expect("Failed to create phash database.");
 
         // Assert
         assert!(total > 0);
     }
 
+    #[test]
+    fn test_should_create_phash_database_from_memory() {
+        let db_path = PathBuf::from("../data/tmp/memory/");
+        let mut source = MemorySource::new("memory");
+        source.add_file("horse.jpg", std::fs::read("../data/img/horse.jpg").unwrap());
+        source.add_file("notes.txt", b"not an image".to_vec());
+        // images are found by their signature, whatever their extension
+        source.add_file("horse.dat", std::fs::read("../data/img/horse.jpg").unwrap());
+        source.add_file("clip.jpg", std::fs::read("../data/video/vid.mp4").unwrap());
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let algorithms = [PHashAlgorithm::PHash, PHashAlgorithm::DHash];
+        let total = create_phash_database(db_path, &mut source, 0, cancel_flag, &algorithms)
+            .expect("Failed to create phash database.");
+
+        // Assert
+        assert_eq!(total, 2);
+    }
+
     #[test]
     fn test_should_load_keyword_database() {
         let db_path = PathBuf::from("../data/db/");
@@ -344,4 +747,194 @@ mod tests {
         // Assert
         assert!(repo.count_phash() > 0);
     }
+
+    #[test]
+    fn test_should_load_pdq_database() {
+        let db_path = PathBuf::from("../data/db/");
+        let repo = Arc::new(InMemoryRepository::new());
+        load_pdq_database(db_path, repo.clone()).expect("Failed to load pdq database.");
+
+        // Assert
+        assert!(repo.count_pdq() > 0);
+    }
+
+    #[test]
+    fn test_should_import_hash_set() {
+        let db_path = PathBuf::from("../data/tmp/vics/");
+        let file_path = PathBuf::from("../data/tmp/vics.json");
+        fs::create_dir_all(&db_path).unwrap();
+        let _ = fs::remove_file(db_path.join(FILE_HASH));
+        fs::write(
+            &file_path,
+            r#"{"value": [{"Category": 1, "Series": "S1", "MD5": "506bf7f41ca0c6f9e7612c04e93ab235"}]}"#,
+        )
+        .unwrap();
+
+        let total =
+            import_hash_set(db_path.clone(), &file_path).expect("Failed to import hash set.");
+        let repo = Arc::new(InMemoryRepository::new());
+        load_hash_database(db_path, repo.clone()).expect("Failed to load hash database.");
+        let entry = repo.find_hash("506bf7f41ca0c6f9e7612c04e93ab235").unwrap();
+
+        // Assert
+        assert_eq!(total, 1);
+        assert_eq!(entry.source, "vics.json");
+        assert_eq!(entry.category, Some(String::from("1")));
+        assert_eq!(entry.series, Some(String::from("S1")));
+    }
+
+    #[test]
+    fn test_should_add_tagged_media() {
+        let db_path = tempfile::tempdir().unwrap();
+        let data = fs::read("../data/img/horse.jpg").unwrap();
+        let repo: Arc<dyn Repository> = Arc::new(InMemoryRepository::new());
+        let media = Media::from_bytes(
+            repo,
+            String::from("/evidence/horse.jpg"),
+            String::from("horse.jpg"),
+            &data,
+            0,
+            &MediaOptions {
+                phash_algorithms: vec![PHashAlgorithm::DHash],
+                pdq: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let medias = [
+            Media {
+                tag: Some(Tag::Category2),
+                ..media.clone()
+            },
+            Media {
+                tag: Some(Tag::NotRelevant),
+                ..media.clone()
+            },
+            media.clone(),
+        ];
+
+        let total = add_tagged_media(db_path.path().to_path_buf(), "2024-001", &medias)
+            .expect("Failed to add tagged media.");
+        let repo = Arc::new(InMemoryRepository::new());
+        load_hash_database(db_path.path().to_path_buf(), repo.clone()).unwrap();
+        load_phash_database(db_path.path().to_path_buf(), repo.clone()).unwrap();
+        load_pdq_database(db_path.path().to_path_buf(), repo.clone()).unwrap();
+        let entry = repo.find_hash(&media.hash).unwrap();
+
+        // Assert
+        assert_eq!(total, 1);
+        assert_eq!(entry.source, "2024-001");
+        assert_eq!(entry.category, Some(String::from("Category 2")));
+        assert_eq!(entry.filename, Some(String::from("horse.jpg")));
+        for algorithm in [PHashAlgorithm::PHash, PHashAlgorithm::DHash] {
+            let phash = media.phashes.get(algorithm)[0];
+            assert_eq!(repo.match_phash(algorithm, phash, 0).unwrap().1, 0);
+        }
+        let pdq = media.phashes.pdq()[0];
+        assert_eq!(repo.match_pdq(&pdq.hash, 0).unwrap().0.source, "2024-001");
+        assert_eq!(repo.count_video(), 0);
+    }
+
+    #[test]
+    fn test_should_add_tagged_video() {
+        let db_path = tempfile::tempdir().unwrap();
+        let mut phashes = PHashes::new(
+            PHashAlgorithm::PHash,
+            vec![15634510955120228568, 0, 1085102592571150095],
+        );
+        phashes.set_times(vec![0.0, 1.0, 2.0]);
+        let media = Media {
+            name: String::from("video.mp4"),
+            path: String::from("/evidence/video.mp4"),
+            media_type: MediaType::Video,
+            file_type: None,
+            size: 0,
+            file_size: 0,
+            last_modified: 0,
+            hash: String::new(),
+            sha1: String::new(),
+            sha256: String::new(),
+            phash: 0,
+            phashes: phashes.clone(),
+            matched: None,
+            img_buf: None,
+            extension_mismatch: false,
+            tag: Some(Tag::Category1),
+        };
+
+        add_tagged_media(db_path.path().to_path_buf(), "2024-001", &[media])
+            .expect("Failed to add tagged media.");
+        let repo = Arc::new(InMemoryRepository::new());
+        load_video_database(db_path.path().to_path_buf(), repo.clone())
+            .expect("Failed to load video database.");
+        let (entry, alignment) = repo
+            .match_video(&phashes.video_fingerprint(), 0, 5)
+            .unwrap();
+
+        // Assert
+        assert_eq!(repo.count_video(), 1);
+        assert_eq!(entry.source, "2024-001");
+        assert_eq!(entry.filename, Some(String::from("video.mp4")));
+        assert_eq!(
+            entry.value.to_string(),
+            "0.000:D8F8F0E0E4E4F8D8 2.000:0F0F0F0F0F0F0F0F"
+        );
+        assert_eq!((alignment.start, alignment.end), (0.0, 2.0));
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let entry = parse_entry::<u64>("13658446371557175041\tNCMEC\t\timage.jpg", FILE_PHASH);
+        assert_eq!(
+            entry,
+            Some(Entry {
+                value: 13658446371557175041,
+                source: String::from("NCMEC"),
+                category: None,
+                filename: Some(String::from("image.jpg")),
+                series: None,
+            })
+        );
+
+        let entry = parse_entry::<String>("916400f0ad5d96406a8aae1968d65a48", FILE_HASH);
+        assert_eq!(
+            entry,
+            Some(Entry::new(
+                String::from("916400f0ad5d96406a8aae1968d65a48"),
+                FILE_HASH
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_phash_entry() {
+        let line = format_phash_entry(13658446371557175041, "image.jpg", PHashAlgorithm::DHash);
+        let (algorithm, entry) = parse_phash_entry(&line).unwrap();
+
+        // Assert
+        assert_eq!(algorithm, PHashAlgorithm::DHash);
+        assert_eq!(entry.value, 13658446371557175041);
+        assert_eq!(entry.filename, Some(String::from("image.jpg")));
+        assert_eq!(
+            parse_phash_entry("13658446371557175041\tNCMEC").map(|(algorithm, _)| algorithm),
+            Some(PHashAlgorithm::PHash)
+        );
+        assert_eq!(parse_phash_entry("13658446371557175041\t\t\t\t\tPDQ"), None);
+    }
+
+    #[test]
+    fn test_parse_pdq_entry() {
+        let hash = "f8f8f0cee0f4a84f06370a22038f63f0b36e2ed596621e1d33e6b39c4e9c9b22";
+        let entry = parse_pdq_entry(&format!("{hash},100,image.jpg")).unwrap();
+
+        // Assert
+        assert_eq!(entry.value.to_string(), hash);
+        assert_eq!(entry.source, FILE_PDQ);
+        assert_eq!(entry.filename, Some(String::from("image.jpg")));
+        let entry = parse_pdq_entry(&format!("{hash}\tNCMEC\tA1")).unwrap();
+        assert_eq!(entry.source, "NCMEC");
+        assert_eq!(entry.category, Some(String::from("A1")));
+        assert!(parse_pdq_entry(hash).is_some());
+        assert_eq!(parse_pdq_entry("13658446371557175041"), None);
+    }
 }
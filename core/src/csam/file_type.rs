@@ -0,0 +1,244 @@
+use std::fmt;
+
+use super::MediaType;
+
+/// Type of a media file detected from its content (magic bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Jpeg,
+    Png,
+    Gif,
+    Bmp,
+    Tiff,
+    WebP,
+    Heic,
+    Mp4,
+    Mov,
+    ThreeGp,
+    Avi,
+    Mkv,
+    WebM,
+    Flv,
+    MpegTs,
+    Mpeg,
+    Ogg,
+    Wmv,
+}
+
+// Size of the MPEG transport stream packets, each starting with 0x47.
+const TS_PACKET_LEN: usize = 188;
+
+impl FileType {
+    /// Bytes of the beginning of the file needed to detect its type.
+    pub const HEADER_LEN: usize = 512;
+
+    pub const ALL: [FileType; 18] = [
+        Self::Jpeg,
+        Self::Png,
+        Self::Gif,
+        Self::Bmp,
+        Self::Tiff,
+        Self::WebP,
+        Self::Heic,
+        Self::Mp4,
+        Self::Mov,
+        Self::ThreeGp,
+        Self::Avi,
+        Self::Mkv,
+        Self::WebM,
+        Self::Flv,
+        Self::MpegTs,
+        Self::Mpeg,
+        Self::Ogg,
+        Self::Wmv,
+    ];
+
+    /// Detects the type from the first bytes of the file.
+    pub fn from_signature(header: &[u8]) -> Option<Self> {
+        let starts_with = |signature: &[u8]| header.starts_with(signature);
+        let at = |offset: usize, signature: &[u8]| {
+            header.get(offset..offset + signature.len()) == Some(signature)
+        };
+
+        if starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            Some(Self::Png)
+        } else if starts_with(b"GIF87a") || starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if starts_with(b"BM") && Self::is_bmp(header) {
+            Some(Self::Bmp)
+        } else if starts_with(b"II*\0") || starts_with(b"MM\0*") {
+            Some(Self::Tiff)
+        } else if starts_with(b"RIFF") && at(8, b"WEBP") {
+            Some(Self::WebP)
+        } else if starts_with(b"RIFF") && at(8, b"AVI ") {
+            Some(Self::Avi)
+        } else if at(4, b"ftyp") {
+            Self::from_brand(header.get(8..12)?)
+        } else if at(4, b"moov") || at(4, b"mdat") || at(4, b"wide") || at(4, b"free") {
+            Some(Self::Mov)
+        } else if starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            // EBML document, WebM when its doc type is `webm`
+            let doc_type = &header[..header.len().min(64)];
+            if doc_type.windows(4).any(|window| window == b"webm") {
+                Some(Self::WebM)
+            } else {
+                Some(Self::Mkv)
+            }
+        } else if starts_with(b"FLV\x01") {
+            Some(Self::Flv)
+        } else if starts_with(b"OggS") {
+            Some(Self::Ogg)
+        } else if starts_with(&[0x30, 0x26, 0xB2, 0x75, 0x8E, 0x66, 0xCF, 0x11]) {
+            Some(Self::Wmv)
+        } else if starts_with(&[0x00, 0x00, 0x01, 0xBA]) || starts_with(&[0x00, 0x00, 0x01, 0xB3]) {
+            Some(Self::Mpeg)
+        } else if header.len() > 2 * TS_PACKET_LEN
+            && (0..3).all(|packet| header[packet * TS_PACKET_LEN] == 0x47)
+        {
+            Some(Self::MpegTs)
+        } else {
+            None
+        }
+    }
+
+    // ISO base media file, identified by the major brand of the `ftyp` box.
+    fn from_brand(brand: &[u8]) -> Option<Self> {
+        match brand {
+            b"heic" | b"heix" | b"hevc" | b"hevx" | b"heim" | b"heis" | b"mif1" | b"msf1" => {
+                Some(Self::Heic)
+            }
+            b"qt  " => Some(Self::Mov),
+            [b'3', b'g', ..] => Some(Self::ThreeGp),
+            _ => Some(Self::Mp4),
+        }
+    }
+
+    // "BM" is too short to identify a bitmap, so the size of its DIB header is checked too.
+    fn is_bmp(header: &[u8]) -> bool {
+        match header.get(14..18) {
+            Some(size) => matches!(
+                u32::from_le_bytes([size[0], size[1], size[2], size[3]]),
+                12 | 16 | 40 | 52 | 56 | 64 | 108 | 124
+            ),
+            None => false,
+        }
+    }
+
+    pub fn media_type(&self) -> MediaType {
+        match self {
+            Self::Jpeg
+            | Self::Png
+            | Self::Gif
+            | Self::Bmp
+            | Self::Tiff
+            | Self::WebP
+            | Self::Heic => MediaType::Image,
+            _ => MediaType::Video,
+        }
+    }
+
+    /// Extensions of the type, in lower case.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::Jpeg => &["jpg", "jpeg", "jpe", "jfif"],
+            Self::Png => &["png"],
+            Self::Gif => &["gif"],
+            Self::Bmp => &["bmp", "dib"],
+            Self::Tiff => &["tif", "tiff"],
+            Self::WebP => &["webp"],
+            Self::Heic => &["heic", "heif"],
+            Self::Mp4 => &["mp4", "m4v"],
+            Self::Mov => &["mov", "qt"],
+            Self::ThreeGp => &["3gp", "3g2"],
+            Self::Avi => &["avi"],
+            Self::Mkv => &["mkv"],
+            Self::WebM => &["webm"],
+            Self::Flv => &["flv"],
+            Self::MpegTs => &["ts", "mts", "m2ts"],
+            Self::Mpeg => &["mpg", "mpeg", "vob"],
+            Self::Ogg => &["ogg", "ogv"],
+            Self::Wmv => &["wmv", "asf"],
+        }
+    }
+
+    /// Returns `true` when the extension does not belong to the type.
+    pub fn is_extension_mismatch(&self, extension: Option<&str>) -> bool {
+        match extension {
+            Some(extension) => !self
+                .extensions()
+                .contains(&extension.to_lowercase().as_str()),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Jpeg => "JPEG",
+            Self::Png => "PNG",
+            Self::Gif => "GIF",
+            Self::Bmp => "BMP",
+            Self::Tiff => "TIFF",
+            Self::WebP => "WebP",
+            Self::Heic => "HEIC",
+            Self::Mp4 => "MP4",
+            Self::Mov => "QuickTime",
+            Self::ThreeGp => "3GP",
+            Self::Avi => "AVI",
+            Self::Mkv => "Matroska",
+            Self::WebM => "WebM",
+            Self::Flv => "FLV",
+            Self::MpegTs => "MPEG-TS",
+            Self::Mpeg => "MPEG",
+            Self::Ogg => "Ogg",
+            Self::Wmv => "WMV",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_signature() {
+        let header = std::fs::read("../data/img/horse.jpg").unwrap();
+        let mut ts = vec![0u8; 3 * TS_PACKET_LEN];
+        ts[0] = 0x47;
+        ts[TS_PACKET_LEN] = 0x47;
+        ts[2 * TS_PACKET_LEN] = 0x47;
+        let webm = b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81\x01\x42\x82\x84webm";
+
+        // Assert
+        assert_eq!(FileType::from_signature(&header), Some(FileType::Jpeg));
+        assert_eq!(
+            FileType::from_signature(b"\0\0\0\x18ftypmp42"),
+            Some(FileType::Mp4)
+        );
+        assert_eq!(
+            FileType::from_signature(b"\0\0\0\x18ftypheic"),
+            Some(FileType::Heic)
+        );
+        assert_eq!(
+            FileType::from_signature(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(FileType::WebP)
+        );
+        assert_eq!(FileType::from_signature(webm), Some(FileType::WebM));
+        assert_eq!(FileType::from_signature(&ts), Some(FileType::MpegTs));
+        assert_eq!(FileType::from_signature(b"BM plain text"), None);
+        assert_eq!(FileType::from_signature(b""), None);
+    }
+
+    #[test]
+    fn test_extension_mismatch() {
+        // Assert
+        assert!(!FileType::Jpeg.is_extension_mismatch(Some("JPG")));
+        assert!(FileType::Jpeg.is_extension_mismatch(Some("dat")));
+        assert!(FileType::Mp4.is_extension_mismatch(None));
+        assert_eq!(FileType::Heic.media_type(), MediaType::Image);
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// Cryptographic hash algorithms supported in the hash database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum HashAlgorithm {
+    MD5,
+    SHA1,
+    SHA256,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 3] = [Self::MD5, Self::SHA1, Self::SHA256];
+
+    /// Detects the algorithm of a hexadecimal digest by its length.
+    pub fn from_digest(digest: &str) -> Option<Self> {
+        if !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        Self::from_digest_len(digest.len())
+    }
+
+    pub fn from_digest_len(len: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.digest_len() == len)
+    }
+
+    /// Length of the digest in hexadecimal.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            Self::MD5 => 32,
+            Self::SHA1 => 40,
+            Self::SHA256 => 64,
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MD5 => write!(f, "MD5"),
+            Self::SHA1 => write!(f, "SHA-1"),
+            Self::SHA256 => write!(f, "SHA-256"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_digest() {
+        let md5 = "506bf7f41ca0c6f9e7612c04e93ab235";
+        let sha1 = "b7b6e21916253608c9ff081db046a58100536963";
+        let sha256 = "9b7bc4bef1e301d3b6d4be5a9af84cce7960b518b78dd39d6da60df5c1c6f943";
+
+        // Assert
+        assert_eq!(HashAlgorithm::from_digest(md5), Some(HashAlgorithm::MD5));
+        assert_eq!(HashAlgorithm::from_digest(sha1), Some(HashAlgorithm::SHA1));
+        assert_eq!(
+            HashAlgorithm::from_digest(sha256),
+            Some(HashAlgorithm::SHA256)
+        );
+        assert_eq!(HashAlgorithm::from_digest("506bf7f41ca0c6f9"), None);
+        assert_eq!(
+            HashAlgorithm::from_digest("x06bf7f41ca0c6f9e7612c04e93ab235"),
+            None
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// Alteration of an image hashed besides the original, so copies that were
+/// rotated, mirrored or cropped still match the perceptual hash database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ImageTransform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    /// Mirrored along the main diagonal.
+    Transpose,
+    /// Mirrored along the other diagonal.
+    Transverse,
+    /// The centre of the image, without the borders added around a copy.
+    CenterCrop,
+}
+
+impl ImageTransform {
+    /// The rotations and mirrors of the image besides itself, and the centre crop.
+    pub const ALL: [ImageTransform; 8] = [
+        Self::Rotate90,
+        Self::Rotate180,
+        Self::Rotate270,
+        Self::FlipHorizontal,
+        Self::FlipVertical,
+        Self::Transpose,
+        Self::Transverse,
+        Self::CenterCrop,
+    ];
+
+    /// Parses the name written by `Display`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|transform| transform.to_string() == name)
+    }
+
+    // Number of the transform in the encoding of `PHashes`, never reused.
+    pub(crate) fn id(&self) -> u8 {
+        match self {
+            Self::Rotate90 => 1,
+            Self::Rotate180 => 2,
+            Self::Rotate270 => 3,
+            Self::FlipHorizontal => 4,
+            Self::FlipVertical => 5,
+            Self::Transpose => 6,
+            Self::Transverse => 7,
+            Self::CenterCrop => 8,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|transform| transform.id() == id)
+    }
+}
+
+impl fmt::Display for ImageTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Rotate90 => write!(f, "Rotated 90°"),
+            Self::Rotate180 => write!(f, "Rotated 180°"),
+            Self::Rotate270 => write!(f, "Rotated 270°"),
+            Self::FlipHorizontal => write!(f, "Mirrored horizontally"),
+            Self::FlipVertical => write!(f, "Mirrored vertically"),
+            Self::Transpose => write!(f, "Transposed"),
+            Self::Transverse => write!(f, "Transversed"),
+            Self::CenterCrop => write!(f, "Centre crop"),
+        }
+    }
+}
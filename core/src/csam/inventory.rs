@@ -0,0 +1,231 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+
+use super::{MatchType, Media, MediaType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InventoryFormat {
+    Csv,
+    JsonLines,
+}
+
+impl InventoryFormat {
+    /// Format of the file by its extension, CSV when it is not `.jsonl` or `.json`.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().map(|e| e.to_string_lossy().to_lowercase()) {
+            Some(e) if e == "jsonl" || e == "json" => Self::JsonLines,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// Media seen during a scan, or the error that prevented it from being processed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct InventoryRecord {
+    pub path: String,
+    pub name: String,
+    pub media_type: String,
+    /// Type detected from the file signature.
+    pub file_type: String,
+    pub extension_mismatch: Option<bool>,
+    /// Size of the file in bytes.
+    pub size: Option<u64>,
+    pub last_modified: Option<String>,
+    pub md5: String,
+    pub phash: String,
+    pub match_type: String,
+    pub distance: Option<u32>,
+    /// Alteration of the media undone to match the perceptual hash.
+    pub transform: String,
+    /// Time ranges of a video match, in the media and in the known video.
+    pub alignment: String,
+    /// Classification given by the examiner.
+    pub tag: String,
+    pub error: String,
+}
+
+impl InventoryRecord {
+    pub fn error(path: &str, error: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            name: Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            error: error.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Media> for InventoryRecord {
+    fn from(media: &Media) -> Self {
+        let matched = media.matched.as_ref();
+
+        Self {
+            path: media.path.clone(),
+            name: media.name.clone(),
+            media_type: match media.media_type {
+                MediaType::Image => String::from("image"),
+                MediaType::Video => String::from("video"),
+            },
+            file_type: media
+                .file_type
+                .map(|file_type| file_type.to_string())
+                .unwrap_or_default(),
+            extension_mismatch: Some(media.extension_mismatch),
+            size: Some(media.file_size),
+            last_modified: Utc
+                .timestamp_opt(media.last_modified, 0)
+                .single()
+                .map(|date_time| date_time.format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+            md5: media.hash.clone(),
+            phash: format!("{:016X}", media.phash),
+            match_type: matched
+                .map(|matched| match matched.match_type {
+                    MatchType::Hash(algorithm) => algorithm.to_string(),
+                    MatchType::PHash(algorithm) => algorithm.to_string(),
+                    MatchType::Pdq => String::from("PDQ"),
+                    MatchType::Video => String::from("Video"),
+                    MatchType::Keyword => String::from("Keyword"),
+                })
+                .unwrap_or_default(),
+            distance: matched.map(|matched| matched.distance),
+            transform: matched
+                .and_then(|matched| matched.transform)
+                .map(|transform| transform.to_string())
+                .unwrap_or_default(),
+            alignment: matched
+                .and_then(|matched| matched.alignment)
+                .map(|alignment| alignment.to_string())
+                .unwrap_or_default(),
+            tag: media.tag.map(|tag| tag.to_string()).unwrap_or_default(),
+            error: String::new(),
+        }
+    }
+}
+
+/// Writes the inventory in the given format.
+pub fn write<W: Write>(
+    writer: W,
+    format: InventoryFormat,
+    records: &[InventoryRecord],
+) -> anyhow::Result<()> {
+    match format {
+        InventoryFormat::Csv => write_csv(writer, records),
+        InventoryFormat::JsonLines => write_jsonl(writer, records),
+    }
+}
+
+pub fn write_csv<W: Write>(writer: W, records: &[InventoryRecord]) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for record in records.iter() {
+        writer.serialize(record)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes one JSON object per line (JSON Lines).
+pub fn write_jsonl<W: Write>(writer: W, records: &[InventoryRecord]) -> anyhow::Result<()> {
+    let mut writer = io::BufWriter::new(writer);
+    for record in records.iter() {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csam::{FileType, HashAlgorithm, Match, PHashAlgorithm, PHashes, Tag};
+
+    fn records() -> Vec<InventoryRecord> {
+        let media = Media {
+            name: String::from("horse.jpg"),
+            path: String::from("/evidence/horse.jpg"),
+            media_type: MediaType::Image,
+            file_type: Some(FileType::Jpeg),
+            size: 1,
+            file_size: 1024,
+            last_modified: 0,
+            hash: String::from("506bf7f41ca0c6f9e7612c04e93ab235"),
+            sha1: String::new(),
+            sha256: String::new(),
+            phash: 15634510955120228568,
+            phashes: PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568]),
+            matched: Some(Match {
+                match_type: MatchType::Hash(HashAlgorithm::MD5),
+                reference: String::from("506bf7f41ca0c6f9e7612c04e93ab235"),
+                distance: 0,
+                source: String::from("hash.txt"),
+                category: None,
+                filename: None,
+                series: None,
+                transform: None,
+                alignment: None,
+            }),
+            img_buf: None,
+            extension_mismatch: false,
+            tag: Some(Tag::Category1),
+        };
+        vec![
+            InventoryRecord::from(&media),
+            InventoryRecord::error("/evidence/broken.png", "invalid image, \"truncated\""),
+        ]
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut buf = Vec::new();
+        write_csv(&mut buf, &records()).expect("Failed to write inventory.");
+        let csv = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        // Assert
+        assert_eq!(
+            lines[0],
+            "path,name,media_type,file_type,extension_mismatch,size,last_modified,md5,phash,\
+            match_type,distance,transform,alignment,tag,error"
+        );
+        assert_eq!(
+            lines[1],
+            "/evidence/horse.jpg,horse.jpg,image,JPEG,false,1024,1970-01-01T00:00:00Z,\
+            506bf7f41ca0c6f9e7612c04e93ab235,D8F8F0E0E4E4F8D8,MD5,0,,,Category 1,"
+        );
+        assert_eq!(
+            lines[2],
+            "/evidence/broken.png,broken.png,,,,,,,,,,,,,\"invalid image, \"\"truncated\"\"\""
+        );
+    }
+
+    #[test]
+    fn test_write_jsonl() {
+        let mut buf = Vec::new();
+        write_jsonl(&mut buf, &records()).expect("Failed to write inventory.");
+        let lines: Vec<serde_json::Value> = String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        // Assert
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["size"], 1024);
+        assert_eq!(lines[0]["match_type"], "MD5");
+        assert_eq!(lines[0]["tag"], "Category 1");
+        assert_eq!(lines[1]["size"], serde_json::Value::Null);
+        assert_eq!(lines[1]["error"], "invalid image, \"truncated\"");
+        assert_eq!(
+            InventoryFormat::from_path(Path::new("inventory.JSONL")),
+            InventoryFormat::JsonLines
+        );
+    }
+}
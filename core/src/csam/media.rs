@@ -1,11 +1,17 @@
-use super::repository::Repository;
-use crate::utils;
+use super::cache::{Analysis, ScanCache};
+use super::repository::{Entry, Repository};
+use super::{
+    Content, EvidenceItem, FileType, HashAlgorithm, ImageTransform, PHashAlgorithm, PHashes, Tag,
+    VideoSampling,
+};
+use crate::utils::{self, hasher::Hashes, pdq::Pdq, video_fingerprint::Alignment};
 
 use anyhow::{Context, Result};
 use bytes::Bytes;
-use chrono::Local;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
 use std::sync::Arc;
-use walkdir::DirEntry;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MediaType {
@@ -13,11 +19,110 @@ pub enum MediaType {
     Video,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchType {
+    Hash(HashAlgorithm),
+    PHash(PHashAlgorithm),
+    Pdq,
+    /// Frames of a known video found in order inside the video.
+    Video,
+    Keyword,
+}
+
+/// Entry of the CSAM database matched by a media.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub match_type: MatchType,
+    /// Matched value of the database: the hash, the keyword or the
+    /// perceptual hash in hexadecimal, of the first matched frame of a video.
+    pub reference: String,
+    /// Hamming distance of a perceptual hash match.
+    pub distance: u32,
+    pub source: String,
+    pub category: Option<String>,
+    pub filename: Option<String>,
+    pub series: Option<String>,
+    /// Alteration of the media undone to match the perceptual hash.
+    pub transform: Option<ImageTransform>,
+    /// Time ranges of a video match, in the media and in the known video.
+    pub alignment: Option<Alignment>,
+}
+
+impl Match {
+    fn new(match_type: MatchType, entry: Entry<String>, distance: u32) -> Self {
+        Self {
+            match_type,
+            reference: entry.value,
+            distance,
+            source: entry.source,
+            category: entry.category,
+            filename: entry.filename,
+            series: entry.series,
+            transform: None,
+            alignment: None,
+        }
+    }
+}
+
+impl fmt::Display for Match {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.match_type {
+            MatchType::Hash(algorithm) => return write!(f, "{algorithm}"),
+            MatchType::Keyword => return write!(f, "Keyword [ {} ]", self.reference),
+            MatchType::PHash(algorithm) => algorithm.to_string(),
+            MatchType::Pdq => String::from("PDQ"),
+            MatchType::Video => String::from("Video"),
+        };
+        if let Some(alignment) = self.alignment {
+            return write!(f, "{name} [ {} ] {alignment}", self.distance);
+        }
+        match self.transform {
+            Some(transform) => write!(f, "{name} [ {} ] {transform}", self.distance),
+            None => write!(f, "{name} [ {} ]", self.distance),
+        }
+    }
+}
+
+/// What is computed for every media besides MD5 and the default perceptual
+/// hash, and how it is matched.
 #[derive(Debug, Clone)]
-enum MatchType {
-    MD5,
-    PHash(u64, u32),
-    Keyword(String),
+pub struct MediaOptions {
+    /// Algorithms of the hashes in the database.
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    /// Algorithms of the perceptual hashes in the database.
+    pub phash_algorithms: Vec<PHashAlgorithm>,
+    /// Also hashes the rotations, mirrors and centre crop of every frame, which
+    /// multiplies the lookups in the perceptual hash database by nine.
+    pub transforms: bool,
+    /// Whether the database has PDQ hashes.
+    pub pdq: bool,
+    /// Highest Hamming distance of a PDQ match.
+    pub pdq_threshold: u32,
+    /// How the frames of the videos are sampled.
+    pub video_sampling: VideoSampling,
+}
+
+impl Default for MediaOptions {
+    fn default() -> Self {
+        Self {
+            hash_algorithms: Vec::new(),
+            phash_algorithms: Vec::new(),
+            transforms: false,
+            pdq: false,
+            pdq_threshold: Media::PDQ_THRESHOLD,
+            video_sampling: VideoSampling::default(),
+        }
+    }
+}
+
+// File of a media, on disk or in memory.
+struct MediaFile {
+    name: String,
+    path: String,
+    media_type: MediaType,
+    file_type: Option<FileType>,
+    size: u64,
+    last_modified: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -25,172 +130,958 @@ pub struct Media {
     pub name: String,
     pub path: String,
     pub media_type: MediaType,
+    /// Type detected from the content of the file.
+    pub file_type: Option<FileType>,
     pub size: usize,
+    /// Size of the file in bytes.
+    pub file_size: u64,
     pub last_modified: i64,
     pub hash: String,
+    pub sha1: String,
+    pub sha256: String,
     pub phash: u64,
-    pub match_type: String,
-    pub hamming: u32,
+    /// Perceptual hashes of every frame, kept to match the media again.
+    pub phashes: PHashes,
+    pub matched: Option<Match>,
     pub img_buf: Option<Bytes>,
+    /// The extension of the file does not match its detected type.
+    pub extension_mismatch: bool,
+    /// Classification given by the examiner.
+    pub tag: Option<Tag>,
 }
 
 impl Media {
     pub const THUMBNAIL_SIZE: u32 = 240;
     pub const MAX_DISTANCE_HAMMING: u32 = 20;
+    /// Highest Hamming distance of a PDQ match by default, out of 256 bits.
+    pub const PDQ_THRESHOLD: u32 = 31;
+    /// Lowest quality of a frame matched by its PDQ hash; below it the frame is
+    /// too featureless and matches unrelated images.
+    pub const PDQ_MIN_QUALITY: u32 = 50;
+    /// Highest Hamming distance of two frames aligned in a video match, below
+    /// the one of a single image since the frames are many.
+    pub const VIDEO_FRAME_DISTANCE: u32 = 10;
+    /// Frames of a known video that must be found in order inside a video.
+    pub const VIDEO_MIN_FRAMES: usize = 5;
 
-    pub fn new(repo: Arc<dyn Repository>, entry: DirEntry) -> Result<Self> {
-        let metadata = entry
-            .metadata()
-            .with_context(|| "could not get file metadata")?;
-        let name = entry.file_name().to_str().unwrap().to_owned();
-        let media_path = entry.path().to_str().unwrap().to_owned();
+    /// Processes the media, computing besides MD5 and the default perceptual
+    /// hash only what the `options` ask for.
+    ///
+    /// With a `cache`, the hashes, perceptual hashes and thumbnail of a media
+    /// processed before are reused and only the match is done again.
+    pub fn new(
+        repo: Arc<dyn Repository>,
+        item: EvidenceItem,
+        options: &MediaOptions,
+        cache: Option<&ScanCache>,
+    ) -> Result<Self> {
+        // get the media type, by the signature of the file when it is known
+        let (media_type, file_type) = match &item.content {
+            Content::File(path) => {
+                let header = utils::media::read_header(path).unwrap_or_default();
+                Media::detect_type(&item.name, &header)
+            }
+            Content::Memory(data) => Media::detect_type(&item.name, data),
+        };
 
-        // get the media type
-        let media_type = match entry.path().extension() {
-            Some(e) if utils::media::is_image(&e.to_string_lossy().to_lowercase()) => {
-                MediaType::Image
+        // the cached media is only reused when it has every hash of the database
+        let algorithms = Media::algorithms(&options.hash_algorithms);
+        let phash_algorithms = Media::phash_algorithms(&options.phash_algorithms);
+        let cached = cache
+            .and_then(|cache| cache.get(&item.path, item.size, item.last_modified))
+            .filter(|analysis| {
+                algorithms
+                    .iter()
+                    .all(|algorithm| !analysis.hashes.get(*algorithm).is_empty())
+                    && phash_algorithms.iter().all(|algorithm| {
+                        analysis.phashes.contains(*algorithm)
+                            && (!options.transforms
+                                || analysis.phashes.contains_transformed(*algorithm))
+                    })
+                    && (!options.pdq || analysis.phashes.contains_pdq(options.transforms))
+                    && (media_type == MediaType::Image
+                        || analysis.sampling == Some(options.video_sampling))
+            });
+
+        let analysis = match cached {
+            Some(analysis) => analysis,
+            None => {
+                let analysis =
+                    Media::analyze(&item, media_type, &algorithms, &phash_algorithms, options)?;
+                // a media that could not be decoded is not cached, to be tried again
+                if let (Some(cache), Some(_)) = (cache, &analysis.thumbnail) {
+                    cache.insert(&item.path, item.size, item.last_modified, &analysis);
+                }
+                analysis
             }
-            _ => MediaType::Video,
         };
-        // get the media size
-        let media_size = (metadata.len() as f64 / 1024.0_f64).round() as usize;
-        // get the last modification date
-        let media_last_modified = Local::now().timestamp()
-            - metadata
-                .modified()
-                .with_context(|| "could not get attribute `modified` from metadata")?
-                .elapsed()
-                .with_context(|| "could not get elapsed time from metadata")?
-                .as_secs() as i64;
-
-        // get the md5 hash of the file
-        let md5_hash = utils::media::get_md5_hash_of_file(&media_path).unwrap_or_default();
-
-        // make thumbnail
-        let (dynamic_img, img_buf) = match media_type {
-            MediaType::Image => {
-                match utils::media::make_thumbnail_to_vec(&media_path, Self::THUMBNAIL_SIZE) {
-                    Ok((img, buf)) => (Some(vec![img]), Some(Bytes::from(buf))),
-                    Err(err) => {
-                        tracing::error!("{} : {}", media_path.as_str(), err);
-                        (None, None)
+
+        let file = MediaFile {
+            name: item.name,
+            path: item.path,
+            media_type,
+            file_type,
+            size: item.size,
+            last_modified: item.last_modified,
+        };
+        Media::build(repo, file, analysis, options)
+    }
+
+    /// Processes a media that is only in memory, such as a file inside an archive.
+    pub fn from_bytes(
+        repo: Arc<dyn Repository>,
+        path: String,
+        name: String,
+        data: &[u8],
+        last_modified: i64,
+        options: &MediaOptions,
+    ) -> Result<Self> {
+        let item = EvidenceItem {
+            path,
+            name,
+            size: data.len() as u64,
+            last_modified,
+            content: Content::Memory(data.to_vec()),
+        };
+        Media::new(repo, item, options, None)
+    }
+
+    // Hashes, perceptual hashes and thumbnail of the media.
+    fn analyze(
+        item: &EvidenceItem,
+        media_type: MediaType,
+        algorithms: &[HashAlgorithm],
+        phash_algorithms: &[PHashAlgorithm],
+        options: &MediaOptions,
+    ) -> Result<Analysis> {
+        let MediaOptions {
+            transforms,
+            pdq,
+            video_sampling,
+            ..
+        } = *options;
+        let (hashes, frames) = match &item.content {
+            Content::File(path) => {
+                // get the hashes of the file in a single pass
+                let hashes = utils::hasher::hash_file(path, algorithms).unwrap_or_default();
+
+                // make thumbnail
+                let frames = match media_type {
+                    MediaType::Image => image::open(path)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|img| utils::media::image_frames(img, Self::THUMBNAIL_SIZE, pdq)),
+                    MediaType::Video => {
+                        utils::media::decoder::make_thumbnail_to_vec(path, video_sampling, pdq)
                     }
-                }
+                };
+                (hashes, frames)
+            }
+            Content::Memory(data) => {
+                let hashes =
+                    utils::hasher::hash_reader(data.as_slice(), algorithms).unwrap_or_default();
+
+                let frames = match media_type {
+                    MediaType::Image => image::load_from_memory(data)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|img| utils::media::image_frames(img, Self::THUMBNAIL_SIZE, pdq)),
+                    MediaType::Video => {
+                        // the decoder only reads videos from files
+                        let extension = Path::new(&item.name)
+                            .extension()
+                            .map(|e| format!(".{}", e.to_string_lossy()))
+                            .unwrap_or_default();
+                        let mut file = tempfile::Builder::new().suffix(&extension).tempfile()?;
+                        file.write_all(data)?;
+                        utils::media::decoder::make_thumbnail_to_vec(
+                            file.path(),
+                            video_sampling,
+                            pdq,
+                        )
+                    }
+                };
+                (hashes, frames)
             }
-            MediaType::Video => match utils::media::decoder::make_thumbnail_to_vec(&media_path) {
-                Ok((imgs, buf)) => (Some(imgs), Some(Bytes::from(buf))),
-                Err(err) => {
-                    tracing::error!("{} : {}", media_path.as_str(), err);
-                    (None, None)
-                }
-            },
         };
 
-        // perceptual hash of the file
-        #[allow(clippy::unnecessary_unwrap)]
-        let phash_vec = if media_size > 0 && dynamic_img.is_some() {
-            dynamic_img
-                .unwrap()
-                .into_iter()
-                .map(|img| {
-                    utils::media::get_perceptual_hash_of_image(img)
-                        .with_context(|| "could not generate perceptual hash")
-                })
-                .collect::<anyhow::Result<Vec<u64>>>()
-        } else {
-            Ok(vec![0])
+        let (frames, thumbnail) = match frames {
+            Ok(frames) => {
+                let thumbnail = frames.thumbnail.clone();
+                (Some(frames), Some(thumbnail))
+            }
+            Err(err) => {
+                tracing::error!("{} : {}", item.path.as_str(), err);
+                (None, None)
+            }
         };
 
-        // checks if the media is in the CSAM database
-        let (phash, match_type, distance_hamming) = {
-            let phash_vec = phash_vec?;
-
-            if phash_vec.len() > 0 {
-                let phash = phash_vec[0];
-                match Media::find_csam(repo.clone(), &name, &md5_hash, &phash_vec) {
-                    Some(match_type) => match match_type {
-                        MatchType::MD5 => (phash, String::from("MD5"), 0),
-                        MatchType::Keyword(keyword) => (phash, format!("Keyword [ {keyword} ]"), 0),
-                        MatchType::PHash(phash, distance_hamming) => (
-                            phash,
-                            format!("PHash [ {distance_hamming} ]"),
-                            distance_hamming,
-                        ),
-                    },
-                    None => (0u64, String::new(), 0u32),
+        // perceptual hashes of the file
+        let frames = frames.filter(|_| Media::size_in_kb(item.size) > 0);
+        let hash_frames =
+            |algorithm: PHashAlgorithm, transform: Option<ImageTransform>| match &frames {
+                Some(frames) => frames
+                    .images
+                    .iter()
+                    .map(|img| {
+                        let img = match transform {
+                            Some(transform) => utils::media::transform_image(img, transform),
+                            None => img.clone(),
+                        };
+                        utils::media::get_perceptual_hash_of_image(img, algorithm)
+                            .with_context(|| "could not generate perceptual hash")
+                    })
+                    .collect::<anyhow::Result<Vec<u64>>>(),
+                None => Ok(vec![0]),
+            };
+
+        let mut phashes = PHashes::default();
+        if let Some(frames) = &frames {
+            phashes.set_times(frames.times.clone());
+        }
+        for algorithm in phash_algorithms.iter() {
+            phashes.insert(*algorithm, hash_frames(*algorithm, None)?);
+            if transforms {
+                for transform in ImageTransform::ALL {
+                    let frames = hash_frames(*algorithm, Some(transform))?;
+                    phashes.insert_transformed(*algorithm, transform, frames);
                 }
-            } else {
-                (0u64, String::new(), 0u32)
             }
+        }
+
+        // PDQ hashes of the frames, only when the database has them, from the
+        // decoded frames as in the reference implementation
+        let pdq_frames = |transform: Option<ImageTransform>| match &frames {
+            Some(frames) => frames
+                .pdq_images
+                .iter()
+                .map(|img| match transform {
+                    Some(transform) => {
+                        utils::pdq::pdq_hash(&utils::media::transform_image(img, transform))
+                    }
+                    None => utils::pdq::pdq_hash(img),
+                })
+                .collect::<Vec<Pdq>>(),
+            None => Vec::new(),
         };
+        if pdq {
+            phashes.insert_pdq(None, pdq_frames(None));
+            if transforms {
+                for transform in ImageTransform::ALL {
+                    phashes.insert_pdq(Some(transform), pdq_frames(Some(transform)));
+                }
+            }
+        }
+
+        // also kept when the video could not be decoded, as it would not be
+        // with the same sampling either
+        let sampling = (media_type == MediaType::Video).then_some(video_sampling);
+
+        Ok(Analysis {
+            hashes,
+            phashes,
+            thumbnail,
+            sampling,
+        })
+    }
+
+    fn build(
+        repo: Arc<dyn Repository>,
+        file: MediaFile,
+        analysis: Analysis,
+        options: &MediaOptions,
+    ) -> Result<Self> {
+        let Analysis {
+            hashes,
+            phashes,
+            thumbnail,
+            ..
+        } = analysis;
+
+        // checks if the media is in the CSAM database
+        let (phash, matched) = Media::match_database(repo, &file.name, &hashes, &phashes, options);
+
+        let extension_mismatch = file.file_type.is_some_and(|file_type| {
+            file_type.is_extension_mismatch(
+                Path::new(&file.name)
+                    .extension()
+                    .map(|e| e.to_string_lossy())
+                    .as_deref(),
+            )
+        });
 
         let media = Media {
-            name,
-            path: media_path,
-            media_type,
-            size: media_size,
-            last_modified: media_last_modified,
-            hash: md5_hash,
+            name: file.name,
+            path: file.path,
+            media_type: file.media_type,
+            file_type: file.file_type,
+            extension_mismatch,
+            size: Media::size_in_kb(file.size),
+            file_size: file.size,
+            last_modified: file.last_modified,
+            hash: hashes.md5,
+            sha1: hashes.sha1,
+            sha256: hashes.sha256,
             phash,
-            match_type,
-            hamming: distance_hamming,
-            img_buf,
+            phashes,
+            matched,
+            img_buf: thumbnail.map(Bytes::from),
+            tag: None,
         };
 
         Ok(media)
     }
 
+    /// Matches the media again against the database, with the hashes and
+    /// perceptual hashes computed when it was processed. Returns whether the
+    /// match changed.
+    ///
+    /// Hashes and perceptual hashes of algorithms that were not in the database
+    /// at the time of the search are not computed, so they are not matched.
+    pub fn rematch(&mut self, repo: Arc<dyn Repository>, options: &MediaOptions) -> bool {
+        let hashes = Hashes {
+            md5: self.hash.clone(),
+            sha1: self.sha1.clone(),
+            sha256: self.sha256.clone(),
+        };
+        let (phash, matched) =
+            Media::match_database(repo, &self.name, &hashes, &self.phashes, options);
+
+        let changed = self.matched != matched;
+        self.phash = phash;
+        self.matched = matched;
+        changed
+    }
+
+    // The match of the media and the perceptual hash of the frame that matched,
+    // 0 without a match.
+    fn match_database(
+        repo: Arc<dyn Repository>,
+        name: &str,
+        hashes: &Hashes,
+        phashes: &PHashes,
+        options: &MediaOptions,
+    ) -> (u64, Option<Match>) {
+        let hashes = options
+            .hash_algorithms
+            .iter()
+            .map(|algorithm| (*algorithm, hashes.get(*algorithm)))
+            .filter(|(_, hash)| !hash.is_empty())
+            .collect::<Vec<(HashAlgorithm, &str)>>();
+
+        match Media::find_csam(repo, name, &hashes, phashes, options.pdq_threshold) {
+            Some((phash, matched)) => (phash, Some(matched)),
+            None => (0u64, None),
+        }
+    }
+
+    fn size_in_kb(size: u64) -> usize {
+        (size as f64 / 1024.0_f64).round() as usize
+    }
+
+    // The signature in the header wins over the extension of the file.
+    pub(crate) fn detect_type(name: &str, header: &[u8]) -> (MediaType, Option<FileType>) {
+        match FileType::from_signature(header) {
+            Some(file_type) => (file_type.media_type(), Some(file_type)),
+            None => (Media::media_type(name), None),
+        }
+    }
+
+    fn media_type(name: &str) -> MediaType {
+        match Path::new(name).extension() {
+            Some(e) if utils::media::is_image(&e.to_string_lossy().to_lowercase()) => {
+                MediaType::Image
+            }
+            _ => MediaType::Video,
+        }
+    }
+
+    // MD5 and the algorithms of the hashes in the database.
+    fn algorithms(hash_algorithms: &[HashAlgorithm]) -> Vec<HashAlgorithm> {
+        let mut algorithms = vec![HashAlgorithm::MD5];
+        algorithms.extend(hash_algorithms.iter().filter(|a| **a != HashAlgorithm::MD5));
+        algorithms
+    }
+
+    // The default perceptual hash and the algorithms of the perceptual hashes
+    // in the database.
+    fn phash_algorithms(phash_algorithms: &[PHashAlgorithm]) -> Vec<PHashAlgorithm> {
+        let mut algorithms = vec![PHashAlgorithm::default()];
+        algorithms.extend(
+            phash_algorithms
+                .iter()
+                .filter(|a| **a != PHashAlgorithm::default()),
+        );
+        algorithms
+    }
+
+    pub fn is_csam(&self) -> bool {
+        self.matched.is_some()
+    }
+
+    // Returns the match and the perceptual hash of the frame that matched.
     fn find_csam(
         repo: Arc<dyn Repository>,
         name: &str,
-        hash: &str,
-        phash_vec: &[u64],
-    ) -> Option<MatchType> {
-        if Media::find_csam_by_hash(repo.clone(), hash) {
-            return Some(MatchType::MD5);
+        hashes: &[(HashAlgorithm, &str)],
+        phashes: &PHashes,
+        pdq_threshold: u32,
+    ) -> Option<(u64, Match)> {
+        let phash = phashes
+            .get(PHashAlgorithm::default())
+            .first()
+            .copied()
+            .unwrap_or_default();
+
+        for (algorithm, hash) in hashes.iter() {
+            if let Some(entry) = Media::find_csam_by_hash(repo.clone(), hash) {
+                return Some((phash, Match::new(MatchType::Hash(*algorithm), entry, 0)));
+            }
         }
 
-        if let Some(keyword) = Media::find_csam_by_keyword(repo.clone(), name) {
-            return Some(MatchType::Keyword(keyword));
+        if let Some(entry) = Media::find_csam_by_keyword(repo.clone(), name) {
+            return Some((phash, Match::new(MatchType::Keyword, entry, 0)));
         }
 
-        if phash_vec.len() == 0 {
-            return None;
+        // PDQ hashes are shared between organisations, so they are matched first
+        let pdq_match = phashes
+            .pdq_variants()
+            .flat_map(|(transform, frames)| frames.iter().map(move |pdq| (transform, *pdq)))
+            .filter(|(_, pdq)| pdq.quality >= Media::PDQ_MIN_QUALITY)
+            .filter_map(|(transform, pdq)| {
+                repo.match_pdq(&pdq.hash, pdq_threshold)
+                    .map(|(entry, distance)| (transform, entry, distance))
+            })
+            .min_by_key(|(_, _, distance)| *distance);
+        if let Some((transform, entry, distance)) = pdq_match {
+            let entry = Entry {
+                value: entry.value.to_string(),
+                source: entry.source,
+                category: entry.category,
+                filename: entry.filename,
+                series: entry.series,
+            };
+            let matched = Match {
+                transform,
+                ..Match::new(MatchType::Pdq, entry, distance)
+            };
+            return Some((phash, matched));
         }
 
-        if phash_vec.len() == 1 {
-            let phash = phash_vec[0];
-            if let Some(distance_hamming) = Media::find_csam_by_phash(repo.clone(), phash) {
-                return Some(MatchType::PHash(phash, distance_hamming));
+        // a known video playing inside the video is stronger than any of its frames
+        let video = phashes.video_fingerprint();
+        if video.len() > 1 {
+            let video_match =
+                repo.match_video(&video, Media::VIDEO_FRAME_DISTANCE, Media::VIDEO_MIN_FRAMES);
+            if let Some((entry, alignment)) = video_match {
+                let frame_at = |frames: &[(f64, u64)], time: f64| {
+                    frames
+                        .iter()
+                        .find(|(frame_time, _)| *frame_time == time)
+                        .map(|(_, phash)| *phash)
+                        .unwrap_or_default()
+                };
+                let entry = Entry {
+                    value: format!(
+                        "{:016X}",
+                        frame_at(entry.value.frames(), alignment.reference_start)
+                    ),
+                    source: entry.source,
+                    category: entry.category,
+                    filename: entry.filename,
+                    series: entry.series,
+                };
+                let matched = Match {
+                    alignment: Some(alignment),
+                    ..Match::new(MatchType::Video, entry, alignment.distance)
+                };
+                return Some((frame_at(video.frames(), alignment.start), matched));
             }
-        } else {
-            let phash = phash_vec
-                .into_iter()
-                .map(
-                    |&phash| match Media::find_csam_by_phash(repo.clone(), phash) {
-                        Some(distance_hamming) => (phash, distance_hamming),
-                        None => (0u64, 0u32),
-                    },
-                )
-                .filter(|phash| phash.0 != 0 && phash.1 > 0)
-                .reduce(|lhs, rhs| if lhs.1 < rhs.1 { lhs } else { rhs })
-                .unwrap_or((0u64, 0));
-
-            return Some(MatchType::PHash(phash.0, phash.1));
         }
 
-        None
+        // the frame closest to the database, only compared with the entries of
+        // the same algorithm; on a tie the original frame wins over the altered ones
+        phashes
+            .variants()
+            .flat_map(|(algorithm, transform, frames)| {
+                frames
+                    .iter()
+                    .map(move |phash| (algorithm, transform, *phash))
+            })
+            .filter(|(_, _, phash)| *phash != 0)
+            .filter_map(|(algorithm, transform, phash)| {
+                Media::find_csam_by_phash(repo.clone(), algorithm, phash)
+                    .map(|(entry, distance)| (algorithm, transform, phash, entry, distance))
+            })
+            .min_by_key(|(_, _, _, _, distance)| *distance)
+            .map(|(algorithm, transform, phash, entry, distance)| {
+                let entry = Entry {
+                    value: format!("{:016X}", entry.value),
+                    source: entry.source,
+                    category: entry.category,
+                    filename: entry.filename,
+                    series: entry.series,
+                };
+                let matched = Match {
+                    transform,
+                    ..Match::new(MatchType::PHash(algorithm), entry, distance)
+                };
+                (phash, matched)
+            })
+    }
+
+    fn find_csam_by_hash(repo: Arc<dyn Repository>, hash: &str) -> Option<Entry<String>> {
+        repo.find_hash(hash)
+    }
+
+    fn find_csam_by_keyword(repo: Arc<dyn Repository>, name: &str) -> Option<Entry<String>> {
+        repo.find_keyword(name)
     }
 
-    fn find_csam_by_hash(repo: Arc<dyn Repository>, hash: &str) -> bool {
-        repo.contains_hash(hash)
+    fn find_csam_by_phash(
+        repo: Arc<dyn Repository>,
+        algorithm: PHashAlgorithm,
+        phash: u64,
+    ) -> Option<(Entry<u64>, u32)> {
+        repo.match_phash(algorithm, phash, Media::MAX_DISTANCE_HAMMING)
     }
+}
 
-    fn find_csam_by_keyword(repo: Arc<dyn Repository>, name: &str) -> Option<String> {
-        repo.contains_keyword(name)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csam::repository::InMemoryRepository;
+    use crate::csam::SamplingMode;
+    use crate::utils::pdq::PdqHash;
+    use crate::utils::video_fingerprint::VideoFingerprint;
+
+    #[test]
+    fn test_find_csam_should_return_closest_frame() {
+        let repo = Arc::new(InMemoryRepository::new());
+        repo.add_phashes(
+            PHashAlgorithm::PHash,
+            &[Entry {
+                value: 15634510955120228568,
+                source: String::from("phash.txt"),
+                category: Some(String::from("A1")),
+                filename: Some(String::from("video.mp4")),
+                series: None,
+            }],
+        )
+        .unwrap();
+        let frames = vec![1, 15634510955120226520, 15634510955120228568];
+        let phashes = PHashes::new(PHashAlgorithm::PHash, frames);
+        let (phash, matched) =
+            Media::find_csam(repo, "video.mp4", &[], &phashes, Media::PDQ_THRESHOLD).unwrap();
+
+        // Assert
+        assert_eq!(phash, 15634510955120228568);
+        assert_eq!(matched.match_type, MatchType::PHash(PHashAlgorithm::PHash));
+        assert_eq!(matched.reference, "D8F8F0E0E4E4F8D8");
+        assert_eq!(matched.distance, 0);
+        assert_eq!(matched.category, Some(String::from("A1")));
+        assert_eq!(matched.to_string(), "PHash [ 0 ]");
     }
 
-    fn find_csam_by_phash(repo: Arc<dyn Repository>, phash: u64) -> Option<u32> {
-        repo.match_phash(phash, Media::MAX_DISTANCE_HAMMING)
+    #[test]
+    fn test_find_csam_should_report_hash_algorithm() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let sha1 = "b7b6e21916253608c9ff081db046a58100536963";
+        repo.add_hash(sha1).unwrap();
+        let hashes = [
+            (HashAlgorithm::MD5, "506bf7f41ca0c6f9e7612c04e93ab235"),
+            (HashAlgorithm::SHA1, sha1),
+        ];
+        let (_, matched) =
+            Media::find_csam(repo, "horse.jpg", &hashes, &PHashes::default(), 0).unwrap();
+
+        // Assert
+        assert_eq!(matched.match_type, MatchType::Hash(HashAlgorithm::SHA1));
+        assert_eq!(matched.reference, sha1);
+        assert_eq!(matched.to_string(), "SHA-1");
+    }
+
+    #[test]
+    fn test_media_from_bytes() {
+        let repo = Arc::new(InMemoryRepository::new());
+        repo.add_hash("506bf7f41ca0c6f9e7612c04e93ab235").unwrap();
+        let data = std::fs::read("../data/img/horse.jpg").unwrap();
+        let media = Media::from_bytes(
+            repo,
+            String::from("evidence.zip!/folder/horse.jpg"),
+            String::from("horse.jpg"),
+            &data,
+            0,
+            &MediaOptions {
+                hash_algorithms: vec![HashAlgorithm::MD5],
+                ..Default::default()
+            },
+        )
+        .expect("Failed to process media.");
+
+        // Assert
+        assert_eq!(media.media_type, MediaType::Image);
+        assert_eq!(media.path, "evidence.zip!/folder/horse.jpg");
+        assert_eq!(media.file_size, data.len() as u64);
+        assert!(media.img_buf.is_some());
+        assert_ne!(media.phash, 0);
+        assert!(media.is_csam());
+        assert_eq!(media.file_type, Some(FileType::Jpeg));
+        assert!(!media.extension_mismatch);
+    }
+
+    #[test]
+    fn test_media_should_reuse_cached_analysis() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ScanCache::open(&dir.path().join("cache.sqlite")).unwrap();
+        let item = EvidenceItem::from_file(Path::new("../data/img/horse.jpg")).unwrap();
+        // a hash only known by the cache proves the file was not hashed again
+        let analysis = Analysis {
+            hashes: Hashes {
+                md5: String::from("00000000000000000000000000000001"),
+                ..Default::default()
+            },
+            phashes: PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568]),
+            thumbnail: None,
+            sampling: None,
+        };
+        cache.insert(&item.path, item.size, item.last_modified, &analysis);
+        let repo = Arc::new(InMemoryRepository::new());
+        repo.add_hash("00000000000000000000000000000001").unwrap();
+
+        let options = MediaOptions {
+            hash_algorithms: vec![HashAlgorithm::MD5],
+            ..Default::default()
+        };
+        let media = Media::new(repo.clone(), item, &options, Some(&cache)).unwrap();
+        let item = EvidenceItem::from_file(Path::new("../data/img/horse.jpg")).unwrap();
+        // SHA-1 was never computed, so the media is processed again
+        let options = MediaOptions {
+            hash_algorithms: vec![HashAlgorithm::MD5, HashAlgorithm::SHA1],
+            ..Default::default()
+        };
+        let rehashed = Media::new(repo, item, &options, Some(&cache)).unwrap();
+
+        // Assert
+        assert!(media.is_csam());
+        assert_eq!(media.phash, 15634510955120228568);
+        assert_eq!(media.file_type, Some(FileType::Jpeg));
+        assert_eq!(rehashed.hash, "506bf7f41ca0c6f9e7612c04e93ab235");
+        assert!(!rehashed.is_csam());
+        assert!(rehashed.img_buf.is_some());
+    }
+
+    #[test]
+    fn test_media_should_not_cache_failed_decode() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ScanCache::open(&dir.path().join("cache.sqlite")).unwrap();
+        let path = dir.path().join("broken.jpg");
+        std::fs::write(&path, b"not an image").unwrap();
+        let item = EvidenceItem::from_file(&path).unwrap();
+        let (size, last_modified) = (item.size, item.last_modified);
+        let repo = Arc::new(InMemoryRepository::new());
+
+        let media = Media::new(repo, item, &MediaOptions::default(), Some(&cache)).unwrap();
+
+        // Assert
+        assert!(media.img_buf.is_none());
+        assert_eq!(
+            cache.get(&path.to_string_lossy(), size, last_modified),
+            None
+        );
+    }
+
+    #[test]
+    fn test_media_should_analyze_video_again_with_other_sampling() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ScanCache::open(&dir.path().join("cache.sqlite")).unwrap();
+        let item = EvidenceItem::from_file(Path::new("../data/video/vid.mp4")).unwrap();
+        let analysis = Analysis {
+            hashes: Hashes {
+                md5: String::from("00000000000000000000000000000001"),
+                ..Default::default()
+            },
+            phashes: PHashes::new(PHashAlgorithm::PHash, vec![1]),
+            thumbnail: None,
+            sampling: Some(VideoSampling::default()),
+        };
+        cache.insert(&item.path, item.size, item.last_modified, &analysis);
+        let repo = Arc::new(InMemoryRepository::new());
+
+        let media = Media::new(repo.clone(), item, &MediaOptions::default(), Some(&cache)).unwrap();
+        let item = EvidenceItem::from_file(Path::new("../data/video/vid.mp4")).unwrap();
+        let options = MediaOptions {
+            video_sampling: VideoSampling {
+                mode: SamplingMode::Keyframes,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let resampled = Media::new(repo, item, &options, Some(&cache)).unwrap();
+
+        // Assert
+        assert_eq!(media.hash, "00000000000000000000000000000001");
+        assert_eq!(resampled.hash, "a4642213a482c74c0a7ec04c3f232457");
+    }
+
+    #[test]
+    fn test_rematch_should_use_reloaded_database() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let data = std::fs::read("../data/img/horse.jpg").unwrap();
+        let options = MediaOptions {
+            hash_algorithms: vec![HashAlgorithm::MD5],
+            ..Default::default()
+        };
+        let mut media = Media::from_bytes(
+            repo.clone(),
+            String::from("/evidence/horse.jpg"),
+            String::from("horse.jpg"),
+            &data,
+            0,
+            &options,
+        )
+        .unwrap();
+        let phash = media.phashes.get(PHashAlgorithm::PHash)[0];
+
+        // the database gets the perceptual hash after the search
+        repo.add_phash(phash).unwrap();
+        let changed = media.rematch(repo.clone(), &options);
+        let unchanged = media.rematch(repo, &options);
+
+        // Assert
+        assert!(changed);
+        assert!(!unchanged);
+        assert_eq!(media.phash, phash);
+        assert_eq!(
+            media.matched.unwrap().match_type,
+            MatchType::PHash(PHashAlgorithm::PHash)
+        );
+    }
+
+    #[test]
+    fn test_detect_type_should_prefer_signature() {
+        let data = std::fs::read("../data/img/horse.jpg").unwrap();
+        let (media_type, file_type) = Media::detect_type("horse.mp4", &data);
+
+        // Assert
+        assert_eq!(media_type, MediaType::Image);
+        assert_eq!(file_type, Some(FileType::Jpeg));
+        assert_eq!(Media::detect_type("video.avi", b"").0, MediaType::Video);
+    }
+
+    #[test]
+    fn test_find_csam_should_return_none() {
+        let repo = Arc::new(InMemoryRepository::new());
+        repo.add_phash(15634510955120228568).unwrap();
+        let phashes = PHashes::new(PHashAlgorithm::PHash, vec![0, 1, 2]);
+
+        // Assert
+        assert!(Media::find_csam(repo, "video.mp4", &[], &phashes, Media::PDQ_THRESHOLD).is_none());
+    }
+
+    #[test]
+    fn test_find_csam_should_match_only_same_phash_algorithm() {
+        let repo = Arc::new(InMemoryRepository::new());
+        repo.add_phashes(
+            PHashAlgorithm::DHash,
+            &[Entry::new(15634510955120228568, "phash.txt")],
+        )
+        .unwrap();
+        let mut phashes = PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568]);
+        let unmatched = Media::find_csam(
+            repo.clone(),
+            "horse.jpg",
+            &[],
+            &phashes,
+            Media::PDQ_THRESHOLD,
+        );
+        phashes.insert(PHashAlgorithm::DHash, vec![15634510955120226520]);
+        let (_, matched) =
+            Media::find_csam(repo, "horse.jpg", &[], &phashes, Media::PDQ_THRESHOLD).unwrap();
+
+        // Assert
+        assert!(unmatched.is_none());
+        assert_eq!(matched.match_type, MatchType::PHash(PHashAlgorithm::DHash));
+        assert_eq!(matched.to_string(), "DHash [ 1 ]");
+    }
+
+    #[test]
+    fn test_media_should_compute_phash_algorithms_of_database() {
+        let data = std::fs::read("../data/img/horse.jpg").unwrap();
+        let media = Media::from_bytes(
+            Arc::new(InMemoryRepository::new()),
+            String::from("/evidence/horse.jpg"),
+            String::from("horse.jpg"),
+            &data,
+            0,
+            &MediaOptions {
+                phash_algorithms: vec![PHashAlgorithm::AHash],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Assert
+        assert_eq!(media.phashes.get(PHashAlgorithm::PHash).len(), 1);
+        assert_eq!(media.phashes.get(PHashAlgorithm::AHash).len(), 1);
+        assert!(!media.phashes.contains(PHashAlgorithm::DHash));
+        assert_ne!(
+            media.phashes.get(PHashAlgorithm::PHash),
+            media.phashes.get(PHashAlgorithm::AHash)
+        );
+    }
+
+    #[test]
+    fn test_media_should_match_rotated_copy() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let phashes = utils::media::get_perceptual_hashes_of_file(
+            "../data/img/horse.jpg",
+            &[PHashAlgorithm::PHash],
+        )
+        .unwrap();
+        repo.add_phash(phashes[0]).unwrap();
+        let mut data = Vec::new();
+        image::open("../data/img/horse.jpg")
+            .unwrap()
+            .rotate90()
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+        let media = Media::from_bytes(
+            repo,
+            String::from("/evidence/rotated.png"),
+            String::from("rotated.png"),
+            &data,
+            0,
+            &MediaOptions {
+                transforms: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let matched = media.matched.unwrap();
+
+        // Assert
+        assert_eq!(matched.match_type, MatchType::PHash(PHashAlgorithm::PHash));
+        assert_eq!(matched.transform, Some(ImageTransform::Rotate270));
+        assert!(matched.to_string().ends_with("Rotated 270°"));
+        assert!(media.phashes.contains_transformed(PHashAlgorithm::PHash));
+    }
+
+    #[test]
+    fn test_find_csam_should_match_pdq_of_quality_frames() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let hash = PdqHash::from_words([u64::MAX, 0, u64::MAX, 0]);
+        repo.add_pdq(hash).unwrap();
+        repo.add_phash(15634510955120228568).unwrap();
+        let mut phashes = PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568]);
+        phashes.insert_pdq(None, vec![Pdq { hash, quality: 10 }]);
+        let (_, low_quality) = Media::find_csam(
+            repo.clone(),
+            "horse.jpg",
+            &[],
+            &phashes,
+            Media::PDQ_THRESHOLD,
+        )
+        .unwrap();
+        let near = PdqHash::from_words([u64::MAX, 0, u64::MAX, 0xFF]);
+        phashes.insert_pdq(
+            None,
+            vec![Pdq {
+                hash: near,
+                quality: 100,
+            }],
+        );
+        let (_, matched) = Media::find_csam(
+            repo.clone(),
+            "horse.jpg",
+            &[],
+            &phashes,
+            Media::PDQ_THRESHOLD,
+        )
+        .unwrap();
+        let (_, beyond_threshold) = Media::find_csam(repo, "horse.jpg", &[], &phashes, 7).unwrap();
+
+        // Assert
+        assert_eq!(
+            low_quality.match_type,
+            MatchType::PHash(PHashAlgorithm::PHash)
+        );
+        assert_eq!(matched.match_type, MatchType::Pdq);
+        assert_eq!(matched.reference, hash.to_string());
+        assert_eq!(matched.to_string(), "PDQ [ 8 ]");
+        assert_eq!(
+            beyond_threshold.match_type,
+            MatchType::PHash(PHashAlgorithm::PHash)
+        );
+    }
+
+    #[test]
+    fn test_media_should_match_pdq_of_database() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let img = image::open("../data/img/horse.jpg").unwrap();
+        repo.add_pdq(utils::pdq::pdq_hash(&img).hash).unwrap();
+        let data = std::fs::read("../data/img/horse.jpg").unwrap();
+        let media = Media::from_bytes(
+            repo,
+            String::from("/evidence/horse.jpg"),
+            String::from("horse.jpg"),
+            &data,
+            0,
+            &MediaOptions {
+                pdq: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let matched = media.matched.unwrap();
+
+        // Assert
+        assert_eq!(matched.match_type, MatchType::Pdq);
+        assert_eq!(matched.distance, 0);
+        assert!(media.phashes.pdq()[0].quality >= Media::PDQ_MIN_QUALITY);
+    }
+
+    #[test]
+    fn test_find_csam_should_match_known_video_inside_video() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let clip: Vec<u64> = (1..=6)
+            .map(|idx: u64| idx.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+        let times: Vec<f64> = (0..20).map(f64::from).collect();
+        repo.add_video(VideoFingerprint::new(&times, &clip))
+            .unwrap();
+        // the known video from its second second, 5 seconds into the video
+        let mut frames: Vec<u64> = (100..120)
+            .map(|idx: u64| idx.wrapping_mul(0x2545_F491_4F6C_DD1D))
+            .collect();
+        frames[5..10].copy_from_slice(&clip[1..]);
+        let mut phashes = PHashes::new(PHashAlgorithm::PHash, frames.clone());
+        let unaligned = Media::find_csam(
+            repo.clone(),
+            "video.mp4",
+            &[],
+            &phashes,
+            Media::PDQ_THRESHOLD,
+        );
+        phashes.set_times(times);
+        let (phash, matched) =
+            Media::find_csam(repo, "video.mp4", &[], &phashes, Media::PDQ_THRESHOLD).unwrap();
+        let alignment = matched.alignment.unwrap();
+
+        // Assert
+        assert!(unaligned.is_none());
+        assert_eq!(matched.match_type, MatchType::Video);
+        assert_eq!(phash, clip[1]);
+        assert_eq!(matched.reference, format!("{:016X}", clip[1]));
+        assert_eq!((alignment.start, alignment.end), (5.0, 9.0));
+        assert_eq!(
+            (alignment.reference_start, alignment.reference_end),
+            (1.0, 5.0)
+        );
+        assert_eq!(
+            matched.to_string(),
+            "Video [ 0 ] 00:00:05-00:00:09 = 00:00:01-00:00:05"
+        );
     }
 }
@@ -1,7 +1,27 @@
+mod cache;
+pub mod case;
 pub mod db;
+mod file_type;
+mod hash_algorithm;
+mod image_transform;
+pub mod inventory;
 mod media;
+mod phash_algorithm;
+pub mod report;
 pub mod repository;
 mod service;
+mod source;
+mod tag;
+pub mod vics;
+mod video_sampling;
 
+pub use cache::ScanCache;
+pub use file_type::*;
+pub use hash_algorithm::*;
+pub use image_transform::*;
 pub use media::*;
+pub use phash_algorithm::*;
 pub use service::*;
+pub use source::*;
+pub use tag::*;
+pub use video_sampling::*;
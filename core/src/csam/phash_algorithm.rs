@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::ImageTransform;
+use crate::utils::pdq::{Pdq, PdqHash};
+use crate::utils::video_fingerprint::VideoFingerprint;
+
+// Number of the PDQ hashes in the encoding of `PHashes`, after the algorithms.
+const PDQ_ID: u8 = 0x0F;
+// Number of the times of the frames in the encoding of `PHashes`.
+const TIMES_ID: u8 = 0x0E;
+
+/// Perceptual hash algorithms supported in the perceptual hash database.
+///
+/// Hashes of different algorithms are not comparable, so every entry of the
+/// database records the algorithm that produced it.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum PHashAlgorithm {
+    /// Perception hash of the thresholded image, the one of the databases
+    /// that do not record the algorithm.
+    #[default]
+    PHash,
+    /// Perception hash of the grayscale image.
+    PHashGray,
+    DHash,
+    AHash,
+}
+
+impl PHashAlgorithm {
+    pub const ALL: [PHashAlgorithm; 4] = [Self::PHash, Self::PHashGray, Self::DHash, Self::AHash];
+
+    /// Parses the name written by `Display`, ignoring the case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.to_string().eq_ignore_ascii_case(name.trim()))
+    }
+
+    // Number of the algorithm in the encoding of `PHashes`, never reused.
+    fn id(&self) -> u8 {
+        match self {
+            Self::PHash => 0,
+            Self::PHashGray => 1,
+            Self::DHash => 2,
+            Self::AHash => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|algorithm| algorithm.id() == id)
+    }
+}
+
+impl fmt::Display for PHashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PHash => write!(f, "PHash"),
+            Self::PHashGray => write!(f, "PHash Gray"),
+            Self::DHash => write!(f, "DHash"),
+            Self::AHash => write!(f, "AHash"),
+        }
+    }
+}
+
+/// Perceptual hashes of every frame of a media, by algorithm, its PDQ hashes and
+/// the time of the frames of a video.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PHashes {
+    frames: BTreeMap<PHashAlgorithm, Vec<u64>>,
+    // frames of the altered image, only hashed when asked for
+    transformed: BTreeMap<(PHashAlgorithm, ImageTransform), Vec<u64>>,
+    // PDQ hashes of the frames of the original and of the altered images
+    pdq: BTreeMap<Option<ImageTransform>, Vec<Pdq>>,
+    // time in seconds of every frame, empty for images
+    times: Vec<f64>,
+}
+
+impl PHashes {
+    pub fn new(algorithm: PHashAlgorithm, frames: Vec<u64>) -> Self {
+        let mut phashes = Self::default();
+        phashes.insert(algorithm, frames);
+        phashes
+    }
+
+    pub fn insert(&mut self, algorithm: PHashAlgorithm, frames: Vec<u64>) {
+        self.frames.insert(algorithm, frames);
+    }
+
+    pub fn insert_transformed(
+        &mut self,
+        algorithm: PHashAlgorithm,
+        transform: ImageTransform,
+        frames: Vec<u64>,
+    ) {
+        self.transformed.insert((algorithm, transform), frames);
+    }
+
+    pub fn insert_pdq(&mut self, transform: Option<ImageTransform>, frames: Vec<Pdq>) {
+        self.pdq.insert(transform, frames);
+    }
+
+    pub fn set_times(&mut self, times: Vec<f64>) {
+        self.times = times;
+    }
+
+    /// Time in seconds of every frame of a video, empty for images.
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    /// Frames of a video hashed with the default algorithm, with their time.
+    pub fn video_fingerprint(&self) -> VideoFingerprint {
+        VideoFingerprint::new(&self.times, self.get(PHashAlgorithm::default()))
+    }
+
+    /// Frames hashed with the `algorithm`, empty when it was not computed.
+    pub fn get(&self, algorithm: PHashAlgorithm) -> &[u64] {
+        self.frames
+            .get(&algorithm)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, algorithm: PHashAlgorithm) -> bool {
+        self.frames.contains_key(&algorithm)
+    }
+
+    /// Whether the altered images were also hashed with the `algorithm`.
+    pub fn contains_transformed(&self, algorithm: PHashAlgorithm) -> bool {
+        ImageTransform::ALL
+            .iter()
+            .all(|transform| self.transformed.contains_key(&(algorithm, *transform)))
+    }
+
+    /// Whether the PDQ hashes were computed, also of the altered images when
+    /// `transforms` is set.
+    pub fn contains_pdq(&self, transforms: bool) -> bool {
+        self.pdq.contains_key(&None)
+            && (!transforms
+                || ImageTransform::ALL
+                    .iter()
+                    .all(|transform| self.pdq.contains_key(&Some(*transform))))
+    }
+
+    /// PDQ hashes of the frames of the original image.
+    pub fn pdq(&self) -> &[Pdq] {
+        self.pdq.get(&None).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// PDQ hashes of the original image followed by those of the altered ones.
+    pub fn pdq_variants(&self) -> impl Iterator<Item = (Option<ImageTransform>, &[Pdq])> {
+        self.pdq
+            .iter()
+            .map(|(transform, frames)| (*transform, frames.as_slice()))
+    }
+
+    /// Frames of the original image by algorithm.
+    pub fn iter(&self) -> impl Iterator<Item = (PHashAlgorithm, &[u64])> {
+        self.frames
+            .iter()
+            .map(|(algorithm, frames)| (*algorithm, frames.as_slice()))
+    }
+
+    /// Frames of the original image followed by the frames of the altered ones.
+    pub fn variants(
+        &self,
+    ) -> impl Iterator<Item = (PHashAlgorithm, Option<ImageTransform>, &[u64])> {
+        self.iter()
+            .map(|(algorithm, frames)| (algorithm, None, frames))
+            .chain(
+                self.transformed
+                    .iter()
+                    .map(|((algorithm, transform), frames)| {
+                        (*algorithm, Some(*transform), frames.as_slice())
+                    }),
+            )
+    }
+
+    /// Encoding kept in the scan cache and in the cases: for every algorithm
+    /// its number, the number of frames and the frames, in little endian.
+    /// The number of the transform of an altered image goes in the high bits
+    /// of the number of the algorithm. A PDQ hash takes the place of five
+    /// frames, its four words followed by its quality, and the time of a frame
+    /// takes the place of one.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let transform_id = |transform: Option<ImageTransform>| {
+            transform
+                .map(|transform| transform.id())
+                .unwrap_or_default()
+        };
+
+        let mut bytes = Vec::new();
+        for (algorithm, transform, frames) in self.variants() {
+            bytes.push(transform_id(transform) << 4 | algorithm.id());
+            bytes.extend((frames.len() as u32).to_le_bytes());
+            bytes.extend(frames.iter().flat_map(|phash| phash.to_le_bytes()));
+        }
+        for (transform, frames) in self.pdq_variants() {
+            bytes.push(transform_id(transform) << 4 | PDQ_ID);
+            bytes.extend((frames.len() as u32 * 5).to_le_bytes());
+            for pdq in frames.iter() {
+                bytes.extend(pdq.hash.words().iter().flat_map(|word| word.to_le_bytes()));
+                bytes.extend((pdq.quality as u64).to_le_bytes());
+            }
+        }
+        if !self.times.is_empty() {
+            bytes.push(TIMES_ID);
+            bytes.extend((self.times.len() as u32).to_le_bytes());
+            bytes.extend(self.times.iter().flat_map(|time| time.to_le_bytes()));
+        }
+        bytes
+    }
+
+    /// Decodes `to_bytes`, skipping algorithms and transforms that are not known.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut phashes = Self::default();
+        let mut rest = bytes;
+        while let [id, a, b, c, d, tail @ ..] = rest {
+            let len = u32::from_le_bytes([*a, *b, *c, *d]) as usize;
+            let Some(frames) = tail.get(..len * 8) else {
+                break;
+            };
+            let frames: Vec<u64> = frames
+                .chunks_exact(8)
+                .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+                .collect();
+            let transform = match id >> 4 {
+                0 => Some(None),
+                transform_id => ImageTransform::from_id(transform_id).map(Some),
+            };
+            match (id & 0x0F, transform) {
+                (TIMES_ID, Some(None)) => {
+                    phashes.set_times(frames.into_iter().map(f64::from_bits).collect());
+                }
+                (PDQ_ID, Some(transform)) => {
+                    let frames = frames
+                        .chunks_exact(5)
+                        .map(|words| Pdq {
+                            hash: PdqHash::from_words([words[0], words[1], words[2], words[3]]),
+                            quality: words[4] as u32,
+                        })
+                        .collect();
+                    phashes.insert_pdq(transform, frames);
+                }
+                (algorithm_id, Some(transform)) => {
+                    match (PHashAlgorithm::from_id(algorithm_id), transform) {
+                        (Some(algorithm), None) => phashes.insert(algorithm, frames),
+                        (Some(algorithm), Some(transform)) => {
+                            phashes.insert_transformed(algorithm, transform, frames)
+                        }
+                        (None, _) => (),
+                    }
+                }
+                (_, None) => (),
+            }
+            rest = &tail[len * 8..];
+        }
+        phashes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phashes_should_roundtrip_bytes() {
+        let mut phashes = PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568, 1]);
+        phashes.insert(PHashAlgorithm::DHash, vec![2]);
+        phashes.insert(PHashAlgorithm::AHash, vec![]);
+        phashes.insert_transformed(PHashAlgorithm::PHash, ImageTransform::Rotate90, vec![3, 4]);
+        let pdq = Pdq {
+            hash: PdqHash::from_words([u64::MAX, 0, 5, 1 << 63]),
+            quality: 100,
+        };
+        phashes.insert_pdq(None, vec![pdq, Pdq::default()]);
+        phashes.insert_pdq(Some(ImageTransform::CenterCrop), vec![pdq]);
+        phashes.set_times(vec![0.0, 1.001]);
+
+        let decoded = PHashes::from_bytes(&phashes.to_bytes());
+
+        // Assert
+        assert_eq!(decoded, phashes);
+        assert_eq!(decoded.get(PHashAlgorithm::DHash), &[2]);
+        assert!(decoded.get(PHashAlgorithm::PHashGray).is_empty());
+        assert_eq!(
+            decoded.variants().last(),
+            Some((
+                PHashAlgorithm::PHash,
+                Some(ImageTransform::Rotate90),
+                &[3, 4][..]
+            ))
+        );
+        assert!(!decoded.contains_transformed(PHashAlgorithm::PHash));
+        assert_eq!(decoded.pdq(), &[pdq, Pdq::default()]);
+        assert!(decoded.contains_pdq(false));
+        assert!(!decoded.contains_pdq(true));
+        assert_eq!(decoded.times(), &[0.0, 1.001]);
+        assert_eq!(decoded.video_fingerprint().len(), 2);
+        assert_eq!(
+            PHashAlgorithm::from_name("phash gray"),
+            Some(PHashAlgorithm::PHashGray)
+        );
+        assert_eq!(PHashAlgorithm::from_name("PDQ"), None);
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::{self, Write};
+
+use base64::Engine;
+use chrono::{Local, TimeZone};
+
+use super::Media;
+
+/// Case details printed in the header of the report.
+#[derive(Debug, Clone, Default)]
+pub struct ReportInfo {
+    pub case_number: String,
+    pub examiner: String,
+    pub evidence_root: String,
+    /// Start and end of the scan as Unix timestamps.
+    pub scan_started: Option<i64>,
+    pub scan_finished: Option<i64>,
+    pub databases: Vec<DatabaseInfo>,
+}
+
+/// Database file used in the scan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatabaseInfo {
+    pub name: String,
+    /// Last modification of the database file.
+    pub version: String,
+    pub count: usize,
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:24px;color:#222}\
+h1{font-size:22px}h2{font-size:18px;margin-top:28px}\
+table{border-collapse:collapse;width:100%}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+th{background:#eee}td.hash{font-family:monospace;word-break:break-all}\
+img{max-width:160px;max-height:160px}";
+
+/// Writes a self-contained HTML report of the media, with the thumbnails
+/// embedded in the document.
+pub fn write_html<W: Write>(writer: W, info: &ReportInfo, medias: &[Media]) -> anyhow::Result<()> {
+    let mut w = io::BufWriter::new(writer);
+
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html><head><meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>ChaSAM - {}</title>", escape(&info.case_number))?;
+    writeln!(w, "<style>{STYLE}</style></head><body>")?;
+    writeln!(w, "<h1>ChaSAM report</h1>")?;
+
+    writeln!(w, "<table>")?;
+    header_row(&mut w, "Case", &info.case_number)?;
+    header_row(&mut w, "Examiner", &info.examiner)?;
+    header_row(&mut w, "Evidence", &info.evidence_root)?;
+    header_row(&mut w, "Scan started", &format_time(info.scan_started))?;
+    header_row(&mut w, "Scan finished", &format_time(info.scan_finished))?;
+    header_row(
+        &mut w,
+        "Report generated",
+        &format_time(Some(Local::now().timestamp())),
+    )?;
+    writeln!(w, "</table>")?;
+
+    writeln!(w, "<h2>Databases</h2>")?;
+    writeln!(
+        w,
+        "<table><tr><th>Database</th><th>Version</th><th>Entries</th></tr>"
+    )?;
+    for database in info.databases.iter() {
+        writeln!(
+            w,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&database.name),
+            escape(&database.version),
+            database.count
+        )?;
+    }
+    writeln!(w, "</table>")?;
+
+    writeln!(w, "<h2>Hits ({})</h2>", medias.len())?;
+    writeln!(
+        w,
+        "<table><tr><th>#</th><th>Thumbnail</th><th>File</th><th>MD5</th>\
+        <th>PHash</th><th>Match</th><th>Hamming</th><th>Tag</th></tr>"
+    )?;
+    for (index, media) in medias.iter().enumerate() {
+        media_row(&mut w, index + 1, media)?;
+    }
+    writeln!(w, "</table>")?;
+    writeln!(w, "</body></html>")?;
+    w.flush()?;
+
+    Ok(())
+}
+
+fn header_row<W: Write>(w: &mut W, label: &str, value: &str) -> io::Result<()> {
+    writeln!(w, "<tr><th>{}</th><td>{}</td></tr>", label, escape(value))
+}
+
+fn media_row<W: Write>(w: &mut W, id: usize, media: &Media) -> io::Result<()> {
+    let thumbnail = match &media.img_buf {
+        Some(buf) => format!(
+            "<img src=\"data:image/jpeg;base64,{}\" alt=\"\">",
+            base64::engine::general_purpose::STANDARD.encode(buf)
+        ),
+        None => String::new(),
+    };
+    let (match_type, distance) = match &media.matched {
+        Some(matched) => {
+            let mut match_type = escape(&matched.to_string());
+            if !matched.source.is_empty() {
+                match_type.push_str(&format!("<br>{}", escape(&matched.source)));
+            }
+            (match_type, matched.distance.to_string())
+        }
+        None => (String::new(), String::new()),
+    };
+
+    writeln!(
+        w,
+        "<tr><td>{}</td><td>{}</td><td>{}<br>{}</td><td class=\"hash\">{}</td>\
+        <td class=\"hash\">{:016X}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+        id,
+        thumbnail,
+        escape(&media.name),
+        escape(&media.path),
+        escape(&media.hash),
+        media.phash,
+        match_type,
+        distance,
+        media.tag.map(|tag| tag.to_string()).unwrap_or_default()
+    )
+}
+
+fn format_time(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
+        .map(|date_time| date_time.format("%Y-%m-%d %H:%M:%S %:z").to_string())
+        .unwrap_or_default()
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::csam::{Match, MatchType, MediaType, PHashAlgorithm, PHashes, Tag};
+
+    #[test]
+    fn test_write_html() {
+        let info = ReportInfo {
+            case_number: String::from("2024-001"),
+            examiner: String::from("<Examiner>"),
+            evidence_root: String::from("/evidence"),
+            scan_started: Some(0),
+            scan_finished: Some(60),
+            databases: vec![DatabaseInfo {
+                name: String::from("phash.txt"),
+                version: String::from("2024-01-01 00:00:00"),
+                count: 10,
+            }],
+        };
+        let media = Media {
+            name: String::from("horse.jpg"),
+            path: String::from("/evidence/horse.jpg"),
+            media_type: MediaType::Image,
+            file_type: None,
+            size: 1,
+            file_size: 1024,
+            last_modified: 0,
+            hash: String::from("506bf7f41ca0c6f9e7612c04e93ab235"),
+            sha1: String::new(),
+            sha256: String::new(),
+            phash: 15634510955120228568,
+            phashes: PHashes::new(PHashAlgorithm::PHash, vec![15634510955120228568]),
+            matched: Some(Match {
+                match_type: MatchType::PHash(PHashAlgorithm::PHash),
+                reference: String::from("D8F8F0E0E4E4F8D8"),
+                distance: 3,
+                source: String::from("phash.txt"),
+                category: None,
+                filename: None,
+                series: None,
+                transform: None,
+                alignment: None,
+            }),
+            img_buf: Some(Bytes::from_static(b"jpeg")),
+            extension_mismatch: false,
+            tag: Some(Tag::Category3),
+        };
+        let mut buf = Vec::new();
+        write_html(&mut buf, &info, &[media]).expect("Failed to write report.");
+        let html = String::from_utf8(buf).unwrap();
+
+        // Assert
+        assert!(html.contains("<td>&lt;Examiner&gt;</td>"));
+        assert!(html.contains("<td>phash.txt</td><td>2024-01-01 00:00:00</td><td>10</td>"));
+        assert!(html.contains("<h2>Hits (1)</h2>"));
+        assert!(html.contains("data:image/jpeg;base64,anBlZw=="));
+        assert!(html.contains("D8F8F0E0E4E4F8D8"));
+        assert!(html.contains("<td>PHash [ 3 ]<br>phash.txt</td><td>3</td><td>Category 3</td>"));
+    }
+}
@@ -1,14 +1,32 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::sync::RwLock;
 
-use super::Repository;
-use crate::utils;
+use super::{Entry, Repository};
+use crate::csam::{HashAlgorithm, PHashAlgorithm};
+use crate::utils::multi_index::{PHashIndex, PdqIndex};
+use crate::utils::pdq::PdqHash;
+use crate::utils::video_fingerprint::{self, Alignment, VideoFingerprint};
+
+#[derive(Debug, Default)]
+struct PHashStore {
+    index: PHashIndex,
+    entries: Vec<Entry<u64>>,
+}
+
+#[derive(Debug, Default)]
+struct PdqStore {
+    index: PdqIndex,
+    entries: Vec<Entry<PdqHash>>,
+}
 
 #[derive(Debug, Default)]
 pub struct InMemoryRepository {
-    keyword_store: RwLock<HashSet<String>>,
-    hash_store: RwLock<HashSet<String>>,
-    phash_store: RwLock<Vec<u64>>,
+    keyword_store: RwLock<HashMap<String, Entry<String>>>,
+    hash_store: RwLock<HashMap<HashAlgorithm, HashMap<String, Entry<String>>>>,
+    phash_store: RwLock<HashMap<PHashAlgorithm, PHashStore>>,
+    pdq_store: RwLock<PdqStore>,
+    video_store: RwLock<Vec<Entry<VideoFingerprint>>>,
 }
 
 impl InMemoryRepository {
@@ -18,62 +36,174 @@ impl InMemoryRepository {
 }
 
 impl Repository for InMemoryRepository {
-    fn add_keyword(&self, keyword: &str) {
+    fn add_keywords(&self, keywords: &[Entry<String>]) -> anyhow::Result<()> {
         let mut store = self.keyword_store.write().unwrap();
-        store.insert(keyword.to_lowercase());
+        for keyword in keywords.iter() {
+            let value = keyword.value.to_lowercase();
+            store.entry(value.clone()).or_insert_with(|| Entry {
+                value,
+                ..keyword.clone()
+            });
+        }
+        Ok(())
     }
 
-    fn add_hash(&self, hash: &str) {
+    fn add_hashes(&self, hashes: &[Entry<String>]) -> anyhow::Result<()> {
         let mut store = self.hash_store.write().unwrap();
-        store.insert(hash.to_lowercase());
+        for hash in hashes.iter() {
+            let Some(algorithm) = HashAlgorithm::from_digest(&hash.value) else {
+                continue;
+            };
+            let value = hash.value.to_lowercase();
+            store
+                .entry(algorithm)
+                .or_default()
+                .entry(value.clone())
+                .or_insert_with(|| Entry {
+                    value,
+                    ..hash.clone()
+                });
+        }
+        Ok(())
     }
 
-    fn add_phash(&self, phash: u64) {
+    fn add_phashes(&self, algorithm: PHashAlgorithm, phashes: &[Entry<u64>]) -> anyhow::Result<()> {
         let mut store = self.phash_store.write().unwrap();
-        store.push(phash);
+        let store = store.entry(algorithm).or_default();
+        for phash in phashes.iter() {
+            store.index.insert(phash.value);
+            store.entries.push(phash.clone());
+        }
+        Ok(())
     }
 
-    fn remove_all_keywords(&self) {
+    fn add_pdqs(&self, hashes: &[Entry<PdqHash>]) -> anyhow::Result<()> {
+        let mut store = self.pdq_store.write().unwrap();
+        for hash in hashes.iter() {
+            store.index.insert(hash.value);
+            store.entries.push(hash.clone());
+        }
+        Ok(())
+    }
+
+    fn add_videos(&self, videos: &[Entry<VideoFingerprint>]) -> anyhow::Result<()> {
+        self.video_store.write().unwrap().extend_from_slice(videos);
+        Ok(())
+    }
+
+    fn remove_all_keywords(&self) -> anyhow::Result<()> {
         self.keyword_store.write().unwrap().clear();
+        Ok(())
     }
 
-    fn remove_all_hash(&self) {
+    fn remove_all_hash(&self) -> anyhow::Result<()> {
         self.hash_store.write().unwrap().clear();
+        Ok(())
     }
 
-    fn remove_all_phash(&self) {
+    fn remove_all_phash(&self) -> anyhow::Result<()> {
         self.phash_store.write().unwrap().clear();
+        Ok(())
+    }
+
+    fn remove_all_pdq(&self) -> anyhow::Result<()> {
+        let mut store = self.pdq_store.write().unwrap();
+        store.index.clear();
+        store.entries.clear();
+        Ok(())
+    }
+
+    fn remove_all_video(&self) -> anyhow::Result<()> {
+        self.video_store.write().unwrap().clear();
+        Ok(())
     }
 
-    fn contains_keyword(&self, filename: &str) -> Option<String> {
-        for keyword in self.keyword_store.read().unwrap().iter() {
+    fn find_keyword(&self, filename: &str) -> Option<Entry<String>> {
+        for (keyword, entry) in self.keyword_store.read().unwrap().iter() {
             if filename.contains(keyword) {
-                return Some(keyword.clone());
+                return Some(entry.clone());
             }
         }
         None
     }
 
-    fn contains_hash(&self, hash: &str) -> bool {
-        self.hash_store.read().unwrap().contains(hash)
+    fn find_hash(&self, hash: &str) -> Option<Entry<String>> {
+        let algorithm = HashAlgorithm::from_digest(hash)?;
+        self.hash_store
+            .read()
+            .unwrap()
+            .get(&algorithm)
+            .and_then(|store| store.get(hash).cloned())
+    }
+
+    fn hash_algorithms(&self) -> Vec<HashAlgorithm> {
+        let store = self.hash_store.read().unwrap();
+        let mut algorithms = store
+            .iter()
+            .filter(|(_, hashes)| !hashes.is_empty())
+            .map(|(algorithm, _)| *algorithm)
+            .collect::<Vec<HashAlgorithm>>();
+        algorithms.sort();
+        algorithms
+    }
+
+    fn phash_algorithms(&self) -> Vec<PHashAlgorithm> {
+        let store = self.phash_store.read().unwrap();
+        let mut algorithms = store
+            .iter()
+            .filter(|(_, phashes)| !phashes.entries.is_empty())
+            .map(|(algorithm, _)| *algorithm)
+            .collect::<Vec<PHashAlgorithm>>();
+        algorithms.sort();
+        algorithms
     }
 
     fn load_keywords(&self) -> Vec<String> {
         self.keyword_store
             .read()
             .unwrap()
-            .iter()
+            .keys()
             .map(|kw| kw.to_string())
             .collect::<Vec<String>>()
     }
 
-    fn match_phash(&self, phash: u64, max_distance: u32) -> Option<u32> {
+    fn match_phash(
+        &self,
+        algorithm: PHashAlgorithm,
+        phash: u64,
+        max_distance: u32,
+    ) -> Option<(Entry<u64>, u32)> {
         let store = self.phash_store.read().unwrap();
+        let store = store.get(&algorithm)?;
+        store
+            .index
+            .find(phash, max_distance)
+            .map(|(idx, distance)| (store.entries[idx].clone(), distance))
+    }
+
+    fn match_pdq(&self, hash: &PdqHash, max_distance: u32) -> Option<(Entry<PdqHash>, u32)> {
+        let store = self.pdq_store.read().unwrap();
+        store
+            .index
+            .find(*hash, max_distance)
+            .map(|(idx, distance)| (store.entries[idx].clone(), distance))
+    }
+
+    fn match_video(
+        &self,
+        video: &VideoFingerprint,
+        max_distance: u32,
+        min_frames: usize,
+    ) -> Option<(Entry<VideoFingerprint>, Alignment)> {
+        let store = self.video_store.read().unwrap();
         store
             .iter()
-            .map(|l_phash| utils::phash::distance(*l_phash, phash))
-            .filter(|&distance| distance <= max_distance)
-            .min()
+            .filter_map(|entry| {
+                video_fingerprint::align(video, &entry.value, max_distance, min_frames)
+                    .map(|alignment| (entry, alignment))
+            })
+            .min_by_key(|(_, alignment)| (Reverse(alignment.frames), alignment.distance))
+            .map(|(entry, alignment)| (entry.clone(), alignment))
     }
 
     fn count_keyword(&self) -> usize {
@@ -81,17 +211,37 @@ impl Repository for InMemoryRepository {
     }
 
     fn count_hash(&self) -> usize {
-        self.hash_store.read().unwrap().len()
+        self.hash_store
+            .read()
+            .unwrap()
+            .values()
+            .map(|hashes| hashes.len())
+            .sum()
     }
 
     fn count_phash(&self) -> usize {
-        self.phash_store.read().unwrap().len()
+        self.phash_store
+            .read()
+            .unwrap()
+            .values()
+            .map(|phashes| phashes.index.len())
+            .sum()
     }
 
-    fn clear(&self) {
-        self.keyword_store.write().unwrap().clear();
-        self.hash_store.write().unwrap().clear();
-        self.phash_store.write().unwrap().clear();
+    fn count_pdq(&self) -> usize {
+        self.pdq_store.read().unwrap().index.len()
+    }
+
+    fn count_video(&self) -> usize {
+        self.video_store.read().unwrap().len()
+    }
+
+    fn clear(&self) -> anyhow::Result<()> {
+        self.remove_all_keywords()?;
+        self.remove_all_hash()?;
+        self.remove_all_phash()?;
+        self.remove_all_pdq()?;
+        self.remove_all_video()
     }
 }
 
@@ -100,35 +250,135 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_contains_keyword_should_return_true() {
+    fn test_find_keyword_should_return_keyword() {
         let repo = InMemoryRepository::new();
         let filename = "File name 13 year old test xpto.";
         let keyword = "13 year old";
-        repo.add_keyword(keyword);
-        let result = repo.contains_keyword(filename);
+        repo.add_keyword(keyword).unwrap();
+        let result = repo.find_keyword(filename).map(|entry| entry.value);
         assert_eq!(result, Some(keyword.to_owned()));
     }
 
     #[test]
-    fn test_contains_hash_should_return_true() {
+    fn test_find_hash_should_return_entry() {
         let repo = InMemoryRepository::new();
         let hash = "50cd5ed4af91a2723d14f8b9f4254b7d";
-        repo.add_hash(hash);
-        let result = repo.contains_hash(hash);
-        assert_eq!(result, true);
+        repo.add_hash(hash).unwrap();
+        let result = repo.find_hash(hash);
+        assert!(result.is_some());
+    }
+
+    #[test]
+    fn test_hash_algorithms_should_be_detected_by_digest_length() {
+        let repo = InMemoryRepository::new();
+        repo.add_hash("B7B6E21916253608C9FF081DB046A58100536963")
+            .unwrap();
+        repo.add_hash("9b7bc4bef1e301d3b6d4be5a9af84cce7960b518b78dd39d6da60df5c1c6f943")
+            .unwrap();
+        repo.add_hash("not a digest").unwrap();
+
+        // Assert
+        assert_eq!(
+            repo.hash_algorithms(),
+            vec![HashAlgorithm::SHA1, HashAlgorithm::SHA256]
+        );
+        assert_eq!(repo.count_hash(), 2);
+        assert!(repo
+            .find_hash("b7b6e21916253608c9ff081db046a58100536963")
+            .is_some());
     }
 
     #[test]
     fn test_match_phash_should_return_distance_equals_1() {
         let repo = InMemoryRepository::new();
         let phash_1: u64 = 15634510955120228568;
-        repo.add_phash(phash_1);
+        repo.add_phash(phash_1).unwrap();
         let phash_2: u64 = 15634510955120226520;
-        let result = repo.match_phash(phash_2, 10);
+        let result = repo.match_phash(PHashAlgorithm::PHash, phash_2, 10);
         assert_ne!(result, None);
 
-        if let Some(distance) = result {
+        if let Some((_, distance)) = result {
             assert_eq!(distance, 1);
         }
     }
+
+    #[test]
+    fn test_match_phash_should_return_provenance() {
+        let repo = InMemoryRepository::new();
+        let entry = Entry {
+            value: 15634510955120228568,
+            source: String::from("phash.txt"),
+            category: Some(String::from("A1")),
+            filename: Some(String::from("image.jpg")),
+            series: None,
+        };
+        repo.add_phashes(
+            PHashAlgorithm::PHash,
+            &[Entry::new(1, "phash.txt"), entry.clone()],
+        )
+        .unwrap();
+        let result = repo.match_phash(PHashAlgorithm::PHash, 15634510955120226520, 10);
+
+        // Assert
+        assert_eq!(result, Some((entry, 1)));
+    }
+
+    #[test]
+    fn test_match_phash_should_not_mix_algorithms() {
+        let repo = InMemoryRepository::new();
+        let phash: u64 = 15634510955120228568;
+        repo.add_phashes(PHashAlgorithm::DHash, &[Entry::new(phash, "phash.txt")])
+            .unwrap();
+
+        // Assert
+        assert_eq!(repo.match_phash(PHashAlgorithm::PHash, phash, 10), None);
+        assert!(repo.match_phash(PHashAlgorithm::DHash, phash, 10).is_some());
+        assert_eq!(repo.phash_algorithms(), vec![PHashAlgorithm::DHash]);
+        assert_eq!(repo.count_phash(), 1);
+    }
+
+    #[test]
+    fn test_match_pdq_should_return_closest() {
+        let repo = InMemoryRepository::new();
+        let hash = PdqHash::from_words([u64::MAX, 0, 0, 0]);
+        repo.add_pdq(PdqHash::from_words([u64::MAX, 0, 0, 0xFF]))
+            .unwrap();
+        repo.add_pdq(PdqHash::from_words([u64::MAX, 0, 0, 0x0F]))
+            .unwrap();
+
+        // Assert
+        let (entry, distance) = repo.match_pdq(&hash, 31).unwrap();
+        assert_eq!(entry.value, PdqHash::from_words([u64::MAX, 0, 0, 0x0F]));
+        assert_eq!(distance, 4);
+        assert_eq!(repo.match_pdq(&hash, 3), None);
+        assert_eq!(repo.count_pdq(), 2);
+    }
+
+    #[test]
+    fn test_match_video_should_return_longest_alignment() {
+        let repo = InMemoryRepository::new();
+        let times: Vec<f64> = (0..6).map(f64::from).collect();
+        let frames: Vec<u64> = (1..=6)
+            .map(|idx: u64| idx.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+        repo.add_video(VideoFingerprint::new(&times[..3], &frames[..3]))
+            .unwrap();
+        repo.add_videos(&[Entry::new(
+            VideoFingerprint::new(&times, &frames),
+            "video.txt",
+        )])
+        .unwrap();
+
+        // Assert
+        let (entry, alignment) = repo
+            .match_video(&VideoFingerprint::new(&times, &frames), 10, 5)
+            .unwrap();
+        assert_eq!(entry.source, "video.txt");
+        assert_eq!(alignment.frames, 6);
+        assert_eq!(
+            repo.match_video(&VideoFingerprint::new(&times, &[1; 6]), 10, 5),
+            None
+        );
+        assert_eq!(repo.count_video(), 2);
+    }
 }
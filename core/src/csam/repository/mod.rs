@@ -1,20 +1,109 @@
+use std::path::Path;
+
+use super::{HashAlgorithm, PHashAlgorithm};
+use crate::utils::pdq::PdqHash;
+use crate::utils::video_fingerprint::{Alignment, VideoFingerprint};
+
 pub use in_memory_repository::InMemoryRepository;
+pub use sqlite_repository::SqliteRepository;
 
 mod in_memory_repository;
+mod sqlite_repository;
+
+/// Entry of the CSAM database with its provenance.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entry<T> {
+    pub value: T,
+    /// Hash list the entry was imported from.
+    pub source: String,
+    pub category: Option<String>,
+    /// Name of the original file, when known.
+    pub filename: Option<String>,
+    /// Series of images of the same victim, when known.
+    pub series: Option<String>,
+}
+
+impl<T> Entry<T> {
+    pub fn new(value: T, source: &str) -> Self {
+        Self {
+            value,
+            source: source.to_owned(),
+            category: None,
+            filename: None,
+            series: None,
+        }
+    }
+}
 
 pub trait Repository: Send + Sync {
-    fn add_keyword(&self, keyword: &str);
-    fn add_hash(&self, hash: &str);
-    fn add_phash(&self, phash: u64);
-    fn remove_all_keywords(&self);
-    fn remove_all_hash(&self);
-    fn remove_all_phash(&self);
-    fn contains_keyword(&self, filename: &str) -> Option<String>;
-    fn contains_hash(&self, hash: &str) -> bool;
+    /// Attaches the repository to the database directory.
+    /// Repositories that are not persisted on disk ignore it.
+    fn open(&self, _db_path: &Path) -> anyhow::Result<()> {
+        Ok(())
+    }
+    fn add_keyword(&self, keyword: &str) -> anyhow::Result<()> {
+        self.add_keywords(&[Entry::new(keyword.to_owned(), "")])
+    }
+    fn add_hash(&self, hash: &str) -> anyhow::Result<()> {
+        self.add_hashes(&[Entry::new(hash.to_owned(), "")])
+    }
+    fn add_phash(&self, phash: u64) -> anyhow::Result<()> {
+        self.add_phashes(PHashAlgorithm::default(), &[Entry::new(phash, "")])
+    }
+    fn add_pdq(&self, hash: PdqHash) -> anyhow::Result<()> {
+        self.add_pdqs(&[Entry::new(hash, "")])
+    }
+    fn add_video(&self, video: VideoFingerprint) -> anyhow::Result<()> {
+        self.add_videos(&[Entry::new(video, "")])
+    }
+    fn add_keywords(&self, keywords: &[Entry<String>]) -> anyhow::Result<()>;
+    fn add_hashes(&self, hashes: &[Entry<String>]) -> anyhow::Result<()>;
+    fn add_phashes(&self, algorithm: PHashAlgorithm, phashes: &[Entry<u64>]) -> anyhow::Result<()>;
+    fn add_pdqs(&self, hashes: &[Entry<PdqHash>]) -> anyhow::Result<()>;
+    fn add_videos(&self, videos: &[Entry<VideoFingerprint>]) -> anyhow::Result<()>;
+    fn remove_all_keywords(&self) -> anyhow::Result<()>;
+    fn remove_all_hash(&self) -> anyhow::Result<()>;
+    fn remove_all_phash(&self) -> anyhow::Result<()>;
+    fn remove_all_pdq(&self) -> anyhow::Result<()>;
+    fn remove_all_video(&self) -> anyhow::Result<()>;
+    /// Returns the first keyword contained in the file name.
+    fn find_keyword(&self, filename: &str) -> Option<Entry<String>>;
+    fn find_hash(&self, hash: &str) -> Option<Entry<String>>;
+    /// Algorithms of the hashes in the database, so a scan only computes those.
+    fn hash_algorithms(&self) -> Vec<HashAlgorithm>;
+    /// Algorithms of the perceptual hashes in the database, so a scan only computes those.
+    fn phash_algorithms(&self) -> Vec<PHashAlgorithm>;
     fn load_keywords(&self) -> Vec<String>;
-    fn match_phash(&self, phash: u64, max_distance: u32) -> Option<u32>;
+    /// Returns the closest perceptual hash of the `algorithm` within `max_distance`
+    /// and its distance.
+    fn match_phash(
+        &self,
+        algorithm: PHashAlgorithm,
+        phash: u64,
+        max_distance: u32,
+    ) -> Option<(Entry<u64>, u32)>;
+    /// Returns the closest PDQ hash within `max_distance` and its distance.
+    fn match_pdq(&self, hash: &PdqHash, max_distance: u32) -> Option<(Entry<PdqHash>, u32)>;
+    /// Returns the known video that plays for the most frames inside the `video`,
+    /// and where, aligning frames within `max_distance`.
+    fn match_video(
+        &self,
+        video: &VideoFingerprint,
+        max_distance: u32,
+        min_frames: usize,
+    ) -> Option<(Entry<VideoFingerprint>, Alignment)>;
     fn count_keyword(&self) -> usize;
     fn count_hash(&self) -> usize;
     fn count_phash(&self) -> usize;
-    fn clear(&self);
+    fn count_pdq(&self) -> usize;
+    fn count_video(&self) -> usize;
+    fn clear(&self) -> anyhow::Result<()>;
+    /// Returns `true` when the content of the database file `source` was already
+    /// imported with the given `fingerprint`, so it does not need to be parsed again.
+    fn is_synced(&self, _source: &str, _fingerprint: &str) -> bool {
+        false
+    }
+    fn set_synced(&self, _source: &str, _fingerprint: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
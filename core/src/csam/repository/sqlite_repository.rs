@@ -0,0 +1,885 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+
+use anyhow::Context;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::{Entry, Repository};
+use crate::csam::{HashAlgorithm, PHashAlgorithm};
+use crate::utils::multi_index::{PHashIndex, PdqIndex};
+use crate::utils::pdq::PdqHash;
+use crate::utils::video_fingerprint::{self, Alignment, VideoFingerprint};
+
+const FILE_SQLITE: &str = "csam.sqlite";
+
+// Incremented whenever the tables change; older databases are rebuilt from the
+// text databases, since they are only an index of them.
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    PRAGMA synchronous = NORMAL;
+    CREATE TABLE IF NOT EXISTS keyword (
+        value TEXT PRIMARY KEY,
+        source TEXT NOT NULL,
+        category TEXT,
+        filename TEXT,
+        series TEXT
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS hash (
+        value TEXT PRIMARY KEY,
+        source TEXT NOT NULL,
+        category TEXT,
+        filename TEXT,
+        series TEXT
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS phash (
+        value INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        category TEXT,
+        filename TEXT,
+        series TEXT,
+        algorithm TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS pdq (
+        value TEXT NOT NULL,
+        source TEXT NOT NULL,
+        category TEXT,
+        filename TEXT,
+        series TEXT
+    );
+    CREATE TABLE IF NOT EXISTS video (
+        value TEXT NOT NULL,
+        source TEXT NOT NULL,
+        category TEXT,
+        filename TEXT,
+        series TEXT
+    );
+    CREATE TABLE IF NOT EXISTS source (
+        name TEXT PRIMARY KEY,
+        fingerprint TEXT NOT NULL
+    ) WITHOUT ROWID;
+";
+
+const DROP_SCHEMA: &str = "
+    DROP TABLE IF EXISTS keyword;
+    DROP TABLE IF EXISTS hash;
+    DROP TABLE IF EXISTS phash;
+    DROP TABLE IF EXISTS pdq;
+    DROP TABLE IF EXISTS video;
+    DROP TABLE IF EXISTS source;
+";
+
+// Perceptual hashes with the rowid of their entry in the `phash` table.
+#[derive(Debug, Default)]
+struct PHashCache {
+    index: PHashIndex,
+    rowids: Vec<i64>,
+}
+
+impl PHashCache {
+    fn insert(&mut self, rowid: i64, phash: u64) {
+        self.index.insert(phash);
+        self.rowids.push(rowid);
+    }
+
+    fn clear(&mut self) {
+        self.index.clear();
+        self.rowids.clear();
+    }
+}
+
+// PDQ hashes with the rowid of their entry in the `pdq` table.
+#[derive(Debug, Default)]
+struct PdqCache {
+    index: PdqIndex,
+    rowids: Vec<i64>,
+}
+
+impl PdqCache {
+    fn insert(&mut self, rowid: i64, hash: PdqHash) {
+        self.index.insert(hash);
+        self.rowids.push(rowid);
+    }
+
+    fn clear(&mut self) {
+        self.index.clear();
+        self.rowids.clear();
+    }
+}
+
+/// Repository persisted in a SQLite file inside the database directory.
+///
+/// The text databases are indexed once; on the next start the repository is
+/// opened without parsing them again, unless their content has changed.
+/// Perceptual hashes are also kept in an in-memory index for each algorithm,
+/// PDQ hashes in another one, and video fingerprints with their rowid, since
+/// every frame of every video is looked up in them.
+#[derive(Debug)]
+pub struct SqliteRepository {
+    conn: Mutex<Connection>,
+    phash_cache: RwLock<HashMap<PHashAlgorithm, PHashCache>>,
+    pdq_cache: RwLock<PdqCache>,
+    video_cache: RwLock<Vec<(i64, VideoFingerprint)>>,
+}
+
+impl SqliteRepository {
+    /// Creates a repository backed by an in-memory SQLite database until
+    /// [`Repository::open`] attaches it to a database directory.
+    pub fn new() -> anyhow::Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            phash_cache: RwLock::new(HashMap::new()),
+            pdq_cache: RwLock::new(PdqCache::default()),
+            video_cache: RwLock::new(Vec::new()),
+        })
+    }
+
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(DROP_SCHEMA)?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        conn.execute_batch(SCHEMA)
+    }
+
+    fn load_phash_cache(
+        conn: &Connection,
+    ) -> rusqlite::Result<HashMap<PHashAlgorithm, PHashCache>> {
+        let mut cache: HashMap<PHashAlgorithm, PHashCache> = HashMap::new();
+        let mut stmt = conn.prepare("SELECT rowid, value, algorithm FROM phash ORDER BY rowid")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let Some(algorithm) = PHashAlgorithm::from_name(&row.get::<_, String>(2)?) else {
+                continue;
+            };
+            cache
+                .entry(algorithm)
+                .or_default()
+                .insert(row.get(0)?, row.get::<_, i64>(1)? as u64);
+        }
+        Ok(cache)
+    }
+
+    fn load_pdq_cache(conn: &Connection) -> rusqlite::Result<PdqCache> {
+        let mut cache = PdqCache::default();
+        for (rowid, hash) in Self::load_parsed(conn, "pdq")? {
+            cache.insert(rowid, hash);
+        }
+        Ok(cache)
+    }
+
+    fn load_video_cache(conn: &Connection) -> rusqlite::Result<Vec<(i64, VideoFingerprint)>> {
+        Self::load_parsed(conn, "video")
+    }
+
+    // Values of the table kept as text, with their rowid.
+    fn load_parsed<T: std::str::FromStr>(
+        conn: &Connection,
+        table: &str,
+    ) -> rusqlite::Result<Vec<(i64, T)>> {
+        let mut stmt = conn.prepare(&format!("SELECT rowid, value FROM {table} ORDER BY rowid"))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut cache = Vec::new();
+        for row in rows {
+            let (rowid, value) = row?;
+            if let Ok(value) = value.parse::<T>() {
+                cache.push((rowid, value));
+            }
+        }
+        Ok(cache)
+    }
+
+    // Entry of the table by its rowid, with the value parsed from its text.
+    fn parsed_entry<T: std::str::FromStr + Default>(
+        &self,
+        table: &str,
+        rowid: i64,
+    ) -> Option<Entry<T>> {
+        let sql = format!(
+            "SELECT value, source, category, filename, series FROM {table} WHERE rowid = ?1"
+        );
+        let entry = self
+            .conn
+            .lock()
+            .unwrap()
+            .prepare_cached(&sql)
+            .and_then(|mut stmt| stmt.query_row(params![rowid], Self::entry_from_row));
+
+        match entry {
+            Ok(entry) => Some(Entry {
+                value: entry.value.parse().unwrap_or_default(),
+                source: entry.source,
+                category: entry.category,
+                filename: entry.filename,
+                series: entry.series,
+            }),
+            Err(err) => {
+                tracing::error!("Could not read from sqlite database.\nError: {:?}", err);
+                None
+            }
+        }
+    }
+
+    fn entry_from_row(row: &Row) -> rusqlite::Result<Entry<String>> {
+        Ok(Entry {
+            value: row.get(0)?,
+            source: row.get(1)?,
+            category: row.get(2)?,
+            filename: row.get(3)?,
+            series: row.get(4)?,
+        })
+    }
+
+    // Inserts the values in a single transaction, returning the result of
+    // every insert, or the error that rolled all of them back.
+    fn insert_batch<T, R, F>(&self, sql: &str, values: &[T], bind: F) -> anyhow::Result<Vec<R>>
+    where
+        F: Fn(&mut rusqlite::Statement, &T) -> rusqlite::Result<R>,
+    {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut results = Vec::with_capacity(values.len());
+        {
+            let mut stmt = tx.prepare_cached(sql)?;
+            for value in values.iter() {
+                results.push(bind(&mut stmt, value)?);
+            }
+        }
+        tx.commit()
+            .with_context(|| "Could not write in sqlite database.")?;
+        Ok(results)
+    }
+
+    fn execute(&self, sql: &str) -> anyhow::Result<()> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(sql, [])
+            .with_context(|| "Could not write in sqlite database.")?;
+        Ok(())
+    }
+
+    fn count(&self, table: &str) -> usize {
+        let sql = format!("SELECT COUNT(*) FROM {table}");
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(&sql, [], |row| row.get::<_, i64>(0))
+            .unwrap_or_default() as usize
+    }
+}
+
+impl Repository for SqliteRepository {
+    fn open(&self, db_path: &Path) -> anyhow::Result<()> {
+        let file_path = db_path.join(FILE_SQLITE);
+        let conn = Connection::open(&file_path)
+            .with_context(|| format!("Could not open file: {}", file_path.display()))?;
+        Self::migrate(&conn)?;
+
+        let phash_cache = Self::load_phash_cache(&conn)?;
+        let pdq_cache = Self::load_pdq_cache(&conn)?;
+        let video_cache = Self::load_video_cache(&conn)?;
+        *self.conn.lock().unwrap() = conn;
+        *self.phash_cache.write().unwrap() = phash_cache;
+        *self.pdq_cache.write().unwrap() = pdq_cache;
+        *self.video_cache.write().unwrap() = video_cache;
+
+        Ok(())
+    }
+
+    fn add_keywords(&self, keywords: &[Entry<String>]) -> anyhow::Result<()> {
+        self.insert_batch(
+            "INSERT OR IGNORE INTO keyword (value, source, category, filename, series)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            keywords,
+            |stmt, keyword| {
+                stmt.execute(params![
+                    keyword.value.to_lowercase(),
+                    keyword.source,
+                    keyword.category,
+                    keyword.filename,
+                    keyword.series
+                ])
+            },
+        )?;
+
+        Ok(())
+    }
+
+    fn add_hashes(&self, hashes: &[Entry<String>]) -> anyhow::Result<()> {
+        self.insert_batch(
+            "INSERT OR IGNORE INTO hash (value, source, category, filename, series)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            hashes,
+            |stmt, hash| {
+                if HashAlgorithm::from_digest(&hash.value).is_none() {
+                    return Ok(0);
+                }
+                stmt.execute(params![
+                    hash.value.to_lowercase(),
+                    hash.source,
+                    hash.category,
+                    hash.filename,
+                    hash.series
+                ])
+            },
+        )?;
+
+        Ok(())
+    }
+
+    fn add_phashes(&self, algorithm: PHashAlgorithm, phashes: &[Entry<u64>]) -> anyhow::Result<()> {
+        let algorithm_name = algorithm.to_string();
+        let rowids = self.insert_batch(
+            "INSERT INTO phash (value, source, category, filename, series, algorithm)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            phashes,
+            |stmt, phash| {
+                stmt.insert(params![
+                    phash.value as i64,
+                    phash.source,
+                    phash.category,
+                    phash.filename,
+                    phash.series,
+                    algorithm_name
+                ])
+            },
+        )?;
+
+        let mut cache = self.phash_cache.write().unwrap();
+        let cache = cache.entry(algorithm).or_default();
+        rowids
+            .into_iter()
+            .zip(phashes.iter())
+            .for_each(|(rowid, phash)| cache.insert(rowid, phash.value));
+
+        Ok(())
+    }
+
+    fn add_pdqs(&self, hashes: &[Entry<PdqHash>]) -> anyhow::Result<()> {
+        let rowids = self.insert_batch(
+            "INSERT INTO pdq (value, source, category, filename, series)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            hashes,
+            |stmt, hash| {
+                stmt.insert(params![
+                    hash.value.to_string(),
+                    hash.source,
+                    hash.category,
+                    hash.filename,
+                    hash.series
+                ])
+            },
+        )?;
+
+        let mut cache = self.pdq_cache.write().unwrap();
+        for (rowid, hash) in rowids.into_iter().zip(hashes.iter()) {
+            cache.insert(rowid, hash.value);
+        }
+
+        Ok(())
+    }
+
+    fn add_videos(&self, videos: &[Entry<VideoFingerprint>]) -> anyhow::Result<()> {
+        let rowids = self.insert_batch(
+            "INSERT INTO video (value, source, category, filename, series)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            videos,
+            |stmt, video| {
+                stmt.insert(params![
+                    video.value.to_string(),
+                    video.source,
+                    video.category,
+                    video.filename,
+                    video.series
+                ])
+            },
+        )?;
+
+        self.video_cache.write().unwrap().extend(
+            rowids
+                .into_iter()
+                .zip(videos.iter().map(|video| video.value.clone())),
+        );
+
+        Ok(())
+    }
+
+    fn remove_all_keywords(&self) -> anyhow::Result<()> {
+        self.execute("DELETE FROM keyword")
+    }
+
+    fn remove_all_hash(&self) -> anyhow::Result<()> {
+        self.execute("DELETE FROM hash")
+    }
+
+    fn remove_all_phash(&self) -> anyhow::Result<()> {
+        self.execute("DELETE FROM phash")?;
+        self.phash_cache
+            .write()
+            .unwrap()
+            .values_mut()
+            .for_each(PHashCache::clear);
+        Ok(())
+    }
+
+    fn remove_all_pdq(&self) -> anyhow::Result<()> {
+        self.execute("DELETE FROM pdq")?;
+        self.pdq_cache.write().unwrap().clear();
+        Ok(())
+    }
+
+    fn remove_all_video(&self) -> anyhow::Result<()> {
+        self.execute("DELETE FROM video")?;
+        self.video_cache.write().unwrap().clear();
+        Ok(())
+    }
+
+    fn find_keyword(&self, filename: &str) -> Option<Entry<String>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .prepare_cached(
+                "SELECT value, source, category, filename, series FROM keyword
+                 WHERE instr(?1, value) > 0 LIMIT 1",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_row(params![filename], Self::entry_from_row)
+                    .optional()
+            })
+            .unwrap_or_default()
+    }
+
+    fn find_hash(&self, hash: &str) -> Option<Entry<String>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .prepare_cached(
+                "SELECT value, source, category, filename, series FROM hash WHERE value = ?1",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_row(params![hash], Self::entry_from_row)
+                    .optional()
+            })
+            .unwrap_or_default()
+    }
+
+    fn hash_algorithms(&self) -> Vec<HashAlgorithm> {
+        let conn = self.conn.lock().unwrap();
+        let lengths = conn
+            .prepare("SELECT DISTINCT length(value) FROM hash")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, usize>(0))?
+                    .collect::<rusqlite::Result<Vec<usize>>>()
+            });
+
+        let mut algorithms = lengths
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(HashAlgorithm::from_digest_len)
+            .collect::<Vec<HashAlgorithm>>();
+        algorithms.sort();
+        algorithms
+    }
+
+    fn phash_algorithms(&self) -> Vec<PHashAlgorithm> {
+        let cache = self.phash_cache.read().unwrap();
+        let mut algorithms = cache
+            .iter()
+            .filter(|(_, phashes)| !phashes.index.is_empty())
+            .map(|(algorithm, _)| *algorithm)
+            .collect::<Vec<PHashAlgorithm>>();
+        algorithms.sort();
+        algorithms
+    }
+
+    fn load_keywords(&self) -> Vec<String> {
+        let conn = self.conn.lock().unwrap();
+        let keywords = conn
+            .prepare("SELECT value FROM keyword")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()
+            });
+
+        keywords.unwrap_or_default()
+    }
+
+    fn match_phash(
+        &self,
+        algorithm: PHashAlgorithm,
+        phash: u64,
+        max_distance: u32,
+    ) -> Option<(Entry<u64>, u32)> {
+        let (rowid, distance) = {
+            let cache = self.phash_cache.read().unwrap();
+            let cache = cache.get(&algorithm)?;
+            let (idx, distance) = cache.index.find(phash, max_distance)?;
+            (cache.rowids[idx], distance)
+        };
+
+        let entry = self
+            .conn
+            .lock()
+            .unwrap()
+            .prepare_cached(
+                "SELECT value, source, category, filename, series FROM phash WHERE rowid = ?1",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_row(params![rowid], |row| {
+                    Ok(Entry {
+                        value: row.get::<_, i64>(0)? as u64,
+                        source: row.get(1)?,
+                        category: row.get(2)?,
+                        filename: row.get(3)?,
+                        series: row.get(4)?,
+                    })
+                })
+            });
+
+        match entry {
+            Ok(entry) => Some((entry, distance)),
+            Err(err) => {
+                tracing::error!("Could not read from sqlite database.\nError: {:?}", err);
+                None
+            }
+        }
+    }
+
+    fn match_pdq(&self, hash: &PdqHash, max_distance: u32) -> Option<(Entry<PdqHash>, u32)> {
+        let (rowid, distance) = {
+            let cache = self.pdq_cache.read().unwrap();
+            let (idx, distance) = cache.index.find(*hash, max_distance)?;
+            (cache.rowids[idx], distance)
+        };
+
+        self.parsed_entry("pdq", rowid)
+            .map(|entry| (entry, distance))
+    }
+
+    fn match_video(
+        &self,
+        video: &VideoFingerprint,
+        max_distance: u32,
+        min_frames: usize,
+    ) -> Option<(Entry<VideoFingerprint>, Alignment)> {
+        let (rowid, alignment) = self
+            .video_cache
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|(rowid, value)| {
+                video_fingerprint::align(video, value, max_distance, min_frames)
+                    .map(|alignment| (*rowid, alignment))
+            })
+            .min_by_key(|(_, alignment)| (Reverse(alignment.frames), alignment.distance))?;
+
+        self.parsed_entry("video", rowid)
+            .map(|entry| (entry, alignment))
+    }
+
+    fn count_keyword(&self) -> usize {
+        self.count("keyword")
+    }
+
+    fn count_hash(&self) -> usize {
+        self.count("hash")
+    }
+
+    fn count_phash(&self) -> usize {
+        self.phash_cache
+            .read()
+            .unwrap()
+            .values()
+            .map(|phashes| phashes.index.len())
+            .sum()
+    }
+
+    fn count_pdq(&self) -> usize {
+        self.pdq_cache.read().unwrap().index.len()
+    }
+
+    fn count_video(&self) -> usize {
+        self.video_cache.read().unwrap().len()
+    }
+
+    fn clear(&self) -> anyhow::Result<()> {
+        self.remove_all_keywords()?;
+        self.remove_all_hash()?;
+        self.remove_all_phash()?;
+        self.remove_all_pdq()?;
+        self.remove_all_video()?;
+        self.execute("DELETE FROM source")
+    }
+
+    fn is_synced(&self, source: &str, fingerprint: &str) -> bool {
+        self.conn
+            .lock()
+            .unwrap()
+            .prepare_cached("SELECT 1 FROM source WHERE name = ?1 AND fingerprint = ?2")
+            .and_then(|mut stmt| stmt.exists(params![source, fingerprint]))
+            .unwrap_or_default()
+    }
+
+    fn set_synced(&self, source: &str, fingerprint: &str) -> anyhow::Result<()> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT OR REPLACE INTO source (name, fingerprint) VALUES (?1, ?2)",
+                params![source, fingerprint],
+            )
+            .with_context(|| "Could not write in sqlite database.")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_keyword_should_return_keyword() {
+        let repo = SqliteRepository::new().unwrap();
+        let filename = "File name 13 year old test xpto.";
+        let keyword = "13 year old";
+        repo.add_keyword(keyword).unwrap();
+        let result = repo.find_keyword(filename).map(|entry| entry.value);
+        assert_eq!(result, Some(keyword.to_owned()));
+    }
+
+    #[test]
+    fn test_find_hash_should_return_entry() {
+        let repo = SqliteRepository::new().unwrap();
+        let hash = "50cd5ed4af91a2723d14f8b9f4254b7d";
+        repo.add_hash(hash).unwrap();
+        let result = repo.find_hash(hash);
+        assert!(result.is_some());
+    }
+
+    #[test]
+    fn test_hash_algorithms_should_be_detected_by_digest_length() {
+        let repo = SqliteRepository::new().unwrap();
+        repo.add_hash("B7B6E21916253608C9FF081DB046A58100536963")
+            .unwrap();
+        repo.add_hash("9b7bc4bef1e301d3b6d4be5a9af84cce7960b518b78dd39d6da60df5c1c6f943")
+            .unwrap();
+        repo.add_hash("not a digest").unwrap();
+
+        // Assert
+        assert_eq!(
+            repo.hash_algorithms(),
+            vec![HashAlgorithm::SHA1, HashAlgorithm::SHA256]
+        );
+        assert_eq!(repo.count_hash(), 2);
+        assert!(repo
+            .find_hash("b7b6e21916253608c9ff081db046a58100536963")
+            .is_some());
+    }
+
+    #[test]
+    fn test_match_phash_should_return_distance_equals_1() {
+        let repo = SqliteRepository::new().unwrap();
+        let phash_1: u64 = 15634510955120228568;
+        repo.add_phash(phash_1).unwrap();
+        let phash_2: u64 = 15634510955120226520;
+        let result = repo.match_phash(PHashAlgorithm::PHash, phash_2, 10);
+        assert_ne!(result, None);
+
+        if let Some((_, distance)) = result {
+            assert_eq!(distance, 1);
+        }
+    }
+
+    #[test]
+    fn test_match_phash_should_return_provenance() {
+        let repo = SqliteRepository::new().unwrap();
+        let entry = Entry {
+            value: 15634510955120228568,
+            source: String::from("phash.txt"),
+            category: Some(String::from("A1")),
+            filename: Some(String::from("image.jpg")),
+            series: None,
+        };
+        repo.add_phashes(
+            PHashAlgorithm::PHash,
+            &[Entry::new(1, "phash.txt"), entry.clone()],
+        )
+        .unwrap();
+        let result = repo.match_phash(PHashAlgorithm::PHash, 15634510955120226520, 10);
+
+        // Assert
+        assert_eq!(result, Some((entry, 1)));
+    }
+
+    #[test]
+    fn test_match_phash_should_not_mix_algorithms() {
+        let repo = SqliteRepository::new().unwrap();
+        let phash: u64 = 15634510955120228568;
+        repo.add_phashes(PHashAlgorithm::DHash, &[Entry::new(phash, "phash.txt")])
+            .unwrap();
+
+        // Assert
+        assert_eq!(repo.match_phash(PHashAlgorithm::PHash, phash, 10), None);
+        assert!(repo.match_phash(PHashAlgorithm::DHash, phash, 10).is_some());
+        assert_eq!(repo.phash_algorithms(), vec![PHashAlgorithm::DHash]);
+        assert_eq!(repo.count_phash(), 1);
+    }
+
+    #[test]
+    fn test_match_pdq_should_return_closest() {
+        let repo = SqliteRepository::new().unwrap();
+        let hash = PdqHash::from_words([u64::MAX, 0, 0, 0]);
+        repo.add_pdq(PdqHash::from_words([u64::MAX, 0, 0, 0xFF]))
+            .unwrap();
+        repo.add_pdq(PdqHash::from_words([u64::MAX, 0, 0, 0x0F]))
+            .unwrap();
+
+        // Assert
+        let (entry, distance) = repo.match_pdq(&hash, 31).unwrap();
+        assert_eq!(entry.value, PdqHash::from_words([u64::MAX, 0, 0, 0x0F]));
+        assert_eq!(distance, 4);
+        assert_eq!(repo.match_pdq(&hash, 3), None);
+        assert_eq!(repo.count_pdq(), 2);
+    }
+
+    #[test]
+    fn test_match_video_should_return_longest_alignment() {
+        let repo = SqliteRepository::new().unwrap();
+        let times: Vec<f64> = (0..6).map(f64::from).collect();
+        let frames: Vec<u64> = (1..=6)
+            .map(|idx: u64| idx.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+        repo.add_video(VideoFingerprint::new(&times[..3], &frames[..3]))
+            .unwrap();
+        repo.add_videos(&[Entry::new(
+            VideoFingerprint::new(&times, &frames),
+            "video.txt",
+        )])
+        .unwrap();
+
+        // Assert
+        let (entry, alignment) = repo
+            .match_video(&VideoFingerprint::new(&times, &frames), 10, 5)
+            .unwrap();
+        assert_eq!(entry.source, "video.txt");
+        assert_eq!(alignment.frames, 6);
+        assert_eq!(
+            repo.match_video(&VideoFingerprint::new(&times, &[1; 6]), 10, 5),
+            None
+        );
+        assert_eq!(repo.count_video(), 2);
+    }
+
+    #[test]
+    fn test_failed_batch_should_not_be_cached() {
+        let repo = SqliteRepository::new().unwrap();
+        repo.conn
+            .lock()
+            .unwrap()
+            .execute_batch("DROP TABLE pdq")
+            .unwrap();
+
+        let result = repo.add_pdq(PdqHash::from_words([u64::MAX, 0, 0, 0]));
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(repo.count_pdq(), 0);
+        assert!(repo
+            .match_pdq(&PdqHash::from_words([u64::MAX, 0, 0, 0]), 31)
+            .is_none());
+    }
+
+    #[test]
+    fn test_failed_remove_should_be_reported() {
+        let repo = SqliteRepository::new().unwrap();
+        repo.add_pdq(PdqHash::from_words([u64::MAX, 0, 0, 0]))
+            .unwrap();
+        repo.conn
+            .lock()
+            .unwrap()
+            .execute_batch("DROP TABLE pdq")
+            .unwrap();
+
+        // Assert
+        assert!(repo.remove_all_pdq().is_err());
+        assert!(repo.clear().is_err());
+        assert_eq!(repo.count_pdq(), 1);
+    }
+
+    #[test]
+    fn test_should_persist_database() {
+        let db_path = std::path::PathBuf::from("../data/tmp/sqlite/");
+        std::fs::create_dir_all(&db_path).unwrap();
+
+        let repo = SqliteRepository::new().unwrap();
+        repo.open(&db_path)
+            .expect("Failed to open sqlite database.");
+        repo.clear().unwrap();
+        repo.add_hash("50cd5ed4af91a2723d14f8b9f4254b7d").unwrap();
+        repo.add_phashes(
+            PHashAlgorithm::DHash,
+            &[Entry {
+                value: 15634510955120228568,
+                source: String::from("phash.txt"),
+                category: Some(String::from("A1")),
+                filename: None,
+                series: None,
+            }],
+        )
+        .unwrap();
+        repo.add_pdq(PdqHash::from_words([u64::MAX, 0, 0, 0x0F]))
+            .unwrap();
+        let video: VideoFingerprint = "0.000:D8F8F0E0E4E4F8D8 1.000:0F0F0F0F0F0F0F0F"
+            .parse()
+            .unwrap();
+        repo.add_videos(&[Entry::new(video.clone(), "video.txt")])
+            .unwrap();
+        repo.set_synced("hash.txt", "1:1").unwrap();
+        drop(repo);
+
+        let repo = SqliteRepository::new().unwrap();
+        repo.open(&db_path)
+            .expect("Failed to open sqlite database.");
+
+        // Assert
+        assert_eq!(repo.count_hash(), 1);
+        assert_eq!(repo.count_phash(), 1);
+        assert!(repo.is_synced("hash.txt", "1:1"));
+        assert!(!repo.is_synced("hash.txt", "1:2"));
+
+        let (entry, distance) = repo
+            .match_phash(PHashAlgorithm::DHash, 15634510955120226520, 10)
+            .unwrap();
+        assert_eq!(distance, 1);
+        assert_eq!(repo.phash_algorithms(), vec![PHashAlgorithm::DHash]);
+        assert!(repo
+            .match_phash(PHashAlgorithm::PHash, 15634510955120226520, 10)
+            .is_none());
+        assert_eq!(entry.category, Some(String::from("A1")));
+
+        let (entry, distance) = repo
+            .match_pdq(&PdqHash::from_words([u64::MAX, 0, 0, 0]), 31)
+            .unwrap();
+        assert_eq!(entry.value, PdqHash::from_words([u64::MAX, 0, 0, 0x0F]));
+        assert_eq!(distance, 4);
+        assert_eq!(repo.count_pdq(), 1);
+
+        let (entry, alignment) = repo.match_video(&video, 10, 5).unwrap();
+        assert_eq!(entry.value, video);
+        assert_eq!(entry.source, "video.txt");
+        assert_eq!(alignment.frames, 2);
+        assert_eq!(repo.count_video(), 1);
+    }
+}
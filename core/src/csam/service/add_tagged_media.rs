@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use super::Service;
+use crate::csam::{db, Media};
+
+impl Service {
+    /// Adds the media tagged as CSAM to the hash and perceptual hash databases
+    /// and reloads them, returning the number of media added.
+    pub async fn add_tagged_media(
+        &self,
+        db_path: PathBuf,
+        source: String,
+        medias: Vec<Media>,
+    ) -> anyhow::Result<usize> {
+        let repo = self.repo.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let count = db::add_tagged_media(db_path.clone(), &source, &medias)?;
+            db::load_hash_database(db_path.clone(), repo.clone())?;
+            db::load_phash_database(db_path, repo)?;
+            Ok(count)
+        })
+        .await?
+    }
+}
@@ -2,21 +2,27 @@ use std::path::PathBuf;
 use std::sync::atomic::Ordering;
 
 use super::Service;
-use crate::csam::db;
+use crate::csam::{db, EvidenceSource};
 
 impl Service {
     pub async fn create_hash_database(
         &self,
         db_path: PathBuf,
-        root: PathBuf,
+        mut source: Box<dyn EvidenceSource>,
     ) -> anyhow::Result<usize> {
         self.cancel_flag.store(false, Ordering::SeqCst);
         let cancel_flag = self.cancel_flag.clone();
         let repo = self.repo.clone();
+        let archive_depth = self.archive_depth.load(Ordering::SeqCst);
 
         tokio::task::spawn_blocking(move || {
             let count_before = repo.count_hash();
-            let _ = db::create_hash_database(db_path.clone(), root, cancel_flag)?;
+            let _ = db::create_hash_database(
+                db_path.clone(),
+                &mut *source,
+                archive_depth,
+                cancel_flag,
+            )?;
             db::load_hash_database(db_path, repo.clone())?;
             let count_after = repo.count_hash();
             Ok(count_after - count_before)
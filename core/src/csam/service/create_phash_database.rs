@@ -2,21 +2,29 @@ use std::path::PathBuf;
 use std::sync::atomic::Ordering;
 
 use super::Service;
-use crate::csam::db;
+use crate::csam::{db, EvidenceSource, PHashAlgorithm};
 
 impl Service {
     pub async fn create_phash_database(
         &self,
         db_path: PathBuf,
-        root: PathBuf,
+        mut source: Box<dyn EvidenceSource>,
+        algorithms: Vec<PHashAlgorithm>,
     ) -> anyhow::Result<usize> {
         self.cancel_flag.store(false, Ordering::SeqCst);
         let cancel_flag = self.cancel_flag.clone();
         let repo = self.repo.clone();
+        let archive_depth = self.archive_depth.load(Ordering::SeqCst);
 
         tokio::task::spawn_blocking(move || {
             let count_before = repo.count_phash();
-            let _ = db::create_phash_database(db_path.clone(), root, cancel_flag)?;
+            let _ = db::create_phash_database(
+                db_path.clone(),
+                &mut *source,
+                archive_depth,
+                cancel_flag,
+                &algorithms,
+            )?;
             db::load_phash_database(db_path, repo.clone())?;
             let count_after = repo.count_phash();
             Ok(count_after - count_before)
@@ -0,0 +1,22 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+use super::Service;
+use crate::csam::inventory::{self, InventoryFormat, InventoryRecord};
+
+impl Service {
+    /// Exports the inventory of a scan as CSV, or as JSON Lines when the file
+    /// has the `.jsonl` extension.
+    pub async fn export_inventory(
+        &self,
+        file_path: PathBuf,
+        records: Vec<InventoryRecord>,
+    ) -> anyhow::Result<()> {
+        tokio::task::spawn_blocking(move || {
+            let format = InventoryFormat::from_path(&file_path);
+            let file = File::create(file_path)?;
+            inventory::write(file, format, &records)
+        })
+        .await?
+    }
+}
@@ -2,6 +2,7 @@ use std::path::{Component, PathBuf};
 use std::sync::atomic::Ordering;
 
 use super::Service;
+use crate::utils::archive;
 
 impl Service {
     pub async fn export_media(&self, save_path: &PathBuf, medias: &[String]) -> anyhow::Result<()> {
@@ -12,7 +13,9 @@ impl Service {
             .map(|media| {
                 let from_path = PathBuf::from(media);
                 let mut to_path = save_path.to_owned();
-                for component in from_path.components() {
+                // files inside archives are saved in a directory named after the archive
+                let member_path = PathBuf::from(media.replace(archive::SEPARATOR, "/"));
+                for component in member_path.components() {
                     if let Component::Normal(component) = component {
                         to_path.push(component)
                     }
@@ -36,7 +39,14 @@ impl Service {
         if !to_dir.exists() {
             tokio::fs::create_dir_all(to_dir).await?;
         }
-        tokio::fs::copy(from_path, to_path).await?;
+        let media = from_path.to_string_lossy();
+        if archive::is_virtual_path(&media) {
+            let media = media.into_owned();
+            let data = tokio::task::spawn_blocking(move || archive::extract(&media)).await??;
+            tokio::fs::write(to_path, data).await?;
+        } else {
+            tokio::fs::copy(from_path, to_path).await?;
+        }
 
         Ok(())
     }
@@ -0,0 +1,21 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+use super::Service;
+use crate::csam::{vics, Media};
+
+impl Service {
+    /// Exports the media as a Project VIC (VICS) JSON case.
+    pub async fn export_vics(
+        &self,
+        file_path: PathBuf,
+        case_number: String,
+        medias: Vec<Media>,
+    ) -> anyhow::Result<()> {
+        tokio::task::spawn_blocking(move || {
+            let file = File::create(file_path)?;
+            vics::write_case(file, &case_number, &medias)
+        })
+        .await?
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+
+use super::Service;
+use crate::csam::{
+    db,
+    report::{self, DatabaseInfo, ReportInfo},
+    Media,
+};
+
+impl Service {
+    /// Generates the HTML report of the media.
+    pub async fn generate_report(
+        &self,
+        file_path: PathBuf,
+        info: ReportInfo,
+        medias: Vec<Media>,
+    ) -> anyhow::Result<()> {
+        tokio::task::spawn_blocking(move || {
+            let file = File::create(file_path)?;
+            report::write_html(file, &info, &medias)
+        })
+        .await?
+    }
+
+    /// Returns the version and number of entries of each database file.
+    pub async fn database_info(&self, db_path: PathBuf) -> Vec<DatabaseInfo> {
+        db::database_versions(&db_path)
+            .into_iter()
+            .map(|(name, modified)| DatabaseInfo {
+                name: name.to_owned(),
+                version: modified
+                    .map(|modified| {
+                        DateTime::<Local>::from(modified)
+                            .format("%Y-%m-%d %H:%M:%S %:z")
+                            .to_string()
+                    })
+                    .unwrap_or_default(),
+                count: match name {
+                    db::FILE_HASH => self.repo.count_hash(),
+                    db::FILE_PHASH => self.repo.count_phash(),
+                    db::FILE_PDQ => self.repo.count_pdq(),
+                    db::FILE_VIDEO => self.repo.count_video(),
+                    _ => self.repo.count_keyword(),
+                },
+            })
+            .collect()
+    }
+}
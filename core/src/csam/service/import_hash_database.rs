@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use super::Service;
+use crate::csam::db;
+
+impl Service {
+    /// Imports a Project VIC (VICS) or CAID JSON hash set into the hash database,
+    /// returning the number of new hashes.
+    pub async fn import_hash_database(
+        &self,
+        db_path: PathBuf,
+        file_path: PathBuf,
+    ) -> anyhow::Result<usize> {
+        let repo = self.repo.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let count_before = repo.count_hash();
+            let _ = db::import_hash_set(db_path.clone(), &file_path)?;
+            db::load_hash_database(db_path, repo.clone())?;
+            let count_after = repo.count_hash();
+            Ok(count_after.saturating_sub(count_before))
+        })
+        .await?
+    }
+}
@@ -5,6 +5,14 @@ use crate::csam::db;
 
 impl Service {
     pub async fn load_database(&self, db_path: PathBuf) -> anyhow::Result<()> {
+        tokio::task::spawn_blocking({
+            let db_path = db_path.clone();
+            let repo = self.repo.clone();
+            move || repo.open(&db_path)
+        })
+        .await?
+        .map_err(|err| anyhow::anyhow!("Could not open csam database. Error: {}", err))?;
+
         let mut tasks = vec![];
         tasks.push(tokio::task::spawn_blocking({
             let db_path = db_path.clone();
@@ -17,9 +25,19 @@ impl Service {
             move || db::load_keyword_database(db_path, repo)
         }));
         tasks.push(tokio::task::spawn_blocking({
+            let db_path = db_path.clone();
             let repo = self.repo.clone();
             move || db::load_phash_database(db_path, repo)
         }));
+        tasks.push(tokio::task::spawn_blocking({
+            let db_path = db_path.clone();
+            let repo = self.repo.clone();
+            move || db::load_pdq_database(db_path, repo)
+        }));
+        tasks.push(tokio::task::spawn_blocking({
+            let repo = self.repo.clone();
+            move || db::load_video_database(db_path, repo)
+        }));
 
         match futures::future::try_join_all(tasks).await {
             Ok(res) => {
@@ -1,14 +1,22 @@
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+    atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+    Arc, RwLock,
 };
 
 use super::repository::Repository;
+use super::{Media, ScanCache, VideoSampling};
 
+mod add_tagged_media;
 mod create_hash_database;
 mod create_phash_database;
+mod export_inventory;
 mod export_media;
+mod export_vics;
+mod generate_report;
+mod import_hash_database;
 mod load_database;
+mod rematch_media;
+mod save_case;
 mod save_keywords;
 mod search_media;
 
@@ -17,16 +25,56 @@ pub use search_media::*;
 pub struct Service {
     repo: Arc<dyn Repository>,
     cancel_flag: Arc<AtomicBool>,
+    archive_depth: AtomicUsize,
+    phash_transforms: AtomicBool,
+    pdq_threshold: AtomicU32,
+    video_sampling: RwLock<VideoSampling>,
+    scan_cache: RwLock<Option<Arc<ScanCache>>>,
 }
 
 impl Service {
+    /// Levels of nested archives opened by default in a search.
+    pub const ARCHIVE_DEPTH: usize = 3;
+
     pub fn new(repo: Arc<dyn Repository>) -> Self {
         Service {
             repo,
             cancel_flag: Arc::new(AtomicBool::new(false)),
+            archive_depth: AtomicUsize::new(Self::ARCHIVE_DEPTH),
+            phash_transforms: AtomicBool::new(false),
+            pdq_threshold: AtomicU32::new(Media::PDQ_THRESHOLD),
+            video_sampling: RwLock::new(VideoSampling::default()),
+            scan_cache: RwLock::new(None),
         }
     }
 
+    /// Sets how many levels of nested archives are opened in a search,
+    /// 0 to not open archives.
+    pub fn set_archive_depth(&self, depth: usize) {
+        self.archive_depth.store(depth, Ordering::SeqCst);
+    }
+
+    /// Sets whether the searches also match the rotated, mirrored and cropped
+    /// copies of the images in the perceptual hash database.
+    pub fn set_phash_transforms(&self, enabled: bool) {
+        self.phash_transforms.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Sets the highest Hamming distance of a PDQ match in the searches.
+    pub fn set_pdq_threshold(&self, threshold: u32) {
+        self.pdq_threshold.store(threshold, Ordering::SeqCst);
+    }
+
+    /// Sets how the frames of the videos are sampled in the searches.
+    pub fn set_video_sampling(&self, sampling: VideoSampling) {
+        *self.video_sampling.write().unwrap() = sampling;
+    }
+
+    /// Sets the cache used by the next searches, `None` to process every media again.
+    pub fn set_scan_cache(&self, cache: Option<ScanCache>) {
+        *self.scan_cache.write().unwrap() = cache.map(Arc::new);
+    }
+
     pub async fn count_keyword(&self) -> usize {
         self.repo.count_keyword()
     }
@@ -39,6 +87,14 @@ impl Service {
         self.repo.count_phash()
     }
 
+    pub async fn count_pdq(&self) -> usize {
+        self.repo.count_pdq()
+    }
+
+    pub async fn count_video(&self) -> usize {
+        self.repo.count_video()
+    }
+
     pub async fn load_keywords(&self) -> Vec<String> {
         self.repo.load_keywords()
     }
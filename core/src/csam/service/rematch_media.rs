@@ -0,0 +1,27 @@
+use std::sync::atomic::Ordering;
+
+use super::Service;
+use crate::csam::{Media, MediaOptions};
+
+impl Service {
+    /// Matches the media of a search again against the loaded database,
+    /// without reading the evidence.
+    pub async fn rematch_media(&self, mut medias: Vec<Media>) -> anyhow::Result<Vec<Media>> {
+        let repo = self.repo.clone();
+        let pdq_threshold = self.pdq_threshold.load(Ordering::SeqCst);
+        let medias = tokio::task::spawn_blocking(move || {
+            let options = MediaOptions {
+                hash_algorithms: repo.hash_algorithms(),
+                pdq_threshold,
+                ..Default::default()
+            };
+            for media in medias.iter_mut() {
+                media.rematch(repo.clone(), &options);
+            }
+            medias
+        })
+        .await?;
+
+        Ok(medias)
+    }
+}
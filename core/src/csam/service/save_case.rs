@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+use super::Service;
+use crate::csam::case::{self, Case};
+
+impl Service {
+    /// Saves the scan session to the case folder.
+    pub async fn save_case(&self, dir: PathBuf, case: Case) -> anyhow::Result<()> {
+        tokio::task::spawn_blocking(move || case::save(&dir, &case)).await?
+    }
+
+    /// Opens the scan session saved in the case folder.
+    pub async fn open_case(&self, dir: PathBuf) -> anyhow::Result<Case> {
+        tokio::task::spawn_blocking(move || case::open(&dir)).await?
+    }
+}
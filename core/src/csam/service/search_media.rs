@@ -1,27 +1,38 @@
-use std::path::{Path, PathBuf};
-use std::sync::atomic::Ordering;
+use std::sync::{atomic::Ordering, Arc};
 use threadpool::ThreadPool;
 use tokio::sync::mpsc::{self, Receiver, Sender};
-use walkdir::WalkDir;
 
 use super::Service;
-use crate::csam::media::Media;
-use crate::utils;
+use crate::csam::{media::Media, EvidenceSource, MediaOptions};
 
 #[derive(Debug)]
 pub enum StateMedia {
     Completed,
+    /// Index of the root being read, number of roots and name of the root.
+    Root(usize, usize, String),
+    /// Media found so far, updated after every root.
     Found(usize),
     Ok(Vec<Media>),
-    Err(anyhow::Error),
+    /// Path of the file that could not be processed and the error.
+    Err(String, anyhow::Error),
 }
 
 impl Service {
-    pub fn search_media(&self, dir: PathBuf, state_sender: Sender<StateMedia>) {
+    /// Searches the media of the roots in a single job.
+    pub fn search_media(
+        &self,
+        mut roots: Vec<Box<dyn EvidenceSource>>,
+        state_sender: Sender<StateMedia>,
+    ) {
         self.cancel_flag.store(false, Ordering::SeqCst);
         let cancel_flag = self.cancel_flag.clone();
         let repo = self.repo.clone();
         let state_sender = state_sender.clone();
+        let archive_depth = self.archive_depth.load(Ordering::SeqCst);
+        let transforms = self.phash_transforms.load(Ordering::SeqCst);
+        let pdq_threshold = self.pdq_threshold.load(Ordering::SeqCst);
+        let video_sampling = *self.video_sampling.read().unwrap();
+        let scan_cache = self.scan_cache.read().unwrap().clone();
 
         std::thread::spawn(move || {
             let (media_sender, media_receiver) = mpsc::channel::<Media>(1000);
@@ -36,47 +47,85 @@ impl Service {
                 num_cpus::get()
             };
             let thread_pool = ThreadPool::new(cpus);
-
-            for entry in WalkDir::new(dir)
-                .follow_links(false)
-                .into_iter()
-                .filter_map(|e| e.ok())
-                .filter(|e| !e.file_type().is_dir() && Service::is_media(e.path()))
-            {
+            let options = Arc::new(MediaOptions {
+                hash_algorithms: repo.hash_algorithms(),
+                phash_algorithms: repo.phash_algorithms(),
+                transforms,
+                pdq: repo.count_pdq() > 0,
+                pdq_threshold,
+                video_sampling,
+            });
+
+            let count = roots.len();
+            for (index, source) in roots.iter_mut().enumerate() {
                 if cancel_flag.load(Ordering::SeqCst) {
                     break;
                 }
 
-                found_files += 1;
-
-                let c_stop_flag = cancel_flag.clone();
-                let c_repo = repo.clone();
-                let c_media_sender = media_sender.clone();
-                let c_state_sender = state_sender.clone();
+                state_sender
+                    .blocking_send(StateMedia::Root(index, count, source.name()))
+                    .expect("could not send `StateMedia::Root`");
 
-                thread_pool.execute(move || {
-                    if c_stop_flag.load(Ordering::SeqCst) {
-                        return;
+                let result = source.read_media(archive_depth, &mut |path, item| {
+                    if cancel_flag.load(Ordering::SeqCst) {
+                        return false;
                     }
 
-                    match Media::new(c_repo, entry) {
-                        Ok(media) => {
-                            c_media_sender
-                                .blocking_send(media)
-                                .expect("could not send `Media`");
-                        }
+                    let item = match item {
+                        Ok(item) => item,
                         Err(err) => {
-                            c_state_sender
-                                .blocking_send(StateMedia::Err(err))
+                            state_sender
+                                .blocking_send(StateMedia::Err(path, err))
                                 .expect("could not send `StateMedia::Err`");
+                            return true;
                         }
+                    };
+
+                    found_files += 1;
+
+                    // the files read from archives and disk images wait in memory
+                    while thread_pool.queued_count() > 2 * cpus {
+                        std::thread::sleep(std::time::Duration::from_millis(10));
                     }
+
+                    let c_stop_flag = cancel_flag.clone();
+                    let c_repo = repo.clone();
+                    let c_options = options.clone();
+                    let c_scan_cache = scan_cache.clone();
+                    let c_media_sender = media_sender.clone();
+                    let c_state_sender = state_sender.clone();
+
+                    thread_pool.execute(move || {
+                        if c_stop_flag.load(Ordering::SeqCst) {
+                            return;
+                        }
+
+                        match Media::new(c_repo, item, &c_options, c_scan_cache.as_deref()) {
+                            Ok(media) => {
+                                c_media_sender
+                                    .blocking_send(media)
+                                    .expect("could not send `Media`");
+                            }
+                            Err(err) => {
+                                c_state_sender
+                                    .blocking_send(StateMedia::Err(path, err))
+                                    .expect("could not send `StateMedia::Err`");
+                            }
+                        }
+                    });
+                    true
                 });
-            }
 
-            state_sender
-                .blocking_send(StateMedia::Found(found_files))
-                .expect("could not send `StateMedia::Found`");
+                if let Err(err) = result {
+                    state_sender
+                        .blocking_send(StateMedia::Err(source.name(), err))
+                        .expect("could not send `StateMedia::Err`");
+                }
+
+                state_sender
+                    .blocking_send(StateMedia::Found(found_files))
+                    .expect("could not send `StateMedia::Found`");
+            }
 
             // wait for thread pool to process all jobs
             thread_pool.join();
@@ -119,9 +168,4 @@ impl Service {
             drop(state_sender);
         });
     }
-
-    #[inline]
-    fn is_media(entry: &Path) -> bool {
-        matches!(entry.extension(), Some(e) if utils::media::is_media(&e.to_string_lossy().to_lowercase()))
-    }
 }
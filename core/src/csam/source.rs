@@ -0,0 +1,546 @@
+use anyhow::Context;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use crate::utils::{
+    self,
+    archive::{ArchiveMember, SEPARATOR},
+};
+
+/// Content of a file of the evidence.
+#[derive(Debug)]
+pub enum Content {
+    /// File of the local filesystem, read when it is processed.
+    File(PathBuf),
+    /// File read from an archive, a disk image, ...
+    Memory(Vec<u8>),
+}
+
+/// Media file yielded by an evidence source.
+#[derive(Debug)]
+pub struct EvidenceItem {
+    /// Path of the file, a virtual path inside archives and disk images.
+    pub path: String,
+    pub name: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+    pub last_modified: i64,
+    pub content: Content,
+}
+
+impl EvidenceItem {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let metadata = path
+            .metadata()
+            .with_context(|| "could not get file metadata")?;
+        let last_modified = metadata
+            .modified()
+            .with_context(|| "could not get attribute `modified` from metadata")?
+            .duration_since(UNIX_EPOCH)
+            .map(|modified| modified.as_secs() as i64)
+            .unwrap_or_default();
+
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            size: metadata.len(),
+            last_modified,
+            content: Content::File(path.to_path_buf()),
+        })
+    }
+
+    pub fn reader(&self) -> io::Result<Box<dyn Read + '_>> {
+        match &self.content {
+            Content::File(path) => Ok(Box::new(File::open(path)?)),
+            Content::Memory(data) => Ok(Box::new(data.as_slice())),
+        }
+    }
+}
+
+impl From<ArchiveMember> for EvidenceItem {
+    fn from(member: ArchiveMember) -> Self {
+        Self {
+            path: member.path,
+            name: member.name,
+            size: member.data.len() as u64,
+            last_modified: member.last_modified,
+            content: Content::Memory(member.data),
+        }
+    }
+}
+
+/// Where the media of a search or of a database build come from.
+pub trait EvidenceSource: Send {
+    /// Path or description of the source.
+    fn name(&self) -> String;
+
+    /// Calls `f` with every media file of the source, or with the path of
+    /// every file that could not be read and the error, until it returns
+    /// `false`. Archives are opened up to `archive_depth` levels.
+    fn read_media(
+        &mut self,
+        archive_depth: usize,
+        f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+    ) -> anyhow::Result<()>;
+}
+
+/// Source for a path chosen by the user: a directory, a disk image, an
+/// archive or a single file.
+pub fn source_from_path(path: PathBuf) -> Box<dyn EvidenceSource> {
+    let name = path.to_string_lossy();
+    if path.is_dir() {
+        Box::new(DirectorySource::new(path))
+    } else if utils::disk_image::is_disk_image(&name) {
+        Box::new(DiskImageSource::new(path))
+    } else if utils::archive::is_archive(&name) {
+        Box::new(ArchiveSource::new(path))
+    } else {
+        Box::new(FileListSource::new(vec![path]))
+    }
+}
+
+/// Removes the repeated roots and the ones inside another root, keeping the
+/// others in their order.
+pub fn dedup_roots(roots: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut canonical: Vec<(PathBuf, usize)> = roots
+        .iter()
+        .enumerate()
+        .map(|(index, root)| (root.canonicalize().unwrap_or_else(|_| root.clone()), index))
+        .collect();
+    // the subdirectories of a root are sorted right after it
+    canonical.sort();
+
+    let mut kept: Vec<usize> = Vec::with_capacity(roots.len());
+    let mut parent: Option<&Path> = None;
+    for (path, index) in canonical.iter() {
+        if parent.is_some_and(|parent| path.starts_with(parent)) {
+            continue;
+        }
+        parent = Some(path);
+        kept.push(*index);
+    }
+    kept.sort();
+
+    let mut roots: Vec<Option<PathBuf>> = roots.into_iter().map(Some).collect();
+    kept.into_iter()
+        .filter_map(|index| roots[index].take())
+        .collect()
+}
+
+/// Reads a list of paths to scan, one per line, such as the one exported by
+/// another tool. Empty lines and lines starting with `#` are skipped, and
+/// relative paths are taken from the folder of the list.
+pub fn read_path_list(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read the path list: {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    let paths = content
+        .lines()
+        .map(|line| line.trim_start_matches('\u{feff}').trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect();
+    Ok(paths)
+}
+
+/// Media of a local directory and of its subdirectories, including the ones in
+/// its archives and disk images.
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl EvidenceSource for DirectorySource {
+    fn name(&self) -> String {
+        self.root.to_string_lossy().into_owned()
+    }
+
+    fn read_media(
+        &mut self,
+        archive_depth: usize,
+        f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+    ) -> anyhow::Result<()> {
+        for entry in WalkDir::new(&self.root)
+            .follow_links(false)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| !e.file_type().is_dir())
+        {
+            if !read_file(entry.path(), archive_depth, f) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Files listed one by one, such as the ones exported by another tool.
+/// Directories in the list are read with their subdirectories.
+pub struct FileListSource {
+    paths: Vec<PathBuf>,
+}
+
+impl FileListSource {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self { paths }
+    }
+}
+
+impl EvidenceSource for FileListSource {
+    fn name(&self) -> String {
+        match self.paths.as_slice() {
+            [path] => path.to_string_lossy().into_owned(),
+            paths => format!("{} files", paths.len()),
+        }
+    }
+
+    fn read_media(
+        &mut self,
+        archive_depth: usize,
+        f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+    ) -> anyhow::Result<()> {
+        let mut is_running = true;
+        for path in self.paths.iter() {
+            if path.is_dir() {
+                DirectorySource::new(path).read_media(archive_depth, &mut |path, item| {
+                    is_running = f(path, item);
+                    is_running
+                })?;
+            } else if !path.exists() {
+                is_running = f(
+                    path.to_string_lossy().into_owned(),
+                    Err(anyhow::anyhow!("file not found")),
+                );
+            } else {
+                is_running = read_file(path, archive_depth, f);
+            }
+
+            if !is_running {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Media of a ZIP, TAR, TAR.GZ or 7z archive and of the archives nested in it.
+pub struct ArchiveSource {
+    path: PathBuf,
+}
+
+impl ArchiveSource {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl EvidenceSource for ArchiveSource {
+    fn name(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn read_media(
+        &mut self,
+        archive_depth: usize,
+        f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+    ) -> anyhow::Result<()> {
+        // the archive itself is always opened
+        utils::archive::read_media(&self.path, archive_depth.max(1), &mut |path, member| {
+            f(path, member.map(EvidenceItem::from))
+        })?;
+        Ok(())
+    }
+}
+
+/// Media of the filesystems of a raw or E01 disk image.
+pub struct DiskImageSource {
+    path: PathBuf,
+}
+
+impl DiskImageSource {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl EvidenceSource for DiskImageSource {
+    fn name(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn read_media(
+        &mut self,
+        archive_depth: usize,
+        f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+    ) -> anyhow::Result<()> {
+        utils::disk_image::read_media(&self.path, archive_depth, &mut |path, member| {
+            f(path, member.map(EvidenceItem::from))
+        })
+    }
+}
+
+/// Images and videos carved from a raw stream, such as a disk image, a
+/// pagefile or a dump of unallocated space.
+pub struct CarvingSource {
+    path: PathBuf,
+}
+
+impl CarvingSource {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl EvidenceSource for CarvingSource {
+    fn name(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn read_media(
+        &mut self,
+        _archive_depth: usize,
+        f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+    ) -> anyhow::Result<()> {
+        utils::carver::carve(&self.path, &mut |path, member| {
+            f(path, member.map(EvidenceItem::from))
+        })
+    }
+}
+
+/// Files kept in memory, mostly to test the processing of the media.
+#[derive(Default)]
+pub struct MemorySource {
+    name: String,
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl MemorySource {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Adds the file `name`, its path being `{source name}!/{name}`.
+    pub fn add_file(&mut self, name: &str, data: Vec<u8>) {
+        self.files.push((name.to_owned(), data));
+    }
+}
+
+impl EvidenceSource for MemorySource {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn read_media(
+        &mut self,
+        _archive_depth: usize,
+        f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+    ) -> anyhow::Result<()> {
+        for (name, data) in self.files.iter() {
+            let path = format!("{}{SEPARATOR}{name}", self.name);
+            let item = EvidenceItem {
+                path: path.clone(),
+                name: name.rsplit('/').next().unwrap_or(name).to_owned(),
+                size: data.len() as u64,
+                last_modified: 0,
+                content: Content::Memory(data.clone()),
+            };
+            if !f(path, Ok(item)) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Reads a local file: the media in it when it is a disk image or an archive,
+// otherwise the file itself when it is a media. Returns `false` when `f` stops
+// the reading.
+fn read_file(
+    path: &Path,
+    archive_depth: usize,
+    f: &mut dyn FnMut(String, anyhow::Result<EvidenceItem>) -> bool,
+) -> bool {
+    let name = path.to_string_lossy();
+    let is_disk_image = utils::disk_image::is_disk_image(&name);
+    let is_archive = utils::archive::is_archive(&name);
+    if !is_disk_image && !is_archive {
+        if utils::media::is_media_file(path) {
+            return f(name.into_owned(), EvidenceItem::from_file(path));
+        }
+        return true;
+    }
+    if is_archive && archive_depth == 0 {
+        return true;
+    }
+
+    let mut is_running = true;
+    let mut read = |path: String, item: anyhow::Result<EvidenceItem>| {
+        is_running = f(path, item);
+        is_running
+    };
+    let result = if is_disk_image {
+        DiskImageSource::new(path).read_media(archive_depth, &mut read)
+    } else {
+        ArchiveSource::new(path).read_media(archive_depth, &mut read)
+    };
+
+    match result {
+        Ok(()) => is_running,
+        Err(err) => is_running && f(name.into_owned(), Err(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(source: &mut dyn EvidenceSource, archive_depth: usize) -> Vec<String> {
+        let mut paths = Vec::new();
+        source
+            .read_media(archive_depth, &mut |path, item| {
+                assert!(item.is_ok(), "{path}");
+                paths.push(path);
+                true
+            })
+            .expect("Failed to read the source.");
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_file_list_source() {
+        let dir = Path::new("../data/tmp/source/");
+        std::fs::create_dir_all(dir).unwrap();
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("DCIM/horse.jpg", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, &img).unwrap();
+        std::fs::write(dir.join("photos.zip"), zip.finish().unwrap().into_inner()).unwrap();
+        std::fs::write(dir.join("notes.txt"), b"not a media").unwrap();
+        let mut source = FileListSource::new(vec![
+            PathBuf::from("../data/img/horse.jpg"),
+            dir.join("photos.zip"),
+            dir.join("notes.txt"),
+        ]);
+
+        let paths = read(&mut source, 1);
+        let without_archives = read(&mut source, 0);
+
+        // Assert
+        assert_eq!(
+            paths,
+            vec![
+                String::from("../data/img/horse.jpg"),
+                format!("../data/tmp/source/photos.zip{SEPARATOR}DCIM/horse.jpg"),
+            ]
+        );
+        assert_eq!(
+            without_archives,
+            vec![String::from("../data/img/horse.jpg")]
+        );
+    }
+
+    #[test]
+    fn test_dedup_roots() {
+        let roots = vec![
+            PathBuf::from("../data/img"),
+            PathBuf::from("../data/db/"),
+            PathBuf::from("../data"),
+            PathBuf::from("../data/img/horse.jpg"),
+            PathBuf::from("../data/db"),
+            PathBuf::from("/not/found"),
+        ];
+
+        // Assert
+        assert_eq!(
+            dedup_roots(roots),
+            vec![PathBuf::from("../data"), PathBuf::from("/not/found")]
+        );
+        assert_eq!(
+            dedup_roots(vec![
+                PathBuf::from("../data/db"),
+                PathBuf::from("../data/img")
+            ]),
+            vec![PathBuf::from("../data/db"), PathBuf::from("../data/img")]
+        );
+    }
+
+    #[test]
+    fn test_read_path_list() {
+        let dir = Path::new("../data/tmp/source/");
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(
+            dir.join("paths.txt"),
+            "\u{feff}# exported paths\r\n/mnt/evidence\r\n\r\nphotos.zip\n",
+        )
+        .unwrap();
+
+        let paths = read_path_list(&dir.join("paths.txt")).expect("Failed to read the list.");
+
+        // Assert
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("/mnt/evidence"), dir.join("photos.zip")]
+        );
+    }
+
+    #[test]
+    fn test_memory_source_stops() {
+        let mut source = MemorySource::new("memory");
+        source.add_file("a.jpg", vec![1]);
+        source.add_file("folder/b.jpg", vec![2]);
+        let mut items = Vec::new();
+        source
+            .read_media(0, &mut |_, item| {
+                items.push(item.unwrap());
+                false
+            })
+            .unwrap();
+
+        // Assert
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, format!("memory{SEPARATOR}a.jpg"));
+        assert_eq!(items[0].size, 1);
+    }
+
+    #[test]
+    fn test_rar_archive_should_be_reported() {
+        let dir = Path::new("../data/tmp/source_rar/");
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("photos.rar"), b"Rar!\x1a\x07\x00").unwrap();
+        let mut errors = Vec::new();
+        FileListSource::new(vec![dir.join("photos.rar")])
+            .read_media(1, &mut |path, item| {
+                errors.push((path, item.err().map(|err| err.to_string())));
+                true
+            })
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            errors,
+            vec![(
+                String::from("../data/tmp/source_rar/photos.rar"),
+                Some(String::from(
+                    "RAR archives are not supported, extract first"
+                ))
+            )]
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+/// Classification of a media given by the examiner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tag {
+    Category1,
+    Category2,
+    Category3,
+    NotRelevant,
+    AgeDifficult,
+    NeedsReview,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 6] = [
+        Self::Category1,
+        Self::Category2,
+        Self::Category3,
+        Self::NotRelevant,
+        Self::AgeDifficult,
+        Self::NeedsReview,
+    ];
+
+    /// Whether the examiner classified the media as CSAM.
+    pub fn is_csam(&self) -> bool {
+        matches!(self, Self::Category1 | Self::Category2 | Self::Category3)
+    }
+
+    /// Parses the name written by `Display`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tag| tag.to_string() == name)
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Category1 => write!(f, "Category 1"),
+            Self::Category2 => write!(f, "Category 2"),
+            Self::Category3 => write!(f, "Category 3"),
+            Self::NotRelevant => write!(f, "Not relevant"),
+            Self::AgeDifficult => write!(f, "Age difficult"),
+            Self::NeedsReview => write!(f, "Needs review"),
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use chrono::{TimeZone, Utc};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use super::repository::Entry;
+use super::{HashAlgorithm, Media, Tag};
+
+const CASE_METADATA: &str = "http://github.com/ICMEC/ProjectVic/DataModels/1.3.xml#Cases";
+const APPLICATION_NAME: &str = "ChaSAM";
+
+// Media of a Project VIC (VICS) OData export. CAID exports follow the same model.
+#[derive(Debug, Default, Deserialize)]
+struct VicsMedia {
+    #[serde(rename = "MD5", alias = "md5", alias = "Md5")]
+    md5: Option<String>,
+    #[serde(rename = "SHA1", alias = "sha1", alias = "Sha1")]
+    sha1: Option<String>,
+    #[serde(rename = "SHA256", alias = "sha256", alias = "Sha256")]
+    sha256: Option<String>,
+    #[serde(rename = "Category", alias = "category")]
+    category: Option<Value>,
+    #[serde(rename = "Series", alias = "series", alias = "SeriesName")]
+    series: Option<Value>,
+    #[serde(rename = "MediaFiles", alias = "mediaFiles")]
+    media_files: Option<Vec<VicsMediaFile>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct VicsMediaFile {
+    #[serde(rename = "MD5", alias = "md5", alias = "Md5")]
+    md5: Option<String>,
+    #[serde(rename = "SHA1", alias = "sha1", alias = "Sha1")]
+    sha1: Option<String>,
+    #[serde(rename = "SHA256", alias = "sha256", alias = "Sha256")]
+    sha256: Option<String>,
+    #[serde(rename = "FileName", alias = "fileName", alias = "Filename")]
+    file_name: Option<String>,
+}
+
+/// Reads the hashes of a Project VIC (VICS) or CAID JSON hash set, calling `f`
+/// for every hash with the category and series of its media.
+///
+/// The media are streamed from the `value` (or `Media`) array of the export, so
+/// hash sets larger than the available memory can be imported.
+pub fn read_hash_set<R, F>(reader: R, source: &str, mut f: F) -> anyhow::Result<()>
+where
+    R: Read,
+    F: FnMut(Entry<String>),
+{
+    let mut on_media = |media: VicsMedia| media_entries(media, source, &mut f);
+    let mut deserializer = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+    RootSeed(&mut on_media).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+
+    Ok(())
+}
+
+fn media_entries<F>(media: VicsMedia, source: &str, f: &mut F)
+where
+    F: FnMut(Entry<String>),
+{
+    let category = media.category.as_ref().and_then(value_to_string);
+    let series = media.series.as_ref().and_then(value_to_string);
+    let files = media.media_files.unwrap_or_default();
+    let first_name = files.iter().find_map(|file| file.file_name.clone());
+
+    let mut digests: Vec<(String, Option<String>)> = Vec::new();
+    let mut add = |digest: Option<String>, file_name: Option<String>| {
+        let Some(digest) = digest.map(|digest| digest.trim().to_lowercase()) else {
+            return;
+        };
+        if HashAlgorithm::from_digest(&digest).is_some()
+            && !digests.iter().any(|(value, _)| *value == digest)
+        {
+            digests.push((digest, file_name));
+        }
+    };
+
+    for file in files.into_iter() {
+        add(file.md5, file.file_name.clone());
+        add(file.sha1, file.file_name.clone());
+        add(file.sha256, file.file_name);
+    }
+    add(media.md5, first_name.clone());
+    add(media.sha1, first_name.clone());
+    add(media.sha256, first_name);
+
+    for (digest, file_name) in digests.into_iter() {
+        f(Entry {
+            value: digest,
+            source: source.to_owned(),
+            category: category.clone(),
+            filename: file_name,
+            series: series.clone(),
+        });
+    }
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) if !value.trim().is_empty() => Some(value.trim().to_owned()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+// Root of the export: an OData object with the media in `value`, or the media array.
+struct RootSeed<'a, F>(&'a mut F);
+
+impl<'de, F: FnMut(VicsMedia)> DeserializeSeed<'de> for RootSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, F: FnMut(VicsMedia)> Visitor<'de> for RootSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a VICS OData object or an array of media")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut found = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key.eq_ignore_ascii_case("value") || key.eq_ignore_ascii_case("media") {
+                map.next_value_seed(MediaSeqSeed(&mut *self.0))?;
+                found = true;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        if !found {
+            return Err(de::Error::missing_field("value"));
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        MediaSeqSeed(self.0).visit_seq(seq)
+    }
+}
+
+struct MediaSeqSeed<'a, F>(&'a mut F);
+
+impl<'de, F: FnMut(VicsMedia)> DeserializeSeed<'de> for MediaSeqSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F: FnMut(VicsMedia)> Visitor<'de> for MediaSeqSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of media")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(media) = seq.next_element::<VicsMedia>()? {
+            (self.0)(media);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct VicsCase<'a> {
+    #[serde(rename = "odata.metadata")]
+    metadata: &'static str,
+    value: [VicsCaseValue<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct VicsCaseValue<'a> {
+    #[serde(skip_serializing_if = "str::is_empty")]
+    case_number: &'a str,
+    source_application_name: &'static str,
+    media: Vec<VicsCaseMedia<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct VicsCaseMedia<'a> {
+    #[serde(rename = "odata.id")]
+    odata_id: String,
+    #[serde(rename = "MediaID")]
+    media_id: usize,
+    category: Option<u32>,
+    #[serde(rename = "MD5", skip_serializing_if = "str::is_empty")]
+    md5: &'a str,
+    #[serde(rename = "SHA1", skip_serializing_if = "str::is_empty")]
+    sha1: &'a str,
+    #[serde(rename = "SHA256", skip_serializing_if = "str::is_empty")]
+    sha256: &'a str,
+    media_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    series: Option<&'a str>,
+    is_precategorized: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comments: Option<String>,
+    media_files: [VicsCaseMediaFile<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct VicsCaseMediaFile<'a> {
+    #[serde(rename = "MediaFileID")]
+    media_file_id: usize,
+    file_name: &'a str,
+    file_path: &'a str,
+    #[serde(rename = "MD5", skip_serializing_if = "str::is_empty")]
+    md5: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    written: Option<String>,
+}
+
+/// Writes the scanned media as a Project VIC (VICS) case, so the results can be
+/// ingested by Griffeye and other Project VIC compatible software.
+///
+/// The category of a matched media is kept when the hash set used the numeric
+/// Project VIC categories, unless the examiner tagged it; the match and the tag
+/// are described in the comments.
+pub fn write_case<W: Write>(writer: W, case_number: &str, medias: &[Media]) -> anyhow::Result<()> {
+    let media = medias
+        .iter()
+        .enumerate()
+        .map(|(index, media)| case_media(index + 1, media))
+        .collect();
+    let case = VicsCase {
+        metadata: CASE_METADATA,
+        value: [VicsCaseValue {
+            case_number,
+            source_application_name: APPLICATION_NAME,
+            media,
+        }],
+    };
+
+    let mut writer = io::BufWriter::new(writer);
+    serde_json::to_writer_pretty(&mut writer, &case)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn case_media(id: usize, media: &Media) -> VicsCaseMedia<'_> {
+    let matched = media.matched.as_ref();
+    let precategory = matched
+        .and_then(|matched| matched.category.as_deref())
+        .and_then(|category| category.parse::<u32>().ok());
+    let tag_category = media.tag.and_then(self::tag_category);
+    let mut comments: Vec<String> = Vec::new();
+    if let Some(matched) = matched {
+        let mut comment = format!("{matched}: {}", matched.reference);
+        if !matched.source.is_empty() {
+            comment.push_str(&format!(" ({})", matched.source));
+        }
+        comments.push(comment);
+    }
+    if let Some(tag) = media.tag {
+        comments.push(format!("Tag: {tag}"));
+    }
+    let written = Utc
+        .timestamp_opt(media.last_modified, 0)
+        .single()
+        .map(|date_time| date_time.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+
+    VicsCaseMedia {
+        odata_id: format!("Media({id})"),
+        media_id: id,
+        category: tag_category.or(precategory),
+        md5: &media.hash,
+        sha1: &media.sha1,
+        sha256: &media.sha256,
+        media_size: media.file_size,
+        series: matched.and_then(|matched| matched.series.as_deref()),
+        is_precategorized: tag_category.is_none() && precategory.is_some(),
+        comments: (!comments.is_empty()).then(|| comments.join("; ")),
+        media_files: [VicsCaseMediaFile {
+            media_file_id: id,
+            file_name: &media.name,
+            file_path: &media.path,
+            md5: &media.hash,
+            written,
+        }],
+    }
+}
+
+// Project VIC category of a tag: 0 not pertinent, 1 child abuse material and
+// 2 child exploitive or age difficult.
+fn tag_category(tag: Tag) -> Option<u32> {
+    match tag {
+        Tag::Category1 | Tag::Category2 | Tag::Category3 => Some(1),
+        Tag::AgeDifficult => Some(2),
+        Tag::NotRelevant => Some(0),
+        Tag::NeedsReview => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csam::{Match, MatchType, MediaType, PHashAlgorithm, PHashes};
+
+    fn read(json: &str) -> Vec<Entry<String>> {
+        let mut entries = Vec::new();
+        read_hash_set(json.as_bytes(), "vics.json", |entry| entries.push(entry))
+            .expect("Failed to read hash set.");
+        entries
+    }
+
+    #[test]
+    fn test_read_vics_odata() {
+        let json = r#"{
+            "odata.metadata": "http://github.com/ICMEC/ProjectVic/DataModels/1.2.xml#Media",
+            "value": [
+                {
+                    "odata.id": "Media(1)",
+                    "MediaID": 1,
+                    "Category": 1,
+                    "Series": "Series A",
+                    "MD5": "506BF7F41CA0C6F9E7612C04E93AB235",
+                    "SHA1": "b7b6e21916253608c9ff081db046a58100536963",
+                    "MediaFiles": [
+                        { "FileName": "horse.jpg", "MD5": "506bf7f41ca0c6f9e7612c04e93ab235" }
+                    ]
+                },
+                { "MediaID": 2, "Category": null, "MD5": "", "MediaFiles": null }
+            ]
+        }"#;
+        let entries = read(json);
+
+        // Assert
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].value, "506bf7f41ca0c6f9e7612c04e93ab235");
+        assert_eq!(entries[0].source, "vics.json");
+        assert_eq!(entries[0].category, Some(String::from("1")));
+        assert_eq!(entries[0].series, Some(String::from("Series A")));
+        assert_eq!(entries[0].filename, Some(String::from("horse.jpg")));
+        assert_eq!(entries[1].value, "b7b6e21916253608c9ff081db046a58100536963");
+        assert_eq!(entries[1].filename, Some(String::from("horse.jpg")));
+    }
+
+    #[test]
+    fn test_read_media_array() {
+        let json = r#"[
+            { "Category": "A", "SHA1": "b7b6e21916253608c9ff081db046a58100536963" }
+        ]"#;
+        let entries = read(json);
+
+        // Assert
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].category, Some(String::from("A")));
+        assert_eq!(entries[0].filename, None);
+    }
+
+    #[test]
+    fn test_write_case() {
+        let media = Media {
+            name: String::from("horse.jpg"),
+            path: String::from("/evidence/horse.jpg"),
+            media_type: MediaType::Image,
+            file_type: None,
+            size: 1,
+            file_size: 1024,
+            last_modified: 0,
+            hash: String::from("506bf7f41ca0c6f9e7612c04e93ab235"),
+            sha1: String::new(),
+            sha256: String::new(),
+            phash: 0,
+            phashes: PHashes::new(PHashAlgorithm::PHash, vec![0]),
+            matched: Some(Match {
+                match_type: MatchType::Hash(HashAlgorithm::MD5),
+                reference: String::from("506bf7f41ca0c6f9e7612c04e93ab235"),
+                distance: 0,
+                source: String::from("vics.json"),
+                category: Some(String::from("1")),
+                filename: None,
+                series: Some(String::from("Series A")),
+                transform: None,
+                alignment: None,
+            }),
+            img_buf: None,
+            extension_mismatch: false,
+            tag: None,
+        };
+        let tagged = Media {
+            tag: Some(Tag::AgeDifficult),
+            ..media.clone()
+        };
+        let mut buf = Vec::new();
+        write_case(&mut buf, "2024-001", &[media, tagged]).expect("Failed to write case.");
+        let case: Value = serde_json::from_slice(&buf).unwrap();
+
+        // Assert
+        let media = &case["value"][0]["Media"][0];
+        assert_eq!(case["value"][0]["CaseNumber"], "2024-001");
+        assert_eq!(media["Category"], 1);
+        assert_eq!(media["MediaSize"], 1024);
+        assert_eq!(media["IsPrecategorized"], true);
+        assert_eq!(
+            media["Comments"],
+            "MD5: 506bf7f41ca0c6f9e7612c04e93ab235 (vics.json)"
+        );
+        assert_eq!(media["MediaFiles"][0]["FilePath"], "/evidence/horse.jpg");
+        assert_eq!(media["MediaFiles"][0]["Written"], "1970-01-01T00:00:00Z");
+        let tagged = &case["value"][0]["Media"][1];
+        assert_eq!(tagged["Category"], 2);
+        assert_eq!(tagged["IsPrecategorized"], false);
+        assert_eq!(
+            tagged["Comments"],
+            "MD5: 506bf7f41ca0c6f9e7612c04e93ab235 (vics.json); Tag: Age difficult"
+        );
+    }
+
+    #[test]
+    fn test_read_invalid_hash_set() {
+        let result = read_hash_set(r#"{ "hashes": [] }"#.as_bytes(), "vics.json", |_| ());
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
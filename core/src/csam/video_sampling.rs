@@ -0,0 +1,72 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How the frames of a video are chosen to be hashed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SamplingMode {
+    /// One frame a second, decoding the whole video. Every frame is hashed,
+    /// the cap only limits the contact sheet.
+    #[default]
+    Interval,
+    /// As many frames as the cap, evenly spaced along the video, seeking to
+    /// each of them.
+    Evenly,
+    /// Only the keyframes, without decoding the frames between them.
+    Keyframes,
+    /// The first frame of every scene, decoding the whole video, thinned
+    /// evenly to the cap.
+    SceneChange,
+}
+
+impl SamplingMode {
+    pub const ALL: [SamplingMode; 4] = [
+        Self::Interval,
+        Self::Evenly,
+        Self::Keyframes,
+        Self::SceneChange,
+    ];
+
+    /// Parses the name written by `Display`, ignoring the case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.to_string().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+impl fmt::Display for SamplingMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Interval => write!(f, "Interval"),
+            Self::Evenly => write!(f, "Evenly"),
+            Self::Keyframes => write!(f, "Keyframes"),
+            Self::SceneChange => write!(f, "Scene change"),
+        }
+    }
+}
+
+/// How the frames of a video are sampled and at most how many are kept, or
+/// shown in the contact sheet by the `Interval` mode.
+///
+/// The video database is aligned most reliably with the `Interval` mode, whose
+/// frames are at the same moments in every copy of a video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoSampling {
+    pub mode: SamplingMode,
+    pub max_frames: usize,
+}
+
+impl VideoSampling {
+    /// Frames kept by default, as many as the contact sheet shows.
+    pub const MAX_FRAMES: usize = 100;
+}
+
+impl Default for VideoSampling {
+    fn default() -> Self {
+        Self {
+            mode: SamplingMode::default(),
+            max_frames: Self::MAX_FRAMES,
+        }
+    }
+}
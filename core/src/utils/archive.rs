@@ -0,0 +1,509 @@
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+
+use super::{carver, disk_image, media};
+use crate::csam::FileType;
+
+/// Separates the path of an archive from the path of a file inside it,
+/// as in `evidence.zip!/folder/img.jpg`.
+pub const SEPARATOR: &str = "!/";
+
+/// Members larger than this are not read into memory.
+pub(crate) const MAX_MEMBER_SIZE: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveType {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZ,
+    /// Only recognised to be reported, reading it needs the proprietary unrar
+    /// library.
+    Rar,
+}
+
+impl ArchiveType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".7z") {
+            Some(Self::SevenZ)
+        } else if name.ends_with(".rar") {
+            Some(Self::Rar)
+        } else {
+            None
+        }
+    }
+
+    /// Fails for the archives that are recognised but cannot be opened.
+    pub fn check_supported(self) -> anyhow::Result<()> {
+        match self {
+            Self::Rar => anyhow::bail!("RAR archives are not supported, extract first"),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn is_archive(name: &str) -> bool {
+    ArchiveType::from_name(name).is_some()
+}
+
+pub fn is_virtual_path(path: &str) -> bool {
+    path.contains(SEPARATOR)
+}
+
+/// Media file read from an archive or a disk image.
+#[derive(Debug)]
+pub struct ArchiveMember {
+    /// Virtual path, with the path of the archive and of the member.
+    pub path: String,
+    pub name: String,
+    pub last_modified: i64,
+    pub data: Vec<u8>,
+}
+
+/// Reads the media of the archive, following nested archives up to `max_depth`
+/// levels, and calls `f` with the virtual path of every media or of every
+/// member that could not be read, until it returns `false`.
+///
+/// Returns `false` when `f` stopped the reading.
+pub fn read_media<F>(path: &Path, max_depth: usize, f: &mut F) -> anyhow::Result<bool>
+where
+    F: FnMut(String, anyhow::Result<ArchiveMember>) -> bool,
+{
+    let archive_path = path.to_string_lossy();
+    let archive_type = ArchiveType::from_name(&archive_path)
+        .with_context(|| format!("unknown archive type: {archive_path}"))?;
+    let file = File::open(path)?;
+
+    read_media_from(file, archive_type, &archive_path, 1, max_depth, f)
+}
+
+/// Reads the media of an archive found inside a disk image or another container.
+pub(crate) fn read_media_from_memory<F>(
+    data: Vec<u8>,
+    archive_path: &str,
+    max_depth: usize,
+    f: &mut F,
+) -> anyhow::Result<bool>
+where
+    F: FnMut(String, anyhow::Result<ArchiveMember>) -> bool,
+{
+    let archive_type = ArchiveType::from_name(archive_path)
+        .with_context(|| format!("unknown archive type: {archive_path}"))?;
+    read_media_from(
+        Cursor::new(data),
+        archive_type,
+        archive_path,
+        1,
+        max_depth,
+        f,
+    )
+}
+
+fn read_media_from<R, F>(
+    reader: R,
+    archive_type: ArchiveType,
+    archive_path: &str,
+    depth: usize,
+    max_depth: usize,
+    f: &mut F,
+) -> anyhow::Result<bool>
+where
+    R: Read + Seek,
+    F: FnMut(String, anyhow::Result<ArchiveMember>) -> bool,
+{
+    if depth > max_depth {
+        return Ok(true);
+    }
+
+    let mut is_running = true;
+    for_each_entry(
+        reader,
+        archive_type,
+        &mut |name, last_modified, size, entry| {
+            let path = format!("{archive_path}{SEPARATOR}{name}");
+            let nested_type = ArchiveType::from_name(name);
+            if nested_type.is_some() && depth >= max_depth {
+                return true;
+            }
+            if let Some(Err(err)) = nested_type.map(ArchiveType::check_supported) {
+                is_running = f(path, Err(err));
+                return is_running;
+            }
+
+            // files renamed to hide their type are recognised by their signature
+            let mut data = Vec::new();
+            let is_media = matches!(extension(name), Some(e) if media::is_media(&e));
+            if nested_type.is_none() && !is_media {
+                let mut header = Read::take(&mut *entry, FileType::HEADER_LEN as u64);
+                if let Err(err) = header.read_to_end(&mut data) {
+                    is_running = f(path, Err(err.into()));
+                    return is_running;
+                }
+                if FileType::from_signature(&data).is_none() {
+                    return true;
+                }
+            }
+
+            match read_member(entry, size) {
+                Ok(rest) => data.extend(rest),
+                Err(err) => {
+                    is_running = f(path, Err(err));
+                    return is_running;
+                }
+            }
+
+            if let Some(nested_type) = nested_type {
+                let cursor = Cursor::new(data);
+                is_running =
+                    match read_media_from(cursor, nested_type, &path, depth + 1, max_depth, f) {
+                        Ok(is_running) => is_running,
+                        Err(err) => f(path, Err(err)),
+                    };
+            } else {
+                let member = ArchiveMember {
+                    path: path.clone(),
+                    name: file_name(name).to_owned(),
+                    last_modified,
+                    data,
+                };
+                is_running = f(path, Ok(member));
+            }
+
+            is_running
+        },
+    )?;
+
+    Ok(is_running)
+}
+
+/// Extracts the file of the virtual path, opening the disk image and the
+/// nested archives in it, or carving it from its source.
+pub fn extract(virtual_path: &str) -> anyhow::Result<Vec<u8>> {
+    let mut parts = virtual_path.split(SEPARATOR);
+    let archive_path = parts.next().unwrap_or_default();
+    let members: Vec<&str> = parts.collect();
+    if members.is_empty() {
+        anyhow::bail!("not a path inside an archive: {virtual_path}");
+    }
+
+    let mut data = if carver::is_carved_member(members[0]) {
+        carver::extract(archive_path, members[0])?
+    } else if disk_image::is_disk_image(archive_path) {
+        disk_image::extract(archive_path, members[0])?
+    } else {
+        let archive_type = ArchiveType::from_name(archive_path)
+            .with_context(|| format!("unknown archive type: {archive_path}"))?;
+        extract_member(File::open(archive_path)?, archive_type, members[0])?
+    };
+
+    for (index, member) in members.iter().enumerate().skip(1) {
+        let archive_type = ArchiveType::from_name(members[index - 1])
+            .with_context(|| format!("unknown archive type: {}", members[index - 1]))?;
+        data = extract_member(Cursor::new(data), archive_type, member)?;
+    }
+
+    Ok(data)
+}
+
+fn extract_member<R: Read + Seek>(
+    reader: R,
+    archive_type: ArchiveType,
+    member: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let mut data = None;
+    let mut error = None;
+    for_each_entry(reader, archive_type, &mut |name, _, size, entry| {
+        if name != member {
+            return true;
+        }
+        match read_member(entry, size) {
+            Ok(member) => data = Some(member),
+            Err(err) => error = Some(err),
+        }
+        false
+    })?;
+
+    if let Some(err) = error {
+        return Err(err);
+    }
+    data.with_context(|| format!("file not found in the archive: {member}"))
+}
+
+// Calls `f` with the name, last modification, size and content of every file
+// of the archive, until it returns `false`.
+fn for_each_entry<R, F>(mut reader: R, archive_type: ArchiveType, f: &mut F) -> anyhow::Result<()>
+where
+    R: Read + Seek,
+    F: FnMut(&str, i64, u64, &mut dyn Read) -> bool,
+{
+    match archive_type {
+        ArchiveType::Zip => {
+            let mut archive = zip::ZipArchive::new(reader)?;
+            for index in 0..archive.len() {
+                let mut file = archive.by_index(index)?;
+                if !file.is_file() {
+                    continue;
+                }
+                let name = file.name().to_owned();
+                let last_modified = file
+                    .last_modified()
+                    .and_then(|date_time| {
+                        NaiveDate::from_ymd_opt(
+                            date_time.year() as i32,
+                            date_time.month() as u32,
+                            date_time.day() as u32,
+                        )?
+                        .and_hms_opt(
+                            date_time.hour() as u32,
+                            date_time.minute() as u32,
+                            date_time.second() as u32,
+                        )
+                    })
+                    .map(|date_time| date_time.and_utc().timestamp())
+                    .unwrap_or_default();
+                let size = file.size();
+                if !f(&name, last_modified, size, &mut file) {
+                    break;
+                }
+            }
+        }
+        ArchiveType::Tar => for_each_tar_entry(reader, f)?,
+        ArchiveType::TarGz => for_each_tar_entry(GzDecoder::new(reader), f)?,
+        ArchiveType::SevenZ => {
+            let len = reader.seek(SeekFrom::End(0))?;
+            reader.seek(SeekFrom::Start(0))?;
+            let mut archive =
+                sevenz_rust::SevenZReader::new(reader, len, sevenz_rust::Password::empty())?;
+            archive.for_each_entries(|entry, entry_reader| {
+                if entry.is_directory() {
+                    return Ok(true);
+                }
+                let last_modified = if entry.has_last_modified_date {
+                    entry.last_modified_date().to_unix_time()
+                } else {
+                    0
+                };
+                Ok(f(entry.name(), last_modified, entry.size(), entry_reader))
+            })?;
+        }
+        ArchiveType::Rar => archive_type.check_supported()?,
+    }
+
+    Ok(())
+}
+
+fn for_each_tar_entry<R, F>(reader: R, f: &mut F) -> anyhow::Result<()>
+where
+    R: Read,
+    F: FnMut(&str, i64, u64, &mut dyn Read) -> bool,
+{
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let last_modified = entry.header().mtime().unwrap_or_default() as i64;
+        let size = entry.size();
+        if !f(&name, last_modified, size, &mut entry) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_member(reader: &mut dyn Read, size: u64) -> anyhow::Result<Vec<u8>> {
+    if size > MAX_MEMBER_SIZE {
+        anyhow::bail!("file too large to be read from the archive: {size} bytes");
+    }
+    let mut data = Vec::with_capacity(size as usize);
+    io::copy(&mut Read::take(reader, MAX_MEMBER_SIZE), &mut data)?;
+    Ok(data)
+}
+
+fn file_name(name: &str) -> &str {
+    name.rsplit(['/', '\\']).next().unwrap_or(name)
+}
+
+fn extension(name: &str) -> Option<String> {
+    Path::new(file_name(name))
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn zip_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files.iter() {
+            writer
+                .start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, data) in files.iter() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mtime(60);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn read(data: Vec<u8>, archive_type: ArchiveType, max_depth: usize) -> Vec<(String, bool)> {
+        let mut members = Vec::new();
+        read_media_from(
+            Cursor::new(data),
+            archive_type,
+            "evidence.zip",
+            1,
+            max_depth,
+            &mut |path, member| {
+                members.push((path, member.is_ok()));
+                true
+            },
+        )
+        .expect("Failed to read archive.");
+        members
+    }
+
+    #[test]
+    fn test_read_nested_archives() {
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let tar_gz = tar_gz_archive(&[("inner/horse.jpg", &img), ("notes.txt", b"notes")]);
+        let zip = zip_archive(&[
+            ("folder/horse.jpg", &img),
+            ("nested.tar.gz", &tar_gz),
+            ("renamed.dat", &img),
+            ("old.rar", b"Rar!\x1a\x07\x00"),
+        ]);
+
+        let members = read(zip.clone(), ArchiveType::Zip, 2);
+        let shallow = read(zip, ArchiveType::Zip, 1);
+
+        // Assert
+        assert_eq!(
+            members,
+            vec![
+                (String::from("evidence.zip!/folder/horse.jpg"), true),
+                (
+                    String::from("evidence.zip!/nested.tar.gz!/inner/horse.jpg"),
+                    true
+                ),
+                (String::from("evidence.zip!/renamed.dat"), true),
+                (String::from("evidence.zip!/old.rar"), false),
+            ]
+        );
+        assert_eq!(shallow.len(), 2);
+    }
+
+    #[test]
+    fn test_read_seven_zip() {
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let mut writer = sevenz_rust::SevenZWriter::new(Cursor::new(Vec::new())).unwrap();
+        let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+        entry.name = String::from("horse.jpg");
+        writer
+            .push_archive_entry(entry, Some(img.as_slice()))
+            .unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        let mut member = None;
+        read_media_from(
+            Cursor::new(data),
+            ArchiveType::SevenZ,
+            "evidence.7z",
+            1,
+            1,
+            &mut |_, result| {
+                member = result.ok();
+                true
+            },
+        )
+        .expect("Failed to read archive.");
+        let member = member.unwrap();
+
+        // Assert
+        assert_eq!(member.path, "evidence.7z!/horse.jpg");
+        assert_eq!(member.name, "horse.jpg");
+        assert_eq!(member.data, img);
+    }
+
+    #[test]
+    fn test_read_should_stop_when_cancelled() {
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let tar_gz = tar_gz_archive(&[("inner/a.jpg", &img), ("inner/b.jpg", &img)]);
+        let zip = zip_archive(&[("nested.tar.gz", &tar_gz), ("c.jpg", &img)]);
+
+        let mut paths = Vec::new();
+        let is_running = read_media_from(
+            Cursor::new(zip),
+            ArchiveType::Zip,
+            "evidence.zip",
+            1,
+            2,
+            &mut |path, _| {
+                paths.push(path);
+                false
+            },
+        )
+        .unwrap();
+
+        // Assert
+        assert!(!is_running);
+        assert_eq!(paths, vec!["evidence.zip!/nested.tar.gz!/inner/a.jpg"]);
+    }
+
+    #[test]
+    fn test_extract_nested_member() {
+        let dir = Path::new("../data/tmp/archive/");
+        std::fs::create_dir_all(dir).unwrap();
+        let tar_gz = tar_gz_archive(&[("inner/notes.txt", b"notes")]);
+        let zip = zip_archive(&[("nested.tgz", &tar_gz)]);
+        let zip_path = dir.join("evidence.zip");
+        std::fs::write(&zip_path, zip).unwrap();
+
+        let virtual_path = format!("{}!/nested.tgz!/inner/notes.txt", zip_path.display());
+        let data = extract(&virtual_path).expect("Failed to extract member.");
+
+        // Assert
+        assert_eq!(data, b"notes");
+        assert!(extract(&format!("{}!/missing.jpg", zip_path.display())).is_err());
+    }
+
+    #[test]
+    fn test_archive_type() {
+        // Assert
+        assert_eq!(ArchiveType::from_name("a.TAR.GZ"), Some(ArchiveType::TarGz));
+        assert_eq!(ArchiveType::from_name("a.rar"), Some(ArchiveType::Rar));
+        assert!(ArchiveType::Rar.check_supported().is_err());
+        assert_eq!(ArchiveType::from_name("a.jpg"), None);
+        assert!(is_virtual_path("a.zip!/b.jpg"));
+    }
+}
@@ -0,0 +1,526 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::archive::{ArchiveMember, MAX_MEMBER_SIZE, SEPARATOR};
+use super::disk_image;
+use crate::csam::{FileType, MediaType};
+
+/// Largest image carved, bigger candidates are taken as false positives.
+const MAX_IMAGE_SIZE: u64 = 64 * 1024 * 1024;
+// Bytes scanned for headers at a time.
+const WINDOW_LEN: usize = 4 * 1024 * 1024;
+// Bytes buffered while following the structure of a candidate.
+const BUFFER_LEN: usize = 64 * 1024;
+// Top-level boxes of MP4 and QuickTime files.
+const MP4_BOXES: [&[u8; 4]; 15] = [
+    b"ftyp", b"moov", b"mdat", b"free", b"skip", b"wide", b"uuid", b"meta", b"pdin", b"moof",
+    b"mfra", b"styp", b"sidx", b"pnot", b"udta",
+];
+
+/// File found in a byte stream by its header and its structure.
+#[derive(Debug)]
+pub struct CarvedFile {
+    /// Byte offset of the file in the stream.
+    pub offset: u64,
+    pub file_type: FileType,
+    pub data: Vec<u8>,
+}
+
+impl CarvedFile {
+    /// Name of the carved file: its offset and the extension of its type.
+    pub fn name(&self) -> String {
+        format!("@{}.{}", self.offset, self.file_type.extensions()[0])
+    }
+}
+
+/// Carves the images and videos of the file, a raw or a disk image, a pagefile,
+/// a dump of unallocated clusters, ..., and calls `f` with the virtual path of
+/// every file carved, such as `unallocated.bin!/@1024.jpg`, until it returns
+/// `false`.
+///
+/// Disk images are carved over the media they hold, allocated or not.
+pub fn carve<F>(path: &Path, f: &mut F) -> anyhow::Result<()>
+where
+    F: FnMut(String, anyhow::Result<ArchiveMember>) -> bool,
+{
+    let source = path.to_string_lossy();
+    let mut reader = open(path)?;
+    carve_from(&mut reader, &mut |file| {
+        let name = file.name();
+        let path = format!("{source}{SEPARATOR}{name}");
+        let member = ArchiveMember {
+            path: path.clone(),
+            name,
+            // the filesystem metadata of a carved file is lost
+            last_modified: 0,
+            data: file.data,
+        };
+        f(path, Ok(member))
+    })?;
+    Ok(())
+}
+
+/// Scans the stream for the headers of JPEG, PNG, GIF, BMP and MP4 files,
+/// following their structure to find where they end, and calls `f` with every
+/// file carved until it returns `false`.
+pub fn carve_from<R, F>(reader: &mut R, f: &mut F) -> io::Result<()>
+where
+    R: Read + Seek + ?Sized,
+    F: FnMut(CarvedFile) -> bool,
+{
+    let size = reader.seek(SeekFrom::End(0))?;
+    let mut position = 0;
+    while position < size {
+        let window = read_at(reader, position, WINDOW_LEN.min((size - position) as usize))?;
+        // the headers at the end of the window are scanned with the next one
+        let scan_len = if position + window.len() as u64 >= size {
+            window.len()
+        } else {
+            window.len() - FileType::HEADER_LEN
+        };
+        let mut next = position + scan_len as u64;
+
+        for index in 0..scan_len {
+            let header = &window[index..window.len().min(index + FileType::HEADER_LEN)];
+            let Some(file_type) = carvable_type(header) else {
+                continue;
+            };
+            let offset = position + index as u64;
+            let Some(len) = file_len(reader, offset, size, file_type)? else {
+                continue;
+            };
+
+            let data = read_at(reader, offset, len as usize)?;
+            if !f(CarvedFile {
+                offset,
+                file_type,
+                data,
+            }) {
+                return Ok(());
+            }
+            // files are not carved from inside another one
+            next = offset + len;
+            break;
+        }
+        position = next;
+    }
+    Ok(())
+}
+
+/// Extracts the carved file `member`, such as `@1024.jpg`, from the file `source`.
+pub fn extract(source: &str, member: &str) -> anyhow::Result<Vec<u8>> {
+    let offset = member_offset(member).with_context(|| format!("not a carved file: {member}"))?;
+    let mut reader = open(Path::new(source))?;
+    let size = reader.seek(SeekFrom::End(0))?;
+
+    let header = read_at(
+        &mut reader,
+        offset,
+        FileType::HEADER_LEN.min(size.saturating_sub(offset) as usize),
+    )?;
+    let file_type = carvable_type(&header)
+        .with_context(|| format!("no media found at offset {offset} of {source}"))?;
+    let len = file_len(&mut reader, offset, size, file_type)?
+        .with_context(|| format!("no media found at offset {offset} of {source}"))?;
+    Ok(read_at(&mut reader, offset, len as usize)?)
+}
+
+/// Returns `true` when the member of a virtual path names a carved file.
+pub fn is_carved_member(member: &str) -> bool {
+    member_offset(member).is_some()
+}
+
+fn member_offset(member: &str) -> Option<u64> {
+    let (offset, extension) = member.strip_prefix('@')?.split_once('.')?;
+    if extension.is_empty() || extension.contains('/') {
+        return None;
+    }
+    offset.parse().ok()
+}
+
+fn open(path: &Path) -> anyhow::Result<Box<dyn disk_image::ReadSeek>> {
+    if disk_image::is_disk_image(&path.to_string_lossy()) {
+        disk_image::open(path)
+    } else {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+// Types carved, the only ones whose end can be found from their structure.
+fn carvable_type(header: &[u8]) -> Option<FileType> {
+    // cheap test of the first byte before matching the signatures
+    let first = *header.first()?;
+    let is_mp4 = header.get(4..8) == Some(b"ftyp");
+    if !is_mp4 && ![0xFF, 0x89, b'G', b'B'].contains(&first) {
+        return None;
+    }
+
+    match FileType::from_signature(header)? {
+        file_type @ (FileType::Mp4 | FileType::Mov | FileType::ThreeGp) if is_mp4 => {
+            Some(file_type)
+        }
+        file_type @ (FileType::Jpeg | FileType::Png | FileType::Gif | FileType::Bmp) => {
+            Some(file_type)
+        }
+        _ => None,
+    }
+}
+
+// Length of the file of the type at `offset`, `None` when it is not valid.
+fn file_len<R: Read + Seek + ?Sized>(
+    reader: &mut R,
+    offset: u64,
+    size: u64,
+    file_type: FileType,
+) -> io::Result<Option<u64>> {
+    let max_len = match file_type.media_type() {
+        MediaType::Image => MAX_IMAGE_SIZE,
+        MediaType::Video => MAX_MEMBER_SIZE,
+    };
+    let mut stream = Stream {
+        reader,
+        buf: Vec::new(),
+        start: 0,
+        end: size.min(offset + max_len),
+    };
+
+    let end = match file_type {
+        FileType::Jpeg => jpeg_end(&mut stream, offset)?,
+        FileType::Png => png_end(&mut stream, offset)?,
+        FileType::Gif => gif_end(&mut stream, offset)?,
+        FileType::Bmp => bmp_end(&mut stream, offset)?,
+        _ => mp4_end(&mut stream, offset)?,
+    };
+    Ok(end.map(|end| end - offset))
+}
+
+// Follows the segments of the JPEG up to the End Of Image marker, skipping the
+// entropy-coded data after every Start Of Scan.
+fn jpeg_end<R: Read + Seek + ?Sized>(
+    stream: &mut Stream<R>,
+    offset: u64,
+) -> io::Result<Option<u64>> {
+    let mut position = offset + 2;
+    loop {
+        let Some(marker) = stream.get(position, 2)? else {
+            return Ok(None);
+        };
+        let (prefix, marker) = (marker[0], marker[1]);
+        if prefix != 0xFF {
+            return Ok(None);
+        }
+        match marker {
+            // fill bytes
+            0xFF => position += 1,
+            0xD9 => return Ok(Some(position + 2)),
+            0x01 | 0xD0..=0xD7 => position += 2,
+            0x00 | 0xD8 => return Ok(None),
+            _ => {
+                let Some(len) = stream.get(position + 2, 2)? else {
+                    return Ok(None);
+                };
+                let len = u16::from_be_bytes([len[0], len[1]]) as u64;
+                if len < 2 {
+                    return Ok(None);
+                }
+                position += 2 + len;
+                if marker == 0xDA {
+                    let Some(next) = entropy_end(stream, position)? else {
+                        return Ok(None);
+                    };
+                    position = next;
+                }
+            }
+        }
+    }
+}
+
+// Position of the first marker after the entropy-coded data, where 0xFF is
+// only followed by a stuffed zero or a restart marker.
+fn entropy_end<R: Read + Seek + ?Sized>(
+    stream: &mut Stream<R>,
+    mut position: u64,
+) -> io::Result<Option<u64>> {
+    while let Some(bytes) = stream.get(position, 2)? {
+        if bytes[0] == 0xFF && !matches!(bytes[1], 0x00 | 0xD0..=0xD7 | 0xFF) {
+            return Ok(Some(position));
+        }
+        position += 1;
+    }
+    Ok(None)
+}
+
+// Follows the chunks of the PNG, starting with `IHDR`, up to `IEND`.
+fn png_end<R: Read + Seek + ?Sized>(
+    stream: &mut Stream<R>,
+    offset: u64,
+) -> io::Result<Option<u64>> {
+    let mut position = offset + 8;
+    let mut is_first = true;
+    loop {
+        let Some(chunk) = stream.get(position, 8)? else {
+            return Ok(None);
+        };
+        let len = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as u64;
+        let chunk_type = [chunk[4], chunk[5], chunk[6], chunk[7]];
+        if !chunk_type.iter().all(u8::is_ascii_alphabetic) || (is_first && &chunk_type != b"IHDR") {
+            return Ok(None);
+        }
+        // length, type, data and CRC
+        position += 12 + len;
+        if &chunk_type == b"IEND" {
+            return Ok(stream.contains(position).then_some(position));
+        }
+        is_first = false;
+    }
+}
+
+// Follows the blocks of the GIF up to its trailer.
+fn gif_end<R: Read + Seek + ?Sized>(
+    stream: &mut Stream<R>,
+    offset: u64,
+) -> io::Result<Option<u64>> {
+    let Some(screen) = stream.get(offset + 6, 7)? else {
+        return Ok(None);
+    };
+    let mut position = offset + 13 + color_table_len(screen[4]);
+    loop {
+        let Some(block) = stream.get(position, 1)? else {
+            return Ok(None);
+        };
+        match block[0] {
+            0x3B => return Ok(Some(position + 1)),
+            // extension: label and sub-blocks
+            0x21 => position += 2,
+            // image descriptor, local color table, LZW code size and sub-blocks
+            0x2C => {
+                let Some(descriptor) = stream.get(position, 10)? else {
+                    return Ok(None);
+                };
+                position += 11 + color_table_len(descriptor[9]);
+            }
+            _ => return Ok(None),
+        }
+        match sub_blocks_end(stream, position)? {
+            Some(end) => position = end,
+            None => return Ok(None),
+        }
+    }
+}
+
+fn color_table_len(flags: u8) -> u64 {
+    if flags & 0x80 != 0 {
+        3 << ((flags & 0x07) + 1)
+    } else {
+        0
+    }
+}
+
+fn sub_blocks_end<R: Read + Seek + ?Sized>(
+    stream: &mut Stream<R>,
+    mut position: u64,
+) -> io::Result<Option<u64>> {
+    loop {
+        let Some(len) = stream.get(position, 1)? else {
+            return Ok(None);
+        };
+        position += 1 + len[0] as u64;
+        if len[0] == 0 {
+            return Ok(Some(position));
+        }
+    }
+}
+
+// Bitmaps store their size in the header.
+fn bmp_end<R: Read + Seek + ?Sized>(
+    stream: &mut Stream<R>,
+    offset: u64,
+) -> io::Result<Option<u64>> {
+    let Some(header) = stream.get(offset, 14)? else {
+        return Ok(None);
+    };
+    let size = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as u64;
+    let pixels = u32::from_le_bytes([header[10], header[11], header[12], header[13]]) as u64;
+    if header[6..10] != [0, 0, 0, 0] || pixels < 26 || pixels >= size {
+        return Ok(None);
+    }
+    Ok(stream.contains(offset + size).then_some(offset + size))
+}
+
+// Follows the top-level boxes of the MP4 while they are known ones, requiring
+// the `moov` box with the metadata and the `mdat` box with the media.
+fn mp4_end<R: Read + Seek + ?Sized>(
+    stream: &mut Stream<R>,
+    offset: u64,
+) -> io::Result<Option<u64>> {
+    let mut position = offset;
+    let (mut has_moov, mut has_mdat) = (false, false);
+    while let Some(header) = stream.get(position, 16)? {
+        let box_type = [header[4], header[5], header[6], header[7]];
+        if !MP4_BOXES.contains(&&box_type) {
+            break;
+        }
+        let len = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            1 => u64::from_be_bytes(header[8..16].try_into().unwrap()),
+            len => len as u64,
+        };
+        if len < 8 || !stream.contains(position + len) {
+            break;
+        }
+        has_moov |= &box_type == b"moov";
+        has_mdat |= &box_type == b"mdat";
+        position += len;
+    }
+    Ok((has_moov && has_mdat).then_some(position))
+}
+
+// Buffered access to the bytes of a candidate, up to `end`.
+struct Stream<'a, R: ?Sized> {
+    reader: &'a mut R,
+    buf: Vec<u8>,
+    /// Position of the first byte of `buf`.
+    start: u64,
+    end: u64,
+}
+
+impl<R: Read + Seek + ?Sized> Stream<'_, R> {
+    fn contains(&self, position: u64) -> bool {
+        position <= self.end
+    }
+
+    // The `len` bytes at `position`, `None` past the end of the stream.
+    fn get(&mut self, position: u64, len: usize) -> io::Result<Option<&[u8]>> {
+        if position + len as u64 > self.end {
+            return Ok(None);
+        }
+        if position < self.start || position + len as u64 > self.start + self.buf.len() as u64 {
+            let buf_len = len.max(BUFFER_LEN).min((self.end - position) as usize);
+            self.buf = read_at(self.reader, position, buf_len)?;
+            self.start = position;
+        }
+        let start = (position - self.start) as usize;
+        Ok(Some(&self.buf[start..start + len]))
+    }
+}
+
+fn read_at<R: Read + Seek + ?Sized>(
+    reader: &mut R,
+    offset: u64,
+    len: usize,
+) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn gif() -> Vec<u8> {
+        let mut gif = b"GIF89a\x01\x00\x01\x00\x80\x00\x00".to_vec();
+        gif.extend([0, 0, 0, 0xFF, 0xFF, 0xFF]);
+        gif.extend(b"\x21\xF9\x04\x01\x00\x00\x00\x00");
+        gif.extend(b"\x2C\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00\x3B");
+        gif
+    }
+
+    fn mp4() -> Vec<u8> {
+        let mut mp4 = b"\0\0\0\x18ftypisom\0\0\x02\0isomiso2".to_vec();
+        mp4.extend(b"\0\0\0\x10moov\0\0\0\x08mvhd");
+        mp4.extend(b"\0\0\0\x0Cmdat\x01\x02\x03\x04");
+        mp4
+    }
+
+    #[test]
+    fn test_carve_from_blob() {
+        let jpeg = std::fs::read("../data/img/horse.jpg").unwrap();
+        let mut blob = vec![0xFFu8; 1000];
+        blob.extend(&jpeg);
+        blob.extend(b"GIF89a garbage");
+        let gif_offset = blob.len() as u64;
+        blob.extend(gif());
+        blob.extend(vec![0u8; 333]);
+        let mp4_offset = blob.len() as u64;
+        blob.extend(mp4());
+        blob.extend(b"\0\0\0\x08junk");
+
+        let mut files = Vec::new();
+        carve_from(&mut Cursor::new(blob), &mut |file| {
+            files.push(file);
+            true
+        })
+        .unwrap();
+
+        // Assert
+        let found: Vec<(u64, FileType)> = files.iter().map(|f| (f.offset, f.file_type)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (1000, FileType::Jpeg),
+                (gif_offset, FileType::Gif),
+                (mp4_offset, FileType::Mp4)
+            ]
+        );
+        assert_eq!(files[0].data, jpeg);
+        assert_eq!(files[1].data, gif());
+        assert_eq!(files[2].data, mp4());
+        assert_eq!(files[0].name(), "@1000.jpg");
+    }
+
+    fn encode(format: image::ImageFormat) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(8, 8, |x, y| image::Rgb([x as u8 * 30, y as u8, 7]));
+        let mut data = Cursor::new(Vec::new());
+        img.write_to(&mut data, format).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_extract_carved_file() {
+        let png = encode(image::ImageFormat::Png);
+        let bmp = encode(image::ImageFormat::Bmp);
+        let dir = Path::new("../data/tmp/carver/");
+        std::fs::create_dir_all(dir).unwrap();
+        let mut blob = vec![0x89u8; 4099];
+        blob.extend(&png);
+        blob.extend(vec![0x42u8; 100]);
+        let bmp_offset = blob.len();
+        blob.extend(&bmp);
+        std::fs::write(dir.join("unallocated.bin"), blob).unwrap();
+
+        let mut paths = Vec::new();
+        carve(&dir.join("unallocated.bin"), &mut |path, _| {
+            paths.push(path);
+            true
+        })
+        .unwrap();
+        let mut carved_before_stop = 0;
+        carve(&dir.join("unallocated.bin"), &mut |_, _| {
+            carved_before_stop += 1;
+            false
+        })
+        .unwrap();
+        let source = dir.join("unallocated.bin").to_string_lossy().into_owned();
+        let data = extract(&source, "@4099.png").expect("Failed to extract the carved file.");
+
+        // Assert
+        assert_eq!(
+            paths,
+            vec![
+                format!("{source}{SEPARATOR}@4099.png"),
+                format!("{source}{SEPARATOR}@{bmp_offset}.bmp")
+            ]
+        );
+        assert_eq!(carved_before_stop, 1);
+        assert_eq!(data, png);
+        assert!(is_carved_member("@4099.png"));
+        assert!(!is_carved_member("DCIM/@4099.png"));
+        assert!(!is_carved_member("@photo.png"));
+    }
+}
@@ -0,0 +1,338 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::Context;
+use flate2::read::ZlibDecoder;
+
+use super::{read_at, seek_position, segment_paths, u32_at, u64_at};
+
+const SIGNATURE: &[u8] = b"EVF\x09\x0D\x0A\xFF\x00";
+const FILE_HEADER_SIZE: u64 = 13;
+const SECTION_DESCRIPTOR_SIZE: u64 = 76;
+const TABLE_HEADER_SIZE: u64 = 24;
+const CHUNK_COMPRESSED: u32 = 0x8000_0000;
+
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    segment: usize,
+    offset: u64,
+    len: u64,
+    compressed: bool,
+}
+
+/// Reader of the media stored in the segments of an EnCase image: `.E01`,
+/// `.E02`, ...
+pub(super) struct EwfReader {
+    segments: Vec<File>,
+    chunks: Vec<Chunk>,
+    chunk_size: u64,
+    size: u64,
+    position: u64,
+    /// Last chunk read, decompressed.
+    cache: Option<(usize, Vec<u8>)>,
+}
+
+impl EwfReader {
+    pub fn open(first: &Path) -> anyhow::Result<Self> {
+        let lowercase = first
+            .extension()
+            .is_some_and(|e| e.to_string_lossy().starts_with('e'));
+        let paths = segment_paths(first, |number| {
+            let extension = segment_extension(number);
+            if lowercase {
+                extension.to_lowercase()
+            } else {
+                extension
+            }
+        });
+
+        let mut reader = Self {
+            segments: Vec::new(),
+            chunks: Vec::new(),
+            chunk_size: 0,
+            size: 0,
+            position: 0,
+            cache: None,
+        };
+        for path in paths.iter() {
+            let mut file = File::open(path)?;
+            reader
+                .read_segment(&mut file)
+                .with_context(|| format!("invalid EWF segment: {}", path.display()))?;
+            reader.segments.push(file);
+        }
+
+        if reader.chunk_size == 0 {
+            anyhow::bail!("EWF volume section not found");
+        }
+        let expected = reader.size.div_ceil(reader.chunk_size);
+        if (reader.chunks.len() as u64) < expected {
+            anyhow::bail!(
+                "incomplete EWF image, {} of {} chunks found",
+                reader.chunks.len(),
+                expected
+            );
+        }
+        Ok(reader)
+    }
+
+    // Reads the sections of the segment, keeping the geometry of the media
+    // and the location of its chunks.
+    fn read_segment(&mut self, file: &mut File) -> anyhow::Result<()> {
+        let header = read_at(file, 0, FILE_HEADER_SIZE as usize)?;
+        if !header.starts_with(SIGNATURE) {
+            anyhow::bail!("not an EWF file");
+        }
+        let segment = self.segments.len();
+        let file_size = file.seek(SeekFrom::End(0))?;
+
+        // chunks are stored in the `sectors` sections, or after the entries in
+        // the `table` sections of the oldest versions
+        let mut regions = Vec::new();
+        let mut tables = Vec::new();
+        let mut offset = FILE_HEADER_SIZE;
+        while offset + SECTION_DESCRIPTOR_SIZE <= file_size {
+            let descriptor = read_at(file, offset, SECTION_DESCRIPTOR_SIZE as usize)?;
+            let section_type = String::from_utf8_lossy(&descriptor[..16])
+                .trim_end_matches('\0')
+                .to_owned();
+            let next = u64_at(&descriptor, 16);
+            let size = u64_at(&descriptor, 24);
+            let data = offset + SECTION_DESCRIPTOR_SIZE;
+
+            match section_type.as_str() {
+                "volume" | "disk" => {
+                    let volume = read_at(file, data, 24)?;
+                    self.chunk_size = u32_at(&volume, 8) as u64 * u32_at(&volume, 12) as u64;
+                    self.size = u64_at(&volume, 16) * u32_at(&volume, 12) as u64;
+                }
+                "sectors" => regions.push((offset, offset + size)),
+                "table" => {
+                    regions.push((offset, offset + size));
+                    tables.push(data);
+                }
+                "next" | "done" => break,
+                _ => (),
+            }
+            if next <= offset {
+                break;
+            }
+            offset = next;
+        }
+
+        for table in tables {
+            let header = read_at(file, table, TABLE_HEADER_SIZE as usize)?;
+            let count = u32_at(&header, 0) as usize;
+            let base = u64_at(&header, 8);
+            let entries = read_at(file, table + TABLE_HEADER_SIZE, count * 4)?;
+            let offsets: Vec<(u64, bool)> = entries
+                .chunks(4)
+                .map(|entry| {
+                    let entry = u32_at(entry, 0);
+                    (
+                        base + (entry & !CHUNK_COMPRESSED) as u64,
+                        entry & CHUNK_COMPRESSED != 0,
+                    )
+                })
+                .collect();
+
+            for (index, (offset, compressed)) in offsets.iter().enumerate() {
+                // a chunk ends where the next one starts, or with its section
+                let end = match offsets.get(index + 1) {
+                    Some((next, _)) => *next,
+                    None => regions
+                        .iter()
+                        .find(|(start, end)| (*start..*end).contains(offset))
+                        .map(|(_, end)| *end)
+                        .unwrap_or(file_size),
+                };
+                self.chunks.push(Chunk {
+                    segment,
+                    offset: *offset,
+                    len: end.saturating_sub(*offset),
+                    compressed: *compressed,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn chunk(&mut self, index: usize) -> io::Result<&[u8]> {
+        if !matches!(self.cache, Some((cached, _)) if cached == index) {
+            let chunk = self.chunks[index];
+            let len = self
+                .chunk_size
+                .min(self.size - index as u64 * self.chunk_size);
+            let file = &mut self.segments[chunk.segment];
+
+            let data = if chunk.compressed {
+                let stored = read_at(file, chunk.offset, chunk.len as usize)?;
+                let mut data = Vec::with_capacity(len as usize);
+                ZlibDecoder::new(stored.as_slice())
+                    .take(len)
+                    .read_to_end(&mut data)?;
+                data.resize(len as usize, 0);
+                data
+            } else {
+                // uncompressed chunks are followed by their checksum
+                read_at(file, chunk.offset, len.min(chunk.len) as usize)?
+            };
+            self.cache = Some((index, data));
+        }
+        Ok(&self.cache.as_ref().unwrap().1)
+    }
+}
+
+impl Read for EwfReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.size || buf.is_empty() {
+            return Ok(0);
+        }
+        let index = (self.position / self.chunk_size) as usize;
+        let start = (self.position % self.chunk_size) as usize;
+        let chunk = self.chunk(index)?;
+        let len = buf.len().min(chunk.len().saturating_sub(start));
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated EWF chunk",
+            ));
+        }
+        buf[..len].copy_from_slice(&chunk[start..start + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for EwfReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.size)?;
+        Ok(self.position)
+    }
+}
+
+// `E01` to `E99`, then `EAA` to `EZZ`, `FAA`, ...
+fn segment_extension(number: usize) -> String {
+    if number < 100 {
+        return format!("E{number:02}");
+    }
+    let number = number - 100;
+    let letter = |value: usize| (b'A' + (value % 26) as u8) as char;
+    format!(
+        "{}{}{}",
+        (b'E' + (number / 676) as u8) as char,
+        letter(number / 26),
+        letter(number)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    const SECTORS_PER_CHUNK: u32 = 64;
+    const CHUNK_SIZE: usize = SECTORS_PER_CHUNK as usize * 512;
+
+    fn section(segment: &mut Vec<u8>, section_type: &str, data: &[u8], is_last: bool) {
+        let offset = segment.len() as u64;
+        let size = SECTION_DESCRIPTOR_SIZE + data.len() as u64;
+        let mut descriptor = vec![0u8; SECTION_DESCRIPTOR_SIZE as usize];
+        descriptor[..section_type.len()].copy_from_slice(section_type.as_bytes());
+        let next = if is_last { offset } else { offset + size };
+        descriptor[16..24].copy_from_slice(&next.to_le_bytes());
+        descriptor[24..32].copy_from_slice(&size.to_le_bytes());
+        segment.extend(descriptor);
+        segment.extend(data);
+    }
+
+    // Segment with the `chunks` of `media`, compressing the even ones.
+    fn segment(
+        number: u16,
+        media: &[u8],
+        chunks: std::ops::Range<usize>,
+        is_last: bool,
+    ) -> Vec<u8> {
+        let mut segment = SIGNATURE.to_vec();
+        segment.push(1);
+        segment.extend(number.to_le_bytes());
+        segment.extend([0, 0]);
+
+        if number == 1 {
+            let mut volume = vec![0u8; 94];
+            volume[4..8].copy_from_slice(&(media.len().div_ceil(CHUNK_SIZE) as u32).to_le_bytes());
+            volume[8..12].copy_from_slice(&SECTORS_PER_CHUNK.to_le_bytes());
+            volume[12..16].copy_from_slice(&512u32.to_le_bytes());
+            volume[16..24].copy_from_slice(&(media.len() as u64 / 512).to_le_bytes());
+            section(&mut segment, "volume", &volume, false);
+        }
+
+        let sectors_offset = segment.len() as u64 + SECTION_DESCRIPTOR_SIZE;
+        let mut sectors = Vec::new();
+        let mut entries = Vec::new();
+        for index in chunks {
+            let chunk = &media[index * CHUNK_SIZE..((index + 1) * CHUNK_SIZE).min(media.len())];
+            let mut entry = (sectors_offset + sectors.len() as u64) as u32;
+            if index % 2 == 0 {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+                encoder.write_all(chunk).unwrap();
+                sectors.extend(encoder.finish().unwrap());
+                entry |= CHUNK_COMPRESSED;
+            } else {
+                sectors.extend(chunk);
+                sectors.extend([0, 0, 0, 0]);
+            }
+            entries.extend(entry.to_le_bytes());
+        }
+        section(&mut segment, "sectors", &sectors, false);
+
+        let mut table = vec![0u8; TABLE_HEADER_SIZE as usize];
+        table[..4].copy_from_slice(&((entries.len() / 4) as u32).to_le_bytes());
+        table.extend(entries);
+        table.extend([0, 0, 0, 0]);
+        section(&mut segment, "table", &table, false);
+        section(
+            &mut segment,
+            if is_last { "done" } else { "next" },
+            &[],
+            true,
+        );
+        segment
+    }
+
+    #[test]
+    fn test_read_segments() {
+        let media: Vec<u8> = (0..CHUNK_SIZE * 2 + 4096).map(|i| (i / 7) as u8).collect();
+        let dir = Path::new("../data/tmp/disk_image/ewf/");
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("disk.E01"), segment(1, &media, 0..2, false)).unwrap();
+        std::fs::write(dir.join("disk.E02"), segment(2, &media, 2..3, true)).unwrap();
+
+        let mut reader = EwfReader::open(&dir.join("disk.E01")).expect("Failed to open image.");
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        let mut middle = vec![0u8; 100];
+        reader
+            .seek(SeekFrom::Start(CHUNK_SIZE as u64 - 50))
+            .unwrap();
+        reader.read_exact(&mut middle).unwrap();
+
+        // Assert
+        assert_eq!(data, media);
+        assert_eq!(middle, &media[CHUNK_SIZE - 50..CHUNK_SIZE + 50]);
+    }
+
+    #[test]
+    fn test_segment_extension() {
+        // Assert
+        assert_eq!(segment_extension(2), "E02");
+        assert_eq!(segment_extension(99), "E99");
+        assert_eq!(segment_extension(100), "EAA");
+        assert_eq!(segment_extension(127), "EBB");
+        assert_eq!(segment_extension(776), "FAA");
+    }
+}
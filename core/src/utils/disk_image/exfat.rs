@@ -0,0 +1,304 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+
+use super::{read_at, u16_at, u32_at, u64_at, FileSystem, FsFile, Slice};
+
+const ENTRY_SIZE: usize = 32;
+const ENTRY_END: u8 = 0x00;
+const ENTRY_FILE: u8 = 0x85;
+const ENTRY_STREAM: u8 = 0xC0;
+const ENTRY_NAME: u8 = 0xC1;
+const ATTRIBUTE_DIRECTORY: u16 = 0x10;
+const FLAG_NO_FAT_CHAIN: u8 = 0x02;
+const END_OF_CHAIN: u32 = 0xFFFF_FFF8;
+
+/// Allocation of a file or directory: first cluster, size and whether its
+/// clusters are contiguous, without a chain in the FAT.
+#[derive(Debug, Clone, Copy)]
+struct Stream {
+    first_cluster: u32,
+    size: u64,
+    contiguous: bool,
+}
+
+pub(super) struct ExFat<'a> {
+    disk: Slice<'a>,
+    fat_offset: u64,
+    heap_offset: u64,
+    cluster_size: u64,
+    cluster_count: u32,
+    root_cluster: u32,
+    /// Allocation of the files listed, by `FsFile::id`.
+    streams: Vec<Stream>,
+}
+
+impl<'a> ExFat<'a> {
+    pub fn open(mut disk: Slice<'a>) -> anyhow::Result<Self> {
+        let boot = read_at(&mut disk, 0, 512)?;
+        let sector_size = 1u64 << boot[108].clamp(9, 12);
+        let cluster_size = sector_size << boot[109].min(16);
+
+        Ok(Self {
+            disk,
+            fat_offset: u32_at(&boot, 80) as u64 * sector_size,
+            heap_offset: u32_at(&boot, 88) as u64 * sector_size,
+            cluster_size,
+            cluster_count: u32_at(&boot, 92),
+            root_cluster: u32_at(&boot, 96),
+            streams: Vec::new(),
+        })
+    }
+
+    fn cluster_offset(&self, cluster: u32) -> u64 {
+        self.heap_offset + (cluster as u64 - 2) * self.cluster_size
+    }
+
+    // Clusters of the stream, following the FAT unless they are contiguous.
+    fn clusters(&mut self, stream: &Stream) -> anyhow::Result<Vec<u32>> {
+        let count = stream.size.div_ceil(self.cluster_size);
+        if stream.contiguous {
+            if stream.first_cluster < 2 {
+                return Ok(Vec::new());
+            }
+            return Ok((0..count as u32)
+                .map(|index| stream.first_cluster + index)
+                .collect());
+        }
+
+        let mut clusters = Vec::new();
+        let mut cluster = stream.first_cluster;
+        let mut seen = HashSet::new();
+        while (2..=self.cluster_count.saturating_add(1)).contains(&cluster) && seen.insert(cluster)
+        {
+            clusters.push(cluster);
+            if stream.size > 0 && clusters.len() as u64 >= count {
+                break;
+            }
+            let entry = read_at(&mut self.disk, self.fat_offset + cluster as u64 * 4, 4)?;
+            cluster = u32_at(&entry, 0);
+            if cluster >= END_OF_CHAIN {
+                break;
+            }
+        }
+        Ok(clusters)
+    }
+
+    fn read_stream(&mut self, stream: &Stream, max_len: u64) -> anyhow::Result<Vec<u8>> {
+        let len = if stream.size == 0 && !stream.contiguous {
+            // the root directory has no size, only a chain of clusters
+            u64::MAX
+        } else {
+            stream.size
+        }
+        .min(max_len);
+
+        let mut data = Vec::new();
+        for cluster in self.clusters(stream)? {
+            if data.len() as u64 >= len {
+                break;
+            }
+            let chunk = self.cluster_size.min(len - data.len() as u64) as usize;
+            let offset = self.cluster_offset(cluster);
+            data.extend(read_at(&mut self.disk, offset, chunk)?);
+        }
+        Ok(data)
+    }
+
+    fn walk(
+        &mut self,
+        dir: Stream,
+        parent: &str,
+        files: &mut Vec<FsFile>,
+        visited: &mut HashSet<u32>,
+    ) -> anyhow::Result<()> {
+        if !visited.insert(dir.first_cluster) {
+            return Ok(());
+        }
+        let entries = self.read_stream(&dir, u64::MAX)?;
+
+        let mut index = 0;
+        while index + ENTRY_SIZE <= entries.len() {
+            let entry = &entries[index..index + ENTRY_SIZE];
+            match entry[0] {
+                ENTRY_END => break,
+                ENTRY_FILE => {
+                    let secondary_count = entry[1] as usize;
+                    let set_end = (index + (secondary_count + 1) * ENTRY_SIZE).min(entries.len());
+                    if let Some((name, stream, is_dir, last_modified)) =
+                        Self::parse_entry_set(&entries[index..set_end])
+                    {
+                        let path = format!("{parent}{name}");
+                        if is_dir {
+                            self.walk(stream, &format!("{path}/"), files, visited)?;
+                        } else {
+                            files.push(FsFile {
+                                path,
+                                size: stream.size,
+                                last_modified,
+                                id: self.streams.len() as u64,
+                            });
+                            self.streams.push(stream);
+                        }
+                    }
+                    index = set_end;
+                }
+                _ => index += ENTRY_SIZE,
+            }
+        }
+        Ok(())
+    }
+
+    // Name, allocation, directory flag and last modification of a file
+    // entry followed by its stream extension and name entries.
+    fn parse_entry_set(set: &[u8]) -> Option<(String, Stream, bool, i64)> {
+        let file = &set[..ENTRY_SIZE];
+        let stream_entry = set.get(ENTRY_SIZE..2 * ENTRY_SIZE)?;
+        if stream_entry[0] != ENTRY_STREAM {
+            return None;
+        }
+        let name_length = stream_entry[3] as usize;
+        let name: Vec<u16> = set[2 * ENTRY_SIZE..]
+            .chunks(ENTRY_SIZE)
+            .filter(|entry| entry[0] == ENTRY_NAME)
+            .flat_map(|entry| (0..15).map(move |i| u16_at(entry, 2 + i * 2)))
+            .take(name_length)
+            .collect();
+
+        let stream = Stream {
+            first_cluster: u32_at(stream_entry, 20),
+            size: u64_at(stream_entry, 24),
+            contiguous: stream_entry[1] & FLAG_NO_FAT_CHAIN != 0,
+        };
+        Some((
+            String::from_utf16_lossy(&name),
+            stream,
+            u16_at(file, 4) & ATTRIBUTE_DIRECTORY != 0,
+            timestamp(u32_at(file, 12)),
+        ))
+    }
+}
+
+impl FileSystem for ExFat<'_> {
+    fn files(&mut self) -> anyhow::Result<Vec<FsFile>> {
+        let root = Stream {
+            first_cluster: self.root_cluster,
+            size: 0,
+            contiguous: false,
+        };
+        let mut files = Vec::new();
+        self.streams.clear();
+        self.walk(root, "", &mut files, &mut HashSet::new())?;
+        Ok(files)
+    }
+
+    fn read(&mut self, file: &FsFile, max_len: u64) -> anyhow::Result<Vec<u8>> {
+        let stream = *self
+            .streams
+            .get(file.id as usize)
+            .ok_or_else(|| anyhow::anyhow!("file not found in the exFAT volume: {}", file.path))?;
+        if stream.size == 0 {
+            return Ok(Vec::new());
+        }
+        self.read_stream(&stream, max_len)
+    }
+}
+
+// Date and time packed as in MS-DOS, in local time.
+fn timestamp(value: u32) -> i64 {
+    NaiveDate::from_ymd_opt(
+        1980 + (value >> 25) as i32,
+        (value >> 21) & 0x0F,
+        (value >> 16) & 0x1F,
+    )
+    .and_then(|date| {
+        date.and_hms_opt(
+            (value >> 11) & 0x1F,
+            (value >> 5) & 0x3F,
+            (value & 0x1F) * 2,
+        )
+    })
+    .map(|date_time| date_time.and_utc().timestamp())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::utils::disk_image::ReadSeek;
+
+    const CLUSTER: usize = 4096;
+
+    // Volume with 512 bytes sectors, 4 KiB clusters, the FAT at sector 8, the
+    // cluster heap at sector 16 and the root directory at cluster 2.
+    fn volume(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut volume = vec![0u8; 16 * 512 + 16 * CLUSTER];
+        volume[3..11].copy_from_slice(b"EXFAT   ");
+        volume[80..84].copy_from_slice(&8u32.to_le_bytes());
+        volume[88..92].copy_from_slice(&16u32.to_le_bytes());
+        volume[92..96].copy_from_slice(&16u32.to_le_bytes());
+        volume[96..100].copy_from_slice(&2u32.to_le_bytes());
+        volume[108] = 9;
+        volume[109] = 3;
+        // root directory in a single cluster
+        volume[8 * 512 + 8..8 * 512 + 12].copy_from_slice(&END_OF_CHAIN.to_le_bytes());
+
+        let mut entries = Vec::new();
+        let mut cluster = 3u32;
+        for (name, data) in files.iter() {
+            let name: Vec<u16> = name.encode_utf16().collect();
+            let mut file = [0u8; ENTRY_SIZE];
+            file[0] = ENTRY_FILE;
+            file[1] = 1 + name.len().div_ceil(15) as u8;
+            // 2024-01-02 03:04:06
+            file[12..16].copy_from_slice(
+                &(44 << 25 | 1 << 21 | 2 << 16 | 3 << 11 | 4 << 5 | 3u32).to_le_bytes(),
+            );
+            let mut stream = [0u8; ENTRY_SIZE];
+            stream[0] = ENTRY_STREAM;
+            stream[1] = FLAG_NO_FAT_CHAIN;
+            stream[3] = name.len() as u8;
+            stream[20..24].copy_from_slice(&cluster.to_le_bytes());
+            stream[24..32].copy_from_slice(&(data.len() as u64).to_le_bytes());
+            entries.extend(file);
+            entries.extend(stream);
+            for part in name.chunks(15) {
+                let mut entry = [0u8; ENTRY_SIZE];
+                entry[0] = ENTRY_NAME;
+                for (i, c) in part.iter().enumerate() {
+                    entry[2 + i * 2..4 + i * 2].copy_from_slice(&c.to_le_bytes());
+                }
+                entries.extend(entry);
+            }
+
+            let offset = 16 * 512 + (cluster as usize - 2) * CLUSTER;
+            volume[offset..offset + data.len()].copy_from_slice(data);
+            cluster += data.len().div_ceil(CLUSTER) as u32;
+        }
+        volume[16 * 512..16 * 512 + entries.len()].copy_from_slice(&entries);
+        volume
+    }
+
+    #[test]
+    fn test_read_files() {
+        let data: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+        let mut disk: Box<dyn ReadSeek> = Box::new(Cursor::new(volume(&[
+            ("notes.txt", b"notes"),
+            ("a long file name.jpg", &data),
+        ])));
+        let size = 16 * 512 + 16 * CLUSTER as u64;
+        let mut fs = ExFat::open(Slice::new(&mut *disk, 0, size)).unwrap();
+
+        let files = fs.files().expect("Failed to list files.");
+
+        // Assert
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1].path, "a long file name.jpg");
+        assert_eq!(files[1].last_modified, 1704164646);
+        assert_eq!(fs.read(&files[0], 1024).unwrap(), b"notes");
+        assert_eq!(fs.read(&files[1], u64::MAX).unwrap(), data);
+        assert_eq!(fs.read(&files[1], 8).unwrap(), &data[..8]);
+    }
+}
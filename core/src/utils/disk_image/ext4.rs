@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+
+use super::{read_at, u16_at, u32_at, FileSystem, FsFile, Slice};
+
+const SUPERBLOCK_OFFSET: u64 = 1024;
+const ROOT_INODE: u32 = 2;
+const FEATURE_INCOMPAT_64BIT: u32 = 0x80;
+const INODE_FLAG_EXTENTS: u32 = 0x8_0000;
+const INODE_FLAG_INLINE_DATA: u32 = 0x1000_0000;
+const MODE_TYPE: u16 = 0xF000;
+const MODE_DIRECTORY: u16 = 0x4000;
+const MODE_REGULAR: u16 = 0x8000;
+const EXTENT_MAGIC: u16 = 0xF30A;
+const MAX_EXTENT_DEPTH: u16 = 5;
+
+/// Blocks `physical..physical + len` hold the blocks `logical..logical + len` of the file.
+#[derive(Debug, Clone, Copy)]
+struct Extent {
+    logical: u64,
+    physical: u64,
+    len: u64,
+}
+
+#[derive(Debug)]
+struct Inode {
+    mode: u16,
+    size: u64,
+    last_modified: i64,
+    flags: u32,
+    block: Vec<u8>,
+}
+
+/// ext2, ext3 and ext4 volumes.
+pub(super) struct Ext4<'a> {
+    disk: Slice<'a>,
+    block_size: u64,
+    inodes_per_group: u32,
+    inode_size: u64,
+    /// First block of the inode table of each group.
+    inode_tables: Vec<u64>,
+}
+
+impl<'a> Ext4<'a> {
+    pub fn open(mut disk: Slice<'a>) -> anyhow::Result<Self> {
+        let superblock = read_at(&mut disk, SUPERBLOCK_OFFSET, 1024)?;
+        let block_size = 1024u64 << u32_at(&superblock, 24).min(6);
+        let blocks_count = u32_at(&superblock, 4) as u64;
+        let first_data_block = u32_at(&superblock, 20) as u64;
+        let blocks_per_group = u32_at(&superblock, 32) as u64;
+        let inodes_per_group = u32_at(&superblock, 40);
+        let inode_size = match u32_at(&superblock, 76) {
+            0 => 128,
+            _ => u16_at(&superblock, 88) as u64,
+        };
+        let is_64bit = u32_at(&superblock, 96) & FEATURE_INCOMPAT_64BIT != 0;
+        let desc_size = match u16_at(&superblock, 254) as u64 {
+            size if is_64bit && size >= 64 => size,
+            _ => 32,
+        };
+        if blocks_per_group == 0 || inodes_per_group == 0 || inode_size < 128 {
+            anyhow::bail!("invalid ext superblock");
+        }
+
+        let groups = (blocks_count.saturating_sub(first_data_block)).div_ceil(blocks_per_group);
+        let descriptors = read_at(
+            &mut disk,
+            (first_data_block + 1) * block_size,
+            (groups * desc_size) as usize,
+        )?;
+        let inode_tables = descriptors
+            .chunks(desc_size as usize)
+            .map(|descriptor| {
+                let high = if desc_size >= 64 {
+                    u32_at(descriptor, 0x28) as u64
+                } else {
+                    0
+                };
+                high << 32 | u32_at(descriptor, 8) as u64
+            })
+            .collect();
+
+        Ok(Self {
+            disk,
+            block_size,
+            inodes_per_group,
+            inode_size,
+            inode_tables,
+        })
+    }
+
+    fn inode(&mut self, number: u32) -> anyhow::Result<Inode> {
+        let group = ((number - 1) / self.inodes_per_group) as usize;
+        let index = ((number - 1) % self.inodes_per_group) as u64;
+        let table = *self
+            .inode_tables
+            .get(group)
+            .ok_or_else(|| anyhow::anyhow!("invalid inode number: {number}"))?;
+        let inode = read_at(
+            &mut self.disk,
+            table * self.block_size + index * self.inode_size,
+            self.inode_size as usize,
+        )?;
+
+        // the extra bits of the timestamps extend them beyond 2038
+        let mut last_modified = u32_at(&inode, 16) as i32 as i64;
+        if self.inode_size > 128 && u16_at(&inode, 0x80) >= 12 {
+            last_modified += ((u32_at(&inode, 0x88) & 0x03) as i64) << 32;
+        }
+
+        Ok(Inode {
+            mode: u16_at(&inode, 0),
+            size: (u32_at(&inode, 108) as u64) << 32 | u32_at(&inode, 4) as u64,
+            last_modified,
+            flags: u32_at(&inode, 32),
+            block: inode[40..100].to_vec(),
+        })
+    }
+
+    fn extents(&mut self, inode: &Inode) -> anyhow::Result<Vec<Extent>> {
+        let mut extents = Vec::new();
+        if inode.flags & INODE_FLAG_EXTENTS != 0 {
+            self.extent_tree(&inode.block, MAX_EXTENT_DEPTH, &mut extents)?;
+        } else {
+            let blocks = inode.size.div_ceil(self.block_size);
+            let mut logical = 0;
+            for (index, level) in (0..15).zip([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3]) {
+                if logical >= blocks {
+                    break;
+                }
+                let block = u32_at(&inode.block, index * 4) as u64;
+                self.block_map(block, level, &mut logical, blocks, &mut extents)?;
+            }
+        }
+        Ok(extents)
+    }
+
+    fn extent_tree(
+        &mut self,
+        node: &[u8],
+        max_depth: u16,
+        extents: &mut Vec<Extent>,
+    ) -> anyhow::Result<()> {
+        if u16_at(node, 0) != EXTENT_MAGIC {
+            anyhow::bail!("invalid extent tree");
+        }
+        let entries = u16_at(node, 2) as usize;
+        let depth = u16_at(node, 6);
+        if depth > max_depth {
+            anyhow::bail!("extent tree too deep");
+        }
+
+        for entry in node[12..].chunks(12).take(entries) {
+            if depth == 0 {
+                let len = u16_at(entry, 4) as u64;
+                // extents longer than 32768 blocks are allocated but not initialized
+                if len > 32768 {
+                    continue;
+                }
+                extents.push(Extent {
+                    logical: u32_at(entry, 0) as u64,
+                    physical: (u16_at(entry, 6) as u64) << 32 | u32_at(entry, 8) as u64,
+                    len,
+                });
+            } else {
+                let leaf = (u16_at(entry, 8) as u64) << 32 | u32_at(entry, 4) as u64;
+                let child = read_at(
+                    &mut self.disk,
+                    leaf * self.block_size,
+                    self.block_size as usize,
+                )?;
+                self.extent_tree(&child, depth - 1, extents)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Maps the blocks pointed directly (`level` 0) or through one, two or
+    // three levels of indirect blocks.
+    fn block_map(
+        &mut self,
+        block: u64,
+        level: u32,
+        logical: &mut u64,
+        blocks: u64,
+        extents: &mut Vec<Extent>,
+    ) -> anyhow::Result<()> {
+        let pointers = self.block_size / 4;
+        if block == 0 {
+            // hole, read as zeros
+            *logical += pointers.pow(level);
+            return Ok(());
+        }
+        if level == 0 {
+            match extents.last_mut() {
+                Some(last)
+                    if last.logical + last.len == *logical && last.physical + last.len == block =>
+                {
+                    last.len += 1
+                }
+                _ => extents.push(Extent {
+                    logical: *logical,
+                    physical: block,
+                    len: 1,
+                }),
+            }
+            *logical += 1;
+            return Ok(());
+        }
+
+        let indirect = read_at(
+            &mut self.disk,
+            block * self.block_size,
+            self.block_size as usize,
+        )?;
+        for index in 0..pointers as usize {
+            if *logical >= blocks {
+                break;
+            }
+            let block = u32_at(&indirect, index * 4) as u64;
+            self.block_map(block, level - 1, logical, blocks, extents)?;
+        }
+        Ok(())
+    }
+
+    fn read_inode(&mut self, inode: &Inode, max_len: u64) -> anyhow::Result<Vec<u8>> {
+        let len = inode.size.min(max_len);
+        if inode.flags & INODE_FLAG_INLINE_DATA != 0 {
+            return Ok(inode.block[..len.min(60) as usize].to_vec());
+        }
+
+        let mut data = vec![0u8; len as usize];
+        for extent in self.extents(inode)? {
+            let start = extent.logical * self.block_size;
+            if start >= len {
+                continue;
+            }
+            let end = ((extent.logical + extent.len) * self.block_size).min(len);
+            let chunk = read_at(
+                &mut self.disk,
+                extent.physical * self.block_size,
+                (end - start) as usize,
+            )?;
+            data[start as usize..end as usize].copy_from_slice(&chunk);
+        }
+        Ok(data)
+    }
+
+    fn walk(
+        &mut self,
+        dir: u32,
+        parent: &str,
+        files: &mut Vec<FsFile>,
+        visited: &mut HashSet<u32>,
+    ) -> anyhow::Result<()> {
+        if !visited.insert(dir) {
+            return Ok(());
+        }
+        let inode = self.inode(dir)?;
+        let entries = self.read_inode(&inode, inode.size)?;
+
+        let mut offset = 0;
+        while offset + 8 <= entries.len() {
+            let number = u32_at(&entries, offset);
+            let rec_len = u16_at(&entries, offset + 4) as usize;
+            let name_len = entries[offset + 6] as usize;
+            if rec_len < 8 {
+                break;
+            }
+            let name = entries
+                .get(offset + 8..offset + 8 + name_len)
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .unwrap_or_default();
+            offset += rec_len;
+
+            if number == 0 || name.is_empty() || name == "." || name == ".." {
+                continue;
+            }
+            let path = format!("{parent}{name}");
+            let inode = self.inode(number)?;
+            match inode.mode & MODE_TYPE {
+                MODE_DIRECTORY => self.walk(number, &format!("{path}/"), files, visited)?,
+                MODE_REGULAR => files.push(FsFile {
+                    path,
+                    size: inode.size,
+                    last_modified: inode.last_modified,
+                    id: number as u64,
+                }),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FileSystem for Ext4<'_> {
+    fn files(&mut self) -> anyhow::Result<Vec<FsFile>> {
+        let mut files = Vec::new();
+        self.walk(ROOT_INODE, "", &mut files, &mut HashSet::new())?;
+        Ok(files)
+    }
+
+    fn read(&mut self, file: &FsFile, max_len: u64) -> anyhow::Result<Vec<u8>> {
+        let inode = self.inode(file.id as u32)?;
+        self.read_inode(&inode, max_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+    use crate::utils::disk_image::ReadSeek;
+
+    // Images made by `mke2fs -d` from a folder with `DCIM/2024/horse.jpg`,
+    // `notes.txt` and `renamed.dat`, a copy of the horse.
+    fn read_files(fixture: &str) -> (Vec<FsFile>, Vec<u8>) {
+        let mut image = Vec::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(fixture).unwrap())
+            .read_to_end(&mut image)
+            .unwrap();
+        let size = image.len() as u64;
+        let mut disk: Box<dyn ReadSeek> = Box::new(Cursor::new(image));
+        let mut fs = Ext4::open(Slice::new(&mut *disk, 0, size)).unwrap();
+
+        let mut files = fs.files().expect("Failed to list files.");
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let data = fs.read(&files[0], u64::MAX).unwrap();
+        (files, data)
+    }
+
+    #[test]
+    fn test_read_ext4_files() {
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let (files, data) = read_files("../data/disk/ext4.img.gz");
+
+        // Assert
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["DCIM/2024/horse.jpg", "notes.txt", "renamed.dat"]
+        );
+        assert_eq!(files[0].size, img.len() as u64);
+        assert_eq!(files[0].last_modified, 1704164646);
+        assert_eq!(data, img);
+    }
+
+    #[test]
+    fn test_read_ext2_files() {
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let (files, data) = read_files("../data/disk/ext2.img.gz");
+
+        // Assert
+        assert_eq!(files.len(), 3);
+        assert_eq!(data, img);
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::Read;
+
+use chrono::NaiveDate;
+
+use super::{FileSystem, FsFile, Slice};
+
+/// FAT12, FAT16 and FAT32 volumes.
+pub(super) struct Fat<'a> {
+    fs: fatfs::FileSystem<Slice<'a>>,
+}
+
+impl<'a> Fat<'a> {
+    pub fn open(slice: Slice<'a>) -> anyhow::Result<Self> {
+        let fs = fatfs::FileSystem::new(slice, fatfs::FsOptions::new())?;
+        Ok(Self { fs })
+    }
+
+    fn walk(
+        dir: fatfs::Dir<Slice<'a>>,
+        parent: &str,
+        files: &mut Vec<FsFile>,
+    ) -> anyhow::Result<()> {
+        for entry in dir.iter() {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name == "." || name == ".." {
+                continue;
+            }
+            let path = format!("{parent}{name}");
+
+            if entry.is_dir() {
+                Self::walk(entry.to_dir(), &format!("{path}/"), files)?;
+            } else if entry.is_file() {
+                files.push(FsFile {
+                    path,
+                    size: entry.len(),
+                    last_modified: timestamp(entry.modified()),
+                    id: 0,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FileSystem for Fat<'_> {
+    fn files(&mut self) -> anyhow::Result<Vec<FsFile>> {
+        let mut files = Vec::new();
+        Self::walk(self.fs.root_dir(), "", &mut files)?;
+        Ok(files)
+    }
+
+    fn read(&mut self, file: &FsFile, max_len: u64) -> anyhow::Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(max_len.min(file.size) as usize);
+        self.fs
+            .root_dir()
+            .open_file(&file.path)?
+            .take(max_len)
+            .read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+// FAT stores the local time of the computer that wrote the file.
+fn timestamp(date_time: fatfs::DateTime) -> i64 {
+    NaiveDate::from_ymd_opt(
+        date_time.date.year as i32,
+        date_time.date.month as u32,
+        date_time.date.day as u32,
+    )
+    .and_then(|date| {
+        date.and_hms_opt(
+            date_time.time.hour as u32,
+            date_time.time.min as u32,
+            date_time.time.sec as u32,
+        )
+    })
+    .map(|date_time| date_time.and_utc().timestamp())
+    .unwrap_or_default()
+}
@@ -0,0 +1,574 @@
+mod ewf;
+mod exfat;
+mod ext4;
+mod fat;
+mod ntfs;
+mod partition;
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use super::archive::{self, ArchiveMember, MAX_MEMBER_SIZE, SEPARATOR};
+use super::media;
+use crate::csam::FileType;
+
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageType {
+    /// Raw (`dd`) image, possibly split in `.001`, `.002`, ... segments.
+    Raw,
+    /// EnCase image (Expert Witness Format), possibly split in `.E01`, `.E02`, ... segments.
+    Ewf,
+}
+
+impl ImageType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.ends_with(".e01") {
+            Some(Self::Ewf)
+        } else if [".dd", ".raw", ".img", ".001"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+        {
+            Some(Self::Raw)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn is_disk_image(name: &str) -> bool {
+    ImageType::from_name(name).is_some()
+}
+
+pub(crate) trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Filesystems read from the volumes of a disk image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FsType {
+    Fat,
+    ExFat,
+    Ntfs,
+    Ext4,
+}
+
+impl FsType {
+    /// Detects the filesystem from the first sectors of the volume.
+    fn detect(boot: &[u8]) -> Option<Self> {
+        let at = |offset: usize, signature: &[u8]| {
+            boot.get(offset..offset + signature.len()) == Some(signature)
+        };
+
+        if at(3, b"NTFS    ") {
+            Some(Self::Ntfs)
+        } else if at(3, b"EXFAT   ") {
+            Some(Self::ExFat)
+        } else if at(510, &[0x55, 0xAA])
+            && (at(54, b"FAT12   ") || at(54, b"FAT16   ") || at(82, b"FAT32   "))
+        {
+            Some(Self::Fat)
+        } else if at(1080, &[0x53, 0xEF]) {
+            Some(Self::Ext4)
+        } else {
+            None
+        }
+    }
+}
+
+/// Volume of the disk image with a supported filesystem.
+#[derive(Debug, Clone, PartialEq)]
+struct Volume {
+    /// Prefix of the paths of the volume: `p1/`, `p2/`, ... or empty when
+    /// the image has no partition table.
+    prefix: String,
+    fs_type: FsType,
+    offset: u64,
+    size: u64,
+}
+
+/// Regular file of a filesystem.
+#[derive(Debug, Clone)]
+struct FsFile {
+    /// Path from the root of the volume, without the leading `/`.
+    path: String,
+    size: u64,
+    last_modified: i64,
+    /// Location of the file in the filesystem: inode, MFT record, first cluster, ...
+    id: u64,
+}
+
+trait FileSystem {
+    fn files(&mut self) -> anyhow::Result<Vec<FsFile>>;
+
+    /// Reads up to `max_len` bytes from the beginning of the file.
+    fn read(&mut self, file: &FsFile, max_len: u64) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Reads the media of every supported filesystem in the disk image, and of
+/// the archives in them up to `archive_depth` levels, calling `f` with the
+/// virtual path of every media or of every file that could not be read, until
+/// it returns `false`.
+///
+/// The image is only read, never mounted or modified.
+pub fn read_media<F>(path: &Path, archive_depth: usize, f: &mut F) -> anyhow::Result<()>
+where
+    F: FnMut(String, anyhow::Result<ArchiveMember>) -> bool,
+{
+    let image_path = path.to_string_lossy();
+    let mut disk = open(path)?;
+    let volumes = volumes(&mut *disk)?;
+    if volumes.is_empty() {
+        anyhow::bail!("no supported filesystem found in the disk image");
+    }
+
+    for volume in volumes.iter() {
+        let volume_path = format!("{image_path}{SEPARATOR}{}", volume.prefix);
+        let mut fs = match open_filesystem(&mut *disk, volume) {
+            Ok(fs) => fs,
+            Err(err) => {
+                if !f(volume_path, Err(err)) {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        let files = match fs.files() {
+            Ok(files) => files,
+            Err(err) => {
+                if !f(volume_path, Err(err)) {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+
+        for file in files.iter() {
+            let path = format!("{volume_path}{}", file.path);
+            let name = file
+                .path
+                .rsplit('/')
+                .next()
+                .unwrap_or(&file.path)
+                .to_owned();
+
+            let is_archive = archive::is_archive(&name);
+            if is_archive && archive_depth == 0 {
+                continue;
+            }
+            let is_media = matches!(
+                Path::new(&name).extension(),
+                Some(e) if media::is_media(&e.to_string_lossy().to_lowercase())
+            );
+            // files renamed to hide their type are recognised by their signature
+            if !is_archive && !is_media {
+                match fs.read(file, FileType::HEADER_LEN as u64) {
+                    Ok(header) if FileType::from_signature(&header).is_some() => (),
+                    Ok(_) => continue,
+                    Err(err) => {
+                        if !f(path, Err(err)) {
+                            return Ok(());
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            let supported = archive::ArchiveType::from_name(&name)
+                .map_or(Ok(()), archive::ArchiveType::check_supported);
+            let data = if let Err(err) = supported {
+                Err(err)
+            } else if file.size > MAX_MEMBER_SIZE {
+                Err(anyhow::anyhow!(
+                    "file too large to be read from the disk image: {} bytes",
+                    file.size
+                ))
+            } else {
+                fs.read(file, file.size)
+            };
+            let is_running = match data {
+                Ok(data) if is_archive => {
+                    match archive::read_media_from_memory(data, &path, archive_depth, f) {
+                        Ok(is_running) => is_running,
+                        Err(err) => f(path, Err(err)),
+                    }
+                }
+                Ok(data) => {
+                    let member = ArchiveMember {
+                        path: path.clone(),
+                        name,
+                        last_modified: file.last_modified,
+                        data,
+                    };
+                    f(path, Ok(member))
+                }
+                Err(err) => f(path, Err(err)),
+            };
+            if !is_running {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the file at `member`, a path such as `p1/DCIM/img.jpg`, from the disk image.
+pub fn extract(image_path: &str, member: &str) -> anyhow::Result<Vec<u8>> {
+    let mut disk = open(Path::new(image_path))?;
+    let volumes = volumes(&mut *disk)?;
+    let volume = volumes
+        .iter()
+        .filter(|volume| member.starts_with(&volume.prefix))
+        .max_by_key(|volume| volume.prefix.len())
+        .with_context(|| format!("volume not found in the disk image: {member}"))?;
+    let file_path = &member[volume.prefix.len()..];
+
+    let mut fs = open_filesystem(&mut *disk, volume)?;
+    let file = fs
+        .files()?
+        .into_iter()
+        .find(|file| file.path == file_path)
+        .with_context(|| format!("file not found in the disk image: {member}"))?;
+    if file.size > MAX_MEMBER_SIZE {
+        anyhow::bail!(
+            "file too large to be read from the disk image: {} bytes",
+            file.size
+        );
+    }
+    fs.read(&file, file.size)
+}
+
+pub(crate) fn open(path: &Path) -> anyhow::Result<Box<dyn ReadSeek>> {
+    let image_type = ImageType::from_name(&path.to_string_lossy())
+        .with_context(|| format!("unknown disk image type: {}", path.display()))?;
+
+    let disk: Box<dyn ReadSeek> = match image_type {
+        ImageType::Ewf => Box::new(ewf::EwfReader::open(path)?),
+        ImageType::Raw if path.to_string_lossy().ends_with(".001") => {
+            Box::new(SplitReader::open(path)?)
+        }
+        ImageType::Raw => Box::new(File::open(path)?),
+    };
+    Ok(disk)
+}
+
+// The whole disk when it holds a filesystem, otherwise its partitions.
+fn volumes(disk: &mut dyn ReadSeek) -> anyhow::Result<Vec<Volume>> {
+    let size = disk.seek(SeekFrom::End(0))?;
+    let boot = read_at(disk, 0, 2048.min(size as usize))?;
+    if let Some(fs_type) = FsType::detect(&boot) {
+        return Ok(vec![Volume {
+            prefix: String::new(),
+            fs_type,
+            offset: 0,
+            size,
+        }]);
+    }
+
+    let mut volumes = Vec::new();
+    for partition in partition::read_partitions(disk, size)? {
+        if partition.offset >= size {
+            continue;
+        }
+        let len = 2048.min(size - partition.offset) as usize;
+        let boot = read_at(disk, partition.offset, len)?;
+        if let Some(fs_type) = FsType::detect(&boot) {
+            volumes.push(Volume {
+                prefix: format!("p{}/", partition.index),
+                fs_type,
+                offset: partition.offset,
+                size: partition.size.min(size - partition.offset),
+            });
+        }
+    }
+    Ok(volumes)
+}
+
+fn open_filesystem<'a>(
+    disk: &'a mut dyn ReadSeek,
+    volume: &Volume,
+) -> anyhow::Result<Box<dyn FileSystem + 'a>> {
+    let slice = Slice::new(disk, volume.offset, volume.size);
+    let fs: Box<dyn FileSystem + 'a> = match volume.fs_type {
+        FsType::Fat => Box::new(fat::Fat::open(slice)?),
+        FsType::ExFat => Box::new(exfat::ExFat::open(slice)?),
+        FsType::Ntfs => Box::new(ntfs::Ntfs::open(slice)?),
+        FsType::Ext4 => Box::new(ext4::Ext4::open(slice)?),
+    };
+    Ok(fs)
+}
+
+fn read_at<R: Read + Seek + ?Sized>(
+    reader: &mut R,
+    offset: u64,
+    len: usize,
+) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+// Little endian integers of the on-disk structures, zero when out of the buffer.
+fn u16_at(buf: &[u8], offset: usize) -> u16 {
+    buf.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .unwrap_or_default()
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> u32 {
+    buf.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .unwrap_or_default()
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> u64 {
+    buf.get(offset..offset + 8)
+        .map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        .unwrap_or_default()
+}
+
+/// Read-only view of a volume of the disk.
+struct Slice<'a> {
+    disk: &'a mut dyn ReadSeek,
+    offset: u64,
+    size: u64,
+    position: u64,
+}
+
+impl<'a> Slice<'a> {
+    fn new(disk: &'a mut dyn ReadSeek, offset: u64, size: u64) -> Self {
+        Self {
+            disk,
+            offset,
+            size,
+            position: 0,
+        }
+    }
+}
+
+impl Read for Slice<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = (buf.len() as u64).min(self.size.saturating_sub(self.position)) as usize;
+        if len == 0 {
+            return Ok(0);
+        }
+        self.disk
+            .seek(SeekFrom::Start(self.offset + self.position))?;
+        let read = self.disk.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for Slice<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.size)?;
+        Ok(self.position)
+    }
+}
+
+// The FAT library requires a writable disk, every write fails.
+impl Write for Slice<'_> {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "disk images are read-only",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn seek_position(pos: SeekFrom, position: u64, size: u64) -> io::Result<u64> {
+    let position = match pos {
+        SeekFrom::Start(offset) => Some(offset),
+        SeekFrom::End(offset) => size.checked_add_signed(offset),
+        SeekFrom::Current(offset) => position.checked_add_signed(offset),
+    };
+    position.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))
+}
+
+/// Raw image split in `.001`, `.002`, ... files, read as a single disk.
+struct SplitReader {
+    segments: Vec<(File, u64)>,
+    size: u64,
+    position: u64,
+}
+
+impl SplitReader {
+    fn open(first: &Path) -> io::Result<Self> {
+        let mut segments = Vec::new();
+        let mut size = 0;
+        for path in segment_paths(first, |number| format!("{number:03}")) {
+            let file = File::open(&path)?;
+            let len = file.metadata()?.len();
+            segments.push((file, size));
+            size += len;
+        }
+        Ok(Self {
+            segments,
+            size,
+            position: 0,
+        })
+    }
+}
+
+impl Read for SplitReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let position = self.position;
+        let Some(index) = self
+            .segments
+            .iter()
+            .rposition(|(_, start)| *start <= position)
+        else {
+            return Ok(0);
+        };
+        let end = self
+            .segments
+            .get(index + 1)
+            .map(|(_, start)| *start)
+            .unwrap_or(self.size);
+        let len = (buf.len() as u64).min(end.saturating_sub(position)) as usize;
+        let (file, start) = &mut self.segments[index];
+        file.seek(SeekFrom::Start(position - *start))?;
+        let read = file.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SplitReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = seek_position(pos, self.position, self.size)?;
+        Ok(self.position)
+    }
+}
+
+// The first segment and the following ones that exist, named by replacing the
+// extension with `extension(2)`, `extension(3)`, ...
+fn segment_paths<F>(first: &Path, extension: F) -> Vec<PathBuf>
+where
+    F: Fn(usize) -> String,
+{
+    let mut paths = vec![first.to_path_buf()];
+    for number in 2.. {
+        let path = first.with_extension(extension(number));
+        if !path.is_file() {
+            break;
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    // MBR with a single FAT partition starting at sector 2048.
+    fn fat_disk(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let offset = 2048 * SECTOR_SIZE as usize;
+        let size = 4 * 1024 * 1024;
+        let mut volume = Cursor::new(vec![0u8; size]);
+        fatfs::format_volume(&mut volume, fatfs::FormatVolumeOptions::new()).unwrap();
+        {
+            let fs = fatfs::FileSystem::new(&mut volume, fatfs::FsOptions::new()).unwrap();
+            for (path, data) in files.iter() {
+                let root = fs.root_dir();
+                if let Some((dir, _)) = path.rsplit_once('/') {
+                    root.create_dir(dir).unwrap();
+                }
+                root.create_file(path).unwrap().write_all(data).unwrap();
+            }
+        }
+
+        let mut disk = vec![0u8; offset];
+        disk[446 + 4] = 0x0C;
+        disk[446 + 8..446 + 12].copy_from_slice(&2048u32.to_le_bytes());
+        disk[446 + 12..446 + 16].copy_from_slice(&((size / 512) as u32).to_le_bytes());
+        disk[510] = 0x55;
+        disk[511] = 0xAA;
+        disk.extend(volume.into_inner());
+        disk
+    }
+
+    #[test]
+    fn test_read_media_from_raw_image() {
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let dir = Path::new("../data/tmp/disk_image/");
+        std::fs::create_dir_all(dir).unwrap();
+        let image_path = dir.join("evidence.dd");
+        let disk = fat_disk(&[
+            ("DCIM/horse.jpg", &img),
+            ("renamed.dat", &img),
+            ("notes.txt", b"notes"),
+        ]);
+        std::fs::write(&image_path, disk).unwrap();
+
+        let mut members = Vec::new();
+        read_media(&image_path, 0, &mut |path, member| {
+            members.push((path, member.map(|member| member.data.len()).ok()));
+            true
+        })
+        .expect("Failed to read disk image.");
+        let mut first = Vec::new();
+        read_media(&image_path, 0, &mut |path, _| {
+            first.push(path);
+            false
+        })
+        .expect("Failed to read disk image.");
+        let data = extract(&image_path.to_string_lossy(), "p1/DCIM/horse.jpg")
+            .expect("Failed to extract file.");
+
+        // Assert
+        let image_path = image_path.display();
+        assert_eq!(
+            members,
+            vec![
+                (format!("{image_path}!/p1/DCIM/horse.jpg"), Some(img.len())),
+                (format!("{image_path}!/p1/renamed.dat"), Some(img.len())),
+            ]
+        );
+        assert_eq!(first, vec![format!("{image_path}!/p1/DCIM/horse.jpg")]);
+        assert_eq!(data, img);
+        assert!(extract(&image_path.to_string(), "p1/missing.jpg").is_err());
+    }
+
+    #[test]
+    fn test_split_reader() {
+        let dir = Path::new("../data/tmp/disk_image/split/");
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("disk.001"), b"0123").unwrap();
+        std::fs::write(dir.join("disk.002"), b"4567").unwrap();
+        std::fs::write(dir.join("disk.003"), b"89").unwrap();
+
+        let mut reader = SplitReader::open(&dir.join("disk.001")).unwrap();
+        let mut data = String::new();
+        reader.seek(SeekFrom::Start(2)).unwrap();
+        reader.read_to_string(&mut data).unwrap();
+
+        // Assert
+        assert_eq!(data, "23456789");
+        assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_image_type() {
+        // Assert
+        assert_eq!(ImageType::from_name("disk.E01"), Some(ImageType::Ewf));
+        assert_eq!(ImageType::from_name("disk.dd"), Some(ImageType::Raw));
+        assert_eq!(ImageType::from_name("disk.001"), Some(ImageType::Raw));
+        assert!(!is_disk_image("horse.jpg"));
+    }
+}
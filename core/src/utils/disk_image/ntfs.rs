@@ -0,0 +1,555 @@
+use std::collections::HashMap;
+
+use super::{read_at, u16_at, u32_at, u64_at, FileSystem, FsFile, Slice};
+
+const RECORD_MFT: u64 = 0;
+const RECORD_ROOT: u64 = 5;
+/// Records below this number are reserved for the metadata files.
+const FIRST_USER_RECORD: u64 = 24;
+const ATTRIBUTE_STANDARD_INFORMATION: u32 = 0x10;
+const ATTRIBUTE_FILE_NAME: u32 = 0x30;
+const ATTRIBUTE_DATA: u32 = 0x80;
+const ATTRIBUTE_END: u32 = 0xFFFF_FFFF;
+const RECORD_IN_USE: u16 = 0x01;
+const RECORD_DIRECTORY: u16 = 0x02;
+const NAMESPACE_DOS: u8 = 2;
+const FLAG_COMPRESSED: u16 = 0x0001;
+const FLAG_ENCRYPTED: u16 = 0x4000;
+// Stride of the update sequence that protects the records.
+const FIXUP_STRIDE: usize = 512;
+const MAX_PATH_DEPTH: usize = 256;
+// Seconds between 1601-01-01, the epoch of NTFS, and 1970-01-01.
+const EPOCH_DIFFERENCE: i64 = 11_644_473_600;
+
+/// Clusters `lcn..lcn + len` of the volume hold the clusters `vcn..vcn + len`
+/// of the attribute, sparse when there is no `lcn`.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    vcn: u64,
+    lcn: Option<u64>,
+    len: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Data {
+    Resident(Vec<u8>),
+    NonResident {
+        runs: Vec<Run>,
+        size: u64,
+        compressed: bool,
+        encrypted: bool,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Record {
+    flags: u16,
+    base: u64,
+    /// Parent directory and name, from the `$FILE_NAME` attributes.
+    name: Option<(u64, String)>,
+    last_modified: i64,
+    data: Option<Data>,
+}
+
+pub(super) struct Ntfs<'a> {
+    disk: Slice<'a>,
+    cluster_size: u64,
+    record_size: u64,
+    mft: Vec<Run>,
+    mft_size: u64,
+    /// Data of the files listed, by MFT record number.
+    data: HashMap<u64, Data>,
+}
+
+impl<'a> Ntfs<'a> {
+    pub fn open(mut disk: Slice<'a>) -> anyhow::Result<Self> {
+        let boot = read_at(&mut disk, 0, 512)?;
+        let sector_size = u16_at(&boot, 11) as u64;
+        let cluster_size = match boot[13] {
+            sectors if sectors > 0x80 => sector_size << (256 - sectors as u32).min(20),
+            sectors => sector_size * sectors as u64,
+        };
+        let record_size = match boot[64] as i8 {
+            clusters if clusters < 0 => 1u64 << (-(clusters as i32)).min(20),
+            clusters => clusters as u64 * cluster_size,
+        };
+        if cluster_size == 0 || record_size < FIXUP_STRIDE as u64 {
+            anyhow::bail!("invalid NTFS boot sector");
+        }
+        let mft_lcn = u64_at(&boot, 48);
+
+        let mut ntfs = Self {
+            disk,
+            cluster_size,
+            record_size,
+            mft: vec![Run {
+                vcn: 0,
+                lcn: Some(mft_lcn),
+                len: record_size.div_ceil(cluster_size),
+            }],
+            mft_size: record_size,
+            data: HashMap::new(),
+        };
+
+        // the MFT is a file too, described by its own first record
+        match ntfs.record(RECORD_MFT)?.and_then(|record| record.data) {
+            Some(Data::NonResident { runs, size, .. }) => {
+                ntfs.mft = runs;
+                ntfs.mft_size = size;
+            }
+            _ => anyhow::bail!("could not read the MFT"),
+        }
+        Ok(ntfs)
+    }
+
+    fn record(&mut self, number: u64) -> anyhow::Result<Option<Record>> {
+        let mut buf = self.read_runs(
+            &self.mft.clone(),
+            number * self.record_size,
+            self.record_size,
+        )?;
+        if &buf[..4] != b"FILE" || !fixup(&mut buf) {
+            return Ok(None);
+        }
+
+        let mut record = Record {
+            flags: u16_at(&buf, 22),
+            base: u64_at(&buf, 32) & 0xFFFF_FFFF_FFFF,
+            ..Default::default()
+        };
+        let mut offset = u16_at(&buf, 20) as usize;
+        while offset + 16 <= buf.len() {
+            let attribute = &buf[offset..];
+            let attribute_type = u32_at(attribute, 0);
+            let len = u32_at(attribute, 4) as usize;
+            if attribute_type == ATTRIBUTE_END || len < 16 || offset + len > buf.len() {
+                break;
+            }
+            let attribute = &attribute[..len];
+            let is_resident = attribute[8] == 0;
+            let is_named = attribute[9] != 0;
+            let value = if is_resident {
+                let value_offset = u16_at(attribute, 20) as usize;
+                let value_len = u32_at(attribute, 16) as usize;
+                attribute
+                    .get(value_offset..value_offset + value_len)
+                    .unwrap_or_default()
+            } else {
+                &[]
+            };
+
+            match attribute_type {
+                ATTRIBUTE_STANDARD_INFORMATION if value.len() >= 16 => {
+                    record.last_modified = timestamp(u64_at(value, 8));
+                }
+                // the DOS name is an alias of the long one
+                ATTRIBUTE_FILE_NAME if value.len() >= 66 && value[65] != NAMESPACE_DOS => {
+                    let name_len = value[64] as usize;
+                    let name: Vec<u16> = (0..name_len).map(|i| u16_at(value, 66 + i * 2)).collect();
+                    record.name = Some((
+                        u64_at(value, 0) & 0xFFFF_FFFF_FFFF,
+                        String::from_utf16_lossy(&name),
+                    ));
+                }
+                // alternate data streams are ignored
+                ATTRIBUTE_DATA if !is_named => {
+                    record.data = Some(if is_resident {
+                        Data::Resident(value.to_vec())
+                    } else {
+                        let flags = u16_at(attribute, 12);
+                        Data::NonResident {
+                            runs: runs(attribute)?,
+                            size: u64_at(attribute, 48),
+                            compressed: flags & FLAG_COMPRESSED != 0,
+                            encrypted: flags & FLAG_ENCRYPTED != 0,
+                        }
+                    });
+                }
+                _ => (),
+            }
+            offset += len;
+        }
+        Ok(Some(record))
+    }
+
+    // Reads `len` bytes from `offset` of the clusters of the runs.
+    fn read_runs(&mut self, runs: &[Run], offset: u64, len: u64) -> anyhow::Result<Vec<u8>> {
+        let mut data = vec![0u8; len as usize];
+        let end = offset + len;
+        for run in runs.iter() {
+            let run_start = run.vcn * self.cluster_size;
+            let run_end = (run.vcn + run.len) * self.cluster_size;
+            let (start, stop) = (run_start.max(offset), run_end.min(end));
+            let Some(lcn) = run.lcn else {
+                continue;
+            };
+            if start >= stop {
+                continue;
+            }
+            let chunk = read_at(
+                &mut self.disk,
+                lcn * self.cluster_size + (start - run_start),
+                (stop - start) as usize,
+            )?;
+            data[(start - offset) as usize..(stop - offset) as usize].copy_from_slice(&chunk);
+        }
+        Ok(data)
+    }
+}
+
+impl FileSystem for Ntfs<'_> {
+    fn files(&mut self) -> anyhow::Result<Vec<FsFile>> {
+        let mut records = HashMap::new();
+        let mut extensions = Vec::new();
+        for number in 0..self.mft_size / self.record_size {
+            match self.record(number)? {
+                Some(record) if record.flags & RECORD_IN_USE == 0 => (),
+                Some(record) if record.base != 0 => extensions.push(record),
+                Some(record) => {
+                    records.insert(number, record);
+                }
+                None => (),
+            }
+        }
+
+        // the runs of fragmented files continue in extension records
+        for extension in extensions {
+            let Some(Data::NonResident { runs: more, .. }) = extension.data else {
+                continue;
+            };
+            if let Some(base) = records.get_mut(&extension.base) {
+                match &mut base.data {
+                    Some(Data::NonResident { runs, .. }) => runs.extend(more),
+                    data @ None => {
+                        *data = Some(Data::NonResident {
+                            runs: more,
+                            size: 0,
+                            compressed: false,
+                            encrypted: false,
+                        })
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+        self.data.clear();
+        for (number, record) in records.iter() {
+            if *number < FIRST_USER_RECORD || record.flags & RECORD_DIRECTORY != 0 {
+                continue;
+            }
+            let (Some(data), Some(path)) = (&record.data, path(&records, *number)) else {
+                continue;
+            };
+            if path.starts_with("$Extend/") {
+                continue;
+            }
+
+            let size = match data {
+                Data::Resident(value) => value.len() as u64,
+                Data::NonResident { size, .. } => *size,
+            };
+            files.push(FsFile {
+                path,
+                size,
+                last_modified: record.last_modified,
+                id: *number,
+            });
+            self.data.insert(*number, data.clone());
+        }
+        files.sort_by_key(|file| file.id);
+        Ok(files)
+    }
+
+    fn read(&mut self, file: &FsFile, max_len: u64) -> anyhow::Result<Vec<u8>> {
+        let data =
+            self.data.get(&file.id).cloned().ok_or_else(|| {
+                anyhow::anyhow!("file not found in the NTFS volume: {}", file.path)
+            })?;
+
+        match data {
+            Data::Resident(mut value) => {
+                value.truncate(max_len as usize);
+                Ok(value)
+            }
+            Data::NonResident {
+                compressed: true, ..
+            } => {
+                anyhow::bail!("compressed NTFS files are not supported")
+            }
+            Data::NonResident {
+                encrypted: true, ..
+            } => {
+                anyhow::bail!("encrypted NTFS files are not supported")
+            }
+            Data::NonResident { runs, size, .. } => self.read_runs(&runs, 0, size.min(max_len)),
+        }
+    }
+}
+
+// Path of the record from the root directory, or under `$Orphan/` when one of
+// its parents is gone.
+fn path(records: &HashMap<u64, Record>, number: u64) -> Option<String> {
+    let mut names = Vec::new();
+    let mut current = number;
+    while current != RECORD_ROOT {
+        let Some(record) = records.get(&current) else {
+            names.push(String::from("$Orphan"));
+            break;
+        };
+        let (parent, name) = record.name.as_ref()?;
+        names.push(name.clone());
+        if names.len() > MAX_PATH_DEPTH {
+            return None;
+        }
+        current = *parent;
+    }
+    names.reverse();
+    Some(names.join("/"))
+}
+
+// Replaces the last two bytes of each sector of the record with the values
+// saved in its update sequence, returning `false` when they do not match.
+fn fixup(record: &mut [u8]) -> bool {
+    let offset = u16_at(record, 4) as usize;
+    let count = u16_at(record, 6) as usize;
+    let Some(sequence) = record.get(offset..offset + count * 2).map(|s| s.to_vec()) else {
+        return false;
+    };
+
+    for index in 1..count {
+        let position = index * FIXUP_STRIDE - 2;
+        if position + 2 > record.len() {
+            break;
+        }
+        if record[position..position + 2] != sequence[..2] {
+            return false;
+        }
+        record[position..position + 2].copy_from_slice(&sequence[index * 2..index * 2 + 2]);
+    }
+    true
+}
+
+// Runs of a non-resident attribute, with the clusters in variable length
+// integers relative to the previous run.
+fn runs(attribute: &[u8]) -> anyhow::Result<Vec<Run>> {
+    let mut runs = Vec::new();
+    let mut vcn = u64_at(attribute, 16);
+    let mut lcn: i64 = 0;
+    let mut offset = u16_at(attribute, 32) as usize;
+
+    while let Some(&header) = attribute.get(offset) {
+        if header == 0 {
+            break;
+        }
+        let len_size = (header & 0x0F) as usize;
+        let offset_size = (header >> 4) as usize;
+        let bytes = attribute
+            .get(offset + 1..offset + 1 + len_size + offset_size)
+            .ok_or_else(|| anyhow::anyhow!("invalid NTFS run list"))?;
+        if len_size == 0 || len_size > 8 || offset_size > 8 {
+            anyhow::bail!("invalid NTFS run list");
+        }
+
+        let len = bytes[..len_size]
+            .iter()
+            .rev()
+            .fold(0u64, |value, b| value << 8 | *b as u64);
+        let run_lcn = if offset_size == 0 {
+            None
+        } else {
+            let delta = bytes[len_size..]
+                .iter()
+                .rev()
+                .fold(0i64, |value, b| value << 8 | *b as i64);
+            // sign extension of the relative cluster number
+            let shift = 64 - 8 * offset_size as u32;
+            lcn += delta.wrapping_shl(shift).wrapping_shr(shift);
+            Some(lcn as u64)
+        };
+
+        runs.push(Run {
+            vcn,
+            lcn: run_lcn,
+            len,
+        });
+        vcn += len;
+        offset += 1 + len_size + offset_size;
+    }
+    Ok(runs)
+}
+
+fn timestamp(value: u64) -> i64 {
+    (value / 10_000_000) as i64 - EPOCH_DIFFERENCE
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::utils::disk_image::ReadSeek;
+
+    const CLUSTER: usize = 4096;
+    const RECORD: usize = 1024;
+    const MFT_LCN: usize = 4;
+    const DATA_LCN: usize = 16;
+
+    fn attribute(attribute_type: u32, header: Vec<u8>, content: &[u8]) -> Vec<u8> {
+        let mut attribute = header;
+        attribute.extend(content);
+        attribute.resize(attribute.len().div_ceil(8) * 8, 0);
+        attribute[..4].copy_from_slice(&attribute_type.to_le_bytes());
+        let len = attribute.len() as u32;
+        attribute[4..8].copy_from_slice(&len.to_le_bytes());
+        attribute
+    }
+
+    fn resident(attribute_type: u32, value: &[u8]) -> Vec<u8> {
+        let mut header = vec![0u8; 24];
+        header[16..20].copy_from_slice(&(value.len() as u32).to_le_bytes());
+        header[20..22].copy_from_slice(&24u16.to_le_bytes());
+        attribute(attribute_type, header, value)
+    }
+
+    fn non_resident(lcn: u8, clusters: u8, size: u64) -> Vec<u8> {
+        let mut header = vec![0u8; 64];
+        header[8] = 1;
+        header[24..32].copy_from_slice(&(clusters as u64 - 1).to_le_bytes());
+        header[32..34].copy_from_slice(&64u16.to_le_bytes());
+        header[40..48].copy_from_slice(&(clusters as u64 * CLUSTER as u64).to_le_bytes());
+        header[48..56].copy_from_slice(&size.to_le_bytes());
+        header[56..64].copy_from_slice(&size.to_le_bytes());
+        attribute(ATTRIBUTE_DATA, header, &[0x11, clusters, lcn, 0])
+    }
+
+    fn file_name(parent: u64, name: &str) -> Vec<u8> {
+        let name: Vec<u16> = name.encode_utf16().collect();
+        let mut value = vec![0u8; 66];
+        value[..8].copy_from_slice(&parent.to_le_bytes());
+        value[64] = name.len() as u8;
+        value[65] = 1;
+        value.extend(name.iter().flat_map(|c| c.to_le_bytes()));
+        resident(ATTRIBUTE_FILE_NAME, &value)
+    }
+
+    fn standard_information(last_modified: i64) -> Vec<u8> {
+        let mut value = vec![0u8; 48];
+        let time = ((last_modified + EPOCH_DIFFERENCE) * 10_000_000) as u64;
+        value[8..16].copy_from_slice(&time.to_le_bytes());
+        resident(ATTRIBUTE_STANDARD_INFORMATION, &value)
+    }
+
+    fn record(flags: u16, attributes: &[Vec<u8>]) -> Vec<u8> {
+        let mut record = vec![0u8; RECORD];
+        record[..4].copy_from_slice(b"FILE");
+        record[4..6].copy_from_slice(&48u16.to_le_bytes());
+        record[6..8].copy_from_slice(&3u16.to_le_bytes());
+        record[20..22].copy_from_slice(&56u16.to_le_bytes());
+        record[22..24].copy_from_slice(&flags.to_le_bytes());
+        let mut offset = 56;
+        for attribute in attributes.iter() {
+            record[offset..offset + attribute.len()].copy_from_slice(attribute);
+            offset += attribute.len();
+        }
+        record[offset..offset + 4].copy_from_slice(&ATTRIBUTE_END.to_le_bytes());
+
+        // update sequence number 1, the original values are zeros
+        record[48..50].copy_from_slice(&1u16.to_le_bytes());
+        record[510..512].copy_from_slice(&1u16.to_le_bytes());
+        record[1022..1024].copy_from_slice(&1u16.to_le_bytes());
+        record
+    }
+
+    fn volume(img: &[u8]) -> Vec<u8> {
+        let records = 28;
+        let mft_clusters = (records * RECORD).div_ceil(CLUSTER);
+        let mut volume = vec![0u8; (DATA_LCN + img.len().div_ceil(CLUSTER)) * CLUSTER];
+        volume[3..11].copy_from_slice(b"NTFS    ");
+        volume[11..13].copy_from_slice(&512u16.to_le_bytes());
+        volume[13] = 8;
+        volume[48..56].copy_from_slice(&(MFT_LCN as u64).to_le_bytes());
+        volume[64] = -10i8 as u8;
+
+        let in_use = RECORD_IN_USE;
+        let directory = RECORD_IN_USE | RECORD_DIRECTORY;
+        let mut mft = vec![vec![0u8; RECORD]; records];
+        mft[0] = record(
+            in_use,
+            &[
+                file_name(RECORD_ROOT, "$MFT"),
+                non_resident(MFT_LCN as u8, mft_clusters as u8, (records * RECORD) as u64),
+            ],
+        );
+        mft[5] = record(directory, &[file_name(RECORD_ROOT, ".")]);
+        mft[24] = record(directory, &[file_name(RECORD_ROOT, "DCIM")]);
+        mft[25] = record(
+            in_use,
+            &[
+                standard_information(1704164646),
+                file_name(24, "horse.jpg"),
+                non_resident(
+                    DATA_LCN as u8,
+                    img.len().div_ceil(CLUSTER) as u8,
+                    img.len() as u64,
+                ),
+            ],
+        );
+        mft[26] = record(
+            in_use,
+            &[
+                file_name(RECORD_ROOT, "notes.txt"),
+                resident(ATTRIBUTE_DATA, b"notes"),
+            ],
+        );
+        // deleted file
+        mft[27] = record(
+            0,
+            &[
+                file_name(RECORD_ROOT, "deleted.jpg"),
+                resident(ATTRIBUTE_DATA, b"deleted"),
+            ],
+        );
+
+        let offset = MFT_LCN * CLUSTER;
+        volume[offset..offset + records * RECORD].copy_from_slice(&mft.concat());
+        volume[DATA_LCN * CLUSTER..DATA_LCN * CLUSTER + img.len()].copy_from_slice(img);
+        volume
+    }
+
+    #[test]
+    fn test_read_files() {
+        let img = std::fs::read("../data/img/horse.jpg").unwrap();
+        let volume = volume(&img);
+        let size = volume.len() as u64;
+        let mut disk: Box<dyn ReadSeek> = Box::new(Cursor::new(volume));
+        let mut fs = Ntfs::open(Slice::new(&mut *disk, 0, size)).unwrap();
+
+        let files = fs.files().expect("Failed to list files.");
+
+        // Assert
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, vec!["DCIM/horse.jpg", "notes.txt"]);
+        assert_eq!(files[0].last_modified, 1704164646);
+        assert_eq!(fs.read(&files[0], u64::MAX).unwrap(), img);
+        assert_eq!(fs.read(&files[1], 3).unwrap(), b"not");
+    }
+
+    #[test]
+    fn test_runs() {
+        let mut attribute = vec![0u8; 64];
+        attribute[32..34].copy_from_slice(&64u16.to_le_bytes());
+        // 4 clusters at 0x1000, 2 sparse clusters, 1 cluster 16 clusters before
+        attribute.extend([0x21, 4, 0x00, 0x10, 0x01, 2, 0x11, 1, 0xF0, 0]);
+
+        let runs = runs(&attribute).unwrap();
+
+        // Assert
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].lcn, Some(0x1000));
+        assert_eq!(runs[1].lcn, None);
+        assert_eq!(runs[2].vcn, 6);
+        assert_eq!(runs[2].lcn, Some(0x1000 - 16));
+    }
+}
@@ -0,0 +1,201 @@
+use std::io;
+
+use super::{read_at, u32_at, u64_at, ReadSeek, SECTOR_SIZE};
+
+const GPT_PROTECTIVE: u8 = 0xEE;
+const EXTENDED: [u8; 3] = [0x05, 0x0F, 0x85];
+// Guard against loops in corrupted chains of extended partitions.
+const MAX_LOGICAL_PARTITIONS: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Partition {
+    /// Number of the partition, the logical ones of an MBR start at 5.
+    pub index: usize,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Partitions of the MBR or GPT partition table, empty when there is none.
+pub(super) fn read_partitions(disk: &mut dyn ReadSeek, size: u64) -> io::Result<Vec<Partition>> {
+    if size < 2 * SECTOR_SIZE {
+        return Ok(Vec::new());
+    }
+    let mbr = read_at(disk, 0, SECTOR_SIZE as usize)?;
+    if mbr[510..512] != [0x55, 0xAA] {
+        return Ok(Vec::new());
+    }
+
+    let entries = mbr_entries(&mbr);
+    if entries
+        .iter()
+        .any(|(partition_type, _, _)| *partition_type == GPT_PROTECTIVE)
+    {
+        return read_gpt(disk);
+    }
+
+    let mut partitions = Vec::new();
+    for (index, (partition_type, start, sectors)) in entries.into_iter().enumerate() {
+        if partition_type == 0 || sectors == 0 {
+            continue;
+        }
+        if EXTENDED.contains(&partition_type) {
+            partitions.extend(read_logical(disk, start, size)?);
+        } else {
+            partitions.push(Partition {
+                index: index + 1,
+                offset: start * SECTOR_SIZE,
+                size: sectors * SECTOR_SIZE,
+            });
+        }
+    }
+    Ok(partitions)
+}
+
+// Type, first sector and number of sectors of the four entries of the MBR.
+fn mbr_entries(sector: &[u8]) -> Vec<(u8, u64, u64)> {
+    (0..4)
+        .map(|index| {
+            let entry = &sector[446 + index * 16..446 + (index + 1) * 16];
+            (entry[4], u32_at(entry, 8) as u64, u32_at(entry, 12) as u64)
+        })
+        .collect()
+}
+
+// Logical partitions, each one described by an EBR of the chain that starts
+// at the extended partition.
+fn read_logical(disk: &mut dyn ReadSeek, extended: u64, size: u64) -> io::Result<Vec<Partition>> {
+    let mut partitions = Vec::new();
+    let mut ebr = extended;
+    while partitions.len() < MAX_LOGICAL_PARTITIONS && (ebr + 1) * SECTOR_SIZE <= size {
+        let sector = read_at(disk, ebr * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+        if sector[510..512] != [0x55, 0xAA] {
+            break;
+        }
+        let entries = mbr_entries(&sector);
+        let (partition_type, start, sectors) = entries[0];
+        if partition_type != 0 && sectors > 0 {
+            partitions.push(Partition {
+                index: 5 + partitions.len(),
+                offset: (ebr + start) * SECTOR_SIZE,
+                size: sectors * SECTOR_SIZE,
+            });
+        }
+
+        let (next_type, next, _) = entries[1];
+        if !EXTENDED.contains(&next_type) || next == 0 {
+            break;
+        }
+        ebr = extended + next;
+    }
+    Ok(partitions)
+}
+
+fn read_gpt(disk: &mut dyn ReadSeek) -> io::Result<Vec<Partition>> {
+    let header = read_at(disk, SECTOR_SIZE, SECTOR_SIZE as usize)?;
+    if &header[..8] != b"EFI PART" {
+        return Ok(Vec::new());
+    }
+    let entries_lba = u64_at(&header, 72);
+    let count = u32_at(&header, 80).min(1024) as usize;
+    let entry_size = u32_at(&header, 84) as usize;
+    if entry_size < 128 {
+        return Ok(Vec::new());
+    }
+
+    let entries = read_at(disk, entries_lba * SECTOR_SIZE, count * entry_size)?;
+    let partitions = entries
+        .chunks(entry_size)
+        .enumerate()
+        .filter(|(_, entry)| entry[..16].iter().any(|b| *b != 0))
+        .map(|(index, entry)| {
+            let first = u64_at(entry, 32);
+            let last = u64_at(entry, 40);
+            Partition {
+                index: index + 1,
+                offset: first * SECTOR_SIZE,
+                size: (last.saturating_sub(first) + 1) * SECTOR_SIZE,
+            }
+        })
+        .collect();
+    Ok(partitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn set_entry(sector: &mut [u8], index: usize, partition_type: u8, start: u32, sectors: u32) {
+        let entry = &mut sector[446 + index * 16..446 + (index + 1) * 16];
+        entry[4] = partition_type;
+        entry[8..12].copy_from_slice(&start.to_le_bytes());
+        entry[12..16].copy_from_slice(&sectors.to_le_bytes());
+        sector[510] = 0x55;
+        sector[511] = 0xAA;
+    }
+
+    #[test]
+    fn test_read_extended_partitions() {
+        let mut disk = vec![0u8; 64 * SECTOR_SIZE as usize];
+        set_entry(&mut disk[..512], 0, 0x07, 2, 8);
+        set_entry(&mut disk[..512], 1, 0x05, 16, 48);
+        // first logical partition and link to the next EBR
+        set_entry(&mut disk[16 * 512..17 * 512], 0, 0x0B, 1, 8);
+        set_entry(&mut disk[16 * 512..17 * 512], 1, 0x05, 16, 16);
+        set_entry(&mut disk[32 * 512..33 * 512], 0, 0x83, 1, 8);
+        let size = disk.len() as u64;
+
+        let partitions = read_partitions(&mut Cursor::new(disk), size).unwrap();
+
+        // Assert
+        assert_eq!(
+            partitions,
+            vec![
+                Partition {
+                    index: 1,
+                    offset: 2 * 512,
+                    size: 8 * 512
+                },
+                Partition {
+                    index: 5,
+                    offset: 17 * 512,
+                    size: 8 * 512
+                },
+                Partition {
+                    index: 6,
+                    offset: 33 * 512,
+                    size: 8 * 512
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_gpt_partitions() {
+        let mut disk = vec![0u8; 64 * SECTOR_SIZE as usize];
+        set_entry(&mut disk[..512], 0, GPT_PROTECTIVE, 1, 63);
+        disk[512..520].copy_from_slice(b"EFI PART");
+        disk[512 + 72..512 + 80].copy_from_slice(&2u64.to_le_bytes());
+        disk[512 + 80..512 + 84].copy_from_slice(&4u32.to_le_bytes());
+        disk[512 + 84..512 + 88].copy_from_slice(&128u32.to_le_bytes());
+        // second entry used, the first one is empty
+        let entry = &mut disk[1024 + 128..1024 + 256];
+        entry[..16].copy_from_slice(&[0xAF; 16]);
+        entry[32..40].copy_from_slice(&34u64.to_le_bytes());
+        entry[40..48].copy_from_slice(&63u64.to_le_bytes());
+        let size = disk.len() as u64;
+
+        let partitions = read_partitions(&mut Cursor::new(disk), size).unwrap();
+
+        // Assert
+        assert_eq!(
+            partitions,
+            vec![Partition {
+                index: 2,
+                offset: 34 * 512,
+                size: 30 * 512
+            }]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::csam::HashAlgorithm;
+
+// Size of the chunks read from the file.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Digests of a content in hexadecimal, empty for the algorithms not computed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Hashes {
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+}
+
+impl Hashes {
+    pub fn get(&self, algorithm: HashAlgorithm) -> &str {
+        match algorithm {
+            HashAlgorithm::MD5 => &self.md5,
+            HashAlgorithm::SHA1 => &self.sha1,
+            HashAlgorithm::SHA256 => &self.sha256,
+        }
+    }
+}
+
+/// Computes the digests of the file in a single pass, without loading it into memory.
+pub fn hash_file<P>(path: P, algorithms: &[HashAlgorithm]) -> io::Result<Hashes>
+where
+    P: AsRef<Path>,
+{
+    hash_reader(File::open(path)?, algorithms)
+}
+
+/// Computes the digests of the content in a single pass over chunks of the reader.
+pub fn hash_reader<R: Read>(mut reader: R, algorithms: &[HashAlgorithm]) -> io::Result<Hashes> {
+    let mut md5 = algorithms
+        .contains(&HashAlgorithm::MD5)
+        .then(md5::Context::new);
+    let mut sha1 = algorithms.contains(&HashAlgorithm::SHA1).then(Sha1::new);
+    let mut sha256 = algorithms
+        .contains(&HashAlgorithm::SHA256)
+        .then(Sha256::new);
+
+    let mut buf = vec![0u8; BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        let chunk = &buf[..n];
+        if let Some(ref mut md5) = md5 {
+            md5.consume(chunk);
+        }
+        if let Some(ref mut sha1) = sha1 {
+            sha1.update(chunk);
+        }
+        if let Some(ref mut sha256) = sha256 {
+            sha256.update(chunk);
+        }
+    }
+
+    Ok(Hashes {
+        md5: md5
+            .map(|md5| format!("{:x}", md5.compute()))
+            .unwrap_or_default(),
+        sha1: sha1
+            .map(|sha1| hex::encode(sha1.finalize()))
+            .unwrap_or_default(),
+        sha256: sha256
+            .map(|sha256| hex::encode(sha256.finalize()))
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file_should_compute_all_digests() {
+        let path = Path::new("../data/img/horse.jpg");
+        let hashes = hash_file(path, &HashAlgorithm::ALL).expect("Failed to get hashes of file.");
+
+        // Assert
+        assert_eq!(hashes.md5, "506bf7f41ca0c6f9e7612c04e93ab235");
+        assert_eq!(hashes.sha1, "b7b6e21916253608c9ff081db046a58100536963");
+        assert_eq!(
+            hashes.sha256,
+            "9b7bc4bef1e301d3b6d4be5a9af84cce7960b518b78dd39d6da60df5c1c6f943"
+        );
+    }
+
+    #[test]
+    fn test_hash_reader_should_compute_only_requested_digests() {
+        // larger than the buffer, so the digests are updated in several chunks
+        let data = vec![7u8; BUFFER_SIZE * 3 + 11];
+        let hashes = hash_reader(data.as_slice(), &[HashAlgorithm::SHA1])
+            .expect("Failed to get hashes of reader.");
+
+        // Assert
+        assert_eq!(hashes.md5, "");
+        assert_eq!(hashes.sha1, hex::encode(Sha1::digest(&data)));
+        assert_eq!(hashes.sha256, "");
+    }
+}
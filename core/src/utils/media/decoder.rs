@@ -1,32 +1,87 @@
 use std::{collections::VecDeque, path::Path};
 
 use anyhow::Context;
-use bytes::Bytes;
-use ffmpeg::{format, media::Type, software::scaling, util::frame};
+use ffmpeg::{
+    ffi::{AV_NOPTS_VALUE, AV_TIME_BASE},
+    format,
+    media::Type,
+    software::scaling,
+    util::frame,
+};
 use ffmpeg_next as ffmpeg;
 use image::{imageops, DynamicImage, GenericImage, ImageBuffer, Rgba};
 
+use super::Frames;
+use crate::csam::{SamplingMode, VideoSampling};
+use crate::utils::pdq;
+
 const FRAME_DIMENSION: u32 = 160;
 
+// Side of the grayscale thumbnails compared to find scene changes.
+const SCENE_DIMENSION: u32 = 32;
+// Mean difference of the pixels, out of 255, from the last frame kept that
+// starts a new scene.
+const SCENE_THRESHOLD: f64 = 30.0;
+
+// Frames kept from the video, only in the sizes they are hashed at, so that
+// the decoded frames are not held for the whole video.
 #[derive(Debug, Default)]
 struct VideoDump {
-    width: u32,
-    height: u32,
-    frames: Vec<Bytes>,
+    /// Frames resized to `FRAME_DIMENSION`.
+    frames: Vec<DynamicImage>,
+    /// Frames downsampled for their PDQ hash, only when asked for.
+    pdq_frames: Vec<DynamicImage>,
+    /// Time of every frame in seconds.
+    times: Vec<f64>,
 }
 
-pub fn make_thumbnail_to_vec<P>(media_path: P) -> anyhow::Result<(Vec<DynamicImage>, Vec<u8>)>
+impl VideoDump {
+    // Keeps only the frames at `positions`.
+    fn retain(&mut self, positions: &[usize]) {
+        let pick = |frames: &[DynamicImage]| -> Vec<DynamicImage> {
+            if frames.is_empty() {
+                Vec::new()
+            } else {
+                positions.iter().map(|idx| frames[*idx].clone()).collect()
+            }
+        };
+        self.frames = pick(&self.frames);
+        self.pdq_frames = pick(&self.pdq_frames);
+        self.times = positions.iter().map(|idx| self.times[*idx]).collect();
+    }
+}
+
+/// Returns the frames sampled from the video, their time in seconds and the
+/// contact sheet made of at most `max_frames` of them, and the frames for their
+/// PDQ hash when `pdq`.
+pub fn make_thumbnail_to_vec<P>(
+    media_path: P,
+    sampling: VideoSampling,
+    pdq: bool,
+) -> anyhow::Result<Frames>
 where
     P: AsRef<Path>,
 {
-    let dump = dump_video_frames(media_path)?;
-    let frames = convert_frames_to_image(&dump)?;
-    let buf = concat_frames(&frames)?;
-
-    Ok((frames, buf))
+    let dump = dump_video_frames(media_path, sampling, pdq)?;
+    let sheet: Vec<DynamicImage> = evenly_spaced(dump.frames.len(), sampling.max_frames.max(1))
+        .into_iter()
+        .map(|idx| dump.frames[idx].clone())
+        .collect();
+    let thumbnail = concat_frames(&sheet)?;
+
+    Ok(Frames {
+        images: dump.frames,
+        pdq_images: dump.pdq_frames,
+        times: dump.times,
+        thumbnail,
+    })
 }
 
-fn dump_video_frames<P: AsRef<Path>>(video_path: P) -> anyhow::Result<VideoDump> {
+fn dump_video_frames<P: AsRef<Path>>(
+    video_path: P,
+    sampling: VideoSampling,
+    pdq: bool,
+) -> anyhow::Result<VideoDump> {
     ffmpeg::init()?;
 
     let options = ffmpeg::Dictionary::new();
@@ -36,71 +91,306 @@ fn dump_video_frames<P: AsRef<Path>>(video_path: P) -> anyhow::Result<VideoDump>
     // let video_path = video_path.as_os_str().to_str().unwrap();
     // format::context::input::dump(&input_format_context, 0, Some(video_path));
 
-    let (video_stream_index, frame_rate, mut decoder) = {
+    let mut reader = FrameReader::new(&input_format_context, pdq)?;
+    let max_frames = sampling.max_frames.max(1);
+    // zero when the container does not know it
+    let duration = input_format_context.duration().max(0) as f64 / AV_TIME_BASE as f64;
+
+    match sampling.mode {
+        SamplingMode::Evenly if duration > 0.0 => {
+            for idx in 0..max_frames {
+                let target = duration * (idx as f64 + 0.5) / max_frames as f64;
+                let position = ((target + reader.start_time) * AV_TIME_BASE as f64) as i64;
+                // seeks to the keyframe before the target and decodes up to it
+                input_format_context.seek(position, ..position)?;
+                reader.decoder.flush();
+                let tolerance = 0.5 / reader.frame_rate.max(1) as f64;
+                reader.decode(&mut input_format_context, false, |reader, decoded, time| {
+                    let after_last = reader.dump.times.last().is_none_or(|last| time > *last);
+                    if time + tolerance < target || !after_last {
+                        return Ok(true);
+                    }
+                    reader.keep(decoded, time)?;
+                    Ok(false)
+                })?;
+            }
+        }
+        SamplingMode::Keyframes => {
+            // keyframes closer than this to the last one kept are skipped
+            let spacing = duration / max_frames as f64;
+            reader.decode(&mut input_format_context, true, |reader, decoded, time| {
+                let spaced = reader
+                    .dump
+                    .times
+                    .last()
+                    .is_none_or(|last| time - last >= spacing);
+                if spaced {
+                    reader.keep(decoded, time)?;
+                }
+                Ok(reader.dump.frames.len() < max_frames)
+            })?;
+        }
+        SamplingMode::SceneChange => {
+            let mut gray_context = scaling::Context::get(
+                reader.decoder.format(),
+                reader.decoder.width(),
+                reader.decoder.height(),
+                format::Pixel::GRAY8,
+                SCENE_DIMENSION,
+                SCENE_DIMENSION,
+                scaling::Flags::AREA,
+            )
+            .with_context(|| "invalid swscontext parameter")?;
+            let mut last_scene: Option<Vec<u8>> = None;
+            let mut sampler = SceneSampler::new(max_frames);
+            reader.decode(&mut input_format_context, false, |reader, decoded, time| {
+                let mut gray_frame = frame::Video::empty();
+                gray_context.run(decoded, &mut gray_frame)?;
+                let stride = gray_frame.stride(0);
+                let scene: Vec<u8> = gray_frame
+                    .data(0)
+                    .chunks(stride)
+                    .take(SCENE_DIMENSION as usize)
+                    .flat_map(|row| row[..SCENE_DIMENSION as usize].iter().copied())
+                    .collect();
+                let changed = last_scene
+                    .as_ref()
+                    .is_none_or(|last| scene_difference(last, &scene) > SCENE_THRESHOLD);
+                if changed {
+                    if sampler.keeps_next() {
+                        reader.keep(decoded, time)?;
+                        sampler.thin(&mut reader.dump);
+                    }
+                    last_scene = Some(scene);
+                }
+                Ok(true)
+            })?;
+            let positions = evenly_spaced(reader.dump.frames.len(), max_frames);
+            reader.dump.retain(&positions);
+        }
+        // also the evenly spaced frames when the duration is unknown; not capped,
+        // as the frames of the video database are one a second too
+        _ => {
+            let mut sampler = IntervalSampler::default();
+            reader.decode(&mut input_format_context, false, |reader, decoded, time| {
+                if sampler.keeps(time) {
+                    reader.keep(decoded, time)?;
+                }
+                Ok(true)
+            })?;
+        }
+    }
+
+    Ok(reader.dump)
+}
+
+// Frames kept by the interval sampling: the first one of every second of the
+// video, by the time of the frames, as the average frame rate is unknown on
+// some streams and meaningless on variable frame rate ones.
+#[derive(Debug, Default)]
+struct IntervalSampler {
+    last_second: Option<i64>,
+}
+
+impl IntervalSampler {
+    // Whether the frame at `time` is kept.
+    fn keeps(&mut self, time: f64) -> bool {
+        // the margin absorbs the rounding of the timestamps to the time base
+        let second = (time + 1e-6).floor() as i64;
+        let keep = self.last_second.is_none_or(|last| second > last);
+        if keep {
+            self.last_second = Some(second);
+        }
+        keep
+    }
+}
+
+// Positions of at most `count` items evenly spread over `len` items.
+fn evenly_spaced(len: usize, count: usize) -> Vec<usize> {
+    if len <= count {
+        return (0..len).collect();
+    }
+    (0..count)
+        .map(|idx| (2 * idx + 1) * len / (2 * count))
+        .collect()
+}
+
+// Scenes kept while decoding: every `stride`-th one, the stride doubling each
+// time the frames reach twice the cap, so that the scenes kept are spread over
+// the whole video before being thinned to the cap.
+#[derive(Debug)]
+struct SceneSampler {
+    max_frames: usize,
+    scenes: usize,
+    stride: usize,
+}
+
+impl SceneSampler {
+    fn new(max_frames: usize) -> Self {
+        Self {
+            max_frames,
+            scenes: 0,
+            stride: 1,
+        }
+    }
+
+    // Whether the next scene is kept.
+    fn keeps_next(&mut self) -> bool {
+        let keep = self.scenes.is_multiple_of(self.stride);
+        self.scenes += 1;
+        keep
+    }
+
+    // Drops every other frame of `dump` once it has twice the cap.
+    fn thin(&mut self, dump: &mut VideoDump) {
+        if dump.frames.len() >= 2 * self.max_frames {
+            let positions: Vec<usize> = (0..dump.frames.len()).step_by(2).collect();
+            dump.retain(&positions);
+            self.stride *= 2;
+        }
+    }
+}
+
+// Mean absolute difference of the pixels of two grayscale thumbnails.
+fn scene_difference(left: &[u8], right: &[u8]) -> f64 {
+    let total: u64 = left
+        .iter()
+        .zip(right.iter())
+        .map(|(l, r)| u64::from(l.abs_diff(*r)))
+        .sum();
+    total as f64 / left.len().max(1) as f64
+}
+
+// Decoder of the best video stream, keeping the frames chosen by the sampling.
+struct FrameReader {
+    stream_index: usize,
+    frame_rate: i32,
+    time_base: f64,
+    /// Time in seconds of the first frame of the stream.
+    start_time: f64,
+    decoder: ffmpeg::decoder::Video,
+    sws_context: scaling::Context,
+    width: u32,
+    height: u32,
+    /// Whether the frames kept are also downsampled for their PDQ hash.
+    pdq: bool,
+    decoded_frames: usize,
+    dump: VideoDump,
+}
+
+impl FrameReader {
+    fn new(input_format_context: &format::context::Input, pdq: bool) -> anyhow::Result<Self> {
         let stream = input_format_context
             .streams()
             .best(Type::Video)
             .ok_or(ffmpeg::Error::StreamNotFound)?;
 
         let frame_rate = f64::from(stream.avg_frame_rate()).round() as i32;
-        let stream_index = stream.index();
+        let time_base = f64::from(stream.time_base());
+        let start_time = match stream.start_time() {
+            AV_NOPTS_VALUE => 0.0,
+            start_time => start_time as f64 * time_base,
+        };
         let decode_context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
         let decoder = decode_context.decoder().video()?;
 
-        (stream_index, frame_rate, decoder)
-    };
-
-    let mut video_dump = VideoDump {
-        width: decoder.width(),
-        height: decoder.height(),
-        ..Default::default()
-    };
-
-    let mut sws_context = scaling::Context::get(
-        decoder.format(),
-        decoder.width(),
-        decoder.height(),
-        format::Pixel::RGBA,
-        decoder.width(),
-        decoder.height(),
-        scaling::Flags::BILINEAR,
-    )
-    .with_context(|| "invalid swscontext parameter")?;
-
-    let mut processed_frames = 0;
-
-    let mut receive_and_process_frames =
-        |decoder: &mut ffmpeg::decoder::Video| -> anyhow::Result<(), ffmpeg::Error> {
-            let mut decoded = frame::Video::empty();
-
-            while decoder.receive_frame(&mut decoded).is_ok() {
-                if processed_frames == 0 || processed_frames == frame_rate {
-                    let mut rgb_frame = frame::Video::empty();
-                    sws_context.run(&decoded, &mut rgb_frame)?;
-
-                    let data = rgb_frame.data(0).to_owned();
-                    video_dump.frames.push(Bytes::from(data));
-
-                    processed_frames = 0;
+        let sws_context = scaling::Context::get(
+            decoder.format(),
+            decoder.width(),
+            decoder.height(),
+            format::Pixel::RGBA,
+            decoder.width(),
+            decoder.height(),
+            scaling::Flags::BILINEAR,
+        )
+        .with_context(|| "invalid swscontext parameter")?;
+
+        Ok(Self {
+            stream_index: stream.index(),
+            frame_rate,
+            time_base,
+            start_time,
+            width: decoder.width(),
+            height: decoder.height(),
+            decoder,
+            sws_context,
+            pdq,
+            decoded_frames: 0,
+            dump: VideoDump::default(),
+        })
+    }
+
+    // Decodes the stream from the current position, only its keyframes when
+    // `keyframes_only`, handing every frame and its time to `visit` until it
+    // returns false.
+    fn decode<F>(
+        &mut self,
+        input_format_context: &mut format::context::Input,
+        keyframes_only: bool,
+        mut visit: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(&mut Self, &frame::Video, f64) -> anyhow::Result<bool>,
+    {
+        let mut decoded = frame::Video::empty();
+
+        for (stream, packet) in input_format_context.packets() {
+            if stream.index() != self.stream_index || (keyframes_only && !packet.is_key()) {
+                continue;
+            }
+            self.decoder.send_packet(&packet)?;
+            while self.decoder.receive_frame(&mut decoded).is_ok() {
+                let time = self.time(&decoded);
+                if !visit(self, &decoded, time)? {
+                    return Ok(());
                 }
+            }
+        }
 
-                processed_frames += 1;
+        self.decoder.send_eof()?;
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let time = self.time(&decoded);
+            if !visit(self, &decoded, time)? {
+                break;
             }
+        }
 
-            Ok(())
-        };
+        Ok(())
+    }
 
-    for (stream, packet) in input_format_context.packets() {
-        if stream.index() == video_stream_index {
-            decoder.send_packet(&packet)?;
-            receive_and_process_frames(&mut decoder)?;
+    // Time of the frame from the start of the stream, or its position when the
+    // stream has no timestamps.
+    fn time(&mut self, decoded: &frame::Video) -> f64 {
+        self.decoded_frames += 1;
+        match decoded.timestamp() {
+            Some(timestamp) => timestamp as f64 * self.time_base - self.start_time,
+            None => (self.decoded_frames - 1) as f64 / self.frame_rate.max(1) as f64,
         }
     }
 
-    decoder.send_eof()?;
-    receive_and_process_frames(&mut decoder)?;
+    fn keep(&mut self, decoded: &frame::Video, time: f64) -> anyhow::Result<()> {
+        let mut rgb_frame = frame::Video::empty();
+        self.sws_context.run(decoded, &mut rgb_frame)?;
+
+        let img_buf = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(
+            self.width,
+            self.height,
+            rgb_frame.data(0).to_owned(),
+        )
+        .ok_or(anyhow::Error::msg("could not to create image buffer"))?;
+        let img = DynamicImage::ImageRgba8(img_buf);
+        self.dump.frames.push(img.resize(
+            FRAME_DIMENSION,
+            FRAME_DIMENSION,
+            imageops::FilterType::Lanczos3,
+        ));
+        if self.pdq {
+            self.dump.pdq_frames.push(pdq::downsample(img));
+        }
+        self.dump.times.push(time);
 
-    Ok(video_dump)
+        Ok(())
+    }
 }
 
 fn concat_frames(frames: &[DynamicImage]) -> anyhow::Result<Vec<u8>> {
@@ -145,28 +435,6 @@ fn concat_frames(frames: &[DynamicImage]) -> anyhow::Result<Vec<u8>> {
     Ok(buf)
 }
 
-fn convert_frames_to_image(dump: &VideoDump) -> anyhow::Result<Vec<DynamicImage>> {
-    let width = dump.width;
-    let height = dump.height;
-    let frames = dump
-        .frames
-        .iter()
-        .map(|frame| {
-            let img_buf = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, frame.to_vec())
-                .ok_or(anyhow::Error::msg("could not to create image buffer"))?;
-            let img = DynamicImage::ImageRgba8(img_buf);
-            let img = img.resize(
-                FRAME_DIMENSION,
-                FRAME_DIMENSION,
-                imageops::FilterType::Lanczos3,
-            );
-            Ok(img)
-        })
-        .collect::<anyhow::Result<Vec<DynamicImage>>>();
-
-    frames
-}
-
 #[inline]
 fn get_cols_and_rows(nframes: usize) -> (usize, usize) {
     if nframes >= 100 {
@@ -216,6 +484,9 @@ fn distribute_frames(nframes: usize, nparts: usize) -> anyhow::Result<VecDeque<u
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::csam::repository::{InMemoryRepository, Repository};
+    use crate::csam::Media;
+    use crate::utils::video_fingerprint::VideoFingerprint;
 
     #[test]
     fn test_should_distribute_frames_in_balanced_parts() {
@@ -232,21 +503,157 @@ mod tests {
     #[test]
     fn test_should_dump_video_frames() {
         let filename = "../data/video/vid.mp4";
-        let video_dump = dump_video_frames(filename).expect("Failed to dump frames.");
+        let video_dump = dump_video_frames(filename, VideoSampling::default(), false)
+            .expect("Failed to dump frames.");
 
         // Assert
         assert_eq!(video_dump.frames.len(), 14);
+        assert!(video_dump.pdq_frames.is_empty());
+        assert!(video_dump.frames[0].width() <= FRAME_DIMENSION);
+        assert_eq!(video_dump.times.len(), 14);
+        assert!(video_dump.times.windows(2).all(|times| times[0] < times[1]));
+    }
+
+    #[test]
+    fn test_should_dump_video_frames_with_every_sampling() {
+        let filename = "../data/video/vid.mp4";
+        let sampling = |mode| VideoSampling {
+            mode,
+            max_frames: 10,
+        };
+        let evenly = dump_video_frames(filename, sampling(SamplingMode::Evenly), false).unwrap();
+        let keyframes =
+            dump_video_frames(filename, sampling(SamplingMode::Keyframes), false).unwrap();
+        let scenes =
+            dump_video_frames(filename, sampling(SamplingMode::SceneChange), false).unwrap();
+        let interval =
+            dump_video_frames(filename, sampling(SamplingMode::Interval), false).unwrap();
+
+        // Assert
+        assert_eq!(evenly.frames.len(), 10);
+        assert_eq!(interval.frames.len(), 14);
+        for dump in [&evenly, &keyframes, &scenes] {
+            assert!(!dump.frames.is_empty() && dump.frames.len() <= 10);
+        }
+        for dump in [&evenly, &keyframes, &scenes, &interval] {
+            assert_eq!(dump.frames.len(), dump.times.len());
+            assert!(dump.times.windows(2).all(|times| times[0] < times[1]));
+        }
+    }
+
+    #[test]
+    fn test_evenly_spaced_should_spread_over_the_items() {
+        // Assert
+        assert_eq!(evenly_spaced(10, 5), vec![1, 3, 5, 7, 9]);
+        assert_eq!(evenly_spaced(3600, 100).len(), 100);
+        assert_eq!(evenly_spaced(3600, 100)[99], 3582);
+        assert_eq!(evenly_spaced(3, 5), vec![0, 1, 2]);
+        assert!(evenly_spaced(0, 5).is_empty());
+    }
+
+    #[test]
+    fn test_interval_sampling_should_find_clip_in_long_video() {
+        let sampling = VideoSampling::default();
+        let frame_rate = 25;
+        let scene = |second: u64| (second + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        // a known clip of 10 seconds, 120 seconds into a video of 150 seconds
+        let second_of = |idx: usize| (idx / frame_rate as usize) as u64;
+        let clip_frame = |idx: usize| scene(1_000 + second_of(idx));
+        let video_frame = |idx: usize| match second_of(idx) {
+            second @ 120..=129 => scene(1_000 + second - 120),
+            second => scene(second),
+        };
+        let sample = |frames: usize, frame: &dyn Fn(usize) -> u64| {
+            let mut sampler = IntervalSampler::default();
+            let kept: Vec<usize> = (0..frames)
+                .filter(|idx| sampler.keeps(*idx as f64 / frame_rate as f64))
+                .collect();
+            let times: Vec<f64> = kept
+                .iter()
+                .map(|idx| *idx as f64 / frame_rate as f64)
+                .collect();
+            let phashes: Vec<u64> = kept.iter().map(|idx| frame(*idx)).collect();
+            VideoFingerprint::new(&times, &phashes)
+        };
+        let repo = InMemoryRepository::new();
+        repo.add_video(sample(10 * frame_rate as usize, &clip_frame))
+            .unwrap();
+        let video = sample(150 * frame_rate as usize, &video_frame);
+        let (_, alignment) = repo
+            .match_video(&video, Media::VIDEO_FRAME_DISTANCE, Media::VIDEO_MIN_FRAMES)
+            .unwrap();
+
+        // Assert
+        assert_eq!(sampling.mode, SamplingMode::Interval);
+        assert_eq!(video.len(), 150);
+        assert_eq!(evenly_spaced(video.len(), sampling.max_frames).len(), 100);
+        assert_eq!((alignment.start, alignment.end), (120.0, 129.0));
+        assert_eq!(alignment.frames, 10);
+    }
+
+    #[test]
+    fn test_interval_sampler_should_follow_the_time_of_the_frames() {
+        let mut sampler = IntervalSampler::default();
+        // a variable frame rate stream
+        let times = [0.0, 0.4, 0.9, 1.0, 1.7, 2.5, 2.6, 4.2, 4.9];
+        let kept: Vec<f64> = times
+            .into_iter()
+            .filter(|time| sampler.keeps(*time))
+            .collect();
+        let mut sampler = IntervalSampler::default();
+        // 30000/1001 fps
+        let ntsc = (0..300)
+            .filter(|idx| sampler.keeps(*idx as f64 * 1001.0 / 30000.0))
+            .count();
+
+        // Assert
+        assert_eq!(kept, vec![0.0, 1.0, 2.5, 4.2]);
+        assert_eq!(ntsc, 10);
+    }
+
+    #[test]
+    fn test_scene_sampler_should_spread_scenes_over_the_video() {
+        let mut sampler = SceneSampler::new(10);
+        let mut dump = VideoDump::default();
+        for scene in 0..1_000 {
+            if sampler.keeps_next() {
+                dump.frames.push(DynamicImage::new_rgb8(1, 1));
+                dump.times.push(f64::from(scene));
+                sampler.thin(&mut dump);
+            }
+        }
+        let kept = dump.frames.len();
+        dump.retain(&evenly_spaced(kept, 10));
+
+        // Assert
+        assert!(kept < 20);
+        assert_eq!(dump.frames.len(), 10);
+        assert_eq!(dump.times[0], 0.0);
+        assert!(dump.times[9] >= 900.0);
+        assert!(dump.times.windows(2).all(|times| times[0] < times[1]));
+    }
+
+    #[test]
+    fn test_scene_difference_should_be_mean_pixel_difference() {
+        // Assert
+        assert_eq!(scene_difference(&[0, 0, 0, 0], &[0, 0, 0, 0]), 0.0);
+        assert_eq!(
+            scene_difference(&[0, 255, 10, 20], &[255, 0, 20, 10]),
+            132.5
+        );
     }
 
     #[test]
     fn test_should_concat_video_frames() {
         let filename = "../data/video/vid.mp4";
-        let video_dump = dump_video_frames(filename).expect("Failed to dump frames.");
-        let video_frames =
-            convert_frames_to_image(&video_dump).expect("Failed to convert frames to image.");
-        let img_buf = concat_frames(&video_frames).expect("Failed to concat frames.");
+        let video_dump = dump_video_frames(filename, VideoSampling::default(), true)
+            .expect("Failed to dump frames.");
+        let img_buf = concat_frames(&video_dump.frames).expect("Failed to concat frames.");
 
         // Assert
         assert_ne!(img_buf.len(), 0);
+        let pdq_frames = &video_dump.pdq_frames;
+        assert_eq!(pdq_frames.len(), video_dump.frames.len());
+        assert!(pdq_frames[0].width().max(pdq_frames[0].height()) <= pdq::MAX_DIMENSION);
     }
 }
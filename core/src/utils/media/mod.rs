@@ -3,12 +3,20 @@ pub mod decoder;
 use anyhow::Result;
 use image::DynamicImage;
 use sha1::{Digest, Sha1};
-use std::{fs, io::Cursor, path::Path};
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::Path;
 
-use super::phash;
+use super::{hasher, pdq, phash};
+use crate::csam::{FileType, HashAlgorithm, ImageTransform, PHashAlgorithm};
 
-const MEDIA_TYPE_IMAGES: &[&str] = &["jpeg", "jpg", "png", "bmp", "tiff", "gif"];
-const MEDIA_TYPE_VIDEOS: &[&str] = &["mpeg", "mpg", "mp4", "avi", "ogg", "webm", "flv"];
+const MEDIA_TYPE_IMAGES: &[&str] = &[
+    "jpeg", "jpg", "png", "bmp", "tiff", "tif", "gif", "webp", "heic", "heif",
+];
+const MEDIA_TYPE_VIDEOS: &[&str] = &[
+    "mpeg", "mpg", "mp4", "m4v", "mov", "3gp", "avi", "mkv", "ogg", "webm", "flv", "wmv", "mts",
+    "m2ts",
+];
 
 pub fn is_image(extension: &str) -> bool {
     MEDIA_TYPE_IMAGES.contains(&extension)
@@ -23,6 +31,28 @@ pub fn is_media(extension: &str) -> bool {
     is_image(extension) || is_video(extension)
 }
 
+/// Reads the beginning of the file, used to detect its type by the signature.
+pub fn read_header<P>(path: P) -> std::io::Result<Vec<u8>>
+where
+    P: AsRef<Path>,
+{
+    let mut header = Vec::with_capacity(FileType::HEADER_LEN);
+    File::open(path)?
+        .take(FileType::HEADER_LEN as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// Returns `true` when the file has a media extension or, otherwise, a media signature.
+pub fn is_media_file(path: &Path) -> bool {
+    match path.extension() {
+        Some(e) if is_media(&e.to_string_lossy().to_lowercase()) => true,
+        _ => read_header(path)
+            .map(|header| FileType::from_signature(&header).is_some())
+            .unwrap_or_default(),
+    }
+}
+
 #[allow(unused)]
 pub fn get_path_hash<P>(path: P) -> Option<String>
 where
@@ -40,13 +70,17 @@ pub fn get_sha1_hash_of_file<P>(path: P) -> Result<String>
 where
     P: AsRef<Path>,
 {
-    if let Some(p) = path.as_ref().to_str() {
-        let data = fs::read(p)?;
-        let hash = &hex::encode(Sha1::digest(&data));
-        return Ok(hash.to_owned());
-    }
+    let hashes = hasher::hash_file(path, &[HashAlgorithm::SHA1])?;
+    Ok(hashes.sha1)
+}
 
-    Ok(String::new())
+#[allow(unused)]
+pub fn get_sha256_hash_of_file<P>(path: P) -> Result<String>
+where
+    P: AsRef<Path>,
+{
+    let hashes = hasher::hash_file(path, &[HashAlgorithm::SHA256])?;
+    Ok(hashes.sha256)
 }
 
 #[allow(unused)]
@@ -54,31 +88,63 @@ pub fn get_md5_hash_of_file<P>(path: P) -> Result<String>
 where
     P: AsRef<Path>,
 {
-    if let Some(p) = path.as_ref().to_str() {
-        let data = fs::read(p)?;
-        let digest = md5::compute(data);
-        return Ok(format!("{:x}", digest));
-    }
-
-    Ok(String::new())
+    let hashes = hasher::hash_file(path, &[HashAlgorithm::MD5])?;
+    Ok(hashes.md5)
 }
 
-#[allow(unused)]
-pub fn get_perceptual_hash_of_file<P>(path: P) -> Result<u64>
+/// Perceptual hashes of the image file, one for each of the `algorithms`.
+pub fn get_perceptual_hashes_of_file<P>(path: P, algorithms: &[PHashAlgorithm]) -> Result<Vec<u64>>
 where
     P: AsRef<Path>,
 {
-    let mut hash: u64 = 0;
-    if let Some(p) = path.as_ref().to_str() {
-        let img = image::open(path)?;
-        hash = phash::perception_hash(img, phash::ColorType::Threshold)?;
+    let img = image::open(path)?;
+    get_perceptual_hashes_of_image(img, algorithms)
+}
+
+/// Perceptual hashes of an image that is only in memory, such as a file inside an archive.
+pub fn get_perceptual_hashes_of_memory(
+    data: &[u8],
+    algorithms: &[PHashAlgorithm],
+) -> Result<Vec<u64>> {
+    let img = image::load_from_memory(data)?;
+    get_perceptual_hashes_of_image(img, algorithms)
+}
+
+fn get_perceptual_hashes_of_image(
+    img: DynamicImage,
+    algorithms: &[PHashAlgorithm],
+) -> Result<Vec<u64>> {
+    algorithms
+        .iter()
+        .map(|algorithm| get_perceptual_hash_of_image(img.clone(), *algorithm))
+        .collect()
+}
+
+pub fn get_perceptual_hash_of_image(img: DynamicImage, algorithm: PHashAlgorithm) -> Result<u64> {
+    match algorithm {
+        PHashAlgorithm::PHash => phash::perception_hash(img, phash::ColorType::Threshold),
+        PHashAlgorithm::PHashGray => phash::perception_hash(img, phash::ColorType::Gray),
+        PHashAlgorithm::DHash => phash::difference_hash(img),
+        PHashAlgorithm::AHash => phash::average_hash(img),
     }
-    Ok(hash)
 }
 
-#[allow(unused)]
-pub fn get_perceptual_hash_of_image(img: DynamicImage) -> Result<u64> {
-    phash::perception_hash(img, phash::ColorType::Threshold)
+/// The image rotated, mirrored or cropped by the `transform`.
+pub fn transform_image(img: &DynamicImage, transform: ImageTransform) -> DynamicImage {
+    match transform {
+        ImageTransform::Rotate90 => img.rotate90(),
+        ImageTransform::Rotate180 => img.rotate180(),
+        ImageTransform::Rotate270 => img.rotate270(),
+        ImageTransform::FlipHorizontal => img.fliph(),
+        ImageTransform::FlipVertical => img.flipv(),
+        ImageTransform::Transpose => img.rotate90().fliph(),
+        ImageTransform::Transverse => img.rotate270().fliph(),
+        ImageTransform::CenterCrop => {
+            // the centre 80% of the image
+            let (x, y) = (img.width() / 10, img.height() / 10);
+            img.crop_imm(x, y, img.width() - 2 * x, img.height() - 2 * y)
+        }
+    }
 }
 
 #[allow(unused)]
@@ -122,6 +188,46 @@ where
     }
 }
 
+/// Frames of a media to be hashed, the image itself for an image.
+#[derive(Debug, Default)]
+pub struct Frames {
+    /// Frames reduced to the thumbnail, for the perceptual hashes.
+    pub images: Vec<DynamicImage>,
+    /// Frames reduced to fit `pdq::MAX_DIMENSION`, for the PDQ hashes, only
+    /// when asked for.
+    pub pdq_images: Vec<DynamicImage>,
+    /// Time of every frame of a video in seconds.
+    pub times: Vec<f64>,
+    /// Thumbnail of an image, or contact sheet of a video, as JPEG.
+    pub thumbnail: Vec<u8>,
+}
+
+/// Makes the thumbnail of a decoded image, also keeping the image for its PDQ
+/// hash when `pdq`, since PDQ is computed from the image and not the thumbnail.
+pub fn image_frames(img: DynamicImage, thumb_size: u32, pdq: bool) -> Result<Frames> {
+    let pdq_images = if pdq {
+        vec![pdq::downsample(img.clone())]
+    } else {
+        Vec::new()
+    };
+    let img = if img.width() > thumb_size || img.height() > thumb_size {
+        img.thumbnail(thumb_size, thumb_size)
+    } else {
+        img
+    };
+    let mut buf = Vec::new();
+    img.write_to(
+        &mut Cursor::new(&mut buf),
+        image::ImageOutputFormat::Jpeg(50),
+    )?;
+    Ok(Frames {
+        images: vec![img],
+        pdq_images,
+        times: Vec::new(),
+        thumbnail: buf,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +256,18 @@ mod tests {
         assert_eq!(hash, "b7b6e21916253608c9ff081db046a58100536963");
     }
 
+    #[test]
+    fn test_get_sha256_hash_of_file() {
+        let path = Path::new("../data/img/horse.jpg");
+        let hash = get_sha256_hash_of_file(path).expect("Failed to get sha256 hash of file.");
+
+        // Assert
+        assert_eq!(
+            hash,
+            "9b7bc4bef1e301d3b6d4be5a9af84cce7960b518b78dd39d6da60df5c1c6f943"
+        );
+    }
+
     #[test]
     fn test_get_md5_hash_of_file() {
         let path = Path::new("../data/img/horse.jpg");
@@ -159,6 +277,21 @@ mod tests {
         assert_eq!(hash, "506bf7f41ca0c6f9e7612c04e93ab235");
     }
 
+    #[test]
+    fn test_transform_image() {
+        let img = image::open("../data/img/horse.jpg").unwrap();
+        let transposed = transform_image(&img, ImageTransform::Transpose);
+        let cropped = transform_image(&img, ImageTransform::CenterCrop);
+
+        // Assert
+        assert_eq!(transposed.width(), img.height());
+        assert_eq!(
+            transposed.to_rgb8().get_pixel(1, 0),
+            img.to_rgb8().get_pixel(0, 1)
+        );
+        assert!(cropped.width() < img.width() && cropped.height() < img.height());
+    }
+
     #[test]
     fn test_make_thumbnail_to_vec() {
         let media_path = Path::new("../data/img/horse.jpg");
@@ -1,4 +1,11 @@
+pub mod archive;
+pub mod carver;
+pub mod disk_image;
 pub mod file_reader;
+pub mod hasher;
 pub mod media;
+pub mod multi_index;
+pub mod pdq;
 pub mod phash;
 pub(crate) mod transform_image;
+pub mod video_fingerprint;
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::pdq::PdqHash;
+use super::phash;
+
+// A bucket lookup costs about as much as comparing ten stored hashes. Measured
+// with `benches/match_phash.rs`, the index of 500000 hashes was faster than a
+// linear scan at the distance of 20 (54 ms against 129 ms for 100 queries),
+// while the index of 50000 hashes was slower (36 ms against 13 ms).
+const BUCKET_COST: usize = 10;
+
+/// A hash that can be split into 16-bit chunks for a `MultiIndex`.
+pub trait ChunkedHash: Copy {
+    /// Number of 16-bit chunks the hash is split into.
+    const CHUNKS: u32;
+
+    fn chunk(&self, chunk: u32) -> u16;
+
+    fn distance(&self, other: &Self) -> u32;
+}
+
+impl ChunkedHash for u64 {
+    const CHUNKS: u32 = 4;
+
+    #[inline]
+    fn chunk(&self, chunk: u32) -> u16 {
+        (self >> (chunk * 16)) as u16
+    }
+
+    #[inline]
+    fn distance(&self, other: &Self) -> u32 {
+        phash::distance(*self, *other)
+    }
+}
+
+impl ChunkedHash for PdqHash {
+    const CHUNKS: u32 = 16;
+
+    #[inline]
+    fn chunk(&self, chunk: u32) -> u16 {
+        (self.words()[(chunk / 4) as usize] >> ((chunk % 4) * 16)) as u16
+    }
+
+    #[inline]
+    fn distance(&self, other: &Self) -> u32 {
+        PdqHash::distance(self, other)
+    }
+}
+
+/// Nearest neighbour index of 64-bit perceptual hashes.
+pub type PHashIndex = MultiIndex<u64>;
+
+/// Nearest neighbour index of 256-bit PDQ hashes.
+pub type PdqIndex = MultiIndex<PdqHash>;
+
+/// Nearest neighbour index of hashes by Hamming distance, using multi-index
+/// hashing on 16-bit chunks.
+///
+/// By the pigeonhole principle, two hashes within distance `d` have at least
+/// one of their `CHUNKS` chunks within distance `d / CHUNKS`, so only the
+/// buckets of those chunk variations need to be compared. Each chunk radius
+/// `s` visits `C(16, s)` more buckets per chunk, so a large distance on a
+/// small index falls back to a linear scan.
+/// Follows https://www.cs.toronto.edu/~norouzi/research/papers/multi_index_hashing.pdf
+#[derive(Debug)]
+pub struct MultiIndex<H> {
+    hashes: Vec<H>,
+    tables: Vec<HashMap<u16, Vec<u32>>>,
+}
+
+impl<H> Default for MultiIndex<H> {
+    fn default() -> Self {
+        Self {
+            hashes: Vec::new(),
+            tables: Vec::new(),
+        }
+    }
+}
+
+impl<H: ChunkedHash> MultiIndex<H> {
+    pub fn insert(&mut self, hash: H) {
+        if self.tables.is_empty() {
+            self.tables = (0..H::CHUNKS).map(|_| HashMap::new()).collect();
+        }
+
+        let idx = self.hashes.len() as u32;
+        for (chunk, table) in self.tables.iter_mut().enumerate() {
+            table.entry(hash.chunk(chunk as u32)).or_default().push(idx);
+        }
+        self.hashes.push(hash);
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    #[allow(unused)]
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+        self.tables.clear();
+    }
+
+    /// Returns the position and the distance of the closest stored hash within
+    /// `max_distance`. Ties are resolved by the first inserted hash.
+    pub fn find(&self, hash: H, max_distance: u32) -> Option<(usize, u32)> {
+        if self.hashes.is_empty() {
+            return None;
+        }
+
+        // buckets visited when there is no match, the common case in a scan
+        let max_radius = max_distance / H::CHUNKS;
+        let buckets: usize = masks_by_weight()
+            .iter()
+            .take(max_radius as usize + 1)
+            .map(|masks| masks.len())
+            .sum();
+        if buckets * H::CHUNKS as usize * BUCKET_COST > self.hashes.len() {
+            return self.find_linear(hash, max_distance);
+        }
+
+        self.find_indexed(hash, max_distance)
+    }
+
+    // Search in the buckets of the chunk variations, by increasing radius.
+    fn find_indexed(&self, hash: H, max_distance: u32) -> Option<(usize, u32)> {
+        let max_radius = (max_distance / H::CHUNKS).min(16);
+        let masks = masks_by_weight();
+        let mut nearest: Option<(usize, u32)> = None;
+
+        for radius in 0..=max_radius {
+            for (chunk, table) in self.tables.iter().enumerate() {
+                let key = hash.chunk(chunk as u32);
+
+                for mask in masks[radius as usize].iter() {
+                    let Some(bucket) = table.get(&(key ^ mask)) else {
+                        continue;
+                    };
+                    for &idx in bucket.iter() {
+                        let idx = idx as usize;
+                        let distance = self.hashes[idx].distance(&hash);
+                        if distance <= max_distance && is_nearer(nearest, idx, distance) {
+                            nearest = Some((idx, distance));
+                        }
+                    }
+                }
+            }
+
+            // Every hash closer than `CHUNKS * (radius + 1)` was already visited.
+            if matches!(nearest, Some((_, distance)) if distance < H::CHUNKS * (radius + 1)) {
+                break;
+            }
+        }
+
+        nearest
+    }
+
+    /// Exhaustive search, used when there are more buckets to visit than hashes.
+    pub fn find_linear(&self, hash: H, max_distance: u32) -> Option<(usize, u32)> {
+        self.hashes
+            .iter()
+            .enumerate()
+            .map(|(idx, l_hash)| (idx, l_hash.distance(&hash)))
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by_key(|&(idx, distance)| (distance, idx))
+    }
+}
+
+#[inline]
+fn is_nearer(nearest: Option<(usize, u32)>, idx: usize, distance: u32) -> bool {
+    match nearest {
+        Some((n_idx, n_distance)) => (distance, idx) < (n_distance, n_idx),
+        None => true,
+    }
+}
+
+// All 16-bit masks grouped by the number of bits set.
+fn masks_by_weight() -> &'static [Vec<u16>] {
+    static MASKS: OnceLock<Vec<Vec<u16>>> = OnceLock::new();
+    MASKS.get_or_init(|| {
+        let mut masks = vec![Vec::new(); 17];
+        for mask in 0..=u16::MAX {
+            masks[mask.count_ones() as usize].push(mask);
+        }
+        masks
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // xorshift64, deterministic random hashes for the tests.
+    fn random_words(seed: u64, n: usize) -> Vec<u64> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            })
+            .collect()
+    }
+
+    fn random_pdq_hashes(seed: u64, n: usize) -> Vec<PdqHash> {
+        random_words(seed, n * 4)
+            .chunks_exact(4)
+            .map(|words| PdqHash::from_words([words[0], words[1], words[2], words[3]]))
+            .collect()
+    }
+
+    // Flips about one bit in eight of the word.
+    fn near(word: u64, noise: u64) -> u64 {
+        word ^ (noise & noise.rotate_left(17) & noise.rotate_left(31))
+    }
+
+    #[test]
+    fn test_find_should_return_same_result_as_linear_scan() {
+        let mut index = PHashIndex::default();
+        let hashes = random_words(0x9E3779B97F4A7C15, 5_000);
+        hashes.iter().for_each(|hash| index.insert(*hash));
+
+        // near duplicates of stored hashes and unrelated hashes
+        let mut queries: Vec<u64> = hashes
+            .iter()
+            .step_by(50)
+            .zip(random_words(7, 100))
+            .map(|(hash, noise)| near(*hash, noise))
+            .collect();
+        queries.extend(random_words(42, 100));
+
+        for max_distance in [0, 1, 3, 4, 7, 10, 16, 20, 23, 24, 32, 64] {
+            for query in queries.iter() {
+                let expected = index.find_linear(*query, max_distance);
+                let message = format!("query {query:X}, max distance {max_distance}");
+                assert_eq!(index.find(*query, max_distance), expected, "{message}");
+                // every bucket of a larger distance is too slow to visit here
+                if max_distance <= 24 {
+                    let indexed = index.find_indexed(*query, max_distance);
+                    assert_eq!(indexed, expected, "{message}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_pdq_should_return_same_result_as_linear_scan() {
+        let mut index = PdqIndex::default();
+        let hashes = random_pdq_hashes(0x9E3779B97F4A7C15, 2_000);
+        hashes.iter().for_each(|hash| index.insert(*hash));
+
+        // near duplicates of stored hashes and unrelated hashes
+        let mut queries: Vec<PdqHash> = hashes
+            .iter()
+            .step_by(20)
+            .zip(random_pdq_hashes(7, 100))
+            .map(|(hash, noise)| {
+                let (words, noise) = (hash.words(), noise.words());
+                PdqHash::from_words(std::array::from_fn(|i| near(words[i], noise[i])))
+            })
+            .collect();
+        queries.extend(random_pdq_hashes(42, 100));
+
+        for max_distance in [0, 1, 15, 16, 31, 32, 40, 63, 64, 128] {
+            for query in queries.iter() {
+                let expected = index.find_linear(*query, max_distance);
+                let message = format!("query {query}, max distance {max_distance}");
+                assert_eq!(index.find(*query, max_distance), expected, "{message}");
+                // every bucket of a larger distance is too slow to visit here
+                if max_distance <= 40 {
+                    let indexed = index.find_indexed(*query, max_distance);
+                    assert_eq!(indexed, expected, "{message}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_should_return_first_inserted_on_tie() {
+        let mut index = PHashIndex::default();
+        index.insert(0b1110);
+        index.insert(0b0111);
+        index.insert(0b1110);
+
+        // Assert
+        assert_eq!(index.find(0b0110, 10), Some((0, 1)));
+        assert_eq!(index.find(0b1110, 10), Some((0, 0)));
+        assert_eq!(index.find_indexed(0b0110, 10), Some((0, 1)));
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn test_find_pdq_should_return_first_inserted_on_tie() {
+        let mut index = PdqIndex::default();
+        index.insert(PdqHash::from_words([0b1110, 0, 0, 0]));
+        index.insert(PdqHash::from_words([0b0111, 0, 0, 0]));
+        index.insert(PdqHash::from_words([0b1110, 0, 0, 0]));
+
+        // Assert
+        assert_eq!(
+            index.find(PdqHash::from_words([0b0110, 0, 0, 0]), 31),
+            Some((0, 1))
+        );
+        assert_eq!(
+            index.find(PdqHash::from_words([0b1110, 0, 0, 0]), 31),
+            Some((0, 0))
+        );
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn test_find_in_empty_index() {
+        let index = PHashIndex::default();
+        assert_eq!(index.find(15634510955120228568, 20), None);
+        assert_eq!(PdqIndex::default().find(PdqHash::default(), 31), None);
+    }
+}
@@ -0,0 +1,399 @@
+#![allow(clippy::needless_range_loop)]
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use image::{imageops, DynamicImage};
+
+// Side of the buffer the image is blurred and decimated to.
+const BUFFER_SIZE: usize = 64;
+// Side of the low frequencies of the DCT that make the hash.
+const DCT_SIZE: usize = 16;
+// Box filter passes of the Jarosz filter, approximating a tent filter.
+const JAROSZ_PASSES: usize = 2;
+// Images smaller than this in any dimension are not hashed.
+const MIN_HASHABLE_DIM: usize = 5;
+
+/// Larger images are downsampled to this side before being hashed, as by the
+/// reference implementation.
+pub const MAX_DIMENSION: u32 = 512;
+
+/// 256-bit PDQ hash, in the order of its hexadecimal representation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PdqHash([u64; 4]);
+
+impl PdqHash {
+    pub fn from_words(words: [u64; 4]) -> Self {
+        Self(words)
+    }
+
+    pub fn words(&self) -> [u64; 4] {
+        self.0
+    }
+
+    /// Hamming distance between the hashes, from 0 to 256.
+    pub fn distance(&self, other: &PdqHash) -> u32 {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(l, r)| (l ^ r).count_ones())
+            .sum()
+    }
+
+    // Bit `k` of the reference implementation, which keeps the hash in sixteen
+    // 16-bit words printed from the last to the first.
+    fn set_bit(&mut self, k: usize) {
+        self.0[3 - k / 64] |= 1 << (k % 64);
+    }
+}
+
+impl fmt::Display for PdqHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for word in self.0.iter() {
+            write!(f, "{word:016x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PdqHash {
+    type Err = anyhow::Error;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            anyhow::bail!("a PDQ hash has 64 hexadecimal digits: {hex}");
+        }
+
+        let mut words = [0u64; 4];
+        for (idx, word) in words.iter_mut().enumerate() {
+            *word = u64::from_str_radix(&hex[idx * 16..(idx + 1) * 16], 16)?;
+        }
+        Ok(Self(words))
+    }
+}
+
+/// PDQ hash of an image and its quality, from 0 to 100. Hashes of images with
+/// a quality below 50 are too featureless to be matched reliably.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Pdq {
+    pub hash: PdqHash,
+    pub quality: u32,
+}
+
+/// pdq_hash function returns the PDQ hash of the image.
+/// Implementation follows
+/// https://github.com/facebook/ThreatExchange/blob/main/pdq/hashing.pdf
+pub fn pdq_hash(img: &DynamicImage) -> Pdq {
+    let img = if img.width().max(img.height()) > MAX_DIMENSION {
+        downsample(img.clone()).to_rgb8()
+    } else {
+        img.to_rgb8()
+    };
+    let (num_cols, num_rows) = (img.width() as usize, img.height() as usize);
+    if num_rows < MIN_HASHABLE_DIM || num_cols < MIN_HASHABLE_DIM {
+        return Pdq::default();
+    }
+
+    let mut luma: Vec<f32> = img
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b] = pixel.0;
+            0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32
+        })
+        .collect();
+
+    jarosz_filter(&mut luma, num_rows, num_cols);
+    let buffer = decimate(&luma, num_rows, num_cols);
+    let quality = quality(&buffer);
+    let dct = dct_64_to_16(&buffer);
+
+    Pdq {
+        hash: hash_from_dct(&dct),
+        quality,
+    }
+}
+
+/// Image reduced to fit `MAX_DIMENSION`, which is all `pdq_hash` reads of a
+/// larger image.
+pub fn downsample(img: DynamicImage) -> DynamicImage {
+    if img.width().max(img.height()) > MAX_DIMENSION {
+        img.resize(MAX_DIMENSION, MAX_DIMENSION, imageops::FilterType::Triangle)
+    } else {
+        img
+    }
+}
+
+// Blurs the image with box filters as wide as the cells it is decimated to.
+fn jarosz_filter(buffer: &mut [f32], num_rows: usize, num_cols: usize) {
+    let window_along_rows = window_size(num_cols);
+    let window_along_cols = window_size(num_rows);
+    let mut temp = vec![0.0; buffer.len()];
+
+    for _ in 0..JAROSZ_PASSES {
+        for row in 0..num_rows {
+            let offset = row * num_cols;
+            box_filter(
+                &buffer[offset..],
+                &mut temp[offset..],
+                num_cols,
+                1,
+                window_along_rows,
+            );
+        }
+        for col in 0..num_cols {
+            box_filter(
+                &temp[col..],
+                &mut buffer[col..],
+                num_rows,
+                num_cols,
+                window_along_cols,
+            );
+        }
+    }
+}
+
+fn window_size(dimension: usize) -> usize {
+    dimension.div_ceil(2 * BUFFER_SIZE)
+}
+
+// Mean of the window centred on every element of the vector, the window
+// shrinking at the edges.
+fn box_filter(input: &[f32], output: &mut [f32], len: usize, stride: usize, window: usize) {
+    let half_window = (window + 2) / 2;
+
+    let (mut left, mut right, mut out) = (0, 0, 0);
+    let mut sum = 0.0;
+    let mut current_window = 0.0;
+
+    // accumulate the first half of the window without writing
+    for _ in 0..half_window - 1 {
+        sum += input[right];
+        current_window += 1.0;
+        right += stride;
+    }
+    // write while the window grows
+    for _ in 0..window - half_window + 1 {
+        sum += input[right];
+        current_window += 1.0;
+        output[out] = sum / current_window;
+        right += stride;
+        out += stride;
+    }
+    // write with the full window
+    for _ in 0..len.saturating_sub(window) {
+        sum += input[right];
+        sum -= input[left];
+        output[out] = sum / current_window;
+        left += stride;
+        right += stride;
+        out += stride;
+    }
+    // write while the window shrinks
+    for _ in 0..half_window - 1 {
+        sum -= input[left];
+        current_window -= 1.0;
+        output[out] = sum / current_window;
+        left += stride;
+        out += stride;
+    }
+}
+
+// Samples the blurred image at the centre of each of the 64x64 cells.
+fn decimate(buffer: &[f32], num_rows: usize, num_cols: usize) -> Vec<[f32; BUFFER_SIZE]> {
+    let mut output = vec![[0.0; BUFFER_SIZE]; BUFFER_SIZE];
+    for i in 0..BUFFER_SIZE {
+        let row = ((i as f64 + 0.5) * num_rows as f64 / BUFFER_SIZE as f64) as usize;
+        for j in 0..BUFFER_SIZE {
+            let col = ((j as f64 + 0.5) * num_cols as f64 / BUFFER_SIZE as f64) as usize;
+            output[i][j] = buffer[row * num_cols + col];
+        }
+    }
+    output
+}
+
+// Sum of the gradients of the decimated image, scaled to 0..100.
+fn quality(buffer: &[[f32; BUFFER_SIZE]]) -> u32 {
+    let gradient = |u: f32, v: f32| (((u - v) * 100.0) / 255.0) as i32;
+
+    let mut gradient_sum: i32 = 0;
+    for i in 0..BUFFER_SIZE - 1 {
+        for j in 0..BUFFER_SIZE {
+            gradient_sum += gradient(buffer[i][j], buffer[i + 1][j]).abs();
+        }
+    }
+    for i in 0..BUFFER_SIZE {
+        for j in 0..BUFFER_SIZE - 1 {
+            gradient_sum += gradient(buffer[i][j], buffer[i][j + 1]).abs();
+        }
+    }
+
+    (gradient_sum / 90).min(100) as u32
+}
+
+// Rows of the DCT matrix for the 16 lowest frequencies, without the DC term.
+fn dct_matrix() -> &'static [[f32; BUFFER_SIZE]; DCT_SIZE] {
+    static MATRIX: OnceLock<[[f32; BUFFER_SIZE]; DCT_SIZE]> = OnceLock::new();
+    MATRIX.get_or_init(|| {
+        let scale = (2.0 / BUFFER_SIZE as f64).sqrt();
+        let mut matrix = [[0.0; BUFFER_SIZE]; DCT_SIZE];
+        for i in 0..DCT_SIZE {
+            for j in 0..BUFFER_SIZE {
+                let angle = std::f64::consts::PI / 2.0 / BUFFER_SIZE as f64
+                    * (i + 1) as f64
+                    * (2 * j + 1) as f64;
+                matrix[i][j] = (scale * angle.cos()) as f32;
+            }
+        }
+        matrix
+    })
+}
+
+// D * A * D^T, keeping only the low frequencies.
+fn dct_64_to_16(buffer: &[[f32; BUFFER_SIZE]]) -> [[f32; DCT_SIZE]; DCT_SIZE] {
+    let matrix = dct_matrix();
+
+    let mut temp = [[0.0f32; BUFFER_SIZE]; DCT_SIZE];
+    for i in 0..DCT_SIZE {
+        for j in 0..BUFFER_SIZE {
+            temp[i][j] = (0..BUFFER_SIZE).map(|k| matrix[i][k] * buffer[k][j]).sum();
+        }
+    }
+
+    let mut output = [[0.0f32; DCT_SIZE]; DCT_SIZE];
+    for i in 0..DCT_SIZE {
+        for j in 0..DCT_SIZE {
+            output[i][j] = (0..BUFFER_SIZE).map(|k| temp[i][k] * matrix[j][k]).sum();
+        }
+    }
+    output
+}
+
+// One bit for each coefficient above the median, the lower of the two middle
+// values as in the reference implementation.
+fn hash_from_dct(dct: &[[f32; DCT_SIZE]; DCT_SIZE]) -> PdqHash {
+    let mut sorted: Vec<f32> = dct.iter().flatten().copied().collect();
+    sorted.sort_by(f32::total_cmp);
+    let median = sorted[sorted.len() / 2 - 1];
+
+    let mut hash = PdqHash::default();
+    for (k, coefficient) in dct.iter().flatten().enumerate() {
+        if *coefficient > median {
+            hash.set_bit(k);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn horse() -> DynamicImage {
+        image::open(Path::new("../data/img/horse.jpg")).expect("Failed to open image.")
+    }
+
+    #[test]
+    fn test_box_filter() {
+        let input: Vec<f32> = (0..16).map(|i| i as f32).collect();
+        let mut output = vec![0.0; 16];
+        box_filter(&input, &mut output, 16, 1, 7);
+
+        // Assert: the window sums of the reference implementation, 0+1+2+3,
+        // 0+1+2+3+4, ..., 12+13+14+15, divided by the size of the window
+        let sums = [
+            6, 10, 15, 21, 28, 35, 42, 49, 56, 63, 70, 77, 84, 75, 65, 54,
+        ];
+        let sizes = [4, 5, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 6, 5, 4];
+        let expected: Vec<f32> = sums
+            .iter()
+            .zip(sizes)
+            .map(|(sum, size)| *sum as f32 / size as f32)
+            .collect();
+        assert_eq!(output, expected);
+        assert_eq!(window_size(64), 1);
+        assert_eq!(window_size(1024), 8);
+    }
+
+    #[test]
+    fn test_pdq_hash() {
+        let img = horse();
+        let pdq = pdq_hash(&img);
+        let resized = pdq_hash(&img.thumbnail(240, 240));
+        let flipped = pdq_hash(&img.flipv());
+
+        // Assert
+        assert_eq!(
+            pdq.hash.words().iter().map(|w| w.count_ones()).sum::<u32>(),
+            128
+        );
+        assert!(pdq.quality >= 50);
+        assert!(pdq.hash.distance(&resized.hash) <= 31);
+        assert!(pdq.hash.distance(&flipped.hash) > 31);
+    }
+
+    #[test]
+    fn test_pdq_hash_should_downsample_large_images() {
+        let img = horse().resize_exact(1200, 800, imageops::FilterType::Triangle);
+        let downsampled = downsample(img.clone());
+
+        // Assert
+        assert_eq!(downsampled.width(), MAX_DIMENSION);
+        assert_eq!(pdq_hash(&img), pdq_hash(&downsampled));
+        assert_eq!(downsample(downsampled.clone()).width(), MAX_DIMENSION);
+    }
+
+    #[test]
+    fn test_pdq_hash_of_flat_image() {
+        let img = DynamicImage::new_rgb8(128, 128);
+
+        // Assert
+        assert_eq!(pdq_hash(&img).quality, 0);
+        assert_eq!(pdq_hash(&DynamicImage::new_rgb8(4, 128)), Pdq::default());
+    }
+
+    #[test]
+    fn test_pdq_hash_hex() {
+        let hex = "f8f8f0cee0f4a84f06370a22038f63f0b36e2ed596621e1d33e6b39c4e9c9b22";
+        let hash: PdqHash = hex.parse().unwrap();
+        let mut bits = PdqHash::default();
+        bits.set_bit(0);
+        bits.set_bit(255);
+
+        // Assert
+        assert_eq!(hash.to_string(), hex);
+        assert_eq!(hex.to_uppercase().parse::<PdqHash>().unwrap(), hash);
+        assert_eq!(
+            bits.to_string(),
+            "8000000000000000000000000000000000000000000000000000000000000001"
+        );
+        assert_eq!(hash.distance(&bits), 128);
+        assert!("f8f8f0ce".parse::<PdqHash>().is_err());
+        assert!(format!("{}zz", &hex[2..]).parse::<PdqHash>().is_err());
+    }
+
+    #[test]
+    #[ignore = "needs the reference test images and their hashes in data/pdq"]
+    fn test_pdq_hash_should_match_reference_implementation() {
+        // expected.txt is the output of the reference pdq-photo-hasher on the
+        // images next to it, one "hash,quality,filename" line per image
+        let dir = Path::new("../data/pdq");
+        let expected = std::fs::read_to_string(dir.join("expected.txt")).unwrap();
+        let mut count = 0;
+        for line in expected.lines().filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.trim().splitn(3, ',').collect();
+            let hash: PdqHash = fields[0].parse().unwrap();
+            let quality: u32 = fields[1].parse().unwrap();
+            let img = image::open(dir.join(fields[2])).unwrap();
+            let pdq = pdq_hash(&img);
+
+            // Assert
+            assert_eq!(pdq.hash.distance(&hash), 0, "{}", fields[2]);
+            assert_eq!(pdq.quality, quality, "{}", fields[2]);
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+}
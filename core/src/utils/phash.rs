@@ -8,7 +8,6 @@ use image::{imageops::FilterType, DynamicImage};
 /// difference_hash function returns a hash computation of difference hash.
 /// Implementation follows
 /// https://www.hackerfactor.com/blog/index.php?/archives/529-Kind-of-Like-That.html
-#[allow(unused)]
 pub fn difference_hash(img: DynamicImage) -> Result<u64> {
     let (w, h) = (9, 8);
     let img_resized = img.resize_exact(w, h, FilterType::Lanczos3);
@@ -34,7 +33,6 @@ pub fn difference_hash(img: DynamicImage) -> Result<u64> {
 /// average_hash function returns a hash computation of average hash vertically.
 /// Implementation follows
 /// https://www.hackerfactor.com/blog/index.php?/archives/432-Looks-Like-It.html
-#[allow(unused)]
 pub fn average_hash(img: DynamicImage) -> Result<u64> {
     let (w, h) = (8, 8);
     let img_resized = img.resize_exact(w, h, FilterType::Lanczos3);
@@ -66,7 +64,6 @@ pub fn average_hash(img: DynamicImage) -> Result<u64> {
     Ok(hash)
 }
 
-#[allow(unused)]
 #[derive(Debug)]
 pub enum ColorType {
     Gray,
@@ -76,7 +73,6 @@ pub enum ColorType {
 /// perception_hash function returns a hash computation of perception hash.
 /// Implementation follows
 /// https://www.hackerfactor.com/blog/index.php?/archives/432-Looks-Like-It.html
-#[allow(unused)]
 pub fn perception_hash(img: DynamicImage, color_type: ColorType) -> Result<u64> {
     let (w, h) = (32, 32);
     let img_resized = img.resize_exact(w, h, FilterType::Lanczos3);
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// Width in seconds of the offsets between two videos voted together. Frames are
+// sampled about once a second, so the frames of two copies are up to half a
+// second apart.
+const OFFSET_STEP: f64 = 0.5;
+
+/// Perceptual hashes of the frames sampled from a video, with the time of
+/// every frame in seconds.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VideoFingerprint {
+    frames: Vec<(f64, u64)>,
+}
+
+impl VideoFingerprint {
+    /// Pairs every frame with its time, without the frames that could not be
+    /// hashed.
+    pub fn new(times: &[f64], phashes: &[u64]) -> Self {
+        let frames = times
+            .iter()
+            .zip(phashes.iter())
+            .filter(|(_, phash)| **phash != 0)
+            .map(|(time, phash)| (*time, *phash))
+            .collect();
+        Self { frames }
+    }
+
+    pub fn frames(&self) -> &[(f64, u64)] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// Frames separated by spaces, each as its time in seconds and its perceptual
+/// hash in hexadecimal: `0.000:D8F8F0E0E4E4F8D8 1.001:D8F8F0E0E4E4F8DA`.
+impl fmt::Display for VideoFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, (time, phash)) in self.frames.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{time:.3}:{phash:016X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for VideoFingerprint {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut frames = Vec::new();
+        for frame in value.split_whitespace() {
+            let Some((time, phash)) = frame.split_once(':') else {
+                anyhow::bail!("a frame is its time and its perceptual hash: {frame}");
+            };
+            frames.push((time.parse::<f64>()?, u64::from_str_radix(phash, 16)?));
+        }
+        if frames.is_empty() {
+            anyhow::bail!("a video fingerprint has at least one frame");
+        }
+        Ok(Self { frames })
+    }
+}
+
+/// Time ranges, in seconds, where a video matches a known one.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Alignment {
+    /// Range in the video.
+    pub start: f64,
+    pub end: f64,
+    /// Range in the known video.
+    pub reference_start: f64,
+    pub reference_end: f64,
+    /// Frames matched along the alignment.
+    pub frames: usize,
+    /// Mean Hamming distance of the matched frames.
+    pub distance: u32,
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} = {}-{}",
+            format_time(self.start),
+            format_time(self.end),
+            format_time(self.reference_start),
+            format_time(self.reference_end)
+        )
+    }
+}
+
+/// align function finds where the `reference` video plays inside the `video`,
+/// so that trimmed and re-encoded copies of a known video are still found.
+///
+/// Every pair of frames within `max_distance` votes for the offset between the
+/// two videos; the frames of the most voted offset are the match. It needs at
+/// least `min_frames` frames, or every frame of a shorter known video, and
+/// never a single frame.
+pub fn align(
+    video: &VideoFingerprint,
+    reference: &VideoFingerprint,
+    max_distance: u32,
+    min_frames: usize,
+) -> Option<Alignment> {
+    let required = min_frames.min(reference.len()).max(2);
+    if video.len() < required {
+        return None;
+    }
+
+    // offset of the videos, frames and distance of every similar pair of frames
+    let mut pairs = Vec::new();
+    for (idx, (time, phash)) in video.frames.iter().enumerate() {
+        for (ref_idx, (ref_time, ref_phash)) in reference.frames.iter().enumerate() {
+            let distance = (phash ^ ref_phash).count_ones();
+            if distance <= max_distance {
+                pairs.push((time - ref_time, idx, ref_idx, distance));
+            }
+        }
+    }
+
+    let bin = |offset: f64| (offset / OFFSET_STEP).round() as i64;
+    let mut votes: BTreeMap<i64, usize> = BTreeMap::new();
+    for (offset, _, _, _) in pairs.iter() {
+        *votes.entry(bin(*offset)).or_default() += 1;
+    }
+    // the neighbouring offsets hold the frames sampled out of phase
+    let score = |offset: i64| -> usize {
+        (offset - 1..=offset + 1)
+            .filter_map(|offset| votes.get(&offset))
+            .sum()
+    };
+    let best = votes.keys().copied().max_by_key(|offset| score(*offset))?;
+
+    // every frame is matched once, to the closest frame along the offset
+    let mut candidates: Vec<_> = pairs
+        .into_iter()
+        .filter(|(offset, _, _, _)| (bin(*offset) - best).abs() <= 1)
+        .collect();
+    candidates.sort_by(|l, r| {
+        let deviation = |offset: f64| (offset - best as f64 * OFFSET_STEP).abs();
+        l.3.cmp(&r.3)
+            .then(deviation(l.0).total_cmp(&deviation(r.0)))
+    });
+    let mut used = vec![false; video.len()];
+    let mut ref_used = vec![false; reference.len()];
+    let mut matched = Vec::new();
+    for (_, idx, ref_idx, distance) in candidates {
+        if !used[idx] && !ref_used[ref_idx] {
+            used[idx] = true;
+            ref_used[ref_idx] = true;
+            matched.push((video.frames[idx].0, reference.frames[ref_idx].0, distance));
+        }
+    }
+    if matched.len() < required {
+        return None;
+    }
+
+    let range = |times: &mut dyn Iterator<Item = f64>| {
+        times.fold((f64::MAX, f64::MIN), |(start, end), time| {
+            (start.min(time), end.max(time))
+        })
+    };
+    let (start, end) = range(&mut matched.iter().map(|(time, _, _)| *time));
+    let (reference_start, reference_end) = range(&mut matched.iter().map(|(_, time, _)| *time));
+    let distance = matched
+        .iter()
+        .map(|(_, _, distance)| *distance)
+        .sum::<u32>() as f64
+        / matched.len() as f64;
+
+    Some(Alignment {
+        start,
+        end,
+        reference_start,
+        reference_end,
+        frames: matched.len(),
+        distance: distance.round() as u32,
+    })
+}
+
+// Time in seconds as `hh:mm:ss`.
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Frames one second apart from `start`.
+    fn video(start: f64, phashes: &[u64]) -> VideoFingerprint {
+        let times: Vec<f64> = (0..phashes.len()).map(|idx| start + idx as f64).collect();
+        VideoFingerprint::new(&times, phashes)
+    }
+
+    // Hashes far from each other.
+    fn scene(idx: u64) -> u64 {
+        idx.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    #[test]
+    fn test_align_should_find_clip_inside_longer_video() {
+        let clip: Vec<u64> = (1..=8).map(scene).collect();
+        let reference = video(0.0, &clip);
+        // the clip from its third frame, re-encoded and sampled 0.4s later,
+        // between unrelated frames
+        let mut frames: Vec<u64> = (100..130).map(scene).collect();
+        for (idx, phash) in clip[2..].iter().enumerate() {
+            frames[10 + idx] = phash ^ 0b101;
+        }
+        let scanned = video(0.4, &frames);
+
+        let alignment = align(&scanned, &reference, 10, 5).unwrap();
+
+        // Assert
+        assert_eq!(alignment.frames, 6);
+        assert_eq!(alignment.distance, 2);
+        assert_eq!((alignment.start, alignment.end), (10.4, 15.4));
+        assert_eq!(
+            (alignment.reference_start, alignment.reference_end),
+            (2.0, 7.0)
+        );
+        assert_eq!(
+            alignment.to_string(),
+            "00:00:10-00:00:15 = 00:00:02-00:00:07"
+        );
+    }
+
+    #[test]
+    fn test_align_should_need_frames_at_the_same_offset() {
+        let clip: Vec<u64> = (1..=8).map(scene).collect();
+        let reference = video(0.0, &clip);
+        // the same frames, but shuffled in time
+        let shuffled: Vec<u64> = [4, 1, 7, 2, 8, 3, 6, 5].map(scene).to_vec();
+        let single = video(0.0, &[scene(1)]);
+
+        // Assert
+        assert_eq!(align(&video(0.0, &shuffled), &reference, 10, 5), None);
+        assert_eq!(align(&video(3.0, &clip[..4]), &reference, 10, 5), None);
+        assert_eq!(align(&reference, &single, 10, 5), None);
+        assert_eq!(
+            align(&video(0.0, &clip[..2]), &reference, 10, 2)
+                .unwrap()
+                .frames,
+            2
+        );
+    }
+
+    #[test]
+    fn test_video_fingerprint_should_roundtrip_text() {
+        let fingerprint =
+            VideoFingerprint::new(&[0.0, 1.001, 2.002], &[15634510955120228568, 0, 1]);
+        let text = fingerprint.to_string();
+
+        // Assert
+        assert_eq!(text, "0.000:D8F8F0E0E4E4F8D8 2.002:0000000000000001");
+        assert_eq!(text.parse::<VideoFingerprint>().unwrap(), fingerprint);
+        assert_eq!(fingerprint.len(), 2);
+        assert!("".parse::<VideoFingerprint>().is_err());
+        assert!("0.000".parse::<VideoFingerprint>().is_err());
+        assert!("x:D8F8F0E0E4E4F8D8".parse::<VideoFingerprint>().is_err());
+    }
+}
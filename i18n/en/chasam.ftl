@@ -27,6 +27,36 @@ image = Image
 video = Video 
 save-selected-media = Save selected media
 media-export-success = Media export success
+rematched-media = Results matched again, changed
+open-case = Open case
+save-case = Save case
+case-opened = Case opened
+case-saved = Case saved
+note = Note
+tag = Tag
+tag-category-1 = Category 1
+tag-category-2 = Category 2
+tag-category-3 = Category 3
+tag-not-relevant = Not relevant
+tag-age-difficult = Age difficult
+tag-needs-review = Needs review
+all-tags = All
+untagged = Untagged
+tag-shortcuts = Keys 1 to 6 in the grid tag the media, 0 removes the tag
+add-tagged-to-database = Add media tagged as CSAM to the database
+tagged-media-added = Media added to the database
+tag-csam-media = Tag the media as category 1, 2 or 3 first.
+save = Save
+export-vics = Export Project VIC (VICS) case
+vics-filter = Project VIC JSON
+vics-export-success = VICS case exported
+generate-report = Generate report
+report-filter = HTML report
+report-success = Report generated
+save-inventory = Save inventory of the listed media
+csv-filter = CSV
+jsonl-filter = JSON Lines
+inventory-export-success = Inventory saved
 select-media = Select one or more media!
 
 # Preferences
@@ -43,16 +73,39 @@ spanish = Spanish
 message-dialog = The program must be restarted for the changes to take effect.
 database = Database
 database-path = Database path
+archive-depth = Archive depth
+archive-depth-description = Levels of nested archives opened, 0 to not open archives
+phash-transforms = Rotated and mirrored copies
+phash-transforms-description = Also match rotated, mirrored and cropped images, slowing down the search
+pdq-threshold = PDQ threshold
+pdq-threshold-description = Highest Hamming distance of a PDQ match, out of 256 bits
+video-sampling = Video frames
+video-sampling-description = How the frames of the videos are chosen
+video-sampling-interval = One a second
+video-sampling-evenly = Evenly spaced
+video-sampling-keyframes = Keyframes only, the fastest
+video-sampling-scene-change = Scene changes
+max-video-frames = Frames per video
+max-video-frames-description = Most frames sampled from a video
+report = Report
+examiner = Examiner
+storage = Storage
+storage-memory = Memory
+storage-sqlite = SQLite
 media-path = Media path
 keyword = Keyword
 keywords = Keywords
 save-keywords = Save keywords
 generate-database = Generate database
+import-hash-set = Import Project VIC / CAID hash set
+hash-set-filter = VICS / CAID JSON
 add-hash = Add MD5 hash
 add-phash = Add perceptual hash
 add-keyword = Add keyword
 add-hash-description = Generate MD5 hash database.
 add-phash-description = Generate perceptual hash database.
+phash-algorithms = Perceptual hash algorithms
+phash-algorithms-description = Algorithms of the generated perceptual hash database
 add-keyword-description = Generate keyword database.
 enter-keyword = Enter a keyword...
 progress = Progress
@@ -61,6 +114,7 @@ generating-hash-database = Generating MD5 hash database...
 generating-phash-database = Generating the perceptual hash database...
 msg-media-path = Media path does not exist.
 total-hash-generated = Total hash generated
+total-hash-imported = Total hash imported
 total-phash-generated = Total perceptual hash generated
 msg-keyword-empty = There is no save keyword.
 
@@ -73,8 +127,15 @@ search = Search
 stop = Stop
 directory = Directory
 select-directory = Select directory
+select-disk-image = Select disk image (raw or E01) or file to carve
+disk-image-filter = Disk image
+all-files-filter = All files
+carve = Carve images and videos from the selected file
+select-path-list = Select a file with the paths to search, one per line
+path-list-filter = Path list
 
 # Statusbar
+root = Root
 processed-files = Processed files:
 out-of = out of
 calculating = calculating
@@ -95,14 +156,31 @@ type = Type
 last-modified = Last modified
 hash = MD5
 phash = Perceptual hash
+pdq = PDQ
+known-videos = Known videos
 match-type = Match type
 hamming-distance = Hamming distance
+match-reference = Matched reference
+match-source = Source list
+match-category = Category
+match-filename = Original file name
+match-series = Series
 
 # Errors
 generic-error = An error ocurred:
 invalid-directory = Invalid directory!
+invalid-carving-file = Select a file to carve!
+invalid-case-folder = The folder does not contain a case!
 open-media-error = Failed to open media
 saved-successfully = Saved successfully!
 failed-to-save = Failed to save!
 failed-to-generate-db = Failed to generate the database!
-media-export-error = Error when exporting media!
\ No newline at end of file
+failed-to-import-hash-set = Failed to import the hash set!
+media-export-error = Error when exporting media!
+vics-export-error = Error when exporting VICS case!
+report-error = Error when generating the report!
+inventory-export-error = Error when saving the inventory!
+case-open-error = Error when opening the case!
+case-save-error = Error when saving the case!
+add-tagged-error = Error when adding the media to the database!
+extension-mismatch = Extension mismatch
@@ -27,6 +27,36 @@ image = Imagen
 video = Video
 save-selected-media = Guardar medios seleccionados
 media-export-success = Éxito en la exportación de medios
+rematched-media = Resultados verificados de nuevo, modificados
+open-case = Abrir caso
+save-case = Guardar caso
+case-opened = Caso abierto
+case-saved = Caso guardado
+note = Nota
+tag = Etiqueta
+tag-category-1 = Categoría 1
+tag-category-2 = Categoría 2
+tag-category-3 = Categoría 3
+tag-not-relevant = No relevante
+tag-age-difficult = Edad dudosa
+tag-needs-review = Revisar
+all-tags = Todas
+untagged = Sin etiqueta
+tag-shortcuts = Las teclas 1 a 6 en la cuadrícula etiquetan el medio, 0 quita la etiqueta
+add-tagged-to-database = Añadir los medios etiquetados como CSAM a la base de datos
+tagged-media-added = Medios añadidos a la base de datos
+tag-csam-media = Etiquete antes los medios como categoría 1, 2 o 3.
+save = Guardar
+export-vics = Exportar caso Project VIC (VICS)
+vics-filter = Project VIC JSON
+vics-export-success = Caso VICS exportado
+generate-report = Generar informe
+report-filter = Informe HTML
+report-success = Informe generado
+save-inventory = Guardar inventario de los medios listados
+csv-filter = CSV
+jsonl-filter = JSON Lines
+inventory-export-success = Inventario guardado
 select-media = ¡Seleccione uno o más medios!
 
 # Preferences
@@ -43,16 +73,39 @@ spanish = Español
 message-dialog = Se debe reiniciar el programa para que los cambios surtan efecto.
 database = Base de datos
 database-path = Directorio de base de datos
+archive-depth = Profundidad de archivos comprimidos
+archive-depth-description = Niveles de archivos comprimidos anidados abiertos, 0 para no abrirlos
+phash-transforms = Copias giradas y reflejadas
+phash-transforms-description = También encuentra imágenes giradas, reflejadas y recortadas, ralentizando la búsqueda
+pdq-threshold = Umbral PDQ
+pdq-threshold-description = Distancia de Hamming máxima de una coincidencia PDQ, de 256 bits
+video-sampling = Fotogramas de los vídeos
+video-sampling-description = Cómo se eligen los fotogramas de los vídeos
+video-sampling-interval = Uno por segundo
+video-sampling-evenly = Espaciados uniformemente
+video-sampling-keyframes = Solo fotogramas clave, el más rápido
+video-sampling-scene-change = Cambios de escena
+max-video-frames = Fotogramas por vídeo
+max-video-frames-description = Máximo de fotogramas extraídos de un vídeo
+report = Informe
+examiner = Examinador
+storage = Almacenamiento
+storage-memory = Memoria
+storage-sqlite = SQLite
 media-path = Directorio de medios
 keyword = Palabra clave
 keywords = Palabras clave
 save-keywords = Guardar palabras clave
 generate-database = Generar base de datos
+import-hash-set = Importar conjunto de hashes Project VIC / CAID
+hash-set-filter = JSON VICS / CAID
 add-hash = Agregar hash MD5
 add-phash = Agregar hash perceptivo
 add-keyword = Agregar palabra clave
 add-hash-description = Generar base de datos hash MD5.
 add-phash-description = Generar base de datos hash perceptual.
+phash-algorithms = Algoritmos de hash perceptual
+phash-algorithms-description = Algoritmos de la base de datos hash perceptual generada
 add-keyword-description = Generar base de datos de palabras clave.
 enter-keyword = Introduzca una palabra clave...
 progress = Progreso
@@ -61,6 +114,7 @@ generating-hash-database = Generando base de datos hash MD5...
 generating-phash-database = Generando la base de datos hash perceptivo...
 msg-media-path = Directorio de medios no existe.
 total-hash-generated = Hash total generado
+total-hash-imported = Hash total importado
 total-phash-generated = Hash perceptivo total generado
 msg-keyword-empty = No hay ninguna palabra clave para guardar.
 
@@ -73,8 +127,15 @@ search = Buscar
 stop = Detener
 directory = Directorio
 select-directory = Seleccionar directorio
+select-disk-image = Seleccionar imagen de disco (raw o E01) o archivo para recuperar
+disk-image-filter = Imagen de disco
+all-files-filter = Todos los archivos
+carve = Recuperar imágenes y videos del archivo seleccionado (carving)
+select-path-list = Seleccionar un archivo con las rutas a buscar, una por línea
+path-list-filter = Lista de rutas
 
 # Statusbar
+root = Origen
 processed-files = Archivos procesados:
 out-of = de
 calculating = calculador
@@ -95,14 +156,31 @@ type = Tipo
 last-modified = Última modificación
 hash = MD5
 phash = Hash perceptual
+pdq = PDQ
+known-videos = Vídeos conocidos
 match-type = Tipo de match
 hamming-distance = Distancia de hamming
+match-reference = Referencia encontrada
+match-source = Lista de origen
+match-category = Categoría
+match-filename = Nombre del archivo original
+match-series = Serie
 
 # Errors
 generic-error = Ocurrió un error:
 invalid-directory = ¡Directorio invalido!
+invalid-carving-file = ¡Seleccione un archivo para recuperar!
+invalid-case-folder = ¡La carpeta no contiene un caso!
 open-media-error = No se pudieron abrir los medios
 saved-successfully = ¡Guardado exitosamente!
 failed-to-save = ¡Error al guardar!
 failed-to-generate-db = ¡No se pudo generar la base de datos!
-media-export-error = ¡Error al exportar medios!
\ No newline at end of file
+failed-to-import-hash-set = ¡No se pudo importar el conjunto de hashes!
+media-export-error = ¡Error al exportar medios!
+vics-export-error = ¡Error al exportar el caso VICS!
+report-error = ¡Error al generar el informe!
+inventory-export-error = ¡Error al guardar el inventario!
+case-open-error = ¡Error al abrir el caso!
+case-save-error = ¡Error al guardar el caso!
+add-tagged-error = ¡Error al añadir los medios a la base de datos!
+extension-mismatch = Extensión no coincidente
@@ -27,6 +27,36 @@ image = Imagem
 video = Vídeo 
 save-selected-media = Salvar mídias selecionadas
 media-export-success = Sucesso na exportação de mídia
+rematched-media = Resultados verificados novamente, alterados
+open-case = Abrir caso
+save-case = Salvar caso
+case-opened = Caso aberto
+case-saved = Caso salvo
+note = Nota
+tag = Marcação
+tag-category-1 = Categoria 1
+tag-category-2 = Categoria 2
+tag-category-3 = Categoria 3
+tag-not-relevant = Não relevante
+tag-age-difficult = Idade duvidosa
+tag-needs-review = Revisar
+all-tags = Todas
+untagged = Sem marcação
+tag-shortcuts = As teclas 1 a 6 na grade marcam a mídia, 0 remove a marcação
+add-tagged-to-database = Adicionar as mídias marcadas como CSAM à base de dados
+tagged-media-added = Mídias adicionadas à base de dados
+tag-csam-media = Marque antes as mídias como categoria 1, 2 ou 3.
+save = Salvar
+export-vics = Exportar caso Project VIC (VICS)
+vics-filter = Project VIC JSON
+vics-export-success = Caso VICS exportado
+generate-report = Gerar relatório
+report-filter = Relatório HTML
+report-success = Relatório gerado
+save-inventory = Salvar inventário das mídias listadas
+csv-filter = CSV
+jsonl-filter = JSON Lines
+inventory-export-success = Inventário salvo
 select-media = Selecione uma ou mais mídias!
 
 # Preferences
@@ -43,16 +73,39 @@ spanish = Espanhol
 message-dialog = O programa deve ser reiniciado para que as alterações tenham efeito.
 database = Base de dados
 database-path = Diretório da base de dados
+archive-depth = Profundidade de arquivos compactados
+archive-depth-description = Níveis de arquivos compactados aninhados abertos, 0 para não abrir
+phash-transforms = Cópias giradas e espelhadas
+phash-transforms-description = Também encontra imagens giradas, espelhadas e recortadas, tornando a busca mais lenta
+pdq-threshold = Limite PDQ
+pdq-threshold-description = Distância de Hamming máxima de uma correspondência PDQ, de 256 bits
+video-sampling = Quadros dos vídeos
+video-sampling-description = Como os quadros dos vídeos são escolhidos
+video-sampling-interval = Um por segundo
+video-sampling-evenly = Espaçados uniformemente
+video-sampling-keyframes = Apenas quadros-chave, o mais rápido
+video-sampling-scene-change = Mudanças de cena
+max-video-frames = Quadros por vídeo
+max-video-frames-description = Máximo de quadros extraídos de um vídeo
+report = Relatório
+examiner = Examinador
+storage = Armazenamento
+storage-memory = Memória
+storage-sqlite = SQLite
 media-path = Diretório da mídia
 keyword = Palavra-chave
 keywords = Palavras-chave
 save-keywords = Salvar palavras-chave
 generate-database = Gerar base de dados
+import-hash-set = Importar conjunto de hashes Project VIC / CAID
+hash-set-filter = JSON VICS / CAID
 add-hash = Adicionar hash MD5
 add-phash = Adicionar hash perceptivo
 add-keyword = Adicionar palavra-chave
 add-hash-description = Gerar base de dados de hash MD5.
 add-phash-description = Gerar base de dados de hash perceptivo.
+phash-algorithms = Algoritmos de hash perceptivo
+phash-algorithms-description = Algoritmos da base de dados de hash perceptivo gerada
 add-keyword-description = Gerar base de dados de palavras-chave.
 enter-keyword = Insira uma palavra-chave...
 progress = Progresso
@@ -61,6 +114,7 @@ generating-hash-database = Gerando o banco de dados de hash MD5...
 generating-phash-database = Gerando o banco de dados de hash perceptivo...
 msg-media-path = O diretório da mídia não existe.
 total-hash-generated = Total de hash gerado
+total-hash-imported = Total de hash importado
 total-phash-generated = Total de hash perceptivo gerado
 msg-keyword-empty = Não há palavra-chave para salvar.
 
@@ -73,8 +127,15 @@ search = Pesquisar
 stop = Parar
 directory = Diretório
 select-directory = Selecionar diretório
+select-disk-image = Selecionar imagem de disco (raw ou E01) ou arquivo para recuperar
+disk-image-filter = Imagem de disco
+all-files-filter = Todos os arquivos
+carve = Recuperar imagens e vídeos do arquivo selecionado (carving)
+select-path-list = Selecionar um arquivo com os caminhos a pesquisar, um por linha
+path-list-filter = Lista de caminhos
 
 # Statusbar
+root = Origem
 processed-files = Arquivos processados:
 out-of = de
 calculating = calculando
@@ -95,14 +156,31 @@ type = Tipo
 last-modified = Última modificação
 hash = MD5
 phash = Hash perceptivo
+pdq = PDQ
+known-videos = Vídeos conhecidos
 match-type = Tipo do match
 hamming-distance = Distância de hamming
+match-reference = Referência encontrada
+match-source = Lista de origem
+match-category = Categoria
+match-filename = Nome do arquivo original
+match-series = Série
 
 # Errors
 generic-error = Um erro ocorreu:
 invalid-directory = Diretório inválido!
+invalid-carving-file = Selecione um arquivo para recuperar!
+invalid-case-folder = A pasta não contém um caso!
 open-media-error = Falha ao abrir mídia
 saved-successfully = Salvo com sucesso!
 failed-to-save = Falha ao salvar!
 failed-to-generate-db = Falha ao gerar à base de dados!
-media-export-error = Erro na exportação da mídia!
\ No newline at end of file
+failed-to-import-hash-set = Falha ao importar o conjunto de hashes!
+media-export-error = Erro na exportação da mídia!
+vics-export-error = Erro na exportação do caso VICS!
+report-error = Erro ao gerar o relatório!
+inventory-export-error = Erro ao salvar o inventário!
+case-open-error = Erro ao abrir o caso!
+case-save-error = Erro ao salvar o caso!
+add-tagged-error = Erro ao adicionar as mídias à base de dados!
+extension-mismatch = Extensão divergente
@@ -21,6 +21,7 @@ use relm4_icons::icon_names;
 use crate::app::components::{
     about_dialog::AboutDialog,
     content::{ContentInput, ContentModel},
+    csam::{CsamInput, CSAM_BROKER},
     preferences::PreferencesModel,
     sidebar::{SidebarModel, SidebarOutput},
 };
@@ -56,6 +57,8 @@ impl App {
 #[derive(Debug)]
 pub enum AppInput {
     SelectedSidebarOption(models::SidebarOption),
+    OpenCase,
+    SaveCase,
     Quit,
 }
 
@@ -195,6 +198,8 @@ impl AsyncComponent for App {
             sender.input_sender(),
             |output| match output {
                 SidebarOutput::SelectedOption(option) => AppInput::SelectedSidebarOption(option),
+                SidebarOutput::OpenCase => AppInput::OpenCase,
+                SidebarOutput::SaveCase => AppInput::SaveCase,
             },
         );
 
@@ -270,6 +275,12 @@ impl AsyncComponent for App {
                 self.content
                     .emit(ContentInput::SelectSidebarOption(sidebar_option));
             }
+            AppInput::OpenCase => {
+                CSAM_BROKER.send(CsamInput::OpenCase);
+            }
+            AppInput::SaveCase => {
+                CSAM_BROKER.send(CsamInput::SaveCase);
+            }
             AppInput::Quit => {
                 main_adw_application().quit();
             }
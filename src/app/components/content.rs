@@ -11,7 +11,10 @@ use relm4::{
 };
 
 use crate::app::{
-    components::{csam::CsamModel, face::FaceModel},
+    components::{
+        csam::{CsamModel, CSAM_BROKER},
+        face::FaceModel,
+    },
     models::SidebarOption,
 };
 use crate::context::AppContext;
@@ -72,7 +75,9 @@ impl SimpleAsyncComponent for ContentModel {
         root: Self::Root,
         _sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self> {
-        let csam_controller = CsamModel::builder().launch(ctx).detach();
+        let csam_controller = CsamModel::builder()
+            .launch_with_broker(ctx, &CSAM_BROKER)
+            .detach();
         let face_controller = FaceModel::builder().launch(()).detach();
 
         let model = ContentModel {
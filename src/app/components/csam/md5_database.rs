@@ -20,10 +20,12 @@ use crate::app::components::progress_dialog::{
     ProgressDialog, ProgressDialogOutput, ProgressSettings,
 };
 use crate::{context::AppContext, fl};
+use core_chasam::csam::DirectorySource;
 
 pub struct MD5DatabaseModel {
     ctx: AppContext,
     open_dialog: Controller<OpenDialog>,
+    import_dialog: Controller<OpenDialog>,
     progress_dialog: Controller<ProgressDialog>,
     media_path: PathBuf,
 }
@@ -33,6 +35,8 @@ pub enum MD5DatabaseInput {
     OpenFileRequest,
     OpenFileResponse(PathBuf),
     GenerateDatabase,
+    ImportRequest,
+    ImportResponse(PathBuf),
     ShowInfoDialog(String),
     ShowProgressDialog(bool),
     GoPrevious,
@@ -100,6 +104,13 @@ impl AsyncComponent for MD5DatabaseModel {
                                 set_tooltip: fl!("generate-database"),
                                 connect_clicked => MD5DatabaseInput::GenerateDatabase,
                             },
+                            gtk::Button {
+                                set_icon_name: "document-open-symbolic",
+                                set_css_classes: &["circular", "accent"],
+                                set_valign: gtk::Align::Center,
+                                set_tooltip: fl!("import-hash-set"),
+                                connect_clicked => MD5DatabaseInput::ImportRequest,
+                            },
                         },
 
                         adw::EntryRow {
@@ -147,6 +158,27 @@ impl AsyncComponent for MD5DatabaseModel {
                 OpenDialogResponse::Cancel => MD5DatabaseInput::Ignore,
             });
 
+        let json_filter = gtk::FileFilter::new();
+        json_filter.set_name(Some(fl!("hash-set-filter")));
+        json_filter.add_pattern("*.json");
+
+        let import_dialog_settings = OpenDialogSettings {
+            folder_mode: false,
+            accept_label: String::from(fl!("open")),
+            cancel_label: String::from(fl!("cancel")),
+            create_folders: false,
+            is_modal: true,
+            filters: vec![json_filter],
+        };
+
+        let import_dialog = OpenDialog::builder()
+            .transient_for_native(&root)
+            .launch(import_dialog_settings)
+            .forward(sender.input_sender(), |response| match response {
+                OpenDialogResponse::Accept(path) => MD5DatabaseInput::ImportResponse(path),
+                OpenDialogResponse::Cancel => MD5DatabaseInput::Ignore,
+            });
+
         let progress_settings = ProgressSettings {
             text: String::from(fl!("wait")),
             secondary_text: Some(String::from(fl!("generating-hash-database"))),
@@ -163,6 +195,7 @@ impl AsyncComponent for MD5DatabaseModel {
         let model = MD5DatabaseModel {
             ctx,
             open_dialog,
+            import_dialog,
             progress_dialog,
             media_path: PathBuf::default(),
         };
@@ -187,6 +220,12 @@ impl AsyncComponent for MD5DatabaseModel {
             MD5DatabaseInput::GenerateDatabase => {
                 self.generate_database(sender).await;
             }
+            MD5DatabaseInput::ImportRequest => {
+                self.import_dialog.emit(OpenDialogMsg::Open);
+            }
+            MD5DatabaseInput::ImportResponse(path) => {
+                self.import_database(path, sender).await;
+            }
             MD5DatabaseInput::ShowInfoDialog(msg) => {
                 let window = root.toplevel_window();
                 dialogs::show_info_dialog(window.as_ref(), Some(fl!("hash")), Some(&msg));
@@ -251,7 +290,7 @@ impl MD5DatabaseModel {
 
                     match ctx
                         .csam_service
-                        .create_hash_database(db_path, media_path)
+                        .create_hash_database(db_path, Box::new(DirectorySource::new(media_path)))
                         .await
                     {
                         Ok(count) => {
@@ -283,4 +322,49 @@ impl MD5DatabaseModel {
                 .drop_on_shutdown()
         });
     }
+
+    async fn import_database(&mut self, file_path: PathBuf, sender: AsyncComponentSender<Self>) {
+        let ctx = self.ctx.clone();
+        let db_path = ctx.get_preference().database_path.clone();
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    out.send(MD5DatabaseCommandOutput::ShowProgressDialog(true))
+                        .unwrap_or_default();
+
+                    match ctx
+                        .csam_service
+                        .import_hash_database(db_path, file_path)
+                        .await
+                    {
+                        Ok(count) => {
+                            out.send(MD5DatabaseCommandOutput::ShowInfoDialog(
+                                format!(
+                                    "{}: {}",
+                                    fl!("total-hash-imported"),
+                                    count.to_formatted_string(&ctx.get_locale())
+                                )
+                                .to_string(),
+                            ))
+                            .unwrap_or_default();
+
+                            out.send(MD5DatabaseCommandOutput::GeneratedDatabase)
+                                .unwrap_or_default();
+                        }
+                        Err(err) => {
+                            tracing::error!("Could not import hash set. Error: {:#}", err);
+                            out.send(MD5DatabaseCommandOutput::ShowInfoDialog(
+                                fl!("failed-to-import-hash-set").to_string(),
+                            ))
+                            .unwrap_or_default();
+                        }
+                    }
+
+                    out.send(MD5DatabaseCommandOutput::ShowProgressDialog(false))
+                        .unwrap_or_default();
+                })
+                .drop_on_shutdown()
+        });
+    }
 }
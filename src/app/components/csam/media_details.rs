@@ -5,7 +5,7 @@ use relm4::{
     gtk::gdk::gdk_pixbuf,
     gtk::{
         self,
-        prelude::{BoxExt, GestureExt, GridExt, OrientableExt, WidgetExt},
+        prelude::{BoxExt, EditableExt, GestureExt, GridExt, OrientableExt, WidgetExt},
     },
     ComponentSender, RelmWidgetExt,
 };
@@ -23,12 +23,16 @@ pub struct MediaDetailsModel {
 pub enum MediaDetailsInput {
     OpenMedia,
     ShowMedia(models::MediaDetail),
+    SetNote(String),
+    SetTag(String),
     Reset,
 }
 
 #[derive(Debug)]
 pub enum MediaDetailsOutput {
     Notify(String, u32),
+    /// Path of the media and the note of the examiner.
+    SetNote(String, String),
 }
 
 #[relm4::component(pub)]
@@ -111,7 +115,7 @@ impl Component for MediaDetailsModel {
                         },
                         attach[1, 2, 1, 1] = &gtk::Label {
                             #[watch]
-                            set_label: &model.media.media_type.name(),
+                            set_label: &model.media.type_name(),
                             set_halign: gtk::Align::Start,
                             set_hexpand: true,
                         },
@@ -170,12 +174,106 @@ impl Component for MediaDetailsModel {
                             set_halign: gtk::Align::Start,
                             set_hexpand: true,
                         },
-                        // attach[0, 8, 1, 1] = &gtk::Label {
+                        attach[0, 8, 1, 1] = &gtk::Label {
+                            set_label: &format!("{}:", fl!("match-reference")),
+                            set_halign: gtk::Align::Start,
+                            set_css_classes: &["key-label"],
+                        },
+                        attach[1, 8, 1, 1] = &gtk::Label {
+                            #[watch]
+                            set_label: &model.media.reference,
+                            set_halign: gtk::Align::Start,
+                            set_hexpand: true,
+                            set_single_line_mode: false,
+                            set_wrap: true,
+                            set_wrap_mode: gtk::pango::WrapMode::Char,
+                        },
+                        attach[0, 9, 1, 1] = &gtk::Label {
+                            set_label: &format!("{}:", fl!("match-source")),
+                            set_halign: gtk::Align::Start,
+                            set_css_classes: &["key-label"],
+                        },
+                        attach[1, 9, 1, 1] = &gtk::Label {
+                            #[watch]
+                            set_label: &model.media.source,
+                            set_halign: gtk::Align::Start,
+                            set_hexpand: true,
+                            set_single_line_mode: false,
+                            set_wrap: true,
+                            set_wrap_mode: gtk::pango::WrapMode::Char,
+                        },
+                        attach[0, 10, 1, 1] = &gtk::Label {
+                            set_label: &format!("{}:", fl!("match-category")),
+                            set_halign: gtk::Align::Start,
+                            set_css_classes: &["key-label"],
+                        },
+                        attach[1, 10, 1, 1] = &gtk::Label {
+                            #[watch]
+                            set_label: &model.media.category,
+                            set_halign: gtk::Align::Start,
+                            set_hexpand: true,
+                            set_single_line_mode: false,
+                            set_wrap: true,
+                            set_wrap_mode: gtk::pango::WrapMode::Char,
+                        },
+                        attach[0, 11, 1, 1] = &gtk::Label {
+                            set_label: &format!("{}:", fl!("match-filename")),
+                            set_halign: gtk::Align::Start,
+                            set_css_classes: &["key-label"],
+                        },
+                        attach[1, 11, 1, 1] = &gtk::Label {
+                            #[watch]
+                            set_label: &model.media.original_filename,
+                            set_halign: gtk::Align::Start,
+                            set_hexpand: true,
+                            set_single_line_mode: false,
+                            set_wrap: true,
+                            set_wrap_mode: gtk::pango::WrapMode::Char,
+                        },
+                        attach[0, 12, 1, 1] = &gtk::Label {
+                            set_label: &format!("{}:", fl!("match-series")),
+                            set_halign: gtk::Align::Start,
+                            set_css_classes: &["key-label"],
+                        },
+                        attach[1, 12, 1, 1] = &gtk::Label {
+                            #[watch]
+                            set_label: &model.media.series,
+                            set_halign: gtk::Align::Start,
+                            set_hexpand: true,
+                            set_single_line_mode: false,
+                            set_wrap: true,
+                            set_wrap_mode: gtk::pango::WrapMode::Char,
+                        },
+                        attach[0, 13, 1, 1] = &gtk::Label {
+                            set_label: &format!("{}:", fl!("tag")),
+                            set_halign: gtk::Align::Start,
+                            set_css_classes: &["key-label"],
+                        },
+                        attach[1, 13, 1, 1] = &gtk::Label {
+                            #[watch]
+                            set_label: &model.media.tag,
+                            set_tooltip: fl!("tag-shortcuts"),
+                            set_halign: gtk::Align::Start,
+                            set_hexpand: true,
+                        },
+                        attach[0, 14, 1, 1] = &gtk::Label {
+                            set_label: &format!("{}:", fl!("note")),
+                            set_halign: gtk::Align::Start,
+                            set_css_classes: &["key-label"],
+                        },
+                        #[name(note_entry)]
+                        attach[1, 14, 1, 1] = &gtk::Entry {
+                            set_hexpand: true,
+                            connect_changed[sender] => move |entry| {
+                                sender.input(MediaDetailsInput::SetNote(entry.text().to_string()));
+                            },
+                        },
+                        // attach[0, 15, 1, 1] = &gtk::Label {
                         //     set_label: &format!("{}:", fl!("hamming-distance")),
                         //     set_halign: gtk::Align::Start,
                         //     set_css_classes: &["key-label"],
                         // },
-                        // attach[1, 8, 1, 1] = &gtk::Label {
+                        // attach[1, 15, 1, 1] = &gtk::Label {
                         //     #[watch]
                         //     set_label: &model.media.hamming,
                         //     set_halign: gtk::Align::Start,
@@ -219,24 +317,39 @@ impl Component for MediaDetailsModel {
                 }
             }
             MediaDetailsInput::ShowMedia(media) => {
+                let thumbnail = || {
+                    media.img_buf.as_ref().and_then(|img_buf| {
+                        let bytes: VecDeque<_> = img_buf.iter().cloned().collect();
+                        gdk_pixbuf::Pixbuf::from_read(bytes).ok()
+                    })
+                };
+                // files inside archives only have the thumbnail
                 self.pixbuf = match media.media_type {
-                    models::MediaType::Image => gdk_pixbuf::Pixbuf::from_file(&media.path).ok(),
-                    models::MediaType::Video => {
-                        if let Some(ref img_buf) = media.img_buf {
-                            let bytes: VecDeque<_> = img_buf.iter().cloned().collect();
-                            gdk_pixbuf::Pixbuf::from_read(bytes).ok()
-                        } else {
-                            None
-                        }
-                    }
+                    models::MediaType::Image => gdk_pixbuf::Pixbuf::from_file(&media.path)
+                        .ok()
+                        .or_else(thumbnail),
+                    models::MediaType::Video => thumbnail(),
                 };
                 self.media = media;
                 self.is_visible = true;
+                widgets.note_entry.set_text(&self.media.note);
+            }
+            MediaDetailsInput::SetNote(note) => {
+                if note != self.media.note {
+                    self.media.note = note.clone();
+                    sender
+                        .output(MediaDetailsOutput::SetNote(self.media.path.clone(), note))
+                        .unwrap_or_default();
+                }
+            }
+            MediaDetailsInput::SetTag(tag) => {
+                self.media.tag = tag;
             }
             MediaDetailsInput::Reset => {
                 self.media = models::MediaDetail::default();
                 self.pixbuf = None;
                 self.is_visible = false;
+                widgets.note_entry.set_text("");
             }
         }
 
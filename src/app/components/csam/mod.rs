@@ -21,9 +21,10 @@ use relm4::{
         prelude::{BoxExt, Cast, FrameExt, GtkWindowExt, ListModelExt, OrientableExt, WidgetExt},
     },
     typed_view::grid::TypedGridView,
-    Component, RelmWidgetExt,
+    Component, MessageBroker, RelmWidgetExt,
 };
 use relm4_components::open_dialog::*;
+use relm4_components::save_dialog::*;
 
 use super::dialogs;
 use crate::app::{
@@ -34,15 +35,38 @@ use crate::app::{
     models,
 };
 use crate::{context::AppContext, fl};
-use core_chasam::csam::StateMedia;
+use core_chasam::csam::{
+    case::{self, Case, CaseMedia},
+    dedup_roots,
+    inventory::InventoryRecord,
+    report::ReportInfo,
+    source_from_path, CarvingSource, EvidenceSource, ScanCache, StateMedia, Tag,
+};
 use media_details::{MediaDetailsInput, MediaDetailsModel, MediaDetailsOutput};
 use statusbar::{StatusbarInput, StatusbarModel};
 use toolbar::{ToolbarModel, ToolbarOutput};
 
+// Folder of the scan caches, one for every set of evidence roots.
+const SCAN_CACHE_DIR: &str = "cache";
+
+pub static CSAM_BROKER: MessageBroker<CsamInput> = MessageBroker::new();
+
+// What the folder chosen in the case dialog is for.
+#[derive(Debug, Clone, Copy)]
+enum CaseAction {
+    Open,
+    Save,
+}
+
 pub struct CsamModel {
     ctx: AppContext,
     save_dialog: Controller<OpenDialog>,
+    vics_dialog: Controller<SaveDialog>,
+    report_dialog: Controller<SaveDialog>,
+    inventory_dialog: Controller<SaveDialog>,
     progress_dialog: Controller<ProgressDialog>,
+    case_dialog: Controller<OpenDialog>,
+    case_action: CaseAction,
     searchbar: Controller<SearchBarModel>,
     toolbar: Controller<ToolbarModel>,
     statusbar: Controller<StatusbarModel>,
@@ -50,11 +74,21 @@ pub struct CsamModel {
     media_filter: Rc<RefCell<models::MediaFilter>>,
     media_details: Controller<MediaDetailsModel>,
     thumbnail_size: i32,
+    evidence_roots: Vec<PathBuf>,
+    scan_started: Option<i64>,
+    scan_finished: Option<i64>,
+    scan_errors: Vec<(String, String)>,
+    /// Folder of the case the results were saved to or opened from.
+    case_dir: Option<PathBuf>,
+    case_created: Option<i64>,
+    /// Path of the media shown in the details, the one tagged with the keyboard.
+    detail_path: Option<String>,
 }
 
 #[derive(Debug)]
 pub enum CsamInput {
-    StartSearch(PathBuf),
+    StartSearch(Vec<PathBuf>),
+    StartCarving(PathBuf),
     StopSearch,
     ZoomIn,
     ZoomOut,
@@ -62,6 +96,8 @@ pub enum CsamInput {
     ImageFilter(bool),
     VideoFilter(bool),
     CSAMFilter(bool),
+    MismatchFilter(bool),
+    TagFilter(models::TagFilter),
     SelectAllMedias(bool),
     SizeFilter0KB(bool),
     SizeFilter30KB(bool),
@@ -72,10 +108,25 @@ pub enum CsamInput {
     SaveSelectedMedia,
     CancelMediaExport,
     SaveFileResponse(PathBuf),
+    GenerateReport,
+    GenerateReportResponse(PathBuf),
+    ExportVics,
+    ExportVicsResponse(PathBuf),
+    SaveInventory,
+    SaveInventoryResponse(PathBuf),
     MediaListSelect(u32),
     ShowInfoDialog(String),
     ShowProgressDialog(bool),
     Notify(String, u32),
+    /// Matches the results again after a database is loaded.
+    RematchMedia,
+    OpenCase,
+    SaveCase,
+    CaseDialogResponse(PathBuf),
+    SetMediaNote(String, String),
+    /// Tags the media shown in the details, `None` removes the tag.
+    TagMedia(Option<Tag>),
+    AddTaggedToDatabase,
     Ignore,
 }
 
@@ -83,7 +134,13 @@ pub enum CsamInput {
 pub enum CsamCommandOutput {
     SearchCompleted,
     AddMedia(anyhow::Result<Vec<models::Media>>),
+    MediaError(String, anyhow::Error),
     MediaFound(usize),
+    SearchRoot(usize, usize, String),
+    MediaRematched(anyhow::Result<Vec<core_chasam::csam::Media>>),
+    CaseOpened(PathBuf, anyhow::Result<Case>),
+    CaseSaved(PathBuf, anyhow::Result<()>),
+    TaggedMediaAdded(anyhow::Result<usize>),
     ShowProgressDialog(bool),
     Notify(String, u32),
 }
@@ -162,6 +219,20 @@ impl AsyncComponent for CsamModel {
                                         connect_activate[sender] => move |_, position| {
                                             sender.input(CsamInput::MediaListSelect(position));
                                         },
+                                        add_controller = gtk::EventControllerKey {
+                                            connect_key_pressed[sender] => move |_, key, _, _| {
+                                                // 1 to 6 tag the media in the details, 0 untags it
+                                                match key.to_unicode().and_then(|c| c.to_digit(10)) {
+                                                    Some(0) => sender.input(CsamInput::TagMedia(None)),
+                                                    Some(digit) if digit as usize <= Tag::ALL.len() => {
+                                                        let tag = Tag::ALL[digit as usize - 1];
+                                                        sender.input(CsamInput::TagMedia(Some(tag)));
+                                                    }
+                                                    _ => return glib::Propagation::Proceed,
+                                                }
+                                                glib::Propagation::Stop
+                                            },
+                                        },
                                     },
                                 },
                             },
@@ -205,6 +276,69 @@ impl AsyncComponent for CsamModel {
                 OpenDialogResponse::Cancel => CsamInput::Ignore,
             });
 
+        let vics_filter = gtk::FileFilter::new();
+        vics_filter.add_pattern("*.json");
+        vics_filter.set_name(Some(fl!("vics-filter")));
+
+        let vics_dialog_settings = SaveDialogSettings {
+            accept_label: String::from(fl!("save")),
+            cancel_label: String::from(fl!("cancel")),
+            create_folders: true,
+            is_modal: true,
+            filters: vec![vics_filter],
+        };
+
+        let vics_dialog = SaveDialog::builder()
+            .transient_for_native(&root)
+            .launch(vics_dialog_settings)
+            .forward(sender.input_sender(), |response| match response {
+                SaveDialogResponse::Accept(path) => CsamInput::ExportVicsResponse(path),
+                SaveDialogResponse::Cancel => CsamInput::Ignore,
+            });
+
+        let report_filter = gtk::FileFilter::new();
+        report_filter.add_pattern("*.html");
+        report_filter.set_name(Some(fl!("report-filter")));
+
+        let report_dialog_settings = SaveDialogSettings {
+            accept_label: String::from(fl!("save")),
+            cancel_label: String::from(fl!("cancel")),
+            create_folders: true,
+            is_modal: true,
+            filters: vec![report_filter],
+        };
+
+        let report_dialog = SaveDialog::builder()
+            .transient_for_native(&root)
+            .launch(report_dialog_settings)
+            .forward(sender.input_sender(), |response| match response {
+                SaveDialogResponse::Accept(path) => CsamInput::GenerateReportResponse(path),
+                SaveDialogResponse::Cancel => CsamInput::Ignore,
+            });
+
+        let csv_filter = gtk::FileFilter::new();
+        csv_filter.add_pattern("*.csv");
+        csv_filter.set_name(Some(fl!("csv-filter")));
+        let jsonl_filter = gtk::FileFilter::new();
+        jsonl_filter.add_pattern("*.jsonl");
+        jsonl_filter.set_name(Some(fl!("jsonl-filter")));
+
+        let inventory_dialog_settings = SaveDialogSettings {
+            accept_label: String::from(fl!("save")),
+            cancel_label: String::from(fl!("cancel")),
+            create_folders: true,
+            is_modal: true,
+            filters: vec![csv_filter, jsonl_filter],
+        };
+
+        let inventory_dialog = SaveDialog::builder()
+            .transient_for_native(&root)
+            .launch(inventory_dialog_settings)
+            .forward(sender.input_sender(), |response| match response {
+                SaveDialogResponse::Accept(path) => CsamInput::SaveInventoryResponse(path),
+                SaveDialogResponse::Cancel => CsamInput::Ignore,
+            });
+
         let progress_settings = ProgressSettings {
             text: fl!("wait").to_string(),
             secondary_text: Some("Exporting media...".to_string()),
@@ -222,7 +356,8 @@ impl AsyncComponent for CsamModel {
             SearchBarModel::builder()
                 .launch(())
                 .forward(sender.input_sender(), |output| match output {
-                    SearchBarOutput::StartSearch(path) => CsamInput::StartSearch(path),
+                    SearchBarOutput::StartSearch(paths) => CsamInput::StartSearch(paths),
+                    SearchBarOutput::StartCarving(path) => CsamInput::StartCarving(path),
                     SearchBarOutput::StopSearch => CsamInput::StopSearch,
                     SearchBarOutput::Notify(msg, timeout) => CsamInput::Notify(msg, timeout),
                 });
@@ -232,6 +367,10 @@ impl AsyncComponent for CsamModel {
             .forward(sender.input_sender(), |output| match output {
                 ToolbarOutput::SelectAll(is_selected) => CsamInput::SelectAllMedias(is_selected),
                 ToolbarOutput::SaveSelected => CsamInput::SaveSelectedMedia,
+                ToolbarOutput::GenerateReport => CsamInput::GenerateReport,
+                ToolbarOutput::ExportVics => CsamInput::ExportVics,
+                ToolbarOutput::SaveInventory => CsamInput::SaveInventory,
+                ToolbarOutput::AddTaggedToDatabase => CsamInput::AddTaggedToDatabase,
                 ToolbarOutput::ZoomIn => CsamInput::ZoomIn,
                 ToolbarOutput::ZoomOut => CsamInput::ZoomOut,
                 ToolbarOutput::HammingDistanceFilter(value) => {
@@ -240,6 +379,8 @@ impl AsyncComponent for CsamModel {
                 ToolbarOutput::ImageFilter(is_active) => CsamInput::ImageFilter(is_active),
                 ToolbarOutput::VideoFilter(is_active) => CsamInput::VideoFilter(is_active),
                 ToolbarOutput::CSAMFilter(is_active) => CsamInput::CSAMFilter(is_active),
+                ToolbarOutput::MismatchFilter(is_active) => CsamInput::MismatchFilter(is_active),
+                ToolbarOutput::TagFilter(filter) => CsamInput::TagFilter(filter),
                 ToolbarOutput::SizeFilter0KB(is_active) => CsamInput::SizeFilter0KB(is_active),
                 ToolbarOutput::SizeFilter30KB(is_active) => CsamInput::SizeFilter30KB(is_active),
                 ToolbarOutput::SizeFilter100KB(is_active) => CsamInput::SizeFilter100KB(is_active),
@@ -271,12 +412,35 @@ impl AsyncComponent for CsamModel {
             .launch(models::MediaDetail::default())
             .forward(sender.input_sender(), |output| match output {
                 MediaDetailsOutput::Notify(msg, timeout) => CsamInput::Notify(msg, timeout),
+                MediaDetailsOutput::SetNote(path, note) => CsamInput::SetMediaNote(path, note),
+            });
+
+        let case_dialog_settings = OpenDialogSettings {
+            folder_mode: true,
+            accept_label: String::from(fl!("open")),
+            cancel_label: String::from(fl!("cancel")),
+            create_folders: true,
+            is_modal: true,
+            filters: Vec::new(),
+        };
+
+        let case_dialog = OpenDialog::builder()
+            .transient_for_native(&root)
+            .launch(case_dialog_settings)
+            .forward(sender.input_sender(), |response| match response {
+                OpenDialogResponse::Accept(path) => CsamInput::CaseDialogResponse(path),
+                OpenDialogResponse::Cancel => CsamInput::Ignore,
             });
 
         let mut model = CsamModel {
             ctx,
             save_dialog,
+            vics_dialog,
+            report_dialog,
+            inventory_dialog,
             progress_dialog,
+            case_dialog,
+            case_action: CaseAction::Open,
             searchbar: searchbar_controller,
             toolbar: toolbar_controller,
             statusbar: statusbar_controller,
@@ -284,6 +448,13 @@ impl AsyncComponent for CsamModel {
             media_filter: Rc::new(RefCell::new(models::MediaFilter::default())),
             media_details: media_details_controller,
             thumbnail_size: models::media::THUMBNAIL_SIZE,
+            evidence_roots: Vec::new(),
+            scan_started: None,
+            scan_finished: None,
+            scan_errors: Vec::new(),
+            case_dir: None,
+            case_created: None,
+            detail_path: None,
         };
 
         let filter = model.media_filter.clone();
@@ -304,11 +475,18 @@ impl AsyncComponent for CsamModel {
         root: &Self::Root,
     ) {
         match message {
-            CsamInput::StartSearch(path) => {
-                self.media_list_wrapper.clear();
-                self.statusbar.emit(StatusbarInput::Loading(true));
-                self.media_details.emit(MediaDetailsInput::Reset);
-                self.on_search(path, &sender).await;
+            CsamInput::StartSearch(paths) => {
+                // roots inside another one are only scanned once
+                let roots = dedup_roots(paths);
+                let sources = roots.iter().cloned().map(source_from_path).collect();
+                self.evidence_roots = roots;
+                self.on_search(sources, &sender).await;
+            }
+            CsamInput::StartCarving(path) => {
+                let sources: Vec<Box<dyn EvidenceSource>> =
+                    vec![Box::new(CarvingSource::new(path.clone()))];
+                self.evidence_roots = vec![path];
+                self.on_search(sources, &sender).await;
             }
             CsamInput::StopSearch => {
                 self.ctx.csam_service.cancel_task();
@@ -316,6 +494,7 @@ impl AsyncComponent for CsamModel {
             CsamInput::MediaListSelect(position) => {
                 if let Some(item) = self.media_list_wrapper.get_visible(position) {
                     let media = &item.borrow().media;
+                    self.detail_path = Some(media.path.clone());
                     self.media_details.emit(MediaDetailsInput::ShowMedia(
                         models::MediaDetail::from(media),
                     ));
@@ -338,6 +517,14 @@ impl AsyncComponent for CsamModel {
                 self.media_filter.borrow_mut().is_csam = is_active;
                 self.apply_media_filters().await;
             }
+            CsamInput::MismatchFilter(is_active) => {
+                self.media_filter.borrow_mut().is_mismatch = is_active;
+                self.apply_media_filters().await;
+            }
+            CsamInput::TagFilter(filter) => {
+                self.media_filter.borrow_mut().tag = filter;
+                self.apply_media_filters().await;
+            }
             CsamInput::ImageFilter(is_active) => {
                 self.media_filter.borrow_mut().is_image = is_active;
                 self.apply_media_filters().await;
@@ -379,6 +566,27 @@ impl AsyncComponent for CsamModel {
             CsamInput::SaveFileResponse(path) => {
                 self.on_save_selected_media(&path, sender.clone()).await;
             }
+            CsamInput::GenerateReport => {
+                self.report_dialog
+                    .emit(SaveDialogMsg::SaveAs(String::from("report.html")));
+            }
+            CsamInput::GenerateReportResponse(path) => {
+                self.on_generate_report(path, sender.clone()).await;
+            }
+            CsamInput::SaveInventory => {
+                self.inventory_dialog
+                    .emit(SaveDialogMsg::SaveAs(String::from("inventory.csv")));
+            }
+            CsamInput::SaveInventoryResponse(path) => {
+                self.on_save_inventory(path, sender.clone()).await;
+            }
+            CsamInput::ExportVics => {
+                self.vics_dialog
+                    .emit(SaveDialogMsg::SaveAs(String::from("vics.json")));
+            }
+            CsamInput::ExportVicsResponse(path) => {
+                self.on_export_vics(path, sender.clone()).await;
+            }
             CsamInput::ShowInfoDialog(msg) => {
                 let window = root.toplevel_window();
                 dialogs::show_info_dialog(window.as_ref(), Some(info::APP_NAME), Some(&msg));
@@ -394,6 +602,45 @@ impl AsyncComponent for CsamModel {
                 let toast = adw::Toast::builder().title(msg).timeout(timeout).build();
                 widgets.overlay.add_toast(toast);
             }
+            CsamInput::RematchMedia => {
+                self.on_rematch_media(sender.clone()).await;
+            }
+            CsamInput::OpenCase => {
+                self.case_action = CaseAction::Open;
+                self.case_dialog.emit(OpenDialogMsg::Open);
+            }
+            CsamInput::SaveCase => match self.case_dir.clone() {
+                Some(dir) => self.on_save_case(dir, sender.clone()).await,
+                None => {
+                    self.case_action = CaseAction::Save;
+                    self.case_dialog.emit(OpenDialogMsg::Open);
+                }
+            },
+            CsamInput::CaseDialogResponse(path) => match self.case_action {
+                CaseAction::Open if !case::is_case(&path) => {
+                    sender.input(CsamInput::ShowInfoDialog(
+                        fl!("invalid-case-folder").to_string(),
+                    ));
+                }
+                CaseAction::Open => self.on_open_case(path, sender.clone()).await,
+                CaseAction::Save => self.on_save_case(path, sender.clone()).await,
+            },
+            CsamInput::SetMediaNote(path, note) => {
+                for position in 0..self.media_list_wrapper.len() {
+                    let item = self.media_list_wrapper.get(position).unwrap();
+                    let mut item = item.borrow_mut();
+                    if item.media.path == path {
+                        item.media.note = note;
+                        break;
+                    }
+                }
+            }
+            CsamInput::TagMedia(tag) => {
+                self.on_tag_media(tag).await;
+            }
+            CsamInput::AddTaggedToDatabase => {
+                self.on_add_tagged_to_database(sender.clone()).await;
+            }
             CsamInput::Ignore => (),
         }
 
@@ -408,12 +655,17 @@ impl AsyncComponent for CsamModel {
     ) {
         match message {
             CsamCommandOutput::SearchCompleted => {
+                self.scan_finished = Some(chrono::Local::now().timestamp());
                 self.searchbar.emit(SearchBarInput::SearchCompleted);
                 self.statusbar.emit(StatusbarInput::Loading(false));
             }
             CsamCommandOutput::MediaFound(found) => {
                 self.statusbar.emit(StatusbarInput::TotalFound(found));
             }
+            CsamCommandOutput::SearchRoot(index, count, name) => {
+                self.statusbar
+                    .emit(StatusbarInput::SearchRoot(index, count, name));
+            }
             CsamCommandOutput::AddMedia(result) => match result {
                 Ok(medias) => {
                     let media_items = medias
@@ -441,6 +693,69 @@ impl AsyncComponent for CsamModel {
                     tracing::error!("{}: {}", fl!("generic-error"), err);
                 }
             },
+            CsamCommandOutput::MediaError(path, err) => {
+                sender.input(CsamInput::Notify(
+                    format!("{}: {}", fl!("generic-error"), err),
+                    5,
+                ));
+                tracing::error!("{}: {} : {}", fl!("generic-error"), path, err);
+                self.scan_errors.push((path, format!("{err:#}")));
+            }
+            CsamCommandOutput::MediaRematched(result) => match result {
+                Ok(medias) => self.apply_media_matches(medias, &sender).await,
+                Err(err) => {
+                    sender.input(CsamInput::Notify(
+                        format!("{}: {}", fl!("generic-error"), err),
+                        5,
+                    ));
+                    tracing::error!("{}: {}", fl!("generic-error"), err);
+                }
+            },
+            CsamCommandOutput::CaseOpened(dir, result) => match result {
+                Ok(case) => self.apply_case(dir, case, &sender).await,
+                Err(err) => {
+                    sender.input(CsamInput::Notify(
+                        format!("{}: {}", fl!("case-open-error"), err),
+                        5,
+                    ));
+                    tracing::error!("{}: {}", fl!("case-open-error"), err);
+                }
+            },
+            CsamCommandOutput::CaseSaved(dir, result) => match result {
+                Ok(_) => {
+                    self.case_dir = Some(dir);
+                    sender.input(CsamInput::Notify(fl!("case-saved").to_string(), 5));
+                }
+                Err(err) => {
+                    sender.input(CsamInput::Notify(
+                        format!("{}: {}", fl!("case-save-error"), err),
+                        5,
+                    ));
+                    tracing::error!("{}: {}", fl!("case-save-error"), err);
+                }
+            },
+            CsamCommandOutput::TaggedMediaAdded(result) => match result {
+                Ok(count) => {
+                    let locale = self.ctx.get_locale();
+                    sender.input(CsamInput::Notify(
+                        format!(
+                            "{}: {}",
+                            fl!("tagged-media-added"),
+                            count.to_formatted_string(&locale)
+                        ),
+                        5,
+                    ));
+                    // other results may match the media just added
+                    sender.input(CsamInput::RematchMedia);
+                }
+                Err(err) => {
+                    sender.input(CsamInput::Notify(
+                        format!("{}: {}", fl!("add-tagged-error"), err),
+                        5,
+                    ));
+                    tracing::error!("{}: {}", fl!("add-tagged-error"), err);
+                }
+            },
             CsamCommandOutput::ShowProgressDialog(show) => {
                 sender.input(CsamInput::ShowProgressDialog(show))
             }
@@ -452,9 +767,40 @@ impl AsyncComponent for CsamModel {
 }
 
 impl CsamModel {
-    async fn on_search(&mut self, path: PathBuf, sender: &AsyncComponentSender<CsamModel>) {
+    async fn on_search(
+        &mut self,
+        sources: Vec<Box<dyn EvidenceSource>>,
+        sender: &AsyncComponentSender<CsamModel>,
+    ) {
+        self.media_list_wrapper.clear();
+        self.statusbar.emit(StatusbarInput::Loading(true));
+        self.media_details.emit(MediaDetailsInput::Reset);
+        self.detail_path = None;
+        self.scan_started = Some(chrono::Local::now().timestamp());
+        self.scan_finished = None;
+        self.scan_errors.clear();
         self.statusbar.emit(StatusbarInput::Calculating);
 
+        // a rescan of the same roots reuses the hashes and thumbnails of the last one
+        let scan_cache = match self.case_dir.as_ref() {
+            Some(dir) => ScanCache::open(&dir.join(case::CACHE_FILE)),
+            None => std::env::current_dir()
+                .map_err(anyhow::Error::from)
+                .and_then(|dir| {
+                    ScanCache::open(&ScanCache::path_for(
+                        &dir.join(SCAN_CACHE_DIR),
+                        &self.evidence_roots,
+                    ))
+                }),
+        };
+        match scan_cache {
+            Ok(scan_cache) => self.ctx.csam_service.set_scan_cache(Some(scan_cache)),
+            Err(err) => {
+                tracing::error!("{}: {}", fl!("generic-error"), err);
+                self.ctx.csam_service.set_scan_cache(None);
+            }
+        }
+
         let (tx, mut rx) = relm4::tokio::sync::mpsc::channel(100);
 
         sender.command(|out, shutdown| {
@@ -466,6 +812,10 @@ impl CsamModel {
                                 out.send(CsamCommandOutput::SearchCompleted)
                                     .unwrap_or_default();
                             }
+                            StateMedia::Root(index, count, name) => {
+                                out.send(CsamCommandOutput::SearchRoot(index, count, name))
+                                    .unwrap_or_default();
+                            }
                             StateMedia::Found(count) => {
                                 out.send(CsamCommandOutput::MediaFound(count))
                                     .unwrap_or_default();
@@ -476,8 +826,8 @@ impl CsamModel {
                                 out.send(CsamCommandOutput::AddMedia(Ok(vec_medias)))
                                     .unwrap_or_default();
                             }
-                            StateMedia::Err(error) => {
-                                out.send(CsamCommandOutput::AddMedia(Err(error)))
+                            StateMedia::Err(path, error) => {
+                                out.send(CsamCommandOutput::MediaError(path, error))
                                     .unwrap_or_default();
                             }
                         }
@@ -486,7 +836,7 @@ impl CsamModel {
                 .drop_on_shutdown()
         });
 
-        self.ctx.csam_service.search_media(path, tx);
+        self.ctx.csam_service.search_media(sources, tx);
     }
 
     async fn on_select_all_medias(&mut self, is_active: bool) {
@@ -552,6 +902,407 @@ impl CsamModel {
         });
     }
 
+    async fn on_generate_report(&mut self, path: PathBuf, sender: AsyncComponentSender<Self>) {
+        let mut selected_media = vec![];
+        for position in 0..self.media_list_wrapper.selection_model.n_items() {
+            let item = self.media_list_wrapper.get_visible(position).unwrap();
+            let item = item.borrow();
+            if item.is_active() {
+                let mut media = core_chasam::csam::Media::from(&item.media);
+                media.img_buf = item.media.img_buf.clone();
+                selected_media.push(media);
+            }
+        }
+
+        if selected_media.is_empty() {
+            sender.input(CsamInput::ShowInfoDialog(fl!("select-media").to_string()));
+            return;
+        }
+
+        let ctx = self.ctx.clone();
+        let preference = ctx.get_preference();
+        let mut info = ReportInfo {
+            case_number: self
+                .case_dir
+                .as_ref()
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            examiner: preference.examiner,
+            evidence_root: self
+                .evidence_roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect::<Vec<String>>()
+                .join("; "),
+            scan_started: self.scan_started,
+            scan_finished: self.scan_finished,
+            databases: Vec::new(),
+        };
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    info.databases = ctx
+                        .csam_service
+                        .database_info(preference.database_path)
+                        .await;
+
+                    match ctx
+                        .csam_service
+                        .generate_report(path, info, selected_media)
+                        .await
+                    {
+                        Ok(_) => {
+                            out.send(CsamCommandOutput::Notify(
+                                fl!("report-success").to_string(),
+                                5,
+                            ))
+                            .unwrap_or_default();
+                        }
+                        Err(err) => {
+                            tracing::error!("Report error: {}", err);
+                            out.send(CsamCommandOutput::Notify(
+                                format!("{}: {}", fl!("report-error"), err),
+                                5,
+                            ))
+                            .unwrap_or_default();
+                        }
+                    }
+                })
+                .drop_on_shutdown()
+        });
+    }
+
+    // Saves the listed media, with the filters applied, and the files that could not be processed.
+    async fn on_save_inventory(&mut self, path: PathBuf, sender: AsyncComponentSender<Self>) {
+        let mut records = vec![];
+        for position in 0..self.media_list_wrapper.selection_model.n_items() {
+            let item = self.media_list_wrapper.get_visible(position).unwrap();
+            let media = core_chasam::csam::Media::from(&item.borrow().media);
+            records.push(InventoryRecord::from(&media));
+        }
+        records.extend(
+            self.scan_errors
+                .iter()
+                .map(|(path, error)| InventoryRecord::error(path, error)),
+        );
+
+        let ctx = self.ctx.clone();
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    match ctx.csam_service.export_inventory(path, records).await {
+                        Ok(_) => {
+                            out.send(CsamCommandOutput::Notify(
+                                fl!("inventory-export-success").to_string(),
+                                5,
+                            ))
+                            .unwrap_or_default();
+                        }
+                        Err(err) => {
+                            tracing::error!("Inventory export error: {}", err);
+                            out.send(CsamCommandOutput::Notify(
+                                format!("{}: {}", fl!("inventory-export-error"), err),
+                                5,
+                            ))
+                            .unwrap_or_default();
+                        }
+                    }
+                })
+                .drop_on_shutdown()
+        });
+    }
+
+    // Exports the selected media or, when none is selected, the listed media.
+    async fn on_export_vics(&mut self, path: PathBuf, sender: AsyncComponentSender<Self>) {
+        let mut listed_media = vec![];
+        let mut selected_media = vec![];
+        for position in 0..self.media_list_wrapper.selection_model.n_items() {
+            let item = self.media_list_wrapper.get_visible(position).unwrap();
+            let item = item.borrow();
+            let media = core_chasam::csam::Media::from(&item.media);
+            if item.is_active() {
+                selected_media.push(media.clone());
+            }
+            listed_media.push(media);
+        }
+        let medias = if selected_media.is_empty() {
+            listed_media
+        } else {
+            selected_media
+        };
+
+        if medias.is_empty() {
+            sender.input(CsamInput::ShowInfoDialog(fl!("select-media").to_string()));
+            return;
+        }
+
+        let ctx = self.ctx.clone();
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    match ctx
+                        .csam_service
+                        .export_vics(path, String::new(), medias)
+                        .await
+                    {
+                        Ok(_) => {
+                            out.send(CsamCommandOutput::Notify(
+                                fl!("vics-export-success").to_string(),
+                                5,
+                            ))
+                            .unwrap_or_default();
+                        }
+                        Err(err) => {
+                            tracing::error!("VICS export error: {}", err);
+                            out.send(CsamCommandOutput::Notify(
+                                format!("{}: {}", fl!("vics-export-error"), err),
+                                5,
+                            ))
+                            .unwrap_or_default();
+                        }
+                    }
+                })
+                .drop_on_shutdown()
+        });
+    }
+
+    async fn on_open_case(&mut self, dir: PathBuf, sender: AsyncComponentSender<Self>) {
+        let ctx = self.ctx.clone();
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    let result = ctx.csam_service.open_case(dir.clone()).await;
+                    out.send(CsamCommandOutput::CaseOpened(dir, result))
+                        .unwrap_or_default();
+                })
+                .drop_on_shutdown()
+        });
+    }
+
+    // Replaces the results with the ones of the case.
+    async fn apply_case(&mut self, dir: PathBuf, case: Case, sender: &AsyncComponentSender<Self>) {
+        self.on_select_all_medias(false).await;
+        self.media_list_wrapper.clear();
+        self.media_details.emit(MediaDetailsInput::Reset);
+        self.detail_path = None;
+        self.statusbar.emit(StatusbarInput::Loading(true));
+
+        let total = case.medias.len();
+        for case_media in case.medias {
+            let mut media = models::Media::from(&case_media.media);
+            media.note = case_media.note;
+            let item = MediaItem::new(media);
+            if item.is_video() {
+                self.statusbar.emit(StatusbarInput::VideoFound(1));
+            } else {
+                self.statusbar.emit(StatusbarInput::ImageFound(1));
+            }
+            if item.is_csam() {
+                self.statusbar.emit(StatusbarInput::CSAMFound(1));
+            }
+            self.media_list_wrapper.append(item);
+            if case_media.selected {
+                let position = self.media_list_wrapper.len() - 1;
+                let item = self.media_list_wrapper.get(position).unwrap();
+                item.borrow_mut().set_active(true);
+            }
+        }
+
+        self.statusbar.emit(StatusbarInput::TotalFound(total));
+        self.statusbar.emit(StatusbarInput::Loading(false));
+
+        self.evidence_roots = case.evidence_roots;
+        self.scan_started = case.scan_started;
+        self.scan_finished = case.scan_finished;
+        self.scan_errors = case.errors;
+        self.case_created = Some(case.created);
+        self.case_dir = Some(dir);
+
+        sender.input(CsamInput::Notify(
+            format!("{}: {}", fl!("case-opened"), case.case_number),
+            5,
+        ));
+    }
+
+    async fn on_save_case(&mut self, dir: PathBuf, sender: AsyncComponentSender<Self>) {
+        let medias = (0..self.media_list_wrapper.len())
+            .filter_map(|position| self.media_list_wrapper.get(position))
+            .map(|item| {
+                let item = item.borrow();
+                let mut media = core_chasam::csam::Media::from(&item.media);
+                media.img_buf = item.media.img_buf.clone();
+                CaseMedia {
+                    media,
+                    selected: item.is_active(),
+                    note: item.media.note.clone(),
+                }
+            })
+            .collect();
+
+        let ctx = self.ctx.clone();
+        let preference = ctx.get_preference();
+        let created = *self
+            .case_created
+            .get_or_insert_with(|| chrono::Local::now().timestamp());
+        let mut case = Case {
+            case_number: dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            examiner: preference.examiner,
+            created,
+            evidence_roots: self.evidence_roots.clone(),
+            scan_started: self.scan_started,
+            scan_finished: self.scan_finished,
+            databases: Vec::new(),
+            errors: self.scan_errors.clone(),
+            medias,
+        };
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    case.databases = ctx
+                        .csam_service
+                        .database_info(preference.database_path)
+                        .await;
+                    let result = ctx.csam_service.save_case(dir.clone(), case).await;
+                    out.send(CsamCommandOutput::CaseSaved(dir, result))
+                        .unwrap_or_default();
+                })
+                .drop_on_shutdown()
+        });
+    }
+
+    async fn on_rematch_media(&mut self, sender: AsyncComponentSender<Self>) {
+        if self.media_list_wrapper.is_empty() {
+            return;
+        }
+
+        let medias: Vec<core_chasam::csam::Media> = (0..self.media_list_wrapper.len())
+            .filter_map(|position| self.media_list_wrapper.get(position))
+            .map(|item| core_chasam::csam::Media::from(&item.borrow().media))
+            .collect();
+        let ctx = self.ctx.clone();
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    let result = ctx.csam_service.rematch_media(medias).await;
+                    out.send(CsamCommandOutput::MediaRematched(result))
+                        .unwrap_or_default();
+                })
+                .drop_on_shutdown()
+        });
+    }
+
+    // Updates the matches of the grid items, the suspects counter and the filters.
+    async fn apply_media_matches(
+        &mut self,
+        medias: Vec<core_chasam::csam::Media>,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        let mut changed = 0;
+        for (position, media) in medias.into_iter().enumerate() {
+            let Some(item) = self.media_list_wrapper.get(position as u32) else {
+                break;
+            };
+            let mut item = item.borrow_mut();
+            // the list was replaced by a new search meanwhile
+            if item.media.path != media.path {
+                return;
+            }
+            if item.media.matched != media.matched {
+                item.media.phash = media.phash;
+                item.media.matched = media.matched;
+                changed += 1;
+            }
+        }
+
+        let suspects = (0..self.media_list_wrapper.len())
+            .filter_map(|position| self.media_list_wrapper.get(position))
+            .filter(|item| item.borrow().is_csam())
+            .count();
+        self.statusbar.emit(StatusbarInput::SetCSAMFound(suspects));
+        self.media_details.emit(MediaDetailsInput::Reset);
+        self.detail_path = None;
+
+        // filtering again also redraws the highlight of the items
+        self.apply_media_filters().await;
+
+        let locale = self.ctx.get_locale();
+        sender.input(CsamInput::Notify(
+            format!(
+                "{}: {}",
+                fl!("rematched-media"),
+                changed.to_formatted_string(&locale)
+            ),
+            5,
+        ));
+    }
+
+    async fn on_tag_media(&mut self, tag: Option<Tag>) {
+        let Some(path) = self.detail_path.as_ref() else {
+            return;
+        };
+
+        for position in 0..self.media_list_wrapper.len() {
+            let item = self.media_list_wrapper.get(position).unwrap();
+            let mut item = item.borrow_mut();
+            if &item.media.path == path {
+                item.media.tag = tag;
+                break;
+            }
+        }
+        self.media_details.emit(MediaDetailsInput::SetTag(
+            tag.map(models::media::tag_name).unwrap_or_default(),
+        ));
+
+        // filtering again also redraws the tag of the items
+        self.apply_media_filters().await;
+    }
+
+    // Adds the media tagged as CSAM to the local databases, with the case as their source.
+    async fn on_add_tagged_to_database(&mut self, sender: AsyncComponentSender<Self>) {
+        let medias: Vec<core_chasam::csam::Media> = (0..self.media_list_wrapper.len())
+            .filter_map(|position| self.media_list_wrapper.get(position))
+            .filter(|item| item.borrow().media.tag.is_some_and(|tag| tag.is_csam()))
+            .map(|item| core_chasam::csam::Media::from(&item.borrow().media))
+            .collect();
+
+        if medias.is_empty() {
+            sender.input(CsamInput::ShowInfoDialog(fl!("tag-csam-media").to_string()));
+            return;
+        }
+
+        let ctx = self.ctx.clone();
+        let preference = ctx.get_preference();
+        let source = self
+            .case_dir
+            .as_ref()
+            .and_then(|dir| dir.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| info::APP_NAME.to_string());
+
+        sender.command(|out, shutdown| {
+            shutdown
+                .register(async move {
+                    let result = ctx
+                        .csam_service
+                        .add_tagged_media(preference.database_path, source, medias)
+                        .await;
+                    out.send(CsamCommandOutput::TaggedMediaAdded(result))
+                        .unwrap_or_default();
+                })
+                .drop_on_shutdown()
+        });
+    }
+
     async fn apply_media_filters(&mut self) {
         self.media_list_wrapper.set_filter_status(0, false);
         self.media_list_wrapper.set_filter_status(0, true);
@@ -603,15 +1354,27 @@ fn on_filter(filter: Rc<RefCell<models::MediaFilter>>) -> impl Fn(&MediaItem) ->
         }
 
         // filter by CSAM file
-        if filter.is_csam && media.match_type.is_empty() {
+        if filter.is_csam && !media.is_csam() {
             return false;
         }
 
+        // filter by extension that does not match the file signature
+        if filter.is_mismatch && !media.extension_mismatch {
+            return false;
+        }
+
+        // filter by tag of the examiner
+        match filter.tag {
+            models::TagFilter::Untagged if media.tag.is_some() => return false,
+            models::TagFilter::Tag(tag) if media.tag != Some(tag) => return false,
+            _ => (),
+        }
+
         // filter by hamming distance
         // if filter.is_csam && (media.hamming > filter.hamming_distance) {
         //     return false;
         // }
-        if (media.hamming > 0) && (media.hamming > filter.hamming_distance) {
+        if (media.hamming() > 0) && (media.hamming() > filter.hamming_distance) {
             return false;
         }
 
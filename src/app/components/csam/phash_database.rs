@@ -20,6 +20,7 @@ use crate::app::components::progress_dialog::{
     ProgressDialog, ProgressDialogOutput, ProgressSettings,
 };
 use crate::{context::AppContext, fl};
+use core_chasam::csam::DirectorySource;
 
 pub struct PHashDatabaseModel {
     ctx: AppContext,
@@ -240,7 +241,9 @@ impl PHashDatabaseModel {
         }
 
         let ctx = self.ctx.clone();
-        let db_path = ctx.get_preference().database_path.clone();
+        let preference = ctx.get_preference();
+        let db_path = preference.database_path.clone();
+        let phash_algorithms = preference.phash_algorithms.clone();
         let media_path = self.media_path.clone();
 
         sender.command(|out, shutdown| {
@@ -251,7 +254,11 @@ impl PHashDatabaseModel {
 
                     match ctx
                         .csam_service
-                        .create_phash_database(db_path, media_path)
+                        .create_phash_database(
+                            db_path,
+                            Box::new(DirectorySource::new(media_path)),
+                            phash_algorithms,
+                        )
                         .await
                     {
                         Ok(count) => {
@@ -18,6 +18,8 @@ pub struct StatusbarModel {
     video_found: usize,
     suspects_found: usize,
     total_found: usize,
+    /// Root being read when the search has several ones.
+    search_root: Option<String>,
 }
 
 #[derive(Debug)]
@@ -26,8 +28,11 @@ pub enum StatusbarInput {
     Calculating,
     ImageFound(usize),
     CSAMFound(usize),
+    /// Suspects after the media were matched again.
+    SetCSAMFound(usize),
     VideoFound(usize),
     TotalFound(usize),
+    SearchRoot(usize, usize, String),
 }
 
 #[relm4::component(pub)]
@@ -67,6 +72,15 @@ impl Component for StatusbarModel {
                         fl!("done").to_string()
                     },
                 },
+
+                gtk::Label {
+                    #[watch]
+                    set_visible: model.is_loading && model.search_root.is_some(),
+                    #[watch]
+                    set_label: model.search_root.as_deref().unwrap_or_default(),
+                    set_max_width_chars: 60,
+                    set_ellipsize: gtk::pango::EllipsizeMode::Middle,
+                },
             },
 
             gtk::Separator {
@@ -151,6 +165,7 @@ impl Component for StatusbarModel {
             video_found: 0,
             suspects_found: 0,
             total_found: 0,
+            search_root: None,
         };
         let widgets = view_output!();
 
@@ -173,6 +188,7 @@ impl Component for StatusbarModel {
                     self.suspects_found = 0;
                     self.video_found = 0;
                     self.total_found = 0;
+                    self.search_root = None;
                     widgets.spinner.start();
                 } else {
                     self.is_loading = false;
@@ -182,11 +198,16 @@ impl Component for StatusbarModel {
             StatusbarInput::Calculating => self.is_calculating = true,
             StatusbarInput::ImageFound(found) => self.image_found += found,
             StatusbarInput::CSAMFound(found) => self.suspects_found += found,
+            StatusbarInput::SetCSAMFound(found) => self.suspects_found = found,
             StatusbarInput::VideoFound(found) => self.video_found += found,
             StatusbarInput::TotalFound(found) => {
                 self.total_found = found;
                 self.is_calculating = false;
             }
+            StatusbarInput::SearchRoot(index, count, name) => {
+                self.search_root = (count > 1)
+                    .then(|| format!("{} {}/{}: {}", fl!("root"), index + 1, count, name));
+            }
         }
 
         self.update_view(widgets, sender);
@@ -10,8 +10,9 @@ use relm4::{
 };
 use relm4_icons::icon_names;
 
+use crate::app::models;
 use crate::{context::AppContext, fl};
-use core_chasam::csam::Media;
+use core_chasam::csam::{Media, Tag};
 
 pub static SELECT_BROKER: MessageBroker<ToolbarInput> = MessageBroker::new();
 
@@ -43,11 +44,17 @@ pub enum ToolbarOutput {
     ZoomOut,
     SelectAll(bool),
     SaveSelected,
+    GenerateReport,
+    ExportVics,
+    SaveInventory,
+    AddTaggedToDatabase,
     HammingDistanceFilter(u32),
     SearchEntry(String),
     ImageFilter(bool),
     VideoFilter(bool),
     CSAMFilter(bool),
+    MismatchFilter(bool),
+    TagFilter(models::TagFilter),
     SizeFilter0KB(bool),
     SizeFilter30KB(bool),
     SizeFilter100KB(bool),
@@ -73,7 +80,7 @@ impl Component for ToolbarModel {
 
             gtk::Box {
                 set_orientation: gtk::Orientation::Horizontal,
-                set_width_request: 450,
+                set_width_request: 490,
                 set_hexpand: false,
                 set_halign: gtk::Align::Start,
 
@@ -105,6 +112,42 @@ impl Component for ToolbarModel {
                     },
                 },
 
+                gtk::Button {
+                    set_icon_name: "x-office-document-symbolic",
+                    set_tooltip: fl!("generate-report"),
+                    add_css_class: "flat",
+                    connect_clicked[sender] => move |_| {
+                        sender.output(ToolbarOutput::GenerateReport).unwrap_or_default();
+                    },
+                },
+
+                gtk::Button {
+                    set_icon_name: "document-save-as-symbolic",
+                    set_tooltip: fl!("export-vics"),
+                    add_css_class: "flat",
+                    connect_clicked[sender] => move |_| {
+                        sender.output(ToolbarOutput::ExportVics).unwrap_or_default();
+                    },
+                },
+
+                gtk::Button {
+                    set_icon_name: "x-office-spreadsheet-symbolic",
+                    set_tooltip: fl!("save-inventory"),
+                    add_css_class: "flat",
+                    connect_clicked[sender] => move |_| {
+                        sender.output(ToolbarOutput::SaveInventory).unwrap_or_default();
+                    },
+                },
+
+                gtk::Button {
+                    set_icon_name: "list-add-symbolic",
+                    set_tooltip: fl!("add-tagged-to-database"),
+                    add_css_class: "flat",
+                    connect_clicked[sender] => move |_| {
+                        sender.output(ToolbarOutput::AddTaggedToDatabase).unwrap_or_default();
+                    },
+                },
+
                 gtk::Label {
                     set_label: fl!("selected-count"),
                     set_xalign: 0.0,
@@ -252,6 +295,58 @@ impl Component for ToolbarModel {
                                             },
                                         }
                                     },
+
+                                    gtk::ListBoxRow {
+                                        gtk::Box {
+                                            #[name(chk_mismatch)]
+                                            gtk::CheckButton {
+                                                set_halign: gtk::Align::Start,
+                                                set_valign: gtk::Align::Center,
+                                                set_margin_start: 0,
+                                                set_margin_end: 0,
+                                                set_active: false,
+                                                connect_toggled[sender] => move |checkbox| {
+                                                    sender
+                                                        .output(ToolbarOutput::MismatchFilter(checkbox.is_active()))
+                                                        .unwrap_or_default();
+                                                }
+                                            },
+                                            gtk::Label {
+                                                set_label: fl!("extension-mismatch"),
+                                                set_xalign: 0.0,
+                                                set_halign: gtk::Align::Start,
+                                                set_valign: gtk::Align::Center,
+                                                set_hexpand: true,
+                                            },
+                                        }
+                                    },
+                                },
+                            },
+
+                            gtk::Label {
+                                set_label: fl!("tag"),
+                                set_xalign: 0.0,
+                                set_margin_bottom: 3,
+                                set_css_classes: &["title-4"],
+                            },
+
+                            gtk::Frame {
+                                #[name(dropdown_tag)]
+                                gtk::DropDown {
+                                    set_model: Some(&tag_options),
+                                    set_margin_all: 3,
+                                    connect_selected_notify[sender] => move |dropdown| {
+                                        // the first options are all media and the untagged ones
+                                        let filter = match dropdown.selected() {
+                                            0 => models::TagFilter::All,
+                                            1 => models::TagFilter::Untagged,
+                                            index => Tag::ALL
+                                                .get(index as usize - 2)
+                                                .map(|tag| models::TagFilter::Tag(*tag))
+                                                .unwrap_or_default(),
+                                        };
+                                        sender.output(ToolbarOutput::TagFilter(filter)).unwrap_or_default();
+                                    },
                                 },
                             },
 
@@ -513,6 +608,13 @@ impl Component for ToolbarModel {
             ctx,
             selection_count: 0,
         };
+
+        let tag_options =
+            gtk::StringList::new(&[fl!("all-tags").as_str(), fl!("untagged").as_str()]);
+        for tag in Tag::ALL {
+            tag_options.append(&models::media::tag_name(tag));
+        }
+
         let widgets = view_output!();
 
         ComponentParts { model, widgets }
@@ -537,6 +639,8 @@ impl Component for ToolbarModel {
             }
             ToolbarInput::CleanFilters => {
                 widgets.chk_csam.set_active(false);
+                widgets.chk_mismatch.set_active(false);
+                widgets.dropdown_tag.set_selected(0);
                 widgets.chk_image.set_active(true);
                 widgets.chk_video.set_active(true);
                 widgets.chk_all_size.set_active(true);
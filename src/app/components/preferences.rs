@@ -1,12 +1,14 @@
 use std::path::PathBuf;
 
+use core_chasam::csam::{PHashAlgorithm, SamplingMode};
 use num_format::ToFormattedString;
 use relm4::{
     adw,
     adw::prelude::{
         ActionRowExt, AdwWindowExt, BoxExt, ButtonExt, CheckButtonExt, ComboRowExt, EditableExt,
-        EntryRowExt, GtkWindowExt, OrientableExt, PreferencesGroupExt, PreferencesPageExt,
-        PreferencesRowExt, PreferencesWindowExt, WidgetExt,
+        EntryRowExt, ExpanderRowExt, GtkWindowExt, OrientableExt, PreferencesGroupExt,
+        PreferencesPageExt, PreferencesRowExt, PreferencesWindowExt, SpinRowExt, SwitchExt,
+        WidgetExt,
     },
     component::{
         AsyncComponent, AsyncComponentController, AsyncComponentParts, AsyncController, Component,
@@ -23,6 +25,7 @@ use crate::app::components::csam::{
     keyword_database::{KeywordDatabaseInput, KeywordDatabaseModel, KeywordDatabaseOutput},
     md5_database::{MD5DatabaseModel, MD5DatabaseOutput},
     phash_database::{PHashDatabaseModel, PHashDatabaseOutput},
+    CsamInput, CSAM_BROKER,
 };
 use crate::app::{components::dialogs, config::settings, models};
 use crate::{context::AppContext, fl};
@@ -36,6 +39,8 @@ pub struct PreferencesModel {
     keyword_database: AsyncController<KeywordDatabaseModel>,
     hash_count: String,
     phash_count: String,
+    pdq_count: String,
+    video_count: String,
     keywords_count: String,
 }
 
@@ -53,6 +58,14 @@ pub enum PreferencesInput {
     OpenFileResponse(PathBuf),
     SetColorScheme(models::ColorScheme),
     SetLanguage(models::Language),
+    SetStorage(models::Storage),
+    SetExaminer(String),
+    SetArchiveDepth(usize),
+    SetPHashAlgorithm(PHashAlgorithm, bool),
+    SetPHashTransforms(bool),
+    SetPdqThreshold(u32),
+    SetVideoSampling(SamplingMode),
+    SetMaxVideoFrames(usize),
     UpdateInfoView(InfoType),
     AddHash,
     AddPHash,
@@ -254,6 +267,28 @@ impl AsyncComponent for PreferencesModel {
                                         },
                                     },
 
+                                    adw::ComboRow {
+                                        set_title: fl!("storage"),
+                                        set_model: Some(&gtk::StringList::new(&[
+                                            fl!("storage-memory"),
+                                            fl!("storage-sqlite"),
+                                        ])),
+                                        set_selected: match model.preference.storage {
+                                            models::Storage::Memory => 0,
+                                            models::Storage::SQLite => 1,
+                                        },
+                                        connect_selected_notify[sender] => move |combo_row| {
+                                            match combo_row.selected() {
+                                                1 => sender.input_sender().send(
+                                                    PreferencesInput::SetStorage(models::Storage::SQLite)
+                                                ).unwrap_or_default(),
+                                                _ => sender.input_sender().send(
+                                                    PreferencesInput::SetStorage(models::Storage::Memory)
+                                                ).unwrap_or_default(),
+                                            }
+                                        },
+                                    },
+
                                     adw::ActionRow {
                                         set_title: fl!("hash"),
                                         #[watch]
@@ -288,6 +323,75 @@ impl AsyncComponent for PreferencesModel {
                                         },
                                     },
 
+                                    adw::ExpanderRow {
+                                        set_title: fl!("phash-algorithms"),
+                                        set_subtitle: fl!("phash-algorithms-description"),
+                                        add_row = &adw::ActionRow {
+                                            set_title: &PHashAlgorithm::PHash.to_string(),
+                                            add_suffix = &gtk::CheckButton {
+                                                #[watch]
+                                                set_active: model.preference.phash_algorithms.contains(&PHashAlgorithm::PHash),
+                                                connect_toggled[sender] => move |chk_button| {
+                                                    sender.input(PreferencesInput::SetPHashAlgorithm(
+                                                        PHashAlgorithm::PHash,
+                                                        chk_button.is_active(),
+                                                    ));
+                                                },
+                                            },
+                                        },
+                                        add_row = &adw::ActionRow {
+                                            set_title: &PHashAlgorithm::PHashGray.to_string(),
+                                            add_suffix = &gtk::CheckButton {
+                                                #[watch]
+                                                set_active: model.preference.phash_algorithms.contains(&PHashAlgorithm::PHashGray),
+                                                connect_toggled[sender] => move |chk_button| {
+                                                    sender.input(PreferencesInput::SetPHashAlgorithm(
+                                                        PHashAlgorithm::PHashGray,
+                                                        chk_button.is_active(),
+                                                    ));
+                                                },
+                                            },
+                                        },
+                                        add_row = &adw::ActionRow {
+                                            set_title: &PHashAlgorithm::DHash.to_string(),
+                                            add_suffix = &gtk::CheckButton {
+                                                #[watch]
+                                                set_active: model.preference.phash_algorithms.contains(&PHashAlgorithm::DHash),
+                                                connect_toggled[sender] => move |chk_button| {
+                                                    sender.input(PreferencesInput::SetPHashAlgorithm(
+                                                        PHashAlgorithm::DHash,
+                                                        chk_button.is_active(),
+                                                    ));
+                                                },
+                                            },
+                                        },
+                                        add_row = &adw::ActionRow {
+                                            set_title: &PHashAlgorithm::AHash.to_string(),
+                                            add_suffix = &gtk::CheckButton {
+                                                #[watch]
+                                                set_active: model.preference.phash_algorithms.contains(&PHashAlgorithm::AHash),
+                                                connect_toggled[sender] => move |chk_button| {
+                                                    sender.input(PreferencesInput::SetPHashAlgorithm(
+                                                        PHashAlgorithm::AHash,
+                                                        chk_button.is_active(),
+                                                    ));
+                                                },
+                                            },
+                                        },
+                                    },
+
+                                    adw::ActionRow {
+                                        set_title: fl!("pdq"),
+                                        #[watch]
+                                        set_subtitle: &model.pdq_count,
+                                    },
+
+                                    adw::ActionRow {
+                                        set_title: fl!("known-videos"),
+                                        #[watch]
+                                        set_subtitle: &model.video_count,
+                                    },
+
                                     adw::ActionRow {
                                         set_title: fl!("keywords"),
                                         #[watch]
@@ -305,6 +409,106 @@ impl AsyncComponent for PreferencesModel {
                                         },
                                     },
                                 },
+
+                                add = &adw::PreferencesGroup {
+                                    set_title: fl!("search"),
+
+                                    adw::SpinRow {
+                                        set_title: fl!("archive-depth"),
+                                        set_subtitle: fl!("archive-depth-description"),
+                                        set_adjustment: Some(&gtk::Adjustment::new(
+                                            model.preference.archive_depth as f64,
+                                            0.0,
+                                            10.0,
+                                            1.0,
+                                            1.0,
+                                            0.0,
+                                        )),
+                                        connect_value_notify[sender] => move |spin_row| {
+                                            sender.input(PreferencesInput::SetArchiveDepth(spin_row.value() as usize));
+                                        },
+                                    },
+
+                                    adw::ActionRow {
+                                        set_title: fl!("phash-transforms"),
+                                        set_subtitle: fl!("phash-transforms-description"),
+                                        add_suffix = &gtk::Switch {
+                                            set_valign: gtk::Align::Center,
+                                            set_active: model.preference.phash_transforms,
+                                            connect_active_notify[sender] => move |switch| {
+                                                sender.input(PreferencesInput::SetPHashTransforms(switch.is_active()));
+                                            },
+                                        },
+                                    },
+
+                                    adw::SpinRow {
+                                        set_title: fl!("pdq-threshold"),
+                                        set_subtitle: fl!("pdq-threshold-description"),
+                                        set_adjustment: Some(&gtk::Adjustment::new(
+                                            model.preference.pdq_threshold as f64,
+                                            0.0,
+                                            128.0,
+                                            1.0,
+                                            10.0,
+                                            0.0,
+                                        )),
+                                        connect_value_notify[sender] => move |spin_row| {
+                                            sender.input(PreferencesInput::SetPdqThreshold(spin_row.value() as u32));
+                                        },
+                                    },
+
+                                    adw::ComboRow {
+                                        set_title: fl!("video-sampling"),
+                                        set_subtitle: fl!("video-sampling-description"),
+                                        set_model: Some(&gtk::StringList::new(&[
+                                            fl!("video-sampling-interval"),
+                                            fl!("video-sampling-evenly"),
+                                            fl!("video-sampling-keyframes"),
+                                            fl!("video-sampling-scene-change"),
+                                        ])),
+                                        set_selected: SamplingMode::ALL
+                                            .iter()
+                                            .position(|mode| *mode == model.preference.video_sampling)
+                                            .unwrap_or_default() as u32,
+                                        connect_selected_notify[sender] => move |combo_row| {
+                                            let mode = SamplingMode::ALL
+                                                .get(combo_row.selected() as usize)
+                                                .copied()
+                                                .unwrap_or_default();
+                                            sender.input(PreferencesInput::SetVideoSampling(mode));
+                                        },
+                                    },
+
+                                    adw::SpinRow {
+                                        set_title: fl!("max-video-frames"),
+                                        set_subtitle: fl!("max-video-frames-description"),
+                                        set_adjustment: Some(&gtk::Adjustment::new(
+                                            model.preference.max_video_frames as f64,
+                                            1.0,
+                                            1000.0,
+                                            1.0,
+                                            10.0,
+                                            0.0,
+                                        )),
+                                        connect_value_notify[sender] => move |spin_row| {
+                                            sender.input(PreferencesInput::SetMaxVideoFrames(spin_row.value() as usize));
+                                        },
+                                    },
+                                },
+
+                                add = &adw::PreferencesGroup {
+                                    set_title: fl!("report"),
+
+                                    adw::EntryRow {
+                                        set_hexpand: true,
+                                        set_title: fl!("examiner"),
+                                        set_show_apply_button: true,
+                                        set_text: &model.preference.examiner,
+                                        connect_apply[sender] => move |entry_row| {
+                                            sender.input(PreferencesInput::SetExaminer(entry_row.text().to_string()));
+                                        },
+                                    },
+                                },
                             }
                         },
                     } -> {
@@ -398,6 +602,16 @@ impl AsyncComponent for PreferencesModel {
             .count_phash()
             .await
             .to_formatted_string(&locale);
+        let pdq_count = ctx
+            .csam_service
+            .count_pdq()
+            .await
+            .to_formatted_string(&locale);
+        let video_count = ctx
+            .csam_service
+            .count_video()
+            .await
+            .to_formatted_string(&locale);
         let keywords_count = ctx
             .csam_service
             .count_keyword()
@@ -413,6 +627,8 @@ impl AsyncComponent for PreferencesModel {
             keyword_database: keyword_database_controller,
             hash_count,
             phash_count,
+            pdq_count,
+            video_count,
             keywords_count,
         };
 
@@ -437,7 +653,11 @@ impl AsyncComponent for PreferencesModel {
                 self.save_preferences().await;
 
                 match self.ctx.csam_service.load_database(db_path).await {
-                    Ok(_) => self.update_info_view(InfoType::All).await,
+                    Ok(_) => {
+                        self.update_info_view(InfoType::All).await;
+                        // the results of the last search still hold the old matches
+                        CSAM_BROKER.send(CsamInput::RematchMedia);
+                    }
                     Err(err) => tracing::error!("{err}"),
                 }
 
@@ -458,6 +678,52 @@ impl AsyncComponent for PreferencesModel {
                     Some(fl!("message-dialog")),
                 );
             }
+            PreferencesInput::SetStorage(storage) => {
+                self.preference.storage = storage;
+                self.save_preferences().await;
+                dialogs::show_info_dialog(
+                    Some(root),
+                    Some(fl!("preferences")),
+                    Some(fl!("message-dialog")),
+                );
+            }
+            PreferencesInput::SetExaminer(examiner) => {
+                self.preference.set_examiner(&examiner);
+                self.save_preferences().await;
+            }
+            PreferencesInput::SetArchiveDepth(archive_depth) => {
+                self.preference.set_archive_depth(archive_depth);
+                self.ctx.csam_service.set_archive_depth(archive_depth);
+                self.save_preferences().await;
+            }
+            PreferencesInput::SetPHashAlgorithm(algorithm, enabled) => {
+                self.preference.set_phash_algorithm(algorithm, enabled);
+                self.save_preferences().await;
+            }
+            PreferencesInput::SetPHashTransforms(enabled) => {
+                self.preference.set_phash_transforms(enabled);
+                self.ctx.csam_service.set_phash_transforms(enabled);
+                self.save_preferences().await;
+            }
+            PreferencesInput::SetPdqThreshold(pdq_threshold) => {
+                self.preference.set_pdq_threshold(pdq_threshold);
+                self.ctx.csam_service.set_pdq_threshold(pdq_threshold);
+                self.save_preferences().await;
+            }
+            PreferencesInput::SetVideoSampling(video_sampling) => {
+                self.preference.set_video_sampling(video_sampling);
+                self.ctx
+                    .csam_service
+                    .set_video_sampling(self.preference.sampling());
+                self.save_preferences().await;
+            }
+            PreferencesInput::SetMaxVideoFrames(max_video_frames) => {
+                self.preference.set_max_video_frames(max_video_frames);
+                self.ctx
+                    .csam_service
+                    .set_video_sampling(self.preference.sampling());
+                self.save_preferences().await;
+            }
             PreferencesInput::UpdateInfoView(info_type) => {
                 self.update_info_view(info_type).await;
             }
@@ -507,6 +773,8 @@ impl PreferencesModel {
             InfoType::All => {
                 self.hash_count = service.count_hash().await.to_formatted_string(&locale);
                 self.phash_count = service.count_phash().await.to_formatted_string(&locale);
+                self.pdq_count = service.count_pdq().await.to_formatted_string(&locale);
+                self.video_count = service.count_video().await.to_formatted_string(&locale);
                 self.keywords_count = service.count_keyword().await.to_formatted_string(&locale);
             }
         }
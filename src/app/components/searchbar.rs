@@ -13,25 +13,34 @@ use relm4::{
 use relm4_components::open_dialog::*;
 use relm4_icons::icon_names;
 
+use core_chasam::csam::read_path_list;
+
 pub struct SearchBarModel {
-    open_dialog: Controller<OpenDialog>,
+    open_dialog: Controller<OpenDialogMulti>,
+    disk_image_dialog: Controller<OpenDialog>,
+    path_list_dialog: Controller<OpenDialog>,
     stopped: bool,
-    file_path: PathBuf,
+    file_paths: Vec<PathBuf>,
 }
 
 #[derive(Debug)]
 pub enum SearchBarInput {
     StartSearch,
+    StartCarving,
     StopSearch,
     SearchCompleted,
     OpenFileRequest,
-    OpenFileResponse(PathBuf),
+    OpenDiskImageRequest,
+    OpenPathListRequest,
+    OpenFileResponse(Vec<PathBuf>),
+    OpenPathListResponse(PathBuf),
     Ignore,
 }
 
 #[derive(Debug)]
 pub enum SearchBarOutput {
-    StartSearch(PathBuf),
+    StartSearch(Vec<PathBuf>),
+    StartCarving(PathBuf),
     StopSearch,
     Notify(String, u32),
 }
@@ -54,19 +63,34 @@ impl Component for SearchBarModel {
 
             append = &gtk::Entry {
                 #[watch]
-                set_text: &model.file_path.to_str().unwrap_or_default(),
+                set_text: &model.file_paths
+                    .iter()
+                    .map(|path| path.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("; "),
                 set_hexpand: true,
                 set_halign: gtk::Align::Fill,
                 set_placeholder_text: Some(fl!("directory")),
                 set_secondary_icon_name: Some(icon_names::FOLDER_OPEN_FILLED),
                 set_secondary_icon_tooltip_text: Some(fl!("select-directory")),
+                set_primary_icon_name: Some("drive-harddisk-symbolic"),
+                set_primary_icon_tooltip_text: Some(fl!("select-disk-image")),
                 connect_icon_release[sender] => move |_, icon_position| {
-                    if icon_position == gtk::EntryIconPosition::Secondary {
-                        sender.input(SearchBarInput::OpenFileRequest);
+                    match icon_position {
+                        gtk::EntryIconPosition::Secondary => {
+                            sender.input(SearchBarInput::OpenFileRequest);
+                        }
+                        _ => sender.input(SearchBarInput::OpenDiskImageRequest),
                     }
                 },
             },
 
+            append = &gtk::Button {
+                set_icon_name: "view-list-symbolic",
+                set_tooltip_text: Some(fl!("select-path-list")),
+                connect_clicked => SearchBarInput::OpenPathListRequest,
+            },
+
             append = &gtk::Button {
                 #[watch]
                 set_visible: model.stopped,
@@ -76,6 +100,16 @@ impl Component for SearchBarModel {
                 connect_clicked => SearchBarInput::StartSearch,
             },
 
+            append = &gtk::Button {
+                #[watch]
+                set_visible: model.stopped,
+                #[watch]
+                set_sensitive: matches!(model.file_paths.as_slice(), [path] if path.is_file()),
+                set_icon_name: "edit-cut-symbolic",
+                set_tooltip_text: Some(fl!("carve")),
+                connect_clicked => SearchBarInput::StartCarving,
+            },
+
             append = &gtk::Button {
                 #[watch]
                 set_visible: !model.stopped,
@@ -101,18 +135,68 @@ impl Component for SearchBarModel {
             filters: Vec::new(),
         };
 
-        let open_dialog = OpenDialog::builder()
+        // several directories, such as mount points, are searched in one job
+        let open_dialog = OpenDialogMulti::builder()
             .transient_for_native(&root)
             .launch(open_dialog_settings)
             .forward(sender.input_sender(), |response| match response {
-                OpenDialogResponse::Accept(path) => SearchBarInput::OpenFileResponse(path),
+                OpenDialogResponse::Accept(paths) => SearchBarInput::OpenFileResponse(paths),
+                OpenDialogResponse::Cancel => SearchBarInput::Ignore,
+            });
+
+        let disk_image_filter = gtk::FileFilter::new();
+        for pattern in ["*.dd", "*.raw", "*.img", "*.001", "*.[eE]01"] {
+            disk_image_filter.add_pattern(pattern);
+        }
+        disk_image_filter.set_name(Some(fl!("disk-image-filter")));
+        // any file can be carved: pagefiles, memory dumps, unallocated space, ...
+        let all_files_filter = gtk::FileFilter::new();
+        all_files_filter.add_pattern("*");
+        all_files_filter.set_name(Some(fl!("all-files-filter")));
+        let disk_image_dialog_settings = OpenDialogSettings {
+            folder_mode: false,
+            accept_label: String::from(fl!("open")),
+            cancel_label: String::from(fl!("cancel")),
+            create_folders: false,
+            is_modal: true,
+            filters: vec![disk_image_filter, all_files_filter],
+        };
+
+        let disk_image_dialog = OpenDialog::builder()
+            .transient_for_native(&root)
+            .launch(disk_image_dialog_settings)
+            .forward(sender.input_sender(), |response| match response {
+                OpenDialogResponse::Accept(path) => SearchBarInput::OpenFileResponse(vec![path]),
+                OpenDialogResponse::Cancel => SearchBarInput::Ignore,
+            });
+
+        let path_list_filter = gtk::FileFilter::new();
+        path_list_filter.add_pattern("*.txt");
+        path_list_filter.add_pattern("*.lst");
+        path_list_filter.set_name(Some(fl!("path-list-filter")));
+        let path_list_dialog_settings = OpenDialogSettings {
+            folder_mode: false,
+            accept_label: String::from(fl!("open")),
+            cancel_label: String::from(fl!("cancel")),
+            create_folders: false,
+            is_modal: true,
+            filters: vec![path_list_filter],
+        };
+
+        let path_list_dialog = OpenDialog::builder()
+            .transient_for_native(&root)
+            .launch(path_list_dialog_settings)
+            .forward(sender.input_sender(), |response| match response {
+                OpenDialogResponse::Accept(path) => SearchBarInput::OpenPathListResponse(path),
                 OpenDialogResponse::Cancel => SearchBarInput::Ignore,
             });
 
         let model = SearchBarModel {
             open_dialog,
+            disk_image_dialog,
+            path_list_dialog,
             stopped: true,
-            file_path: PathBuf::default(),
+            file_paths: Vec::new(),
         };
         let widgets = view_output!();
 
@@ -122,11 +206,11 @@ impl Component for SearchBarModel {
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
         match message {
             SearchBarInput::StartSearch => {
-                if self.file_path.exists() {
+                if !self.file_paths.is_empty() && self.file_paths.iter().all(|path| path.exists()) {
                     self.stopped = false;
-                    let file_path = self.file_path.clone();
+                    let file_paths = self.file_paths.clone();
                     sender
-                        .output(SearchBarOutput::StartSearch(file_path))
+                        .output(SearchBarOutput::StartSearch(file_paths))
                         .unwrap_or_default();
                 } else {
                     let msg = fl!("invalid-directory").to_string();
@@ -135,6 +219,20 @@ impl Component for SearchBarModel {
                         .unwrap_or_default();
                 }
             }
+            SearchBarInput::StartCarving => match self.file_paths.as_slice() {
+                [file_path] if file_path.is_file() => {
+                    self.stopped = false;
+                    sender
+                        .output(SearchBarOutput::StartCarving(file_path.clone()))
+                        .unwrap_or_default();
+                }
+                _ => {
+                    let msg = fl!("invalid-carving-file").to_string();
+                    sender
+                        .output(SearchBarOutput::Notify(msg, 5))
+                        .unwrap_or_default();
+                }
+            },
             SearchBarInput::StopSearch => {
                 sender
                     .output(SearchBarOutput::StopSearch)
@@ -146,9 +244,24 @@ impl Component for SearchBarModel {
             SearchBarInput::OpenFileRequest => {
                 self.open_dialog.emit(OpenDialogMsg::Open);
             }
-            SearchBarInput::OpenFileResponse(path) => {
-                self.file_path = path;
+            SearchBarInput::OpenDiskImageRequest => {
+                self.disk_image_dialog.emit(OpenDialogMsg::Open);
+            }
+            SearchBarInput::OpenPathListRequest => {
+                self.path_list_dialog.emit(OpenDialogMsg::Open);
             }
+            SearchBarInput::OpenFileResponse(paths) => {
+                self.file_paths = paths;
+            }
+            SearchBarInput::OpenPathListResponse(path) => match read_path_list(&path) {
+                Ok(paths) => self.file_paths = paths,
+                Err(err) => {
+                    let msg = format!("{}: {}", fl!("generic-error"), err);
+                    sender
+                        .output(SearchBarOutput::Notify(msg, 5))
+                        .unwrap_or_default();
+                }
+            },
             SearchBarInput::Ignore => (),
         }
     }
@@ -1,14 +1,17 @@
 use relm4::{
     component::{AsyncComponentParts, SimpleAsyncComponent},
     factory::AsyncFactoryVecDeque,
-    gtk::prelude::{OrientableExt, WidgetExt},
+    gtk::prelude::{ButtonExt, OrientableExt, WidgetExt},
     prelude::*,
+    RelmWidgetExt,
 };
+use relm4_icons::icon_names;
 
 use crate::app::{
     factories::sidebar_option::{SidebarOptionModel, SidebarOptionOutput},
     models,
 };
+use crate::fl;
 
 pub struct SidebarModel {
     sidebar_option_factory: AsyncFactoryVecDeque<SidebarOptionModel>,
@@ -17,11 +20,15 @@ pub struct SidebarModel {
 #[derive(Debug)]
 pub enum SidebarInput {
     SelectedOption(models::SidebarOption),
+    OpenCase,
+    SaveCase,
 }
 
 #[derive(Debug)]
 pub enum SidebarOutput {
     SelectedOption(models::SidebarOption),
+    OpenCase,
+    SaveCase,
 }
 
 #[relm4::component(pub async)]
@@ -49,6 +56,26 @@ impl SimpleAsyncComponent for SidebarModel {
                     },
                 }
             },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 6,
+                set_margin_bottom: 6,
+
+                gtk::Button {
+                    set_icon_name: icon_names::FOLDER_OPEN_FILLED,
+                    set_tooltip: fl!("open-case"),
+                    set_css_classes: &["flat"],
+                    connect_clicked => SidebarInput::OpenCase,
+                },
+
+                gtk::Button {
+                    set_icon_name: icon_names::SAVE_FILLED,
+                    set_tooltip: fl!("save-case"),
+                    set_css_classes: &["flat"],
+                    connect_clicked => SidebarInput::SaveCase,
+                },
+            },
         }
     }
 
@@ -90,6 +117,12 @@ impl SimpleAsyncComponent for SidebarModel {
                     .output(SidebarOutput::SelectedOption(option))
                     .unwrap_or_default();
             }
+            SidebarInput::OpenCase => {
+                sender.output(SidebarOutput::OpenCase).unwrap_or_default();
+            }
+            SidebarInput::SaveCase => {
+                sender.output(SidebarOutput::SaveCase).unwrap_or_default();
+            }
         }
     }
 }
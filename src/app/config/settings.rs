@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use core_chasam::csam::{PHashAlgorithm, SamplingMode, VideoSampling};
 use i18n_embed::unic_langid::LanguageIdentifier;
 use once_cell::sync::Lazy;
 use relm4::adw;
@@ -10,7 +11,7 @@ use std::sync::Mutex;
 use toml;
 
 use super::localization;
-use crate::app::models::{ColorScheme, Preference};
+use crate::app::models::{ColorScheme, Preference, Storage};
 
 pub static PREFERENCES: Lazy<Mutex<Preference>> = Lazy::new(|| Mutex::new(Preference::default()));
 
@@ -22,6 +23,38 @@ pub struct SettingsToml {
     pub language: String,
     #[allow(dead_code)]
     pub database_path: String,
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
+    pub examiner: String,
+    #[serde(default = "default_archive_depth")]
+    pub archive_depth: usize,
+    #[serde(default = "default_phash_algorithms")]
+    pub phash_algorithms: Vec<PHashAlgorithm>,
+    #[serde(default)]
+    pub phash_transforms: bool,
+    #[serde(default = "default_pdq_threshold")]
+    pub pdq_threshold: u32,
+    #[serde(default)]
+    pub video_sampling: SamplingMode,
+    #[serde(default = "default_max_video_frames")]
+    pub max_video_frames: usize,
+}
+
+fn default_archive_depth() -> usize {
+    core_chasam::csam::Service::ARCHIVE_DEPTH
+}
+
+fn default_phash_algorithms() -> Vec<PHashAlgorithm> {
+    vec![PHashAlgorithm::default()]
+}
+
+fn default_pdq_threshold() -> u32 {
+    core_chasam::csam::Media::PDQ_THRESHOLD
+}
+
+fn default_max_video_frames() -> usize {
+    VideoSampling::MAX_FRAMES
 }
 
 pub(crate) fn init() -> Result<()> {
@@ -32,6 +65,14 @@ pub(crate) fn init() -> Result<()> {
         preference.set_color_scheme(settings_toml.theme);
         preference.set_language(&settings_toml.language);
         preference.set_database_path(&settings_toml.database_path);
+        preference.set_storage(settings_toml.storage);
+        preference.set_examiner(&settings_toml.examiner);
+        preference.set_archive_depth(settings_toml.archive_depth);
+        preference.set_phash_algorithms(&settings_toml.phash_algorithms);
+        preference.set_phash_transforms(settings_toml.phash_transforms);
+        preference.set_pdq_threshold(settings_toml.pdq_threshold);
+        preference.set_video_sampling(settings_toml.video_sampling);
+        preference.set_max_video_frames(settings_toml.max_video_frames);
     }
 
     set_localization(settings_toml.language)?;
@@ -89,6 +130,14 @@ pub(crate) async fn save_preferences(preference: &Preference) -> Result<()> {
             .to_str()
             .unwrap_or_default()
             .to_string(),
+        storage: preference.storage,
+        examiner: preference.examiner.clone(),
+        archive_depth: preference.archive_depth,
+        phash_algorithms: preference.phash_algorithms.clone(),
+        phash_transforms: preference.phash_transforms,
+        pdq_threshold: preference.pdq_threshold,
+        video_sampling: preference.video_sampling,
+        max_video_frames: preference.max_video_frames,
     };
     set_settings(&settings_toml).context("Failed to save preferences.")?;
 
@@ -96,6 +145,14 @@ pub(crate) async fn save_preferences(preference: &Preference) -> Result<()> {
     preference.set_color_scheme(settings_toml.theme);
     preference.set_language(&settings_toml.language);
     preference.set_database_path(&settings_toml.database_path);
+    preference.set_storage(settings_toml.storage);
+    preference.set_examiner(&settings_toml.examiner);
+    preference.set_archive_depth(settings_toml.archive_depth);
+    preference.set_phash_algorithms(&settings_toml.phash_algorithms);
+    preference.set_phash_transforms(settings_toml.phash_transforms);
+    preference.set_pdq_threshold(settings_toml.pdq_threshold);
+    preference.set_video_sampling(settings_toml.video_sampling);
+    preference.set_max_video_frames(settings_toml.max_video_frames);
 
     Ok(())
 }
@@ -65,7 +65,7 @@ impl MediaItem {
     }
 
     pub fn is_csam(&self) -> bool {
-        !self.media.match_type.is_empty()
+        self.media.is_csam()
     }
 
     fn get_pixbuf(data: &[u8]) -> Option<Pixbuf> {
@@ -78,6 +78,7 @@ pub struct Widgets {
     picture: gtk::Picture,
     checkbox: gtk::CheckButton,
     label: gtk::Label,
+    tag_label: gtk::Label,
 }
 
 // impl Drop for Widgets {
@@ -125,6 +126,13 @@ impl RelmGridItem for MediaItem {
                         set_ellipsize: pango::EllipsizeMode::End,
                     }
                 },
+
+                #[name(tag_label)]
+                gtk::Label {
+                    set_margin_start: 5,
+                    set_halign: gtk::Align::Start,
+                    set_css_classes: &["media-tag"],
+                },
             }
         }
 
@@ -132,6 +140,7 @@ impl RelmGridItem for MediaItem {
             picture,
             checkbox,
             label,
+            tag_label,
         };
 
         (root, widgets)
@@ -142,6 +151,7 @@ impl RelmGridItem for MediaItem {
             picture,
             checkbox,
             label,
+            tag_label,
         } = widgets;
         let media = &self.media;
 
@@ -171,6 +181,9 @@ impl RelmGridItem for MediaItem {
 
         label.set_label(media.name.as_str());
 
+        tag_label.set_visible(media.tag.is_some());
+        tag_label.set_label(&media.tag.map(models::media::tag_name).unwrap_or_default());
+
         if let Some(data) = media.img_buf.as_ref() {
             let pixbuf = Self::get_pixbuf(data);
             picture.set_pixbuf(pixbuf.as_ref());
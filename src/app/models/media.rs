@@ -3,6 +3,7 @@ use chrono::prelude::*;
 
 use crate::fl;
 use core_chasam;
+use core_chasam::csam::{MatchType, Tag};
 
 pub const ZOOM_SIZE: i32 = 20;
 pub const ZOOM_LIMIT: i32 = 240;
@@ -31,13 +32,21 @@ pub struct Media {
     pub name: String,
     pub path: String,
     pub media_type: MediaType,
+    pub file_type: Option<core_chasam::csam::FileType>,
+    pub extension_mismatch: bool,
     pub size: usize,
+    pub file_size: u64,
     pub last_modified: i64,
     pub hash: String,
+    pub sha1: String,
+    pub sha256: String,
     pub phash: u64,
-    pub match_type: String,
-    pub hamming: u32,
+    pub phashes: core_chasam::csam::PHashes,
+    pub matched: Option<core_chasam::csam::Match>,
     pub img_buf: Option<Bytes>,
+    /// Note of the examiner, kept in the case.
+    pub note: String,
+    pub tag: Option<Tag>,
 }
 
 impl From<&core_chasam::csam::Media> for Media {
@@ -49,20 +58,62 @@ impl From<&core_chasam::csam::Media> for Media {
                 core_chasam::csam::MediaType::Image => MediaType::Image,
                 core_chasam::csam::MediaType::Video => MediaType::Video,
             },
+            file_type: media.file_type,
+            extension_mismatch: media.extension_mismatch,
             size: media.size,
+            file_size: media.file_size,
             last_modified: media.last_modified,
             hash: media.hash.clone(),
+            sha1: media.sha1.clone(),
+            sha256: media.sha256.clone(),
             phash: media.phash,
-            match_type: media.match_type.clone(),
-            hamming: media.hamming,
+            phashes: media.phashes.clone(),
+            matched: media.matched.clone(),
             img_buf: media.img_buf.clone(),
+            note: String::new(),
+            tag: media.tag,
+        }
+    }
+}
+
+impl From<&Media> for core_chasam::csam::Media {
+    fn from(media: &Media) -> Self {
+        Self {
+            name: media.name.clone(),
+            path: media.path.clone(),
+            media_type: match media.media_type {
+                MediaType::Image => core_chasam::csam::MediaType::Image,
+                MediaType::Video => core_chasam::csam::MediaType::Video,
+            },
+            file_type: media.file_type,
+            extension_mismatch: media.extension_mismatch,
+            size: media.size,
+            file_size: media.file_size,
+            last_modified: media.last_modified,
+            hash: media.hash.clone(),
+            sha1: media.sha1.clone(),
+            sha256: media.sha256.clone(),
+            phash: media.phash,
+            phashes: media.phashes.clone(),
+            matched: media.matched.clone(),
+            img_buf: None,
+            tag: media.tag,
         }
     }
 }
 
 impl Media {
     pub fn is_csam(&self) -> bool {
-        !self.match_type.is_empty()
+        self.matched.is_some()
+    }
+
+    /// Hamming distance of a perceptual hash match, PDQ matches have their own threshold.
+    pub fn hamming(&self) -> u32 {
+        self.matched
+            .as_ref()
+            .filter(|matched| matched.match_type != MatchType::Pdq)
+            .map(|matched| matched.distance)
+            .unwrap_or_default()
     }
 
     // pub fn is_image(&self) -> bool {
@@ -78,23 +129,51 @@ pub struct MediaDetail {
     pub name: String,
     pub path: String,
     pub media_type: MediaType,
+    pub file_type: String,
     pub size: String,
     pub last_modified: String,
     pub hash: String,
     pub phash: String,
     pub match_type: String,
     // pub hamming: String,
+    pub reference: String,
+    pub source: String,
+    pub category: String,
+    pub original_filename: String,
+    pub series: String,
+    pub tag: String,
+    pub note: String,
     pub img_buf: Option<Bytes>,
 }
 
+impl MediaDetail {
+    /// Media type followed by the type detected from the file signature.
+    pub fn type_name(&self) -> String {
+        if self.file_type.is_empty() {
+            self.media_type.name()
+        } else {
+            format!("{} - {}", self.media_type.name(), self.file_type)
+        }
+    }
+}
+
 impl From<&Media> for MediaDetail {
     fn from(media: &Media) -> Self {
         let date_time = Local.timestamp_opt(media.last_modified, 0);
+        let matched = media.matched.as_ref();
 
         Self {
             name: media.name.clone(),
             path: media.path.clone(),
             media_type: media.media_type.clone(),
+            file_type: match media.file_type {
+                Some(file_type) if media.extension_mismatch => {
+                    let mismatch: &String = fl!("extension-mismatch");
+                    format!("{file_type} ({mismatch})")
+                }
+                Some(file_type) => file_type.to_string(),
+                None => String::new(),
+            },
             size: if media.size > 1024 {
                 format!("{:.2} MB", (media.size / 1024) as f64)
             } else {
@@ -107,25 +186,55 @@ impl From<&Media> for MediaDetail {
             },
             hash: media.hash.clone(),
             phash: format!("{:X}", media.phash),
-            match_type: media.match_type.clone(),
+            match_type: matched.map(|m| m.to_string()).unwrap_or_default(),
             // hamming: media.hamming.to_string(),
+            reference: matched.map(|m| m.reference.clone()).unwrap_or_default(),
+            source: matched.map(|m| m.source.clone()).unwrap_or_default(),
+            category: matched.and_then(|m| m.category.clone()).unwrap_or_default(),
+            original_filename: matched.and_then(|m| m.filename.clone()).unwrap_or_default(),
+            series: matched.and_then(|m| m.series.clone()).unwrap_or_default(),
+            tag: media.tag.map(tag_name).unwrap_or_default(),
+            note: media.note.clone(),
             img_buf: media.img_buf.clone(),
         }
     }
 }
 
+/// Name of the tag in the language of the interface.
+pub fn tag_name(tag: Tag) -> String {
+    let name: &String = match tag {
+        Tag::Category1 => fl!("tag-category-1"),
+        Tag::Category2 => fl!("tag-category-2"),
+        Tag::Category3 => fl!("tag-category-3"),
+        Tag::NotRelevant => fl!("tag-not-relevant"),
+        Tag::AgeDifficult => fl!("tag-age-difficult"),
+        Tag::NeedsReview => fl!("tag-needs-review"),
+    };
+    name.clone()
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TagFilter {
+    #[default]
+    All,
+    Untagged,
+    Tag(Tag),
+}
+
 #[derive(Debug)]
 pub struct MediaFilter {
     pub search_entry: Option<String>,
     pub is_image: bool,
     pub is_video: bool,
     pub is_csam: bool,
+    pub is_mismatch: bool,
     pub is_size_0: bool,
     pub is_size_30: bool,
     pub is_size_100: bool,
     pub is_size_500: bool,
     pub is_size_greater_500: bool,
     pub hamming_distance: u32,
+    pub tag: TagFilter,
 }
 
 impl Default for MediaFilter {
@@ -135,12 +244,14 @@ impl Default for MediaFilter {
             is_image: true,
             is_video: true,
             is_csam: false,
+            is_mismatch: false,
             is_size_0: true,
             is_size_30: true,
             is_size_100: true,
             is_size_500: true,
             is_size_greater_500: true,
             hamming_distance: core_chasam::csam::Media::MAX_DISTANCE_HAMMING,
+            tag: TagFilter::All,
         }
     }
 }
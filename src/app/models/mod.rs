@@ -2,8 +2,9 @@ pub mod media;
 mod preference;
 mod sidebar;
 
-pub use media::{Media, MediaDetail, MediaFilter, MediaType};
+pub use media::{Media, MediaDetail, MediaFilter, MediaType, TagFilter};
 pub use preference::ColorScheme;
 pub use preference::Language;
 pub use preference::Preference;
+pub use preference::Storage;
 pub use sidebar::SidebarOption;
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use core::fmt;
+use core_chasam::csam::{PHashAlgorithm, SamplingMode, VideoSampling};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -63,11 +64,43 @@ impl FromStr for Language {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq)]
+pub enum Storage {
+    Memory,
+    SQLite,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self::Memory
+    }
+}
+
+impl fmt::Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Memory => write!(f, "Memory"),
+            Self::SQLite => write!(f, "SQLite"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Preference {
     pub color_scheme: ColorScheme,
     pub language: Language,
     pub database_path: PathBuf,
+    pub storage: Storage,
+    pub examiner: String,
+    pub archive_depth: usize,
+    /// Algorithms of the perceptual hash database generated from a folder.
+    pub phash_algorithms: Vec<PHashAlgorithm>,
+    /// Whether rotated, mirrored and cropped copies are matched.
+    pub phash_transforms: bool,
+    /// Highest Hamming distance of a PDQ match.
+    pub pdq_threshold: u32,
+    pub video_sampling: SamplingMode,
+    pub max_video_frames: usize,
 }
 
 impl Default for Preference {
@@ -76,17 +109,38 @@ impl Default for Preference {
             color_scheme: ColorScheme::Default,
             language: Language::English,
             database_path: PathBuf::default(),
+            storage: Storage::Memory,
+            examiner: String::new(),
+            archive_depth: core_chasam::csam::Service::ARCHIVE_DEPTH,
+            phash_algorithms: vec![PHashAlgorithm::default()],
+            phash_transforms: false,
+            pdq_threshold: core_chasam::csam::Media::PDQ_THRESHOLD,
+            video_sampling: SamplingMode::default(),
+            max_video_frames: VideoSampling::MAX_FRAMES,
         }
     }
 }
 
 impl Preference {
     #[allow(unused)]
-    pub fn new(color_scheme: ColorScheme, language: Language, database_path: &str) -> Self {
+    pub fn new(
+        color_scheme: ColorScheme,
+        language: Language,
+        database_path: &str,
+        storage: Storage,
+    ) -> Self {
         Self {
             color_scheme,
             language,
             database_path: PathBuf::from_str(database_path).unwrap_or_default(),
+            storage,
+            examiner: String::new(),
+            archive_depth: core_chasam::csam::Service::ARCHIVE_DEPTH,
+            phash_algorithms: vec![PHashAlgorithm::default()],
+            phash_transforms: false,
+            pdq_threshold: core_chasam::csam::Media::PDQ_THRESHOLD,
+            video_sampling: SamplingMode::default(),
+            max_video_frames: VideoSampling::MAX_FRAMES,
         }
     }
 
@@ -101,6 +155,61 @@ impl Preference {
     pub fn set_database_path(&mut self, database_path: &str) {
         self.database_path = PathBuf::from_str(database_path).unwrap_or_default();
     }
+
+    pub fn set_storage(&mut self, storage: Storage) {
+        self.storage = storage;
+    }
+
+    pub fn set_examiner(&mut self, examiner: &str) {
+        self.examiner = examiner.trim().to_owned();
+    }
+
+    pub fn set_archive_depth(&mut self, archive_depth: usize) {
+        self.archive_depth = archive_depth;
+    }
+
+    pub fn set_phash_algorithms(&mut self, phash_algorithms: &[PHashAlgorithm]) {
+        self.phash_algorithms = PHashAlgorithm::ALL
+            .into_iter()
+            .filter(|algorithm| phash_algorithms.contains(algorithm))
+            .collect();
+    }
+
+    /// Adds or removes an algorithm, keeping at least one.
+    pub fn set_phash_algorithm(&mut self, algorithm: PHashAlgorithm, enabled: bool) {
+        let mut phash_algorithms = self.phash_algorithms.clone();
+        phash_algorithms.retain(|a| *a != algorithm);
+        if enabled {
+            phash_algorithms.push(algorithm);
+        }
+        if !phash_algorithms.is_empty() {
+            self.set_phash_algorithms(&phash_algorithms);
+        }
+    }
+
+    pub fn set_phash_transforms(&mut self, phash_transforms: bool) {
+        self.phash_transforms = phash_transforms;
+    }
+
+    pub fn set_pdq_threshold(&mut self, pdq_threshold: u32) {
+        self.pdq_threshold = pdq_threshold;
+    }
+
+    pub fn set_video_sampling(&mut self, video_sampling: SamplingMode) {
+        self.video_sampling = video_sampling;
+    }
+
+    pub fn set_max_video_frames(&mut self, max_video_frames: usize) {
+        self.max_video_frames = max_video_frames.max(1);
+    }
+
+    /// Sampling of the videos handed to the search.
+    pub fn sampling(&self) -> VideoSampling {
+        VideoSampling {
+            mode: self.video_sampling,
+            max_frames: self.max_video_frames,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -115,4 +224,15 @@ mod tests {
         let language_str = Language::English.to_string();
         assert_eq!(language_str, "en");
     }
+
+    #[test]
+    fn test_set_phash_algorithm_should_keep_one() {
+        let mut preference = Preference::default();
+        preference.set_phash_algorithm(PHashAlgorithm::DHash, true);
+        preference.set_phash_algorithm(PHashAlgorithm::PHash, false);
+        preference.set_phash_algorithm(PHashAlgorithm::DHash, false);
+
+        // Assert
+        assert_eq!(preference.phash_algorithms, vec![PHashAlgorithm::DHash]);
+    }
 }
@@ -3,7 +3,10 @@ use std::sync::Arc;
 use num_format::Locale;
 
 use crate::app::{config::settings, models};
-use core_chasam::csam;
+use core_chasam::csam::{
+    self,
+    repository::{InMemoryRepository, Repository, SqliteRepository},
+};
 
 #[derive(Clone)]
 pub struct AppContext {
@@ -12,8 +15,26 @@ pub struct AppContext {
 
 impl AppContext {
     pub fn new() -> Self {
-        let csam_repo = Arc::new(csam::repository::InMemoryRepository::new());
+        let preference = match settings::PREFERENCES.lock() {
+            Ok(preference) => preference.clone(),
+            _ => models::Preference::default(),
+        };
+
+        let csam_repo: Arc<dyn Repository> = match preference.storage {
+            models::Storage::Memory => Arc::new(InMemoryRepository::new()),
+            models::Storage::SQLite => match SqliteRepository::new() {
+                Ok(repo) => Arc::new(repo),
+                Err(err) => {
+                    tracing::error!("Could not create sqlite repository: {err}");
+                    Arc::new(InMemoryRepository::new())
+                }
+            },
+        };
         let csam_service = Arc::new(csam::Service::new(csam_repo));
+        csam_service.set_archive_depth(preference.archive_depth);
+        csam_service.set_phash_transforms(preference.phash_transforms);
+        csam_service.set_pdq_threshold(preference.pdq_threshold);
+        csam_service.set_video_sampling(preference.sampling());
 
         AppContext { csam_service }
     }